@@ -217,6 +217,14 @@ mod json {
         pub release: String,
     }
 
+    /// One version of a package, as listed by the versions endpoint.
+    #[derive(Clone, Deserialize)]
+    pub struct PackageVersion {
+        pub origin:  String,
+        pub name:    String,
+        pub version: String,
+    }
+
     impl From<PackageIdent> for super::PackageIdent {
         fn from(ident: PackageIdent) -> Self {
             super::PackageIdent { origin:  ident.origin,
@@ -842,6 +850,38 @@ impl Client {
         Ok(package.ident.into())
     }
 
+    /// Returns every version of a package that has been uploaded for a target, newest release
+    /// of each first, in a single request. The versions aren't limited to those in any channel.
+    ///
+    /// # Failures
+    ///
+    /// * Package cannot be found
+    /// * Remote Builder is not available
+    pub fn show_package_versions(&self,
+                                 (package, target): (&PackageIdent, PackageTarget),
+                                 token: Option<&str>)
+                                 -> Result<Vec<PackageIdent>> {
+        let url = format!("depot/pkgs/{}/{}/versions", package.origin(), package.name());
+        let mut res = self.maybe_add_authz(self.0.get_with_custom_url(&url, |u| {
+                                               u.set_query(Some(&format!("target={}", target)))
+                                           }),
+                                           token)
+                          .send()?;
+
+        if res.status != StatusCode::Ok {
+            return Err(err_from_response(res));
+        }
+
+        let mut encoded = String::new();
+        res.read_to_string(&mut encoded)
+           .map_err(Error::BadResponseBody)?;
+        debug!("Body: {:?}", encoded);
+        let versions: Vec<json::PackageVersion> = serde_json::from_str(&encoded)?;
+        Ok(versions.into_iter()
+                   .map(|v| PackageIdent::new(v.origin, v.name, Some(v.version), None))
+                   .collect())
+    }
+
     /// Upload a package to a remote Builder.
     ///
     /// # Failures
@@ -41,6 +41,9 @@ pub struct Service {
     pub cfg: ::std::option::Option<std::vec::Vec<u8>>,
     #[prost(message, optional, tag="12")]
     pub sys: ::std::option::Option<SysInfo>,
+    /// The result of the service's latest health check, e.g. "OK" or "CRITICAL".
+    #[prost(string, optional, tag="13")]
+    pub health: ::std::option::Option<std::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -78,7 +78,8 @@ impl From<CService> for Rumor {
                                 initialized:   Some(value.initialized),
                                 pkg:           Some(value.pkg),
                                 cfg:           Some(value.cfg),
-                                sys:           Some(value.sys.into()), };
+                                sys:           Some(value.sys.into()),
+                                health:        value.health, };
         Rumor { r#type:  RumorType::Service as i32,
                 tag:     Vec::default(),
                 from_id: Some(value.member_id),
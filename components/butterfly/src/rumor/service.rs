@@ -33,6 +33,9 @@ pub struct Service {
     pub pkg:           String,
     pub cfg:           Vec<u8>,
     pub sys:           SysInfo,
+    /// The result of the service's latest health check, if the
+    /// member has told us.
+    pub health:        Option<String>,
 }
 
 // Ensures that `cfg` is rendered as a map, and not an array of bytes
@@ -40,7 +43,7 @@ impl Serialize for Service {
     fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
         where S: Serializer
     {
-        let mut strukt = serializer.serialize_struct("service", 7)?;
+        let mut strukt = serializer.serialize_struct("service", 8)?;
         let cfg: toml::value::Table = toml::from_slice(&self.cfg).unwrap_or_default();
         strukt.serialize_field("member_id", &self.member_id)?;
         strukt.serialize_field("service_group", &self.service_group)?;
@@ -49,6 +52,7 @@ impl Serialize for Service {
         strukt.serialize_field("cfg", &cfg)?;
         strukt.serialize_field("sys", &self.sys)?;
         strukt.serialize_field("initialized", &self.initialized)?;
+        strukt.serialize_field("health", &self.health)?;
         strukt.end()
     }
 }
@@ -94,6 +98,7 @@ impl Service {
                   initialized: false,
                   pkg: package.to_string(),
                   sys,
+                  health: None,
                   cfg: cfg.map(|v| {
                               // Directly serializing a toml::value::Table can lead to an error
                               // Wrapping it in a toml::value::Value makes this operation safe
@@ -125,7 +130,8 @@ impl FromProto<newscast::Rumor> for Service {
                      cfg:           payload.cfg.unwrap_or_default(),
                      sys:           payload.sys
                                            .ok_or(Error::ProtocolMismatch("sys"))
-                                           .and_then(SysInfo::from_proto)?, })
+                                           .and_then(SysInfo::from_proto)?,
+                     health:        payload.health, })
     }
 }
 
@@ -137,7 +143,8 @@ impl From<Service> for newscast::Service {
                             initialized:   Some(value.initialized),
                             pkg:           Some(value.pkg),
                             cfg:           Some(value.cfg),
-                            sys:           Some(value.sys.into()), }
+                            sys:           Some(value.sys.into()),
+                            health:        value.health, }
     }
 }
 
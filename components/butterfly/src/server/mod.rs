@@ -1311,7 +1311,8 @@ mod tests {
                   initialized:   Default::default(),
                   pkg:           Default::default(),
                   cfg:           Default::default(),
-                  sys:           Default::default(), }
+                  sys:           Default::default(),
+                  health:        Default::default(), }
     }
 
     #[test]
@@ -48,7 +48,8 @@ use crate::{api_client::{self,
                               PackageArchive,
                               PackageIdent,
                               PackageInstall,
-                              PackageTarget},
+                              PackageTarget,
+                              VersionConstraint},
                     ChannelIdent}};
 use glob;
 use hyper::status::StatusCode;
@@ -348,6 +349,48 @@ pub fn start<U>(ui: &mut U,
     }
 }
 
+/// Find the latest release of a package in the given `channel` whose version satisfies a
+/// constraint, e.g. `~9.6`, so that it can be installed as a fully-qualified `InstallSource`.
+#[allow(clippy::too_many_arguments)]
+pub fn latest_matching<U>(ui: &mut U,
+                          url: &str,
+                          channel: &ChannelIdent,
+                          (ident, target): (&PackageIdent, PackageTarget),
+                          constraint: &VersionConstraint,
+                          product: &str,
+                          version: &str,
+                          fs_root_path: &Path,
+                          token: Option<&str>)
+                          -> Result<PackageIdent>
+    where U: UIWriter
+{
+    ui.status(Status::Determining,
+              format!("latest version of {} matching '{}' in the '{}' channel",
+                      ident, constraint, channel))?;
+    let api_client = Client::new(url, product, version, Some(fs_root_path))?;
+    // The latest release usually matches, so it's asked for first. Otherwise the matching
+    // versions are tried from the newest down, as not every version is in every channel.
+    let latest = api_client.show_package((ident, target), channel, token)?;
+    if constraint.matches(&latest) {
+        return Ok(latest);
+    }
+    let mut versions: Vec<PackageIdent> =
+        api_client.show_package_versions((ident, target), token)?
+                  .into_iter()
+                  .filter(|version| constraint.matches(version))
+                  .collect();
+    versions.sort();
+    for version in versions.iter().rev() {
+        match api_client.show_package((version, target), channel, token) {
+            Ok(release) => return Ok(release),
+            Err(APIError(StatusCode::NotFound, _)) => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Err(Error::PackageNotFound(format!("No release of {} in the '{}' channel matches '{}'",
+                                       ident, channel, constraint)))
+}
+
 pub fn check_install_hooks<T, P>(ui: &mut T,
                                  package: &PackageInstall,
                                  fs_root_path: P)
@@ -24,6 +24,10 @@ pub enum Error {
     BadGlyphStyle(String),
     CantUploadGossipToml,
     ChannelNotFound,
+    /// Occurs when a configuration doesn't match the schema its package ships
+    ConfigInvalid(Vec<String>),
+    /// Occurs when the schema a package ships can't be read or isn't a valid JSON Schema
+    ConfigSchemaInvalid(PathBuf, String),
     CryptoKeyError(String),
     DownloadFailed(String),
     EditorEnv(env::VarError),
@@ -33,6 +37,8 @@ pub enum Error {
     FileNotFound(String),
     GossipFileRelativePath(String),
     HabitatCore(hcore::Error),
+    /// Occurs when secrets would be fetched over plain HTTP without that being allowed
+    InsecureSecretsUrl(String),
     InstallHookFailed(PackageIdent),
     InvalidEventStreamToken(String),
     InvalidInstallHookMode(String),
@@ -51,12 +57,20 @@ pub enum Error {
     /// When an error occurs serializing rendering context
     RenderContextSerialization(serde_json::Error),
     RootRequired,
+    /// Occurs when a template refers to a secret with a name that isn't a relative path
+    SecretInvalidName(String),
+    /// Occurs when none of the secret providers has a secret a template refers to
+    SecretNotFound(String, String),
+    /// Occurs when a secret provider can't be asked for a secret
+    SecretProvider(String),
     StatusFileCorrupt(PathBuf),
     StrFromUtf8Error(str::Utf8Error),
     StringFromUtf8Error(string::FromUtf8Error),
     /// When an error occurs registering template file
     // Boxed due to clippy::large_enum_variant
     TemplateFileError(Box<handlebars::TemplateFileError>),
+    /// Occurs when a template rendered in strict mode refers to variables that aren't defined
+    TemplateMissingVariables(String, Vec<String>),
     /// When an error occurs rendering template
     /// The error is constructed with a handlebars::RenderError's format string instead
     /// of the handlebars::RenderError itself because the cause field of the
@@ -89,6 +103,13 @@ impl fmt::Display for Error {
                 "Can't upload gossip.toml, it's a reserved file name".to_string()
             }
             Error::ChannelNotFound => "Channel not found".to_string(),
+            Error::ConfigInvalid(ref errors) => {
+                format!("Configuration doesn't match the package's schema: {}",
+                        errors.join("; "))
+            }
+            Error::ConfigSchemaInvalid(ref path, ref e) => {
+                format!("Invalid configuration schema {}: {}", path.display(), e)
+            }
             Error::CryptoKeyError(ref s) => format!("Missing or invalid key: {}", s),
             Error::DownloadFailed(ref msg) => msg.to_string(),
             Error::EditorEnv(ref e) => format!("Missing EDITOR environment variable: {}", e),
@@ -103,6 +124,9 @@ impl fmt::Display for Error {
             Error::MissingCLIInputError(ref arg) => {
                 format!("Missing required CLI argument!: {}", arg)
             }
+            Error::InsecureSecretsUrl(ref url) => {
+                format!("Refusing to fetch secrets from {} as it isn't an https URL", url)
+            }
             Error::InstallHookFailed(ref ident) => {
                 format!("Install hook exited unsuccessfully: {}", ident)
             }
@@ -135,6 +159,11 @@ impl fmt::Display for Error {
             Error::RootRequired => {
                 "Root or administrator permissions required to complete operation".to_string()
             }
+            Error::SecretInvalidName(ref name) => format!("Invalid secret name '{}'", name),
+            Error::SecretNotFound(ref name, ref service_group) => {
+                format!("No secret named '{}' for {}", name, service_group)
+            }
+            Error::SecretProvider(ref e) => format!("Unable to get secret: {}", e),
             Error::StatusFileCorrupt(ref path) => {
                 format!("Unable to decode contents of INSTALL_STATUS file, {}",
                         path.display())
@@ -142,6 +171,11 @@ impl fmt::Display for Error {
             Error::StrFromUtf8Error(ref e) => format!("{}", e),
             Error::StringFromUtf8Error(ref e) => format!("{}", e),
             Error::TemplateFileError(ref err) => format!("{:?}", err),
+            Error::TemplateMissingVariables(ref template, ref missing) => {
+                format!("Template {} refers to undefined variables: {}",
+                        template,
+                        missing.join(", "))
+            }
             Error::TemplateRenderError(ref err) => err.to_string(),
             Error::TomlMergeError(ref e) => format!("Failed to merge TOML: {}", e),
             Error::TomlParser(ref err) => format!("Failed to parse TOML: {}", err),
@@ -161,6 +195,8 @@ impl error::Error for Error {
             Error::BadGlyphStyle(_) => "Unknown symbol style",
             Error::CantUploadGossipToml => "Can't upload gossip.toml, it's a reserved filename",
             Error::ChannelNotFound => "Channel not found",
+            Error::ConfigInvalid(_) => "Configuration doesn't match the package's schema",
+            Error::ConfigSchemaInvalid(..) => "Invalid configuration schema",
             Error::CryptoKeyError(_) => "Missing or invalid key",
             Error::DownloadFailed(_) => "Failed to download from remote",
             Error::EditorEnv(_) => "Missing EDITOR environment variable",
@@ -171,6 +207,7 @@ impl error::Error for Error {
                 "Path for gossip file cannot have relative components (eg: ..)"
             }
             Error::HabitatCore(ref err) => err.description(),
+            Error::InsecureSecretsUrl(_) => "Secrets URL isn't an https URL",
             Error::InstallHookFailed(_) => "Install hook exited unsuccessfully",
             Error::InvalidEventStreamToken(_) => "Invalid event stream token provided",
             Error::InvalidInstallHookMode(_) => "Invalid InstallHookMode",
@@ -189,10 +226,14 @@ impl error::Error for Error {
             Error::RootRequired => {
                 "Root or administrator permissions required to complete operation"
             }
+            Error::SecretInvalidName(_) => "Invalid secret name",
+            Error::SecretNotFound(..) => "No secret with the given name",
+            Error::SecretProvider(_) => "Unable to get secret",
             Error::StatusFileCorrupt(_) => "Unable to decode contents of INSTALL_STATUS file",
             Error::StrFromUtf8Error(_) => "Failed to convert a string as UTF-8",
             Error::StringFromUtf8Error(_) => "Failed to convert a string as UTF-8",
             Error::TemplateFileError(ref err) => err.description(),
+            Error::TemplateMissingVariables(..) => "Template refers to undefined variables",
             Error::TemplateRenderError(_) => "Failed to render template",
             Error::TomlMergeError(_) => "Failed to merge TOML!",
             Error::TomlParser(_) => "Failed to parse TOML!",
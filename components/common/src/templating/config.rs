@@ -7,7 +7,8 @@ use crate::{error::{Error,
                          USER_CONFIG_FILE}},
             outputln,
             templating::{package::Pkg,
-                         TemplateRenderer}};
+                         TemplateRenderer,
+                         PARTIALS_DIR}};
 use serde::{Serialize,
             Serializer};
 use serde_json;
@@ -21,6 +22,7 @@ use std::{self,
                  PathBuf},
           result};
 use toml;
+use valico::json_schema;
 
 static LOGKEY: &'static str = "CF";
 static ENV_VAR_PREFIX: &'static str = "HAB";
@@ -30,6 +32,9 @@ static ENV_VAR_PREFIX: &'static str = "HAB";
 /// is deeper than this value crosses into overly complex territory when describing configuration
 /// for a single service.
 static TOML_MAX_MERGE_DEPTH: u16 = 30;
+/// The name of the JSON Schema a package may ship next to its `default.toml`, which the
+/// configuration of its services has to match.
+pub const CONFIG_SCHEMA_FILE: &str = "default.schema.json";
 #[cfg(unix)]
 pub const CONFIG_PERMISSIONS: u32 = 0o740;
 #[cfg(unix)]
@@ -92,6 +97,9 @@ pub struct Cfg {
     pub gossip: Option<toml::value::Table>,
     /// Environment level configuration loaded by the Supervisor's process environment
     pub environment: Option<toml::value::Table>,
+    /// JSON Schema loaded by a Package's `default.schema.json`, which the merged configuration
+    /// has to match
+    pub schema: Option<serde_json::Value>,
     /// Source of the user configuration
     pub user_config_path: UserConfigPath,
     /// Last known incarnation number of the census group's service config
@@ -113,16 +121,30 @@ impl Cfg {
             };
             Self::load_default(pkg_root.as_ref())?
         };
+        let schema = {
+            let pkg_root = match override_config_dir {
+                Some(ref path) => Cow::Borrowed(path),
+                None => Cow::Owned(package.default_config_dir()),
+            };
+            Self::load_schema(pkg_root.as_ref())?
+        };
         let user_config_path = Self::determine_user_config_path(package);
         let user = Self::load_user(user_config_path.get_path())?;
         let environment = Self::load_environment(&package.name())?;
-        Ok(Self { default,
-                  user,
-                  gossip: None,
-                  environment,
-                  gossip_incarnation: 0,
-                  user_config_path,
-                  override_config_dir })
+        let cfg = Self { default,
+                         user,
+                         gossip: None,
+                         environment,
+                         schema,
+                         gossip_incarnation: 0,
+                         user_config_path,
+                         override_config_dir };
+        // A service is still started with a configuration that doesn't match its schema, since
+        // there's no earlier configuration to fall back on.
+        if let Err(err) = cfg.validate_schema() {
+            outputln!("{}", err);
+        }
+        Ok(cfg)
     }
 
     /// Validates a service configuration against a configuration interface.
@@ -144,6 +166,50 @@ impl Cfg {
         }
     }
 
+    /// Validates the merged configuration against the package's schema, if it ships one.
+    ///
+    /// Returns `Error::ConfigInvalid` listing every value that doesn't match the schema.
+    pub fn validate_schema(&self) -> Result<()> {
+        let schema = match self.schema {
+            Some(ref schema) => schema,
+            None => return Ok(()),
+        };
+        let mut scope = json_schema::Scope::new();
+        // NOTE: using `false` instead of `true` allows schemas to use keywords valico doesn't
+        // know about, such as `$comment`.
+        let schema = scope.compile_and_return(schema.clone(), false)
+                          .map_err(|e| {
+                              Error::ConfigSchemaInvalid(PathBuf::from(CONFIG_SCHEMA_FILE),
+                                                         format!("{:?}", e))
+                          })?;
+        let cfg = serde_json::to_value(self).map_err(Error::RenderContextSerialization)?;
+        let state = schema.validate(&cfg);
+        if state.is_valid() {
+            Ok(())
+        } else {
+            Err(Error::ConfigInvalid(state.errors
+                                          .iter()
+                                          .map(|e| {
+                                              let path = match e.get_path() {
+                                                  "" => "/",
+                                                  path => path,
+                                              };
+                                              format!("{}: {}",
+                                                      path,
+                                                      e.get_detail().unwrap_or(e.get_title()))
+                                          })
+                                          .collect()))
+        }
+    }
+
+    /// Validates a configuration that would replace the gossip layer against the package's
+    /// schema, without applying it.
+    pub fn validate_gossip(&self, gossip: &toml::value::Table) -> Result<()> {
+        let mut cfg = self.clone();
+        cfg.gossip = Some(gossip.clone());
+        cfg.validate_schema()
+    }
+
     /// A structured interface which describes configuration keys which are configurable and their
     /// optional default values.
     pub fn interface(&self) -> Option<&toml::value::Table> {
@@ -178,19 +244,35 @@ impl Cfg {
             };
             Self::load_default(pkg_root.as_ref())?
         };
+        let incoming_schema = {
+            let pkg_root = match self.override_config_dir {
+                Some(ref path) => Cow::Borrowed(path),
+                None => Cow::Owned(package.default_config_dir()),
+            };
+            Self::load_schema(pkg_root.as_ref())?
+        };
 
-        if incoming_defaults != self.default {
-            self.default = incoming_defaults;
-            Ok(true)
-        } else {
-            Ok(false)
+        let changed = incoming_defaults != self.default || incoming_schema != self.schema;
+        self.default = incoming_defaults;
+        self.schema = incoming_schema;
+        // The new release's defaults and schema are applied regardless, since they come with it.
+        if changed {
+            if let Err(err) = self.validate_schema() {
+                outputln!("{}", err);
+            }
         }
+        Ok(changed)
     }
 
-    /// Updates the service configuration with data from a census group
-    pub fn set_gossip(&mut self, incarnation: u64, gossip: toml::value::Table) {
+    /// Updates the service configuration with data from a census group.
+    ///
+    /// Data that doesn't match the package's schema isn't applied, and the previous data is kept,
+    /// but the incarnation is still recorded so that it isn't tried again.
+    pub fn set_gossip(&mut self, incarnation: u64, gossip: toml::value::Table) -> Result<()> {
         self.gossip_incarnation = incarnation;
+        self.validate_gossip(&gossip)?;
         self.gossip = Some(gossip);
+        Ok(())
     }
 
     /// Returns a subset of the overall configuration which intersects with the given package
@@ -261,6 +343,21 @@ impl Cfg {
         Self::load_toml_file(config_from, "default.toml")
     }
 
+    fn load_schema<T>(config_from: T) -> Result<Option<serde_json::Value>>
+        where T: AsRef<Path>
+    {
+        let path = config_from.as_ref().join(CONFIG_SCHEMA_FILE);
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                debug!("Failed to open '{}', {}", path.display(), e);
+                return Ok(None);
+            }
+        };
+        serde_json::from_reader(file).map(Some)
+                                     .map_err(|e| Error::ConfigSchemaInvalid(path, e.to_string()))
+    }
+
     fn determine_user_config_path<P: PackageConfigPaths>(package: &P) -> UserConfigPath {
         let recommended_dir = package.recommended_user_config_dir();
         let recommended_path = recommended_dir.join(USER_CONFIG_FILE);
@@ -292,9 +389,14 @@ impl Cfg {
     }
 
     /// Reloads the user configuration file.
+    ///
+    /// A file that doesn't match the package's schema isn't applied, and the previous user
+    /// configuration is kept.
     pub fn reload_user(&mut self) -> Result<()> {
-        let user = Self::load_user(self.user_config_path.get_path())?;
-        self.user = user;
+        let mut cfg = self.clone();
+        cfg.user = Self::load_user(self.user_config_path.get_path())?;
+        cfg.validate_schema()?;
+        self.user = cfg.user;
         Ok(())
     }
 
@@ -394,20 +496,45 @@ pub struct CfgRenderer(TemplateRenderer);
 
 impl CfgRenderer {
     /// Create a new `CfgRenderer` and load template files from a
-    /// configuration directory, if it exists.
+    /// configuration directory, if it exists. Files in its `_partials`
+    /// directory are registered as partials instead.
     pub fn new<T>(templates_path: T) -> Result<Self>
         where T: AsRef<Path>
     {
         if templates_path.as_ref().is_dir() {
-            load_templates(templates_path.as_ref(),
-                           &PathBuf::new(),
-                           TemplateRenderer::new()).map(CfgRenderer)
+            let mut renderer =
+                load_templates(templates_path.as_ref(), &PathBuf::new(), TemplateRenderer::new())?;
+            renderer.register_partials(templates_path.as_ref().join(PARTIALS_DIR))?;
+            Ok(CfgRenderer(renderer))
         } else {
             Ok(CfgRenderer(TemplateRenderer::new()))
         }
     }
 
-    /// Compile and write all configuration files to the configuration directory.
+    /// See `TemplateRenderer::set_strict`.
+    pub fn set_strict(&mut self, strict: bool) { self.0.set_strict(strict); }
+
+    pub fn is_strict(&self) -> bool { self.0.is_strict() }
+
+    /// Render all configuration files without writing them, returning the path in the
+    /// configuration directory that each one is compiled to along with its content.
+    pub fn render<P, T>(&self, render_path: P, ctx: &T) -> Result<Vec<(PathBuf, String)>>
+        where P: AsRef<Path>,
+              T: Serialize
+    {
+        self.0
+            .get_templates()
+            .keys()
+            .filter(|template| !self.0.is_partial(template))
+            .map(|template| {
+                Ok((render_path.as_ref().join(template), self.0.render(template, ctx)?))
+            })
+            .collect()
+    }
+
+    /// Compile and write all configuration files to the configuration directory. Every file is
+    /// rendered before any is written, so a template that fails to render leaves all of them as
+    /// they were.
     ///
     /// Returns `true` if the configuration has changed.
     pub fn compile<P, T>(&self,
@@ -425,10 +552,8 @@ impl CfgRenderer {
         // having issues and be more descriptive about what happened.
 
         let mut changed = false;
-        for template in self.0.get_templates().keys() {
-            let compiled = self.0.render(&template, ctx)?;
+        for (cfg_dest, compiled) in self.render(render_path.as_ref(), ctx)? {
             let compiled_hash = crypto::hash::hash_string(&compiled);
-            let cfg_dest = render_path.as_ref().join(&template);
             let file_hash = match crypto::hash::hash_file(&cfg_dest) {
                 Ok(file_hash) => file_hash,
                 Err(e) => {
@@ -552,6 +677,9 @@ fn load_templates(dir: &Path,
         // We're storing the pathname relative to the input config directory
         // as the identifier for the template
         let relative_path = context.join(&entry.file_name());
+        if relative_path == Path::new(PARTIALS_DIR) {
+            continue;
+        }
         match entry.file_type() {
             Ok(file_type) if file_type.is_file() => {
                 // JW TODO: This error needs improvement. TemplateFileError is too generic.
@@ -932,6 +1060,66 @@ mod test {
         assert_eq!(cfg.user, Some(toml_from_str(toml)));
     }
 
+    const SCHEMA: &str = r#"{
+        "type": "object",
+        "properties": {
+            "port": { "type": "integer", "minimum": 1, "maximum": 65535 },
+            "log_level": { "enum": ["debug", "info", "error"] }
+        },
+        "additionalProperties": false
+    }"#;
+
+    fn cfg_with_schema(cfg_data: &CfgTestData) -> Cfg {
+        let default_dir = cfg_data.pkg.default_config_dir();
+        write_toml(&default_dir.join("default.toml"), "port = 8080
+log_level = \"info\"");
+        write_toml(&default_dir.join(CONFIG_SCHEMA_FILE), SCHEMA);
+        Cfg::new(&cfg_data.pkg, None).expect("create config")
+    }
+
+    #[test]
+    fn validate_config_against_schema() {
+        let cfg_data = CfgTestData::new();
+        let cfg = cfg_with_schema(&cfg_data);
+        assert!(cfg.schema.is_some());
+        assert!(cfg.validate_schema().is_ok());
+        assert!(cfg.validate_gossip(&toml_from_str("port = 9090")).is_ok());
+
+        let wrong_type = "port = \"9090\"";
+        let out_of_range = "port = 70000";
+        let not_allowed = "log_level = \"loud\"";
+        let unknown_key = "prot = 1";
+        for invalid in &[wrong_type, out_of_range, not_allowed, unknown_key] {
+            match cfg.validate_gossip(&toml_from_str(invalid)) {
+                Err(Error::ConfigInvalid(errors)) => assert_eq!(errors.len(), 1),
+                other => panic!("Expected {} to be invalid, got {:?}", invalid, other),
+            }
+        }
+    }
+
+    #[test]
+    fn gossip_that_does_not_match_the_schema_is_not_applied() {
+        let cfg_data = CfgTestData::new();
+        let mut cfg = cfg_with_schema(&cfg_data);
+        cfg.set_gossip(1, toml_from_str("port = 9090"))
+           .expect("apply valid gossip");
+        assert!(cfg.set_gossip(2, toml_from_str("port = -1")).is_err());
+
+        assert_eq!(cfg.gossip, Some(toml_from_str("port = 9090")));
+        assert_eq!(cfg.gossip_incarnation, 2);
+    }
+
+    #[test]
+    fn user_toml_that_does_not_match_the_schema_is_not_applied() {
+        let cfg_data = CfgTestData::new();
+        write_toml(&cfg_data.rucp, "port = 9090");
+        let mut cfg = cfg_with_schema(&cfg_data);
+
+        write_toml(&cfg_data.rucp, "port = \"http\"");
+        assert!(cfg.reload_user().is_err());
+        assert_eq!(cfg.user, Some(toml_from_str("port = 9090")));
+    }
+
     #[test]
     fn serialize_config() {
         let concrete_path = TempDir::new().expect("create temp dir");
@@ -1160,4 +1348,78 @@ mod test {
         assert_eq!(file_content(deep_output_dir.join("config.txt")),
                    "config message is Hello");
     }
+
+    #[test]
+    fn strict_compile_leaves_every_file_alone_when_a_variable_is_missing() {
+        let root = TempDir::new().expect("create temp dir").into_path();
+        let pkg_dir = root.join("pkg/testing/test");
+        fs::create_dir_all(&pkg_dir).expect("create pkg dir");
+        let pg_id = PackageIdent::new("testing", "test", Some("1.0.0"), Some("20170712000000"));
+        let pkg_install = PackageInstall::new_from_parts(pg_id.clone(),
+                                                         pkg_dir.clone(),
+                                                         pkg_dir.clone(),
+                                                         pkg_dir.clone());
+        create_with_content(pkg_dir.join("default.toml"), "message = \"Hello\"");
+        let config_dir = pkg_dir.join("config");
+        fs::create_dir_all(&config_dir).expect("create config dir");
+        create_with_content(config_dir.join("a.txt"), "{{cfg.message}}");
+        create_with_content(config_dir.join("b.txt"), "{{cfg.mesage}}");
+
+        let output_dir = root.join("output");
+        fs::create_dir_all(&output_dir).expect("create output dir");
+        create_with_content(output_dir.join("a.txt"), "previous");
+
+        let pkg = Pkg::from_install(&pkg_install).unwrap();
+        let cfg = Cfg::new(&pkg, None).unwrap();
+        let ctx = RenderContext::new(&pkg, &cfg);
+        let mut renderer = CfgRenderer::new(&config_dir).expect("create cfg renderer");
+        renderer.set_strict(true);
+
+        match renderer.compile("test", &pkg, &output_dir, &ctx) {
+            Err(Error::TemplateMissingVariables(template, missing)) => {
+                assert_eq!(template, "b.txt");
+                assert_eq!(missing, vec!["cfg.mesage (line 1)".to_string()]);
+            }
+            other => panic!("Expected missing variables, got {:?}", other),
+        }
+        assert_eq!(file_content(output_dir.join("a.txt")), "previous");
+        assert!(!output_dir.join("b.txt").exists());
+    }
+
+    #[test]
+    fn partials_are_included_but_not_rendered_and_changes_rerender_dependents() {
+        let root = TempDir::new().expect("create temp dir").into_path();
+        let pkg_dir = root.join("pkg/testing/test");
+        fs::create_dir_all(&pkg_dir).expect("create pkg dir");
+        let pg_id = PackageIdent::new("testing", "test", Some("1.0.0"), Some("20170712000000"));
+        let pkg_install = PackageInstall::new_from_parts(pg_id.clone(),
+                                                         pkg_dir.clone(),
+                                                         pkg_dir.clone(),
+                                                         pkg_dir.clone());
+        create_with_content(pkg_dir.join("default.toml"), "port = 8080");
+        let config_dir = pkg_dir.join("config");
+        let partials_dir = config_dir.join(PARTIALS_DIR);
+        fs::create_dir_all(&partials_dir).expect("create partials dir");
+        create_with_content(config_dir.join("a.conf"), "a {{> upstreams}}");
+        create_with_content(config_dir.join("b.conf"), "b");
+        create_with_content(partials_dir.join("upstreams.conf"), "localhost:{{cfg.port}}");
+
+        let output_dir = root.join("output");
+        fs::create_dir_all(&output_dir).expect("create output dir");
+        let pkg = Pkg::from_install(&pkg_install).unwrap();
+        let cfg = Cfg::new(&pkg, None).unwrap();
+        let ctx = RenderContext::new(&pkg, &cfg);
+
+        let renderer = CfgRenderer::new(&config_dir).expect("create cfg renderer");
+        assert!(renderer.compile("test", &pkg, &output_dir, &ctx).unwrap());
+        assert_eq!(file_content(output_dir.join("a.conf")), "a localhost:8080");
+        assert!(!output_dir.join(PARTIALS_DIR).exists());
+        assert!(!output_dir.join("upstreams").exists());
+
+        create_with_content(partials_dir.join("upstreams.conf"), "127.0.0.1:{{cfg.port}}");
+        let renderer = CfgRenderer::new(&config_dir).expect("create cfg renderer");
+        assert!(renderer.compile("test", &pkg, &output_dir, &ctx).unwrap());
+        assert_eq!(file_content(output_dir.join("a.conf")), "a 127.0.0.1:8080");
+        assert_eq!(file_content(output_dir.join("b.conf")), "b");
+    }
 }
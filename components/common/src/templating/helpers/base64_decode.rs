@@ -0,0 +1,48 @@
+use base64;
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+pub struct Base64DecodeHelper;
+
+impl HelperDef for Base64DecodeHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a string \
+                                                                              parameter for \
+                                                                              \"base64Decode\"")
+                                                        })?;
+        let decoded = base64::decode(param).map_err(|e| {
+                                               RenderError::new(format!("Invalid base64 for \
+                                                                         \"base64Decode\": {}",
+                                                                        e))
+                                           })?;
+        rc.writer.write_all(decoded.as_ref())?;
+        Ok(())
+    }
+}
+
+pub static BASE64_DECODE: Base64DecodeHelper = Base64DecodeHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_base64_decode_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("base64Decode", Box::new(BASE64_DECODE));
+        let expected = "user:pass";
+        assert_eq!(expected,
+                   handlebars.template_render("{{base64Decode \"dXNlcjpwYXNz\"}}", &json!({}))
+                             .unwrap());
+        assert!(handlebars.template_render("{{base64Decode \"not base64!\"}}", &json!({}))
+                          .is_err());
+    }
+}
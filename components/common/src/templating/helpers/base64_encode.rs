@@ -0,0 +1,42 @@
+use base64;
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+pub struct Base64EncodeHelper;
+
+impl HelperDef for Base64EncodeHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a string \
+                                                                              parameter for \
+                                                                              \"base64Encode\"")
+                                                        })?;
+        rc.writer
+          .write_all(base64::encode(param).into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static BASE64_ENCODE: Base64EncodeHelper = Base64EncodeHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_base64_encode_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("base64Encode", Box::new(BASE64_ENCODE));
+        let expected = "dXNlcjpwYXNz";
+        assert_eq!(expected,
+                   handlebars.template_render("{{base64Encode \"user:pass\"}}", &json!({}))
+                             .unwrap());
+    }
+}
@@ -0,0 +1,59 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+pub struct DefaultHelper;
+
+impl HelperDef for DefaultHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let value = h.param(0)
+                     .ok_or_else(|| RenderError::new("Expected 2 parameters for \"default\""))?
+                     .value();
+        let fallback = h.param(1)
+                        .ok_or_else(|| RenderError::new("Expected 2 parameters for \"default\""))?
+                        .value();
+        let chosen = match *value {
+            Json::Null => fallback,
+            Json::String(ref s) if s.is_empty() => fallback,
+            _ => value,
+        };
+        let rendered = match *chosen {
+            Json::String(ref s) => s.to_string(),
+            ref other => other.to_string(),
+        };
+        rc.writer.write_all(rendered.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static DEFAULT: DefaultHelper = DefaultHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_default_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("default", Box::new(DEFAULT));
+        let ctx = json!({"cfg": {"port": 6379, "bind": "", "name": "redis"}});
+        assert_eq!("6379",
+                   handlebars.template_render("{{default cfg.port 8080}}", &ctx)
+                             .unwrap());
+        assert_eq!("8080",
+                   handlebars.template_render("{{default cfg.missing 8080}}", &ctx)
+                             .unwrap());
+        assert_eq!("0.0.0.0",
+                   handlebars.template_render("{{default cfg.bind \"0.0.0.0\"}}", &ctx)
+                             .unwrap());
+        assert_eq!("redis",
+                   handlebars.template_render("{{default cfg.name \"app\"}}", &ctx)
+                             .unwrap());
+    }
+}
@@ -0,0 +1,68 @@
+use std::env;
+
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::super::RenderResult;
+
+/// The environment variable listing, separated by commas, which environment variables templates
+/// may read with `envVar`. Templates can't read any others, so that they can't leak the
+/// Supervisor's environment into configuration files.
+pub const ENV_VAR_ALLOW_LIST_ENVVAR: &str = "HAB_TEMPLATE_ENV_VARS";
+
+#[derive(Clone, Copy)]
+pub struct EnvVarHelper;
+
+impl HelperDef for EnvVarHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let name =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a string \
+                                                                              parameter for \
+                                                                              \"envVar\"")
+                                                        })?;
+        let allowed = env::var(ENV_VAR_ALLOW_LIST_ENVVAR).unwrap_or_default();
+        if !allowed.split(',').any(|allowed| allowed.trim() == name) {
+            return Err(RenderError::new(format!("\"envVar\" can't read {}; add it to {} to \
+                                                 allow it",
+                                                name, ENV_VAR_ALLOW_LIST_ENVVAR)));
+        }
+        let value = env::var(name).unwrap_or_default();
+        rc.writer.write_all(value.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static ENV_VAR: EnvVarHelper = EnvVarHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    crate::locked_env_var!(HAB_TEMPLATE_ENV_VARS, lock_allow_list);
+
+    #[test]
+    fn test_env_var_helper() {
+        let allow_list = lock_allow_list();
+        allow_list.set("HAB_TEST_ENV_VAR_HELPER_DATACENTER, HAB_TEST_ENV_VAR_HELPER_UNSET");
+        env::set_var("HAB_TEST_ENV_VAR_HELPER_DATACENTER", "us-west-2");
+        env::set_var("HAB_TEST_ENV_VAR_HELPER_HIDDEN", "hidden");
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("envVar", Box::new(ENV_VAR));
+        assert_eq!("us-west-2",
+                   handlebars.template_render("{{envVar \"HAB_TEST_ENV_VAR_HELPER_DATACENTER\"}}",
+                                              &json!({}))
+                             .unwrap());
+        assert_eq!("",
+                   handlebars.template_render("{{envVar \"HAB_TEST_ENV_VAR_HELPER_UNSET\"}}",
+                                              &json!({}))
+                             .unwrap());
+        assert!(handlebars.template_render("{{envVar \"HAB_TEST_ENV_VAR_HELPER_HIDDEN\"}}",
+                                           &json!({}))
+                          .is_err());
+    }
+}
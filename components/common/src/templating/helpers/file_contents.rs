@@ -0,0 +1,81 @@
+use std::{fs,
+          path::{Component,
+                 Path}};
+
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::super::RenderResult;
+
+/// Inserts the contents of a file in the service's package, given by its path relative to the
+/// package's directory, e.g. `config/ca.pem`.
+#[derive(Clone, Copy)]
+pub struct FileContentsHelper;
+
+impl HelperDef for FileContentsHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a string \
+                                                                              parameter for \
+                                                                              \"fileContents\"")
+                                                        })?;
+        let relative = Path::new(param);
+        if !relative.components().all(|c| {
+                                     match c {
+                                         Component::Normal(_) | Component::CurDir => true,
+                                         _ => false,
+                                     }
+                                 })
+        {
+            return Err(RenderError::new(format!("\"fileContents\" can only read files \
+                                                 inside the package, not {}",
+                                                param)));
+        }
+        let pkg_path = rc.context().data()["pkg"]["path"]
+                         .as_str()
+                         .map(|p| Path::new(p).join(relative))
+                         .ok_or_else(|| {
+                             RenderError::new("\"fileContents\" can only be used in the \
+                                               templates of a package")
+                         })?;
+        let contents = fs::read_to_string(&pkg_path).map_err(|e| {
+                           RenderError::new(format!("Can't read {} for \"fileContents\": {}",
+                                                    pkg_path.display(),
+                                                    e))
+                       })?;
+        rc.writer.write_all(contents.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static FILE_CONTENTS: FileContentsHelper = FileContentsHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_contents_helper() {
+        let pkg_dir = TempDir::new().unwrap();
+        fs::create_dir_all(pkg_dir.path().join("config")).unwrap();
+        fs::write(pkg_dir.path().join("config").join("ca.pem"), "CERTIFICATE\n").unwrap();
+        let ctx = json!({"pkg": {"path": pkg_dir.path()}});
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("fileContents", Box::new(FILE_CONTENTS));
+        assert_eq!("CERTIFICATE\n",
+                   handlebars.template_render("{{fileContents \"config/ca.pem\"}}", &ctx)
+                             .unwrap());
+        assert!(handlebars.template_render("{{fileContents \"config/missing.pem\"}}", &ctx)
+                          .is_err());
+        assert!(handlebars.template_render("{{fileContents \"../../etc/passwd\"}}", &ctx)
+                          .is_err());
+        assert!(handlebars.template_render("{{fileContents \"/etc/passwd\"}}", &ctx)
+                          .is_err());
+    }
+}
@@ -0,0 +1,63 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+use super::super::RenderResult;
+
+/// Indents every non-empty line of some text by a number of spaces, e.g. to nest the output of
+/// `toYaml` inside another YAML document.
+#[derive(Clone, Copy)]
+pub struct IndentHelper;
+
+impl HelperDef for IndentHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let text = match h.param(0).map(|v| v.value()) {
+            Some(Json::String(s)) => s.to_string(),
+            Some(Json::Null) | None => {
+                return Err(RenderError::new("Expected some text and a number of spaces for \
+                                             \"indent\""));
+            }
+            Some(other) => other.to_string(),
+        };
+        let spaces =
+            h.param(1).and_then(|v| v.value().as_u64()).ok_or_else(|| {
+                                                            RenderError::new("Expected some \
+                                                                              text and a number \
+                                                                              of spaces for \
+                                                                              \"indent\"")
+                                                        })?;
+        let prefix = " ".repeat(spaces as usize);
+        let indented: Vec<String> = text.split('\n')
+                                        .map(|line| {
+                                            if line.is_empty() {
+                                                line.to_string()
+                                            } else {
+                                                format!("{}{}", prefix, line)
+                                            }
+                                        })
+                                        .collect();
+        rc.writer.write_all(indented.join("\n").into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static INDENT: IndentHelper = IndentHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_indent_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("indent", Box::new(INDENT));
+        let ctx = json!({"cfg": {"cert": "-----BEGIN-----\nabc\n\n-----END-----\n"}});
+        let expected = "    -----BEGIN-----\n    abc\n\n    -----END-----\n";
+        assert_eq!(expected,
+                   handlebars.template_render("{{indent cfg.cert 4}}", &ctx)
+                             .unwrap());
+    }
+}
@@ -0,0 +1,120 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+pub enum Operation {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+impl Operation {
+    fn name(self) -> &'static str {
+        match self {
+            Operation::Add => "add",
+            Operation::Subtract => "subtract",
+            Operation::Multiply => "multiply",
+            Operation::Divide => "divide",
+            Operation::Modulo => "modulo",
+        }
+    }
+
+    /// The result, or `None` if it overflows or divides by zero.
+    fn apply(self, a: i64, b: i64) -> Option<i64> {
+        match self {
+            Operation::Add => a.checked_add(b),
+            Operation::Subtract => a.checked_sub(b),
+            Operation::Multiply => a.checked_mul(b),
+            Operation::Divide => a.checked_div(b),
+            Operation::Modulo => a.checked_rem(b),
+        }
+    }
+}
+
+/// Integer arithmetic on two parameters, which may be numbers or strings holding them.
+#[derive(Clone, Copy)]
+pub struct MathHelper(Operation);
+
+impl MathHelper {
+    fn param(&self, h: &Helper<'_>, index: usize) -> RenderResult<i64> {
+        let value = h.param(index).map(|v| v.value());
+        value.and_then(|v| v.as_i64())
+             .or_else(|| value.and_then(|v| v.as_str()).and_then(|s| s.trim().parse().ok()))
+             .ok_or_else(|| {
+                 RenderError::new(format!("Expected 2 integer parameters for \"{}\"",
+                                          self.0.name()))
+             })
+    }
+}
+
+impl HelperDef for MathHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let a = self.param(h, 0)?;
+        let b = self.param(h, 1)?;
+        let result = self.0.apply(a, b).ok_or_else(|| {
+                                           RenderError::new(format!("Can't {} {} and {}",
+                                                                    self.0.name(),
+                                                                    a,
+                                                                    b))
+                                       })?;
+        rc.writer.write_all(result.to_string().into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static ADD: MathHelper = MathHelper(Operation::Add);
+pub static SUBTRACT: MathHelper = MathHelper(Operation::Subtract);
+pub static MULTIPLY: MathHelper = MathHelper(Operation::Multiply);
+pub static DIVIDE: MathHelper = MathHelper(Operation::Divide);
+pub static MODULO: MathHelper = MathHelper(Operation::Modulo);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn handlebars() -> Handlebars {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("add", Box::new(ADD));
+        handlebars.register_helper("subtract", Box::new(SUBTRACT));
+        handlebars.register_helper("multiply", Box::new(MULTIPLY));
+        handlebars.register_helper("divide", Box::new(DIVIDE));
+        handlebars.register_helper("modulo", Box::new(MODULO));
+        handlebars
+    }
+
+    #[test]
+    fn test_math_helpers() {
+        let handlebars = handlebars();
+        let ctx = json!({"cfg": {"port": 8000, "workers": "4"}});
+        assert_eq!("8001",
+                   handlebars.template_render("{{add cfg.port 1}}", &ctx).unwrap());
+        assert_eq!("7996",
+                   handlebars.template_render("{{subtract cfg.port cfg.workers}}", &ctx)
+                             .unwrap());
+        assert_eq!("16",
+                   handlebars.template_render("{{multiply cfg.workers 4}}", &ctx)
+                             .unwrap());
+        assert_eq!("2000",
+                   handlebars.template_render("{{divide cfg.port cfg.workers}}", &ctx)
+                             .unwrap());
+        assert_eq!("3",
+                   handlebars.template_render("{{modulo 7 4}}", &ctx).unwrap());
+    }
+
+    #[test]
+    fn test_math_helpers_errors() {
+        let handlebars = handlebars();
+        assert!(handlebars.template_render("{{divide 1 0}}", &json!({}))
+                          .is_err());
+        assert!(handlebars.template_render("{{add 1 \"one\"}}", &json!({}))
+                          .is_err());
+        assert!(handlebars.template_render("{{add 1}}", &json!({})).is_err());
+    }
+}
@@ -1,5 +1,17 @@
+mod base64_decode;
+mod base64_encode;
+mod default;
 mod each_alive;
+mod env_var;
+mod file_contents;
+mod indent;
+mod math;
 mod pkg_path_for;
+mod regex_replace;
+mod secret;
+mod sha256;
+mod sort_by;
+mod split;
 mod str_concat;
 mod str_join;
 mod str_replace;
@@ -9,12 +21,38 @@ mod to_toml;
 mod to_uppercase;
 mod to_yaml;
 
+use std::collections::BTreeMap;
+
+use handlebars::{Handlebars,
+                 Helper,
+                 RenderContext,
+                 RenderError,
+                 Renderable};
 use serde::Serialize;
 use serde_json::{self,
                  Value as Json};
 
-pub use self::{each_alive::EACH_ALIVE,
+use super::RenderResult;
+
+pub use self::{base64_decode::BASE64_DECODE,
+               base64_encode::BASE64_ENCODE,
+               default::DEFAULT,
+               each_alive::EACH_ALIVE,
+               env_var::{ENV_VAR,
+                         ENV_VAR_ALLOW_LIST_ENVVAR},
+               file_contents::FILE_CONTENTS,
+               indent::INDENT,
+               math::{ADD,
+                      DIVIDE,
+                      MODULO,
+                      MULTIPLY,
+                      SUBTRACT},
                pkg_path_for::PKG_PATH_FOR,
+               regex_replace::REGEX_REPLACE,
+               secret::SECRET,
+               sha256::SHA256,
+               sort_by::SORT_BY,
+               split::SPLIT,
                str_concat::STR_CONCAT,
                str_join::STR_JOIN,
                str_replace::STR_REPLACE,
@@ -43,6 +81,48 @@ impl JsonTruthy for Json {
     }
 }
 
+/// Renders a block helper's template once for each of the given items, which it refers to by its
+/// block parameter, e.g. `{{#split cfg.hosts "," as |host|}}`. The inverse template, if there is
+/// one, is rendered when there are no items.
+fn render_each(name: &str,
+               items: &[Json],
+               h: &Helper<'_>,
+               r: &Handlebars,
+               rc: &mut RenderContext<'_>)
+               -> RenderResult<()> {
+    let template = match h.template() {
+        Some(template) => template,
+        None => return Ok(()),
+    };
+    let block_param = h.block_param().ok_or_else(|| {
+                                         RenderError::new(format!("\"{}\" needs a block \
+                                                                   parameter, e.g. `as |item|`",
+                                                                  name))
+                                     })?;
+    if items.is_empty() {
+        if let Some(else_template) = h.inverse() {
+            else_template.render(r, rc)?;
+        }
+        return Ok(());
+    }
+
+    rc.promote_local_vars();
+    for (i, item) in items.iter().enumerate() {
+        let mut local_rc = rc.derive();
+        local_rc.set_local_var("@first".to_string(), to_json(&(i == 0)));
+        local_rc.set_local_var("@last".to_string(), to_json(&(i == items.len() - 1)));
+        local_rc.set_local_var("@index".to_string(), to_json(&i));
+
+        let mut map = BTreeMap::new();
+        map.insert(block_param.to_string(), item.clone());
+        local_rc.push_block_context(&map)?;
+        template.render(r, &mut local_rc)?;
+        local_rc.pop_block_context();
+    }
+    rc.demote_local_vars();
+    Ok(())
+}
+
 /// Helper which will serialize to Json the given reference or return `Json::Null`
 fn to_json<T>(src: &T) -> Json
     where T: Serialize
@@ -0,0 +1,63 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use regex::Regex;
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+pub struct RegexReplaceHelper;
+
+impl HelperDef for RegexReplaceHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected 3 string \
+                                                                              parameters for \
+                                                                              \"regexReplace\"")
+                                                        })?;
+        let pattern =
+            h.param(1).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected 3 string \
+                                                                              parameters for \
+                                                                              \"regexReplace\"")
+                                                        })?;
+        let replacement =
+            h.param(2).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected 3 string \
+                                                                              parameters for \
+                                                                              \"regexReplace\"")
+                                                        })?;
+        let re = Regex::new(pattern).map_err(|e| {
+                                        RenderError::new(format!("Invalid pattern for \
+                                                                  \"regexReplace\": {}",
+                                                                 e))
+                                    })?;
+        rc.writer
+          .write_all(re.replace_all(param, replacement).into_owned().into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static REGEX_REPLACE: RegexReplaceHelper = RegexReplaceHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_regex_replace_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("regexReplace", Box::new(REGEX_REPLACE));
+        let expected = "db-2.example.com:5432";
+        assert_eq!(expected,
+                   handlebars.template_render("{{regexReplace \"db-2.example.com\" \
+                                               \"^(.*)$\" \"${1}:5432\"}}",
+                                              &json!({}))
+                             .unwrap());
+        assert!(handlebars.template_render("{{regexReplace \"a\" \"(\" \"b\"}}", &json!({}))
+                          .is_err());
+    }
+}
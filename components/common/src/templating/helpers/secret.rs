@@ -0,0 +1,82 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::super::{secrets,
+                   RenderResult};
+
+#[derive(Clone, Copy)]
+pub struct SecretHelper;
+
+impl HelperDef for SecretHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let name = h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                  RenderError::new("Expected a secret name for \"secret\"")
+                              })?;
+        // The complete name, with any application and environment, as the Supervisor uses it
+        // to redact and forget the values resolved here.
+        let service_group = match rc.context().data()["svc"]["service_group"].as_str() {
+            Some(service_group) => service_group.to_string(),
+            None => {
+                return Err(RenderError::new("\"secret\" can only be used in the templates \
+                                             of a running service"));
+            }
+        };
+        // The error names the secret, never its value.
+        let value = secrets::resolve(&service_group, name).map_err(|e| {
+                                                              RenderError::new(e.to_string())
+                                                          })?;
+        rc.writer.write_all(value.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static SECRET: SecretHelper = SecretHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{error::Result,
+                templating::secrets::SecretProvider};
+
+    struct MockProvider;
+
+    impl SecretProvider for MockProvider {
+        fn secret(&self, service_group: &str, name: &str) -> Result<Option<String>> {
+            match (service_group, name) {
+                ("prod.web#redis.default@acme", "db/password") => {
+                    Ok(Some("hunter2".to_string()))
+                }
+                _ => Ok(None),
+            }
+        }
+    }
+
+    #[test]
+    fn test_secret_helper() {
+        secrets::set_providers(vec![Box::new(MockProvider)]);
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("secret", Box::new(SECRET));
+        let ctx = json!({"svc": {"service": "redis",
+                                 "group": "default",
+                                 "org": "acme",
+                                 "service_group": "prod.web#redis.default@acme"}});
+
+        assert_eq!("password = hunter2",
+                   handlebars.template_render("password = {{secret \"db/password\"}}", &ctx)
+                             .unwrap());
+        let err = handlebars.template_render("{{secret \"db/user\"}}", &ctx)
+                            .unwrap_err()
+                            .to_string();
+        assert!(err.contains("db/user"));
+        assert!(handlebars.template_render("{{secret \"db/password\"}}", &json!({}))
+                          .is_err());
+        // Values are redacted under the same complete service group they were resolved for
+        assert_eq!(secrets::redact("prod.web#redis.default@acme", "password = hunter2"),
+                   format!("password = {}", secrets::REDACTED));
+        assert_eq!(secrets::redact("redis.default@acme", "password = hunter2"),
+                   "password = hunter2");
+    }
+}
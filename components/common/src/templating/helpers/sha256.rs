@@ -0,0 +1,42 @@
+use crate::hcore::crypto::hash;
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+pub struct Sha256Helper;
+
+impl HelperDef for Sha256Helper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a string \
+                                                                              parameter for \
+                                                                              \"sha256\"")
+                                                        })?;
+        rc.writer
+          .write_all(hash::sha256_string(param).into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static SHA256: Sha256Helper = Sha256Helper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sha256_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("sha256", Box::new(SHA256));
+        let expected = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert_eq!(expected,
+                   handlebars.template_render("{{sha256 \"hello\"}}", &json!({}))
+                             .unwrap());
+    }
+}
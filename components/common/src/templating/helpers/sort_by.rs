@@ -0,0 +1,85 @@
+use std::cmp::Ordering;
+
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+use super::{super::RenderResult,
+            render_each};
+
+/// Iterates over a list, such as a service group's members, in the order of one of its items'
+/// fields. The field is given as a dotted path, e.g. `sys.ip`.
+#[derive(Clone, Copy)]
+pub struct SortByHelper;
+
+impl HelperDef for SortByHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let list =
+            h.param(0).and_then(|v| v.value().as_array()).ok_or_else(|| {
+                                                              RenderError::new("Expected a list \
+                                                                                and a field for \
+                                                                                \"sortBy\"")
+                                                          })?;
+        let field =
+            h.param(1).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a list \
+                                                                              and a field for \
+                                                                              \"sortBy\"")
+                                                        })?;
+        let mut sorted = list.clone();
+        sorted.sort_by(|a, b| compare(lookup(a, field), lookup(b, field)));
+        render_each("sortBy", &sorted, h, r, rc)
+    }
+}
+
+fn lookup<'a>(item: &'a Json, field: &str) -> &'a Json {
+    field.split('.')
+         .fold(item, |value, key| value.get(key).unwrap_or(&Json::Null))
+}
+
+/// Numbers compare by value and everything else by its text, with missing values first.
+fn compare(a: &Json, b: &Json) -> Ordering {
+    match (a, b) {
+        (Json::Number(a), Json::Number(b)) => {
+            a.as_f64()
+             .partial_cmp(&b.as_f64())
+             .unwrap_or(Ordering::Equal)
+        }
+        (Json::Null, Json::Null) => Ordering::Equal,
+        (Json::Null, _) => Ordering::Less,
+        (_, Json::Null) => Ordering::Greater,
+        (Json::String(a), Json::String(b)) => a.cmp(b),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+pub static SORT_BY: SortByHelper = SortByHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sort_by_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("sortBy", Box::new(SORT_BY));
+        let ctx = json!({"svc": {"members": [
+            {"member_id": "c", "sys": {"ip": "10.0.0.3"}, "pkg": {"release": 3}},
+            {"member_id": "a", "sys": {"ip": "10.0.0.1"}, "pkg": {"release": 20}},
+            {"member_id": "b", "sys": {"ip": "10.0.0.2"}},
+        ]}});
+        assert_eq!("10.0.0.1 10.0.0.2 10.0.0.3 ",
+                   handlebars.template_render("{{#sortBy svc.members \"sys.ip\" as |m|}}\
+                                               {{m.sys.ip}} {{/sortBy}}",
+                                              &ctx)
+                             .unwrap());
+        assert_eq!("b c a ",
+                   handlebars.template_render("{{#sortBy svc.members \"pkg.release\" as |m|}}\
+                                               {{m.member_id}} {{/sortBy}}",
+                                              &ctx)
+                             .unwrap());
+    }
+}
@@ -0,0 +1,62 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+use super::{super::RenderResult,
+            render_each};
+
+#[derive(Clone, Copy)]
+pub struct SplitHelper;
+
+impl HelperDef for SplitHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected 2 string \
+                                                                              parameters for \
+                                                                              \"split\"")
+                                                        })?;
+        let separator =
+            h.param(1).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected 2 string \
+                                                                              parameters for \
+                                                                              \"split\"")
+                                                        })?;
+        let parts: Vec<Json> = if param.is_empty() {
+            Vec::new()
+        } else {
+            param.split(separator)
+                 .map(|part| Json::String(part.to_string()))
+                 .collect()
+        };
+        render_each("split", &parts, h, r, rc)
+    }
+}
+
+pub static SPLIT: SplitHelper = SplitHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("split", Box::new(SPLIT));
+        let ctx = json!({"cfg": {"hosts": "a.example.com,b.example.com"}});
+        let expected = "server a.example.com;\nserver b.example.com;\n";
+        assert_eq!(expected,
+                   handlebars.template_render("{{#split cfg.hosts \",\" as |host|}}server \
+                                               {{host}};\n{{/split}}",
+                                              &ctx)
+                             .unwrap());
+        assert_eq!("none",
+                   handlebars.template_render("{{#split \"\" \",\" as |host|}}{{host}}\
+                                               {{else}}none{{/split}}",
+                                              &ctx)
+                             .unwrap());
+    }
+}
@@ -116,15 +116,23 @@ pub trait Hook: fmt::Debug + Sized + Send {
 
     fn new(package_name: &str, render_pair: RenderPair) -> Self;
 
+    /// Render a hook without writing it, returning the path in the destination service directory
+    /// that it's compiled to along with its content.
+    fn render<T>(&self, ctx: &T) -> Result<(PathBuf, String)>
+        where T: Serialize
+    {
+        let content = self.renderer().render(Self::file_name(), ctx)?;
+        // We make sure we don't use a deprecated file name
+        Ok((self.path().with_file_name(Self::file_name()), content))
+    }
+
     /// Compile a hook into its destination service directory.
     ///
     /// Returns `true` if the hook has changed.
     fn compile<T>(&self, service_group: &str, ctx: &T) -> Result<bool>
         where T: Serialize
     {
-        let content = self.renderer().render(Self::file_name(), ctx)?;
-        // We make sure we don't use a deprecated file name
-        let path = self.path().with_file_name(Self::file_name());
+        let (path, content) = self.render(ctx)?;
         if write_hook(&content, &path)? {
             outputln!(preamble service_group,
                       "Modified hook content in {}",
@@ -185,6 +193,20 @@ pub trait Hook: fmt::Debug + Sized + Send {
               svc_encrypted_password: Option<T>)
               -> Self::ExitValue
         where T: ToString
+    {
+        self.run_and_watch(service_group, pkg, svc_encrypted_password, |_| ())
+    }
+
+    /// Run a compiled hook, passing the ID of its process to `started` once it's running so
+    /// that it can be killed if it runs for too long.
+    fn run_and_watch<T, F>(&self,
+                           service_group: &str,
+                           pkg: &Pkg,
+                           svc_encrypted_password: Option<T>,
+                           started: F)
+                           -> Self::ExitValue
+        where T: ToString,
+              F: FnOnce(u32)
     {
         let mut child = match Self::exec(self.path(), &pkg, svc_encrypted_password) {
             Ok(child) => child,
@@ -194,6 +216,7 @@ pub trait Hook: fmt::Debug + Sized + Send {
                 return Self::ExitValue::default();
             }
         };
+        started(child.id());
         let mut hook_output = HookOutput::new(self.stdout_log_path(), self.stderr_log_path());
         hook_output.stream_output::<Self>(service_group, &mut child);
         match child.wait() {
@@ -287,6 +310,8 @@ pub trait Hook: fmt::Debug + Sized + Send {
 
     fn renderer(&self) -> &TemplateRenderer;
 
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer;
+
     fn stdout_log_path(&self) -> &Path;
 
     fn stderr_log_path(&self) -> &Path;
@@ -358,6 +383,8 @@ impl Hook for InstallHook {
 
     fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
 
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer { &mut self.render_pair.renderer }
+
     fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
 
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
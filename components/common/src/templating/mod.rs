@@ -3,17 +3,23 @@ mod context;
 pub mod helpers;
 pub mod hooks;
 pub mod package;
+pub mod secrets;
+pub mod strict;
 pub mod test_helpers;
 
-use std::{fmt,
+use std::{collections::HashMap,
+          fmt,
+          io,
           ops::{Deref,
                 DerefMut},
+          path::Path,
           result};
 
 use regex::Regex;
 
 use handlebars::{Handlebars,
                  RenderError,
+                 TemplateError,
                  TemplateFileError};
 use serde::Serialize;
 use serde_json;
@@ -25,7 +31,8 @@ use crate::{error::{Error,
             templating::hooks::{Hook,
                                 InstallHook}};
 
-pub use self::context::RenderContext;
+pub use self::{context::RenderContext,
+               strict::MissingVariable};
 
 // This is specifically for finding syntax violations to object access in handlebars templates.
 // This should eventually be removed when we have upgraded the handlebars library and provided
@@ -36,6 +43,10 @@ lazy_static! {
         Regex::new(r"(\{\{[^}]+[^.])(\[)").expect("Failed to compile template deprecation regex");
 }
 
+/// The directory in a package's `config` directory whose files are registered as partials rather
+/// than rendered as configuration files.
+pub const PARTIALS_DIR: &str = "_partials";
+
 /// A convenience method that compiles a package's install hook
 /// and any configuration templates in its config_install folder
 pub fn compile_for_package_install(package: &PackageInstall) -> Result<()> {
@@ -48,10 +59,12 @@ pub fn compile_for_package_install(package: &PackageInstall) -> Result<()> {
     let cfg_renderer = config::CfgRenderer::new(pkg.path.join("config_install"))?;
     cfg_renderer.compile(&pkg.name, &pkg, &pkg.svc_config_install_path, &ctx)?;
 
-    if let Some(ref hook) = InstallHook::load(&pkg.name,
+    if let Some(mut hook) = InstallHook::load(&pkg.name,
                                               &fs::svc_hooks_path(&pkg.name),
                                               &package.installed_path.join("hooks"))
     {
+        hook.renderer_mut()
+            .register_partials(pkg.path.join("config").join(PARTIALS_DIR))?;
         hook.compile(&pkg.name, &ctx)?;
     };
 
@@ -60,16 +73,39 @@ pub fn compile_for_package_install(package: &PackageInstall) -> Result<()> {
 
 pub type RenderResult<T> = result::Result<T, RenderError>;
 
-pub struct TemplateRenderer(Handlebars);
+pub struct TemplateRenderer {
+    handlebars: Handlebars,
+    /// The source of each registered template, for strict mode to look for missing variables.
+    sources:    HashMap<String, String>,
+    /// The source of each registered partial, by name.
+    partials:   HashMap<String, String>,
+    strict:     bool,
+}
 
 impl TemplateRenderer {
     pub fn new() -> Self {
         let mut handlebars = Handlebars::new();
+        handlebars.register_helper("add", Box::new(helpers::ADD));
+        handlebars.register_helper("base64Decode", Box::new(helpers::BASE64_DECODE));
+        handlebars.register_helper("base64Encode", Box::new(helpers::BASE64_ENCODE));
+        handlebars.register_helper("default", Box::new(helpers::DEFAULT));
+        handlebars.register_helper("divide", Box::new(helpers::DIVIDE));
         handlebars.register_helper("eachAlive", Box::new(helpers::EACH_ALIVE));
+        handlebars.register_helper("envVar", Box::new(helpers::ENV_VAR));
+        handlebars.register_helper("fileContents", Box::new(helpers::FILE_CONTENTS));
+        handlebars.register_helper("indent", Box::new(helpers::INDENT));
+        handlebars.register_helper("modulo", Box::new(helpers::MODULO));
+        handlebars.register_helper("multiply", Box::new(helpers::MULTIPLY));
         handlebars.register_helper("pkgPathFor", Box::new(helpers::PKG_PATH_FOR));
+        handlebars.register_helper("regexReplace", Box::new(helpers::REGEX_REPLACE));
+        handlebars.register_helper("secret", Box::new(helpers::SECRET));
+        handlebars.register_helper("sha256", Box::new(helpers::SHA256));
+        handlebars.register_helper("sortBy", Box::new(helpers::SORT_BY));
+        handlebars.register_helper("split", Box::new(helpers::SPLIT));
         handlebars.register_helper("strConcat", Box::new(helpers::STR_CONCAT));
         handlebars.register_helper("strJoin", Box::new(helpers::STR_JOIN));
         handlebars.register_helper("strReplace", Box::new(helpers::STR_REPLACE));
+        handlebars.register_helper("subtract", Box::new(helpers::SUBTRACT));
         handlebars.register_helper("toUppercase", Box::new(helpers::TO_UPPERCASE));
         handlebars.register_helper("toLowercase", Box::new(helpers::TO_LOWERCASE));
         handlebars.register_helper("toJson", Box::new(helpers::TO_JSON));
@@ -77,19 +113,101 @@ impl TemplateRenderer {
         handlebars.register_helper("toYaml", Box::new(helpers::TO_YAML));
 
         handlebars.register_escape_fn(never_escape);
-        TemplateRenderer(handlebars)
+        TemplateRenderer { handlebars,
+                           sources: HashMap::new(),
+                           partials: HashMap::new(),
+                           strict: false }
     }
 
+    /// In strict mode, rendering a template that refers to variables its context doesn't have
+    /// fails instead of rendering them as empty strings. See the `strict` module.
+    pub fn set_strict(&mut self, strict: bool) { self.strict = strict; }
+
+    pub fn is_strict(&self) -> bool { self.strict }
+
     pub fn render<T>(&self, template: &str, ctx: &T) -> Result<String>
         where T: Serialize
     {
         let raw = serde_json::to_value(ctx).map_err(Error::RenderContextSerialization)?;
         debug!("Rendering template with context, {}, {}", template, raw);
-        self.0
+        if self.strict {
+            let missing = self.missing_variables_in(template, &raw);
+            if !missing.is_empty() {
+                return Err(Error::TemplateMissingVariables(template.to_string(),
+                                                           missing.iter()
+                                                                  .map(ToString::to_string)
+                                                                  .collect()));
+            }
+        }
+        self.handlebars
             .render(template, &raw)
             .map_err(|e| Error::TemplateRenderError(format!("{}", e)))
     }
 
+    /// Every variable the registered template refers to that the context doesn't have, whether
+    /// or not the renderer is strict.
+    pub fn missing_variables<T>(&self, template: &str, ctx: &T) -> Result<Vec<MissingVariable>>
+        where T: Serialize
+    {
+        let raw = serde_json::to_value(ctx).map_err(Error::RenderContextSerialization)?;
+        Ok(self.missing_variables_in(template, &raw))
+    }
+
+    fn missing_variables_in(&self,
+                            template: &str,
+                            ctx: &serde_json::Value)
+                            -> Vec<MissingVariable> {
+        self.sources
+            .get(template)
+            .map(|source| strict::missing_variables(source, &self.partials, ctx))
+            .unwrap_or_default()
+    }
+
+    pub fn register_template_string<S>(&mut self,
+                                       name: &str,
+                                       source: S)
+                                       -> result::Result<(), TemplateError>
+        where S: AsRef<str>
+    {
+        self.handlebars.register_template_string(name, source.as_ref())?;
+        self.sources
+            .insert(name.to_string(), source.as_ref().to_string());
+        Ok(())
+    }
+
+    /// Registers every file directly in `dir` as a partial named after the file without its
+    /// extension, so `dir/upstreams.conf` can be included with `{{> upstreams}}`. A missing
+    /// `dir` has no partials.
+    pub fn register_partials<P>(&mut self, dir: P) -> Result<()>
+        where P: AsRef<Path>
+    {
+        let entries = match std::fs::read_dir(dir.as_ref()) {
+            Ok(entries) => entries,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(Error::IO(e)),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+            let name = match path.file_stem() {
+                Some(stem) => stem.to_string_lossy().into_owned(),
+                None => continue,
+            };
+            debug!("Registering partial {} from {}", name, path.display());
+            let source = std::fs::read_to_string(&path)?;
+            self.handlebars
+                .register_template_string(&name, &source)
+                .map_err(TemplateFileError::TemplateError)?;
+            self.partials.insert(name, source);
+        }
+        Ok(())
+    }
+
+    /// Whether `name` was registered as a partial rather than a template.
+    pub fn is_partial(&self, name: &str) -> bool { self.partials.contains_key(name) }
+
     // This method is only implemented so we can intercept the call to Handlebars and display
     // a deprecation message to users. More information here https://github.com/habitat-sh/habitat/issues/6323.
     // When Handlebars is upgraded and users have had sufficient time to update their templates this
@@ -127,7 +245,7 @@ impl TemplateRenderer {
                 });
         }
 
-        self.0.register_template_string(name, template_string)?;
+        self.register_template_string(name, template_string)?;
         Ok(())
     }
 }
@@ -141,11 +259,11 @@ impl fmt::Debug for TemplateRenderer {
 impl Deref for TemplateRenderer {
     type Target = Handlebars;
 
-    fn deref(&self) -> &Handlebars { &self.0 }
+    fn deref(&self) -> &Handlebars { &self.handlebars }
 }
 
 impl DerefMut for TemplateRenderer {
-    fn deref_mut(&mut self) -> &mut Handlebars { &mut self.0 }
+    fn deref_mut(&mut self) -> &mut Handlebars { &mut self.handlebars }
 }
 
 /// Disables HTML escaping which is enabled by default in Handlebars.
@@ -0,0 +1,334 @@
+//! Where the values of the `secret` template helper come from.
+//!
+//! Templates refer to secrets by name, e.g. `{{secret "db/password"}}`, and the Supervisor asks
+//! each of its providers in turn for that name until one of them has it. Resolved values only
+//! ever end up in the rendered files: anything else that shows a service's rendered output, like
+//! a dry run's diffs, should pass it through `redact` first.
+//!
+//! The values that `redact` knows about are kept per service group, and only for as long as
+//! they may still be in its rendered files: the Supervisor calls `rendered` each time it writes
+//! a service's files, and `forget` once the service is gone.
+
+use std::{collections::{HashMap,
+                        HashSet},
+          fs,
+          io::{self,
+               Read},
+          path::PathBuf,
+          sync::RwLock,
+          time::Duration};
+
+use habitat_http_client::ApiClient;
+use hyper::status::StatusCode;
+use url::{percent_encoding::{utf8_percent_encode,
+                             PATH_SEGMENT_ENCODE_SET},
+          Url};
+
+use crate::{error::{Error,
+                    Result},
+            hcore::crypto::{keys::{box_key_pair::WrappedSealedBox,
+                                   parse_name_with_rev},
+                            BoxKeyPair}};
+
+/// What secret values are replaced with by `redact`.
+pub const REDACTED: &str = "<redacted>";
+
+/// How long the HTTP provider waits for its server.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+lazy_static! {
+    static ref PROVIDERS: RwLock<Vec<Box<dyn SecretProvider>>> = RwLock::new(Vec::new());
+    static ref RESOLVED: RwLock<HashMap<String, Resolved>> = RwLock::new(HashMap::new());
+}
+
+/// The secret values a service group's templates have resolved.
+#[derive(Debug, Default)]
+struct Resolved {
+    /// Those in the files that were rendered last.
+    written:   HashSet<String>,
+    /// Those resolved since, which will be in the next files rendered.
+    rendering: HashSet<String>,
+}
+
+/// A source of secret values.
+pub trait SecretProvider: Send + Sync {
+    /// The value of a service group's secret, or `None` if this provider doesn't have it.
+    fn secret(&self, service_group: &str, name: &str) -> Result<Option<String>>;
+}
+
+/// Replaces the providers that secrets are resolved from. They're asked in the given order.
+pub fn set_providers(providers: Vec<Box<dyn SecretProvider>>) {
+    *PROVIDERS.write().expect("Secret providers lock is poisoned") = providers;
+}
+
+/// The value of a service group's secret, from the first provider that has it.
+pub fn resolve(service_group: &str, name: &str) -> Result<String> {
+    if !is_valid_name(name) {
+        return Err(Error::SecretInvalidName(name.to_string()));
+    }
+    for provider in PROVIDERS.read()
+                             .expect("Secret providers lock is poisoned")
+                             .iter()
+    {
+        if let Some(value) = provider.secret(service_group, name)? {
+            remember(service_group, &value);
+            return Ok(value);
+        }
+    }
+    Err(Error::SecretNotFound(name.to_string(), service_group.to_string()))
+}
+
+/// Records that a service group's templates resolved a value, so that it gets redacted.
+fn remember(service_group: &str, value: &str) {
+    if !value.is_empty() {
+        RESOLVED.write()
+                .expect("Resolved secrets lock is poisoned")
+                .entry(service_group.to_string())
+                .or_insert_with(Resolved::default)
+                .rendering
+                .insert(value.to_string());
+    }
+}
+
+/// Records that a service group's files have been rendered with the values resolved since they
+/// were last rendered, so that older values no longer need to be redacted.
+pub fn rendered(service_group: &str) {
+    if let Some(resolved) = RESOLVED.write()
+                                    .expect("Resolved secrets lock is poisoned")
+                                    .get_mut(service_group)
+    {
+        resolved.written = resolved.rendering.drain().collect();
+    }
+}
+
+/// Drops the values a service group has resolved, once it no longer runs here.
+pub fn forget(service_group: &str) {
+    RESOLVED.write()
+            .expect("Resolved secrets lock is poisoned")
+            .remove(service_group);
+}
+
+/// The given text, which was rendered for a service group, with every secret value that could be
+/// in its files replaced by `REDACTED`.
+pub fn redact(service_group: &str, text: &str) -> String {
+    let resolved = RESOLVED.read().expect("Resolved secrets lock is poisoned");
+    let resolved = match resolved.get(service_group) {
+        Some(resolved) => resolved,
+        None => return text.to_string(),
+    };
+    // Longer values first, so that a secret containing another is replaced as a whole.
+    let mut values: Vec<&String> = resolved.written.union(&resolved.rendering).collect();
+    values.sort_by(|a, b| b.len().cmp(&a.len()));
+    values.into_iter()
+          .fold(text.to_string(), |text, value| text.replace(value.as_str(), REDACTED))
+}
+
+/// Names are relative paths made of letters, digits, `-`, `_` and `.`, e.g. `db/password`.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+    && name.split('/').all(|part| {
+                           !part.is_empty()
+                           && part != "."
+                           && part != ".."
+                           && part.chars()
+                                  .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+                       })
+}
+
+/// Secrets kept on disk as `<root>/<service_group>/<name>`, each encrypted for the service
+/// group's key, like the files and configuration sent with `hab file upload` and
+/// `hab config apply`.
+pub struct FileSecretProvider {
+    root:           PathBuf,
+    cache_key_path: PathBuf,
+}
+
+impl FileSecretProvider {
+    pub fn new<P, Q>(root: P, cache_key_path: Q) -> Self
+        where P: Into<PathBuf>,
+              Q: Into<PathBuf>
+    {
+        FileSecretProvider { root:           root.into(),
+                             cache_key_path: cache_key_path.into(), }
+    }
+
+    fn path_for(&self, service_group: &str, name: &str) -> PathBuf {
+        name.split('/')
+            .fold(self.root.join(service_group), |path, part| path.join(part))
+    }
+}
+
+impl SecretProvider for FileSecretProvider {
+    fn secret(&self, service_group: &str, name: &str) -> Result<Option<String>> {
+        let path = self.path_for(service_group, name);
+        let payload = match fs::read_to_string(&path) {
+            Ok(payload) => payload,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::IO(err)),
+        };
+        let payload = WrappedSealedBox::from(payload);
+        // Only a secret sealed for this service group's key may be used by it, so that one
+        // copied from another service group's directory can't be read here.
+        let metadata = BoxKeyPair::secret_metadata(&payload)?;
+        let (key_name, _) = parse_name_with_rev(metadata.receiver.unwrap_or(metadata.sender))?;
+        if key_name != service_group {
+            return Err(Error::SecretProvider(format!("{} isn't encrypted for {}",
+                                                     path.display(),
+                                                     service_group)));
+        }
+        let value = BoxKeyPair::decrypt_with_path(&payload, &self.cache_key_path)?;
+        Ok(Some(String::from_utf8(value)?))
+    }
+}
+
+/// Secrets served over HTTPS: a `GET <url>/<service_group>/<name>` answers with the value as its
+/// body, or with a 404 if there's no such secret. Templates are rendered while the Supervisor
+/// waits, so a server that doesn't answer within `HTTP_TIMEOUT` is given up on.
+pub struct HttpSecretProvider {
+    url:    String,
+    client: ApiClient,
+}
+
+impl HttpSecretProvider {
+    /// A provider for the server at `url`, which must be an `https` URL unless `allow_http` is
+    /// set, as secrets would otherwise be sent in the clear.
+    pub fn new<S>(url: S,
+                  allow_http: bool,
+                  product: &str,
+                  version: &str)
+                  -> Result<Self>
+        where S: AsRef<str>
+    {
+        let url = url.as_ref().trim_end_matches('/');
+        let parsed =
+            Url::parse(url).map_err(|e| Error::SecretProvider(format!("{}: {}", url, e)))?;
+        match parsed.scheme() {
+            "https" => {}
+            "http" if allow_http => {}
+            _ => return Err(Error::InsecureSecretsUrl(url.to_string())),
+        }
+        let client =
+            ApiClient::with_timeout(parsed, product, version, None, HTTP_TIMEOUT).map_err(|e| {
+                Error::SecretProvider(format!("{}: {}", url, e))
+            })?;
+        Ok(HttpSecretProvider { url: url.to_string(),
+                                client })
+    }
+}
+
+impl SecretProvider for HttpSecretProvider {
+    fn secret(&self, service_group: &str, name: &str) -> Result<Option<String>> {
+        // A service group with an application and environment has a `#` in its name.
+        let path = format!("{}/{}",
+                           utf8_percent_encode(service_group, PATH_SEGMENT_ENCODE_SET),
+                           name);
+        let url = format!("{}/{}", self.url, path);
+        let mut res = self.client
+                          .get(&path)
+                          .send()
+                          .map_err(|e| Error::SecretProvider(format!("{}: {}", url, e)))?;
+        match res.status {
+            StatusCode::Ok => {
+                let mut value = String::new();
+                res.read_to_string(&mut value)?;
+                Ok(Some(value))
+            }
+            StatusCode::NotFound => Ok(None),
+            status => Err(Error::SecretProvider(format!("{}: {}", url, status))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::Write,
+              net::TcpListener,
+              thread};
+    use tempfile::TempDir;
+
+    #[test]
+    fn names_are_relative_paths() {
+        assert!(is_valid_name("db/password"));
+        assert!(is_valid_name("api.token"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("/etc/passwd"));
+        assert!(!is_valid_name("db/../../keys"));
+        assert!(!is_valid_name("db//password"));
+    }
+
+    #[test]
+    fn redaction_is_scoped_to_the_service_group_and_its_latest_files() {
+        remember("redact-a.default", "redact-a.default-one");
+        rendered("redact-a.default");
+        remember("redact-a.default", "redact-a.default-two");
+        assert_eq!(redact("redact-a.default", "redact-a.default-one redact-a.default-two"),
+                   format!("{} {}", REDACTED, REDACTED));
+        // Another service group's output isn't touched by this one's values.
+        assert_eq!(redact("redact-b.default", "redact-a.default-one"),
+                   "redact-a.default-one");
+
+        // Once the files are rendered again, values only the older files had are dropped.
+        rendered("redact-a.default");
+        assert_eq!(redact("redact-a.default", "redact-a.default-one redact-a.default-two"),
+                   format!("redact-a.default-one {}", REDACTED));
+        forget("redact-a.default");
+        assert_eq!(redact("redact-a.default", "redact-a.default-two"),
+                   "redact-a.default-two");
+    }
+
+    #[test]
+    fn file_provider_decrypts_secrets_sealed_for_the_service_group() {
+        let tmpdir = TempDir::new().unwrap();
+        let keys = tmpdir.path().join("keys");
+        fs::create_dir_all(&keys).unwrap();
+        let pair = BoxKeyPair::generate_pair_for_service("acme", "redis.default").unwrap();
+        pair.to_pair_files(&keys).unwrap();
+
+        let root = tmpdir.path().join("secrets");
+        let dir = root.join("redis.default@acme").join("db");
+        fs::create_dir_all(&dir).unwrap();
+        let sealed = pair.encrypt(b"hunter2", None).unwrap();
+        fs::write(dir.join("password"), sealed.into_bytes()).unwrap();
+
+        let provider = FileSecretProvider::new(&root, &keys);
+        assert_eq!(provider.secret("redis.default@acme", "db/password")
+                           .unwrap(),
+                   Some("hunter2".to_string()));
+        assert_eq!(provider.secret("redis.default@acme", "db/user").unwrap(),
+                   None);
+
+        // A secret copied to another service group's directory isn't theirs to read.
+        let other = root.join("web.default@acme").join("db");
+        fs::create_dir_all(&other).unwrap();
+        fs::copy(dir.join("password"), other.join("password")).unwrap();
+        assert!(provider.secret("web.default@acme", "db/password").is_err());
+    }
+
+    #[test]
+    fn http_provider_gets_secrets_from_its_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            for response in &["HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: \
+                               7\r\n\r\nhunter2",
+                              "HTTP/1.1 404 Not Found\r\nConnection: close\r\nContent-Length: \
+                               0\r\n\r\n"]
+            {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 1024];
+                let len = stream.read(&mut request).unwrap();
+                assert!(String::from_utf8_lossy(&request[..len])
+                               .starts_with("GET /prod.web%23redis.default/db/"));
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        assert!(HttpSecretProvider::new(&url, false, "hab-sup", "0.0.0").is_err());
+        let provider = HttpSecretProvider::new(&url, true, "hab-sup", "0.0.0").unwrap();
+        assert_eq!(provider.secret("prod.web#redis.default", "db/password").unwrap(),
+                   Some("hunter2".to_string()));
+        assert_eq!(provider.secret("prod.web#redis.default", "db/user").unwrap(), None);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,562 @@
+//! Finding the variables a template refers to that its rendering context doesn't have.
+//!
+//! Handlebars renders a path that doesn't exist, like `{{cfg.typo}}`, as an empty string. In
+//! strict mode the renderer looks for those paths before rendering and fails instead. Only paths
+//! that look like paths into the data the Supervisor renders with (`cfg`, `pkg`, `sys`, `svc` and
+//! `bind`) are checked: anything else is a block parameter or a field of an item.
+//!
+//! Inside an `each`, `eachAlive` or `with` block that has no block parameters, paths are relative
+//! to the block's items, so `{{#each svc.members}}{{sys.ip}}{{/each}}` looks for `sys.ip` in each
+//! member. Where the items can't be known, the paths inside the block aren't checked.
+//!
+//! A template can still refer to data that might not be there, as long as it says so:
+//!
+//! * the arguments of `if` and `unless` may be missing, e.g. `{{#if cfg.tls}}`
+//! * paths inside an `if` block (or an `{{else if}}` section) that start with its argument may be
+//!   missing, e.g. `{{#if bind.database}}{{bind.database.first.sys.ip}}{{/if}}`, and likewise for
+//!   the `else` of an `unless`
+//! * the first argument of `default` may be missing, e.g. `{{default cfg.port 8080}}`
+//!
+//! A partial, `{{> name}}`, is checked where it's included, so the blocks around it still guard
+//! its paths.
+
+use std::{collections::HashMap,
+          fmt};
+
+use serde_json::Value as Json;
+
+/// The data the Supervisor renders templates with.
+const ROOTS: &[&str] = &["bind", "cfg", "pkg", "svc", "sys"];
+
+/// A path that a template refers to and its rendering context doesn't have.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingVariable {
+    pub path:    String,
+    /// The partial the path is in, if it isn't in the template itself.
+    pub partial: Option<String>,
+    pub line:    usize,
+}
+
+impl fmt::Display for MissingVariable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.partial {
+            Some(ref partial) => {
+                write!(f, "{} (partial {}, line {})", self.path, partial, self.line)
+            }
+            None => write!(f, "{} (line {})", self.path, self.line),
+        }
+    }
+}
+
+/// Every path into the rendering context that the template, or a partial it includes from
+/// `partials`, refers to and the context doesn't have, in the order they appear. A path that's
+/// there with a `null` value isn't missing.
+pub fn missing_variables(template: &str,
+                         partials: &HashMap<String, String>,
+                         ctx: &Json)
+                         -> Vec<MissingVariable> {
+    let mut scanner = Scanner { ctx,
+                                partials,
+                                including: Vec::new(),
+                                blocks: Vec::new(),
+                                missing: Vec::new() };
+    scanner.scan(template);
+    scanner.missing
+}
+
+/// A `{{...}}` tag's content, without its delimiters and whitespace control.
+struct Tag<'a> {
+    content: &'a str,
+    line:    usize,
+}
+
+/// The tags of a template, leaving out comments.
+fn tags(template: &str) -> Vec<Tag<'_>> {
+    let mut tags = Vec::new();
+    let mut rest = template;
+    let mut offset = 0;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let (body, close) = if after.starts_with("!--") {
+            (&after[3..], "--}}")
+        } else if after.starts_with('!') {
+            (&after[1..], "}}")
+        } else if after.starts_with('{') {
+            (&after[1..], "}}}")
+        } else {
+            (after, "}}")
+        };
+        let end = match body.find(close) {
+            Some(end) => end,
+            None => break,
+        };
+        if !after.starts_with('!') {
+            let line = template[..offset + start].matches('\n').count() + 1;
+            let content = body[..end].trim_start_matches('~').trim_end_matches('~').trim();
+            tags.push(Tag { content, line });
+        }
+        let consumed = rest.len() - body.len() + end + close.len();
+        offset += consumed;
+        rest = &rest[consumed..];
+    }
+    tags
+}
+
+/// A block that's been opened and not closed yet. `guard` is the path, and the index of the
+/// context it's in, that an `if` block's first section (or an `unless` block's `else` section)
+/// may assume is there.
+struct Block<'a> {
+    guard:   Option<(usize, String)>,
+    guarded: bool,
+    context: Context<'a>,
+}
+
+/// What the paths inside a block are relative to.
+enum Context<'a> {
+    /// The same as outside the block.
+    Same,
+    /// Each of the items the block renders its contents with.
+    Items(Vec<&'a Json>),
+    /// Items that can't be known, so the paths aren't checked.
+    Unknown,
+}
+
+struct Scanner<'a> {
+    ctx:       &'a Json,
+    partials:  &'a HashMap<String, String>,
+    /// The partials being scanned, innermost last.
+    including: Vec<String>,
+    blocks:    Vec<Block<'a>>,
+    missing:   Vec<MissingVariable>,
+}
+
+impl<'a> Scanner<'a> {
+    fn scan(&mut self, template: &str) {
+        for tag in tags(template) {
+            self.tag(&tag);
+        }
+    }
+
+    fn tag(&mut self, tag: &Tag<'_>) {
+        let content = tag.content;
+        if content.starts_with('>') {
+            self.partial(&content[1..]);
+            return;
+        }
+        if content.starts_with('/') {
+            self.blocks.pop();
+            return;
+        }
+        if content == "^" || content == "else" {
+            if let Some(block) = self.blocks.last_mut() {
+                block.guarded = !block.guarded;
+                block.context = Context::Same;
+            }
+            return;
+        }
+        if content.starts_with("else ") {
+            // `{{else if x}}` starts a section of its own, guarded by `x`
+            let tokens = tokenize(&content["else ".len()..]);
+            self.expression(&tokens, tag.line);
+            let guard = self.guard(&tokens);
+            if let Some(block) = self.blocks.last_mut() {
+                block.guarded = tokens.first().map(String::as_str) == Some("if");
+                block.guard = guard;
+                block.context = Context::Same;
+            }
+            return;
+        }
+        let inverted = content.starts_with('^');
+        let (opens, content) = match content.chars().next() {
+            Some('#') | Some('^') => (true, &content[1..]),
+            _ => (false, content),
+        };
+        let tokens = tokenize(content);
+        self.expression(&tokens, tag.line);
+        if opens {
+            let helper = tokens.first().map(String::as_str);
+            let block_params = tokens.iter().any(|t| t.starts_with('|'));
+            let context = match helper {
+                Some("each") | Some("eachAlive") | Some("with") if !block_params => {
+                    self.items(helper == Some("with"), tokens.get(1))
+                }
+                _ => Context::Same,
+            };
+            let guard = self.guard(&tokens);
+            self.blocks.push(Block { guarded: helper == Some("if") && !inverted,
+                                     guard,
+                                     context });
+        }
+    }
+
+    /// The path that an `if` or `unless` helper call's argument may assume is there.
+    fn guard(&self, tokens: &[String]) -> Option<(usize, String)> {
+        match tokens.first().map(String::as_str) {
+            Some("if") | Some("unless") => {
+                tokens.get(1)
+                      .and_then(|t| normalize(t))
+                      .map(|(up, path)| (self.context_index(up), path))
+            }
+            _ => None,
+        }
+    }
+
+    /// The items an `each` block over the path `arg` renders its contents with, or that a
+    /// `with` block's contents are rendered with.
+    fn items(&self, with: bool, arg: Option<&String>) -> Context<'a> {
+        let (up, path) = match arg.and_then(|arg| normalize(arg)) {
+            Some(arg) => arg,
+            None => return Context::Unknown,
+        };
+        let outer = match self.context(self.context_index(up)) {
+            Some(outer) => outer,
+            None => return Context::Unknown,
+        };
+        let mut items = Vec::new();
+        for value in outer {
+            match (lookup(value, &path), with) {
+                (None, _) => return Context::Unknown,
+                (Some(value), true) => items.push(value),
+                (Some(Json::Array(values)), false) => items.extend(values),
+                (Some(Json::Object(map)), false) => items.extend(map.values()),
+                (Some(_), false) => (),
+            }
+        }
+        Context::Items(items)
+    }
+
+    /// The index of the context a path that goes `up` blocks refers to, where 0 is the
+    /// rendering context itself and `None` is `@root`.
+    fn context_index(&self, up: Option<usize>) -> usize {
+        let current = self.contexts().count();
+        up.map_or(0, |up| current.saturating_sub(up))
+    }
+
+    /// The values the paths of the context with the given index are looked up in, if they can
+    /// be known.
+    fn context(&self, index: usize) -> Option<Vec<&'a Json>> {
+        if index == 0 {
+            return Some(vec![self.ctx]);
+        }
+        match self.contexts().nth(index - 1) {
+            Some(Context::Items(items)) => Some(items.clone()),
+            _ => None,
+        }
+    }
+
+    /// The contexts of the open blocks that have their own, innermost last.
+    fn contexts(&self) -> impl Iterator<Item = &Context<'a>> {
+        self.blocks
+            .iter()
+            .map(|block| &block.context)
+            .filter(|context| {
+                match context {
+                    Context::Same => false,
+                    _ => true,
+                }
+            })
+    }
+
+    /// Scans the partial a `{{> name}}` tag includes, unless it's unknown or already being
+    /// scanned.
+    fn partial(&mut self, content: &str) {
+        let name = match content.split_whitespace().next() {
+            Some(name) => name.trim_matches(|c| c == '"' || c == '\''),
+            None => return,
+        };
+        if self.including.iter().any(|n| n == name) {
+            return;
+        }
+        let partials = self.partials;
+        if let Some(source) = partials.get(name) {
+            self.including.push(name.to_string());
+            self.scan(source);
+            self.including.pop();
+        }
+    }
+
+    /// Checks the paths of a helper call, a subexpression or a lone path.
+    fn expression(&mut self, tokens: &[String], line: usize) {
+        let mut tokens = tokens.iter().peekable();
+        let helper = match tokens.next() {
+            Some(first) if first != "(" && first != ")" => first.clone(),
+            _ => return,
+        };
+        let mut args = Vec::new();
+        let mut depth = 0;
+        let mut sub = Vec::new();
+        while let Some(token) = tokens.next() {
+            if depth == 0 && token == "as" && tokens.peek().map_or(false, |t| t.starts_with('|')) {
+                break;
+            }
+            match token.as_str() {
+                "(" => {
+                    if depth > 0 {
+                        sub.push(token.clone());
+                    }
+                    depth += 1;
+                }
+                ")" if depth > 0 => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.expression(&sub, line);
+                        sub.clear();
+                        // A subexpression's value is never missing.
+                        args.push(None);
+                    } else {
+                        sub.push(token.clone());
+                    }
+                }
+                _ if depth > 0 => sub.push(token.clone()),
+                _ => {
+                    // For hash arguments, `key=value`, only the value refers to anything.
+                    let value = match token.find('=') {
+                        Some(i) => &token[i + 1..],
+                        None => token.as_str(),
+                    };
+                    if !value.is_empty() {
+                        args.push(Some(value.to_string()));
+                    }
+                }
+            }
+        }
+
+        if args.is_empty() {
+            self.check(&helper, line);
+            return;
+        }
+        for (i, arg) in args.iter().enumerate() {
+            let exempt = match helper.as_str() {
+                "if" | "unless" => true,
+                "default" => i == 0,
+                _ => false,
+            };
+            if let (Some(arg), false) = (arg, exempt) {
+                self.check(arg, line);
+            }
+        }
+    }
+
+    fn check(&mut self, token: &str, line: usize) {
+        let (up, path) = match normalize(token) {
+            Some(path) => path,
+            None => return,
+        };
+        let index = self.context_index(up);
+        let guarded = self.blocks.iter().any(|block| {
+                                             match (&block.guard, block.guarded) {
+                                                 (Some((i, guard)), true) => {
+                                                     *i == index
+                                                     && (path == *guard
+                                                         || path.starts_with(&format!("{}.",
+                                                                                     guard)))
+                                                 }
+                                                 _ => false,
+                                             }
+                                         });
+        let missing = match self.context(index) {
+            Some(values) => values.iter().any(|value| lookup(value, &path).is_none()),
+            None => false,
+        };
+        if !guarded && missing {
+            self.missing.push(MissingVariable { path,
+                                                partial: self.including.last().cloned(),
+                                                line });
+        }
+    }
+}
+
+/// Splits a tag's content into words, string literals and parentheses.
+fn tokenize(content: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = content.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                tokens.push(c.to_string());
+                chars.next();
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut literal = String::from("\"");
+                for n in chars.by_ref() {
+                    if n == c {
+                        break;
+                    }
+                    literal.push(n);
+                }
+                tokens.push(literal);
+            }
+            _ => {
+                let mut word = String::new();
+                let mut bracketed = false;
+                while let Some(&n) = chars.peek() {
+                    if !bracketed && (n.is_whitespace() || n == '(' || n == ')') {
+                        break;
+                    }
+                    if n == '[' {
+                        bracketed = true;
+                    } else if n == ']' {
+                        bracketed = false;
+                    } else if !bracketed && (n == '"' || n == '\'') && word.ends_with('=') {
+                        // A quoted hash argument value is a literal.
+                        break;
+                    }
+                    word.push(n);
+                    chars.next();
+                }
+                if word.ends_with('=') {
+                    // Skip the literal or subexpression that's the value of a hash argument.
+                    continue;
+                }
+                tokens.push(word);
+            }
+        }
+    }
+    tokens
+}
+
+/// The path a token refers to, if it looks like a path into one of `ROOTS`, and how many blocks
+/// up the context it's relative to is (`None` for `@root`).
+fn normalize(token: &str) -> Option<(Option<usize>, String)> {
+    let mut path = token;
+    let mut up = Some(0);
+    if path.starts_with("@root.") {
+        path = &path["@root.".len()..];
+        up = None;
+    }
+    while path.starts_with("../") {
+        path = &path[3..];
+        up = up.map(|up| up + 1);
+    }
+    let segments: Vec<&str> = path.split('.')
+                                  .map(|s| s.trim_start_matches('[').trim_end_matches(']'))
+                                  .collect();
+    if segments.len() < 2
+       || !ROOTS.contains(&segments[0])
+       || segments.iter().any(|s| s.is_empty())
+    {
+        return None;
+    }
+    Some((up, segments.join(".")))
+}
+
+fn lookup<'a>(ctx: &'a Json, path: &str) -> Option<&'a Json> {
+    path.split('.').try_fold(ctx, |value, segment| {
+                       match value {
+                           Json::Object(map) => map.get(segment),
+                           Json::Array(items) => segment.parse::<usize>()
+                                                        .ok()
+                                                        .and_then(|i| items.get(i)),
+                           _ => None,
+                       }
+                   })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ctx() -> Json {
+        json!({
+            "cfg": {"port": 8080, "tls": null, "servers": ["a", "b"], "db": {"name": "app"}},
+            "svc": {"service": "redis", "group": "default", "members": [
+                {"sys": {"ip": "10.0.0.1"}, "cfg": {"port": 6379}},
+                {"sys": {"ip": "10.0.0.2"}, "cfg": {"port": 6379}}
+            ]},
+            "bind": {}
+        })
+    }
+
+    fn missing(template: &str) -> Vec<String> {
+        missing_with_partials(template, &HashMap::new())
+    }
+
+    fn missing_with_partials(template: &str, partials: &HashMap<String, String>) -> Vec<String> {
+        missing_variables(template, partials, &ctx()).into_iter()
+                                                     .map(|m| m.to_string())
+                                                     .collect()
+    }
+
+    #[test]
+    fn finds_missing_paths_and_their_lines() {
+        assert!(missing("port = {{cfg.port}}\nname = {{cfg.db.name}}").is_empty());
+        assert_eq!(missing("port = {{cfg.port}}\nhost = {{cfg.hots}}\n{{{cfg.db.user}}}"),
+                   vec!["cfg.hots (line 2)", "cfg.db.user (line 3)"]);
+        assert_eq!(missing("{{toToml cfg.nope}} {{strJoin (split cfg.x \",\") sep=cfg.y}}"),
+                   vec!["cfg.nope (line 1)", "cfg.x (line 1)", "cfg.y (line 1)"]);
+    }
+
+    #[test]
+    fn null_values_and_indexes_are_there() {
+        assert!(missing("{{cfg.tls}} {{cfg.servers.[1]}} {{cfg.servers.0}}").is_empty());
+        assert_eq!(missing("{{cfg.servers.[2]}}"), vec!["cfg.servers.2 (line 1)"]);
+    }
+
+    #[test]
+    fn ignores_comments_literals_and_relative_paths() {
+        let template = "{{! cfg.a }}{{!-- {{cfg.b}} --}}{{strConcat \"cfg.c\" 'cfg.d'}}\n\
+                        {{#each cfg.servers as |server|}}{{server}} {{this.name}} \
+                        {{@index}}{{/each}}{{> cfg.partial}}";
+        assert!(missing(template).is_empty());
+    }
+
+    #[test]
+    fn guarded_paths_may_be_missing() {
+        assert!(missing("{{#if cfg.ssl}}{{cfg.ssl.cert}}{{/if}}").is_empty());
+        assert!(missing("{{#if bind.db}}{{bind.db.first.sys.ip}}{{/if}}").is_empty());
+        assert!(missing("{{#unless bind.db}}none{{else}}{{bind.db.members}}{{/unless}}")
+                    .is_empty());
+        assert!(missing("{{default cfg.workers 4}}").is_empty());
+        assert_eq!(missing("{{#if cfg.ssl}}on{{else}}{{cfg.ssl.cert}}{{/if}}{{cfg.ssl.key}}"),
+                   vec!["cfg.ssl.cert (line 1)", "cfg.ssl.key (line 1)"]);
+        assert_eq!(missing("{{default cfg.workers cfg.threads}}"),
+                   vec!["cfg.threads (line 1)"]);
+    }
+
+    #[test]
+    fn else_if_guards_its_own_section() {
+        assert!(missing("{{#if cfg.ssl}}on{{else if cfg.cert}}{{cfg.cert.path}}{{else}}off{{/if}}")
+                    .is_empty());
+        assert_eq!(missing("{{#if cfg.ssl}}on{{else if cfg.cert}}{{cfg.ssl.key}}{{/if}}"),
+                   vec!["cfg.ssl.key (line 1)"]);
+        assert_eq!(missing("{{#if cfg.ssl}}on{{else if cfg.crt}}on{{else}}{{cfg.crt.path}}{{/if}}"),
+                   vec!["cfg.crt.path (line 1)"]);
+    }
+
+    #[test]
+    fn paths_in_each_and_with_are_relative_to_their_items() {
+        assert!(missing("{{#each svc.members}}{{sys.ip}}:{{cfg.port}}{{/each}}").is_empty());
+        assert!(missing("{{#with svc.members.[1]}}{{sys.ip}}{{/with}}").is_empty());
+        assert_eq!(missing("{{#each svc.members}}{{sys.hostname}}{{/each}}"),
+                   vec!["sys.hostname (line 1)"]);
+        assert_eq!(missing("{{#each svc.members}}{{../cfg.port}}{{@root.cfg.nope}}{{/each}}"),
+                   vec!["cfg.nope (line 1)"]);
+        // Block parameters leave the paths relative to the rendering context
+        assert_eq!(missing("{{#each svc.members as |member|}}{{sys.ip}}{{/each}}"),
+                   vec!["sys.ip (line 1)"]);
+        // The `else` of an `each` is rendered outside of the items
+        assert_eq!(missing("{{#each cfg.servers}}{{this}}{{else}}{{sys.ip}}{{/each}}"),
+                   vec!["sys.ip (line 1)"]);
+        // Items that might not be there can't be checked
+        assert!(missing("{{#if bind.db}}{{#each bind.db.members}}{{sys.x}}{{/each}}{{/if}}")
+                    .is_empty());
+    }
+
+    #[test]
+    fn checks_partials_where_they_are_included() {
+        let mut partials = HashMap::new();
+        partials.insert("tls".to_string(),
+                        "cert = {{cfg.ssl.cert}}\nkey = {{cfg.ssl.key}}".to_string());
+        partials.insert("loop".to_string(), "{{> loop}}{{cfg.nope}}".to_string());
+        assert!(missing_with_partials("{{#if cfg.ssl}}{{> tls}}{{/if}}", &partials).is_empty());
+        assert_eq!(missing_with_partials("{{> tls}}", &partials),
+                   vec!["cfg.ssl.cert (partial tls, line 1)",
+                        "cfg.ssl.key (partial tls, line 2)"]);
+        assert_eq!(missing_with_partials("{{> loop}}", &partials),
+                   vec!["cfg.nope (partial loop, line 1)"]);
+    }
+}
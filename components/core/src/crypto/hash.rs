@@ -6,6 +6,7 @@ use std::{fs::File,
 
 use hex;
 use libsodium_sys;
+use sodiumoxide::crypto::hash::sha256;
 
 use crate::error::Result;
 
@@ -46,6 +47,9 @@ pub fn hash_bytes(data: &[u8]) -> String {
     hex::encode(out)
 }
 
+/// Calculate the SHA-256 hash of a string, return as a hex string
+pub fn sha256_string(data: &str) -> String { hex::encode(sha256::hash(data.as_bytes()).0) }
+
 pub fn hash_reader(reader: &mut BufReader<File>) -> Result<String> {
     let mut out = [0u8; libsodium_sys::crypto_generichash_BYTES];
     let mut st = vec![0u8; unsafe { libsodium_sys::crypto_generichash_statebytes() }];
@@ -124,6 +128,13 @@ mod test {
         assert_eq!(computed, expected);
     }
 
+    #[test]
+    fn sha256_string_working() {
+        // The expected value was computed with `echo -n hello | sha256sum`.
+        assert_eq!(sha256_string("hello"),
+                   "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824");
+    }
+
     #[test]
     #[cfg(feature = "functional")]
     fn hash_file_large_binary() {
@@ -91,8 +91,12 @@ pub enum Error {
     InvalidPackageTarget(String),
     /// Occurs when a package type is not recognized.
     InvalidPackageType(String),
+    /// Occurs when a resource limit (memory size, CPU quota, etc.) cannot be parsed.
+    InvalidResourceLimit(String),
     /// Occurs when a service group string cannot be successfully parsed.
     InvalidServiceGroup(String),
+    /// Occurs when a package version constraint cannot be successfully parsed.
+    InvalidVersionConstraint(String),
     /// Occurs when an origin is in an invalid format
     InvalidOrigin(String),
     /// Occurs when an OsString path cannot be converted to a String
@@ -274,6 +278,13 @@ impl fmt::Display for Error {
                         e)
             }
             Error::InvalidPackageType(ref e) => format!("Invalid package type: {}.", e),
+            Error::InvalidResourceLimit(ref e) => format!("Invalid resource limit: {}", e),
+            Error::InvalidVersionConstraint(ref e) => {
+                format!("Invalid version constraint: {}. A valid constraint is one or more \
+                         comma-separated versions, each optionally prefixed with =, >, >=, <, \
+                         <=, ~ or ^ (example: >=1.2,<2.0)",
+                        e)
+            }
             Error::InvalidServiceGroup(ref e) => {
                 format!("Invalid service group: {}. A valid service group string is in the form \
                          service.group (example: redis.production)",
@@ -436,6 +447,10 @@ impl error::Error for Error {
                 "Package targets must be in architecture-platform format (example: x86_64-linux)"
             }
             Error::InvalidPackageType(_) => "Unsupported package type supplied.",
+            Error::InvalidResourceLimit(_) => "Resource limits must be positive quantities",
+            Error::InvalidVersionConstraint(_) => {
+                "Version constraints must be comma-separated comparisons (example: >=1.2,<2.0)"
+            }
             Error::InvalidServiceGroup(_) => {
                 "Service group strings must be in service.group[@organization] format (example: \
                  redis.production or foo.default@bazcorp)"
@@ -5,6 +5,7 @@ use super::{list::package_list_for_ident,
                        BindMapping,
                        MetaFile,
                        PackageType},
+            version_constraint::VersionConstraint,
             Identifiable,
             PackageIdent};
 use crate::{error::{Error,
@@ -75,6 +76,37 @@ impl PackageInstall {
         Ok(package_install)
     }
 
+    /// Verifies an installation of the latest release of a package whose version satisfies the
+    /// given constraint and returns a Result of a `PackageInstall` if one exists.
+    ///
+    /// An optional `fs_root` path may be provided to search for a package that is mounted on a
+    /// filesystem not currently rooted at `/`.
+    pub fn load_matching(ident: &PackageIdent,
+                         constraint: &VersionConstraint,
+                         fs_root_path: Option<&Path>)
+                         -> Result<PackageInstall> {
+        let fs_root_path = fs_root_path.map_or(PathBuf::from("/"), Into::into);
+        let package_root_path = fs::pkg_root_path(Some(&fs_root_path));
+        if !package_root_path.exists() {
+            return Err(Error::PackageNotFound(ident.clone()));
+        }
+
+        let pl: Vec<PackageIdent> = package_list_for_ident(&package_root_path, ident)?
+            .into_iter()
+            .filter(|p| p.satisfies(ident))
+            .collect();
+        match constraint.latest_match(&pl) {
+            Some(id) => {
+                Ok(PackageInstall { installed_path: fs::pkg_install_path(id,
+                                                                         Some(&fs_root_path)),
+                                    fs_root_path,
+                                    package_root_path,
+                                    ident: id.clone() })
+            }
+            None => Err(Error::PackageNotFound(ident.clone())),
+        }
+    }
+
     fn resolve_package_install<T>(ident: &PackageIdent,
                                   fs_root_path: Option<T>)
                                   -> Result<PackageInstall>
@@ -577,6 +609,17 @@ impl PackageInstall {
         }
     }
 
+    /// Returns whether the package's templates should fail to render when they refer to
+    /// undefined variables, which is false if the package doesn't contain a STRICT_TEMPLATES
+    /// Metafile
+    pub fn strict_templates(&self) -> Result<bool> {
+        match self.read_metafile(MetaFile::StrictTemplates) {
+            Ok(body) => Ok(body.trim() == "true"),
+            Err(Error::MetaFileNotFound(MetaFile::StrictTemplates)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Read the contents of a given metafile.
     ///
     /// # Failures
@@ -793,6 +836,17 @@ core/bar=pub:core/publish sub:core/subscribe
         assert!(bind_map.is_empty());
     }
 
+    #[test]
+    fn strict_templates_is_off_without_metafile() {
+        let fs_root = Builder::new().prefix("fs-root").tempdir().unwrap();
+        let pkg_install = testing_package_install("chef/kibana/6.1.2/20180101010101",
+                                                  fs_root.path());
+        assert!(!pkg_install.strict_templates().unwrap());
+
+        write_metafile(&pkg_install, MetaFile::StrictTemplates, "true\n");
+        assert!(pkg_install.strict_templates().unwrap());
+    }
+
     #[test]
     fn load_with_fully_qualified_ident_matching_target() {
         let fs_root = Builder::new().prefix("fs-root").tempdir().unwrap();
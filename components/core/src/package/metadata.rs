@@ -157,6 +157,7 @@ pub enum MetaFile {
     RuntimeEnvironment,
     RuntimePath,
     Services, // Composite-only
+    StrictTemplates,
     SvcGroup,
     SvcUser,
     Target,
@@ -188,6 +189,7 @@ impl fmt::Display for MetaFile {
             MetaFile::RuntimeEnvironment => "RUNTIME_ENVIRONMENT",
             MetaFile::RuntimePath => "RUNTIME_PATH",
             MetaFile::Services => "SERVICES",
+            MetaFile::StrictTemplates => "STRICT_TEMPLATES",
             MetaFile::SvcGroup => "SVC_GROUP",
             MetaFile::SvcUser => "SVC_USER",
             MetaFile::Target => "TARGET",
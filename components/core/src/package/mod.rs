@@ -5,6 +5,7 @@ pub mod list;
 pub mod metadata;
 pub mod plan;
 pub mod target;
+pub mod version_constraint;
 
 pub use self::{archive::{FromArchive,
                          PackageArchive},
@@ -13,7 +14,8 @@ pub use self::{archive::{FromArchive,
                install::PackageInstall,
                list::all_packages,
                plan::Plan,
-               target::PackageTarget};
+               target::PackageTarget,
+               version_constraint::VersionConstraint};
 
 #[cfg(test)]
 pub mod test_support {
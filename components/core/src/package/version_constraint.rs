@@ -0,0 +1,291 @@
+//! Constraints on the version of a package, such as `~9.6` or `>=1.2,<2.0`.
+//!
+//! A constraint is one or more comma-separated comparisons, all of which a version has to satisfy.
+//! Each is a version made of numbers separated by dots, optionally prefixed by an operator:
+//!
+//! * `>`, `>=`, `<` and `<=` compare versions the same way releases are ordered.
+//! * `=`, or no operator, matches the given version and any more specific one, so `=9.6`
+//!   matches `9.6` and `9.6.11`, but not `9.7`.
+//! * `~` allows changes after the second number, or after the first if that's the only one, so
+//!   `~9.6` matches anything from `9.6` up to but excluding `9.7`, and `~9` the same as `^9`.
+//! * `^` allows changes after the first number that isn't zero, so `^1.2` matches anything from
+//!   `1.2` up to but excluding `2`, and `^0.3` anything from `0.3` up to but excluding `0.4`.
+
+use crate::{error::{Error,
+                    Result},
+            package::{ident::version_sort,
+                      PackageIdent},
+            util::{deserialize_using_from_str,
+                   serialize_using_to_string}};
+use serde::{Deserialize,
+            Deserializer,
+            Serialize,
+            Serializer};
+use std::{cmp::Ordering,
+          fmt,
+          result,
+          str::FromStr};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Operator {
+    Exact,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Tilde,
+    Caret,
+}
+
+impl Operator {
+    fn as_str(self) -> &'static str {
+        match self {
+            Operator::Exact => "=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Tilde => "~",
+            Operator::Caret => "^",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Comparison {
+    operator: Operator,
+    version:  Vec<u64>,
+}
+
+impl Comparison {
+    fn matches(&self, version: &str) -> bool {
+        let compared_to = |bound: &[u64]| version_sort(version, &join(bound)).ok();
+        match self.operator {
+            Operator::Greater => compared_to(&self.version) == Some(Ordering::Greater),
+            Operator::GreaterOrEqual => {
+                compared_to(&self.version).map_or(false, |o| o != Ordering::Less)
+            }
+            Operator::Less => compared_to(&self.version) == Some(Ordering::Less),
+            Operator::LessOrEqual => {
+                compared_to(&self.version).map_or(false, |o| o != Ordering::Greater)
+            }
+            Operator::Exact | Operator::Tilde | Operator::Caret => {
+                compared_to(&self.version).map_or(false, |o| o != Ordering::Less)
+                && compared_to(&self.upper_bound()) == Some(Ordering::Less)
+            }
+        }
+    }
+
+    /// The lowest version past those that an `=`, `~` or `^` comparison matches.
+    fn upper_bound(&self) -> Vec<u64> {
+        let last = self.version.len() - 1;
+        let position = match self.operator {
+            Operator::Tilde => last.min(1),
+            Operator::Caret => {
+                self.version
+                    .iter()
+                    .position(|n| *n != 0)
+                    .unwrap_or(last)
+            }
+            _ => last,
+        };
+        let mut bound = self.version[..=position].to_vec();
+        bound[position] += 1;
+        bound
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let (operator, version) =
+            [Operator::GreaterOrEqual,
+             Operator::LessOrEqual,
+             Operator::Greater,
+             Operator::Less,
+             Operator::Exact,
+             Operator::Tilde,
+             Operator::Caret].iter()
+                             .find(|o| value.starts_with(o.as_str()))
+                             .map_or((Operator::Exact, value), |o| {
+                                 (*o, value[o.as_str().len()..].trim_start())
+                             });
+        let version = version.split('.')
+                             .map(str::parse)
+                             .collect::<result::Result<Vec<u64>, _>>()
+                             .map_err(|_| Error::InvalidVersionConstraint(value.to_string()))?;
+        Ok(Comparison { operator, version })
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.operator.as_str(), join(&self.version))
+    }
+}
+
+fn join(version: &[u64]) -> String {
+    version.iter()
+           .map(u64::to_string)
+           .collect::<Vec<_>>()
+           .join(".")
+}
+
+/// A constraint on the version of a package, which the releases a service updates to or that
+/// are installed have to satisfy.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct VersionConstraint(Vec<Comparison>);
+
+impl VersionConstraint {
+    /// Returns true if the given identifier's version satisfies the constraint.
+    pub fn matches(&self, ident: &PackageIdent) -> bool {
+        match ident.version {
+            Some(ref version) => self.0.iter().all(|c| c.matches(version)),
+            None => false,
+        }
+    }
+
+    /// The latest of the given identifiers that satisfies the constraint.
+    pub fn latest_match<'a, I>(&self, idents: I) -> Option<&'a PackageIdent>
+        where I: IntoIterator<Item = &'a PackageIdent>
+    {
+        idents.into_iter().filter(|i| self.matches(i)).max()
+    }
+
+    /// Parse a package identifier that may be followed by a version constraint, e.g.
+    /// `core/postgresql@~9.6`. An identifier with a constraint can't also give a version.
+    pub fn parse_ident(value: &str) -> Result<(PackageIdent, Option<VersionConstraint>)> {
+        let mut parts = value.splitn(2, '@');
+        let ident: PackageIdent = parts.next().unwrap_or_default().parse()?;
+        match parts.next() {
+            Some(constraint) => {
+                if ident.version.is_some() {
+                    return Err(Error::InvalidPackageIdent(value.to_string()));
+                }
+                Ok((ident, Some(constraint.parse()?)))
+            }
+            None => Ok((ident, None)),
+        }
+    }
+}
+
+impl FromStr for VersionConstraint {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let comparisons = value.split(',')
+                               .map(Comparison::from_str)
+                               .collect::<Result<Vec<_>>>()
+                               .map_err(|_| Error::InvalidVersionConstraint(value.to_string()))?;
+        Ok(VersionConstraint(comparisons))
+    }
+}
+
+impl fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let comparisons: Vec<String> = self.0.iter().map(Comparison::to_string).collect();
+        write!(f, "{}", comparisons.join(","))
+    }
+}
+
+impl Serialize for VersionConstraint {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serialize_using_to_string(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for VersionConstraint {
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        deserialize_using_from_str(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(constraint: &str, version: &str) -> bool {
+        let ident = PackageIdent::new("core", "postgresql", Some(version), None);
+        constraint.parse::<VersionConstraint>().unwrap().matches(&ident)
+    }
+
+    #[test]
+    fn comparison_operators() {
+        assert!(matches(">=1.2,<2.0", "1.2.0"));
+        assert!(matches(">=1.2,<2.0", "1.10.3"));
+        assert!(!matches(">=1.2,<2.0", "2.0.0"));
+        assert!(!matches(">=1.2,<2.0", "1.1.9"));
+        assert!(matches(">1.2", "1.2.1"));
+        assert!(!matches(">1.2", "1.2.0"));
+        assert!(matches("<=1.2", "1.2.0"));
+        assert!(!matches("<=1.2", "1.2.1"));
+    }
+
+    #[test]
+    fn exact_tilde_and_caret() {
+        assert!(matches("9.6", "9.6.11"));
+        assert!(matches("=9.6", "9.6"));
+        assert!(!matches("9.6", "9.7.0"));
+        assert!(!matches("=9.6.1", "9.6.10"));
+
+        assert!(matches("~9.6", "9.6.11"));
+        assert!(!matches("~9.6", "10.1"));
+        assert!(matches("~1.2.3", "1.2.9"));
+        assert!(!matches("~1.2.3", "1.2.2"));
+        assert!(!matches("~1.2.3", "1.3.0"));
+        assert!(matches("~9", "9.7"));
+
+        assert!(matches("^1.2", "1.9.0"));
+        assert!(!matches("^1.2", "2.0.0"));
+        assert!(matches("^0.3", "0.3.7"));
+        assert!(!matches("^0.3", "0.4.0"));
+        assert!(!matches("^0.0.3", "0.0.4"));
+    }
+
+    #[test]
+    fn from_str_and_display() {
+        let constraint: VersionConstraint = ">= 1.2, <2.0".parse().unwrap();
+        assert_eq!(constraint.to_string(), ">=1.2,<2.0");
+        assert_eq!("9.6".parse::<VersionConstraint>().unwrap().to_string(), "=9.6");
+        assert!("".parse::<VersionConstraint>().is_err());
+        assert!(">=1.2,".parse::<VersionConstraint>().is_err());
+        assert!("~9.x".parse::<VersionConstraint>().is_err());
+        assert!("latest".parse::<VersionConstraint>().is_err());
+    }
+
+    #[test]
+    fn latest_match() {
+        let idents: Vec<PackageIdent> = ["core/postgresql/9.6.11/20190101000000",
+                                         "core/postgresql/9.6.9/20190201000000",
+                                         "core/postgresql/10.1/20190301000000"].iter()
+                                                                                .map(|i| {
+                                                                                    i.parse()
+                                                                                     .unwrap()
+                                                                                })
+                                                                                .collect();
+        let constraint: VersionConstraint = "~9.6".parse().unwrap();
+        assert_eq!(constraint.latest_match(&idents), Some(&idents[0]));
+        let constraint: VersionConstraint = ">=11".parse().unwrap();
+        assert_eq!(constraint.latest_match(&idents), None);
+    }
+
+    #[test]
+    fn parse_ident() {
+        let (ident, constraint) = VersionConstraint::parse_ident("core/postgresql@~9.6").unwrap();
+        assert_eq!(ident, "core/postgresql".parse().unwrap());
+        assert_eq!(constraint, Some("~9.6".parse().unwrap()));
+
+        let (ident, constraint) = VersionConstraint::parse_ident("core/redis").unwrap();
+        assert_eq!(ident, "core/redis".parse().unwrap());
+        assert_eq!(constraint, None);
+
+        assert!(VersionConstraint::parse_ident("core/redis/4.0@^4").is_err());
+        assert!(VersionConstraint::parse_ident("core/redis@").is_err());
+    }
+}
@@ -408,6 +408,139 @@ impl From<HealthCheckInterval> for Duration {
     fn from(h: HealthCheckInterval) -> Self { h.0 }
 }
 
+/// Limits on the resources a service's processes may consume, applied
+/// by the Launcher through a cgroup (Linux, cgroup v2 only).
+///
+/// Unset limits are left at whatever the host allows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    /// Relative share of CPU time, from 1 to 10000 (`cpu.weight`).
+    pub cpu_weight: Option<u32>,
+    /// Maximum CPU time, in thousandths of a CPU (`cpu.max`).
+    pub cpu_quota:  Option<u64>,
+    /// Maximum memory, in bytes (`memory.max`).
+    pub memory_max: Option<u64>,
+    /// Maximum number of processes and threads (`pids.max`).
+    pub pids_max:   Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool { *self == Self::default() }
+}
+
+/// Settings for writing a service's output to a file under its `logs`
+/// directory, alongside the Supervisor's own output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogFileConfig {
+    /// Set to false to turn off a log file the Supervisor would otherwise
+    /// write by default.
+    pub enabled:  bool,
+    /// Rotate the file once it grows past this many bytes; zero never
+    /// rotates on size.
+    pub max_size: u64,
+    /// Rotate the file once it is this many seconds old; zero never
+    /// rotates on age.
+    pub max_age:  u64,
+    /// The number of rotated files to keep.
+    pub retain:   u32,
+}
+
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        LogFileConfig { enabled:  true,
+                        max_size: 10 * 1024 * 1024,
+                        max_age:  24 * 60 * 60,
+                        retain:   5, }
+    }
+}
+
+/// The log file settings given for one service. Each one that's set
+/// overrides the Supervisor's default for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogFileOverrides {
+    /// Whether to write the file; unset writes it only if the
+    /// Supervisor writes log files by default.
+    pub enabled:  Option<bool>,
+    pub max_size: Option<u64>,
+    pub max_age:  Option<u64>,
+    pub retain:   Option<u32>,
+}
+
+impl LogFileOverrides {
+    pub fn is_empty(&self) -> bool { *self == Self::default() }
+
+    /// The settings in effect given the Supervisor's defaults (`None`
+    /// if it doesn't write log files by default), or `None` if no file
+    /// is to be written.
+    pub fn apply(&self, defaults: Option<LogFileConfig>) -> Option<LogFileConfig> {
+        if !self.enabled.unwrap_or_else(|| defaults.is_some()) {
+            return None;
+        }
+        let defaults = defaults.unwrap_or_default();
+        Some(LogFileConfig { enabled:  true,
+                             max_size: self.max_size.unwrap_or(defaults.max_size),
+                             max_age:  self.max_age.unwrap_or(defaults.max_age),
+                             retain:   self.retain.unwrap_or(defaults.retain), })
+    }
+}
+
+/// A quantity of memory in bytes, which may be given with a `K`, `M`,
+/// `G` or `T` (binary) suffix, e.g. `512M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MemorySize(u64);
+
+impl FromStr for MemorySize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        let s = s.trim();
+        let (digits, multiplier) = match s.chars().last().map(|c| c.to_ascii_uppercase()) {
+            Some('K') => (&s[..s.len() - 1], 1 << 10),
+            Some('M') => (&s[..s.len() - 1], 1 << 20),
+            Some('G') => (&s[..s.len() - 1], 1 << 30),
+            Some('T') => (&s[..s.len() - 1], 1 << 40),
+            _ => (s, 1),
+        };
+        digits.parse::<u64>()
+              .ok()
+              .and_then(|n| n.checked_mul(multiplier))
+              .filter(|n| *n > 0)
+              .map(MemorySize)
+              .ok_or_else(|| Error::InvalidResourceLimit(format!("'{}' is not a memory size", s)))
+    }
+}
+
+impl From<MemorySize> for u64 {
+    fn from(value: MemorySize) -> u64 { value.0 }
+}
+
+/// An amount of CPU time expressed as a (possibly fractional) number of
+/// CPUs, e.g. `0.5` or `2`. Stored in thousandths of a CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CpuQuota(u64);
+
+impl FromStr for CpuQuota {
+    type Err = Error;
+
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        s.trim()
+         .parse::<f64>()
+         .ok()
+         .filter(|cpus| cpus.is_finite())
+         .map(|cpus| (cpus * 1000.0).round())
+         .filter(|millis| *millis >= 1.0 && *millis <= u64::max_value() as f64)
+         .map(|millis| CpuQuota(millis as u64))
+         .ok_or_else(|| Error::InvalidResourceLimit(format!("'{}' is not a number of CPUs", s)))
+    }
+}
+
+impl From<CpuQuota> for u64 {
+    fn from(value: CpuQuota) -> u64 { value.0 }
+}
+
 #[cfg(test)]
 mod test {
     use std::str::FromStr;
@@ -740,4 +873,59 @@ mod test {
         assert_eq!("(5s)".to_owned(),
                    format!("{}", HealthCheckInterval::from_str("5").unwrap()));
     }
+
+    #[test]
+    fn log_file_overrides_apply_field_by_field() {
+        let defaults = LogFileConfig { max_size: 1024,
+                                       retain: 2,
+                                       ..Default::default() };
+        let overrides = LogFileOverrides { max_age: Some(60),
+                                           ..Default::default() };
+        assert_eq!(overrides.apply(Some(defaults)),
+                   Some(LogFileConfig { max_age: 60,
+                                        ..defaults }));
+        // Tuning alone doesn't turn on a file the Supervisor doesn't write by default
+        assert_eq!(overrides.apply(None), None);
+
+        let enabled = LogFileOverrides { enabled: Some(true),
+                                         ..overrides };
+        assert_eq!(enabled.apply(None),
+                   Some(LogFileConfig { max_age: 60,
+                                        ..Default::default() }));
+        let disabled = LogFileOverrides { enabled: Some(false),
+                                          ..overrides };
+        assert_eq!(disabled.apply(Some(defaults)), None);
+    }
+
+    #[test]
+    fn memory_size_from_str() {
+        assert_eq!(MemorySize::from_str("1024").unwrap(), MemorySize(1024));
+        assert_eq!(MemorySize::from_str("2k").unwrap(), MemorySize(2048));
+        assert_eq!(MemorySize::from_str("512M").unwrap(),
+                   MemorySize(512 * 1024 * 1024));
+        assert_eq!(MemorySize::from_str("1G").unwrap(),
+                   MemorySize(1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn memory_size_from_str_invalid() {
+        assert!(MemorySize::from_str("").is_err());
+        assert!(MemorySize::from_str("0").is_err());
+        assert!(MemorySize::from_str("-1M").is_err());
+        assert!(MemorySize::from_str("lots").is_err());
+    }
+
+    #[test]
+    fn cpu_quota_from_str() {
+        assert_eq!(CpuQuota::from_str("2").unwrap(), CpuQuota(2000));
+        assert_eq!(CpuQuota::from_str("0.25").unwrap(), CpuQuota(250));
+    }
+
+    #[test]
+    fn cpu_quota_from_str_invalid() {
+        assert!(CpuQuota::from_str("0").is_err());
+        assert!(CpuQuota::from_str("-1").is_err());
+        assert!(CpuQuota::from_str("NaN").is_err());
+        assert!(CpuQuota::from_str("all of them").is_err());
+    }
 }
@@ -25,8 +25,11 @@ use habitat_core::{crypto::{keys::PairType,
                    package::{ident,
                              Identifiable,
                              PackageIdent,
-                             PackageTarget},
-                   service::{HealthCheckInterval,
+                             PackageTarget,
+                             VersionConstraint},
+                   service::{CpuQuota,
+                             HealthCheckInterval,
+                             MemorySize,
                              ServiceGroup},
                    ChannelIdent};
 use habitat_sup_protocol;
@@ -78,6 +81,32 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
             (aliases: &["co", "con", "conf", "confi"])
             (@setting ArgRequiredElseHelp)
             (subcommand: sub_config_apply().aliases(&["ap", "app", "appl"]))
+            (@subcommand history =>
+                (about: "Lists the configurations the Supervisor has kept that were applied to a \
+                    Service Group, oldest first")
+                (aliases: &["hi", "his", "hist"])
+                (@arg SERVICE_GROUP: +required {valid_service_group}
+                    "Target service group service.group[@organization] (ex: redis.default or foo.default@bazcorp)")
+                (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+                    "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+            )
+            (@subcommand diff =>
+                (about: "Shows the differences between two configurations that were applied to a \
+                    Service Group")
+                (aliases: &["di", "dif"])
+                (@arg SERVICE_GROUP: +required {valid_service_group}
+                    "Target service group service.group[@organization] (ex: redis.default or foo.default@bazcorp)")
+                (@arg OLD_VERSION_NUMBER: +required {valid_numeric::<u64>}
+                    "Version number of the configuration to compare from (ex: 41)")
+                (@arg NEW_VERSION_NUMBER: +required {valid_numeric::<u64>}
+                    "Version number of the configuration to compare to (ex: 42)")
+                (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+                    "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+                (arg: arg_cache_key_path("Path to search for the service group's key, to decrypt \
+                    configurations that were applied encrypted. Default value is hab/cache/keys \
+                    if root and .hab/cache/keys under the home directory otherwise."))
+            )
+            (subcommand: sub_config_rollback().aliases(&["ro", "rol", "roll"]))
             (@subcommand show =>
                 (about: "Displays the default configuration options for a service")
                 (aliases: &["sh", "sho"])
@@ -627,11 +656,15 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
                 (@arg DEFAULT_TOML: -d --("default-toml") +takes_value default_value("./default.toml") "Path to default.toml")
                 (@arg USER_TOML: -u --("user-toml") +takes_value "Path to user.toml, defaults to none")
                 (@arg MOCK_DATA: -m --("mock-data") +takes_value "Path to json file with mock data for template, defaults to none")
+                (@arg PARTIALS_DIR: --("partials-dir") +takes_value default_value("./config/_partials")
+                    "Path to the directory of partials the template can include")
                 (@arg PRINT: -p --("print") "Prints config to STDOUT")
                 (@arg RENDER_DIR: -r --("render-dir") +takes_value default_value("./results") "Path to render templates")
                 (@arg NO_RENDER: -n --("no-render") "Don't write anything to disk, ignores --render-dir")
                 (@arg QUIET: -q --("no-verbose") --quiet
                     "Don't print any helper messages.  When used with `--print` will only print config file")
+                (@arg STRICT: --strict
+                    "Fail without rendering anything if the template refers to undefined variables")
             )
         )
         (@subcommand ring =>
@@ -688,6 +721,7 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
                         directory otherwise."))
                 )
             )
+            (subcommand: sub_svc_health().aliases(&["h", "he", "hea", "heal", "healt"]))
             (subcommand: sub_svc_load().aliases(&["l", "lo", "loa"]))
             (subcommand: sub_svc_start().aliases(&["star"]))
             (subcommand: sub_svc_status().aliases(&["stat", "statu"]))
@@ -780,6 +814,7 @@ pub fn sup_commands(feature_flags: FeatureFlag) -> App<'static, 'static> {
     (usage: "hab sup <SUBCOMMAND>")
     (@setting VersionlessSubcommands)
     (@setting SubcommandRequiredElseHelp)
+    (subcommand: sub_sup_apply().aliases(&["ap", "app", "appl"]))
     (subcommand: sub_sup_bash().aliases(&["b", "ba", "bas"]))
     (subcommand: sub_sup_depart().aliases(&["d", "de", "dep", "depa", "depart"]))
     (subcommand: sub_sup_run(feature_flags).aliases(&["r", "ru"]))
@@ -879,8 +914,9 @@ fn sub_pkg_install(feature_flags: FeatureFlag) -> App<'static, 'static> {
         (@arg CHANNEL: --channel -c +takes_value default_value[stable] env(ChannelIdent::ENVVAR)
             "Install from the specified release channel")
         (@arg PKG_IDENT_OR_ARTIFACT: +required +multiple
-            "One or more Habitat package identifiers (ex: acme/redis) and/or filepaths \
-            to a Habitat Artifact (ex: /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)")
+            "One or more Habitat package identifiers (ex: acme/redis), optionally followed by \
+            a version constraint (ex: acme/redis@~4.0), and/or filepaths to a Habitat Artifact \
+            (ex: /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)")
         (@arg BINLINK: -b --binlink +takes_value {non_empty} env(BINLINK_DIR_ENVVAR)
             default_value(DEFAULT_BINLINK_DIR) "Binlink all binaries from installed package(s)")
         (@arg FORCE: -f --force "Overwrite existing binlinks")
@@ -911,6 +947,28 @@ fn sub_config_apply() -> App<'static, 'static> {
     (@arg FILE: {file_exists_or_stdin}
         "Path to local file on disk (ex: /tmp/config.toml, default: <stdin>)")
     (@arg USER: -u --user +takes_value "Name of a user key to use for encryption")
+    (@arg DRY_RUN: --("dry-run")
+        "Show how the Supervisor's rendered configuration files and hooks would change, \
+        without applying the configuration")
+    (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+        "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+    (arg: arg_cache_key_path("Path to search for encryption keys. \
+        Default value is hab/cache/keys if root and .hab/cache/keys under the home \
+        directory otherwise."))
+    )
+}
+
+fn sub_config_rollback() -> App<'static, 'static> {
+    clap_app!(@subcommand rollback =>
+    (about: "Applies a configuration that was applied to a Service Group before again, as a new \
+        version")
+    (@arg SERVICE_GROUP: +required {valid_service_group}
+        "Target service group service.group[@organization] (ex: redis.default or foo.default@bazcorp)")
+    (@arg VERSION_NUMBER: +required {valid_numeric::<u64>}
+        "Version number of the configuration to apply again (ex: 41)")
+    (@arg NEW_VERSION_NUMBER: --("new-version") +takes_value {valid_numeric::<u64>}
+        "Version number to apply it as [default: one more than the service group's current version]")
+    (@arg USER: -u --user +takes_value "Name of a user key to use for encryption")
     (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
         "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
     (arg: arg_cache_key_path("Path to search for encryption keys. \
@@ -922,6 +980,20 @@ fn sub_config_apply() -> App<'static, 'static> {
 // the following sup related functions are
 // public due to their utilization in `hab-sup`
 // for consistency, all supervisor related clap subcommands are defined in this module
+pub fn sub_sup_apply() -> App<'static, 'static> {
+    clap_app!(@subcommand apply =>
+        (about: "Load, update and unload services so that a Supervisor runs the services a \
+            stack file describes")
+        (@arg FILE: -f --file +required +takes_value {file_exists}
+            "Path to the stack file, a TOML file with a [[service]] table in spec file format \
+            for each service")
+        (@arg PRUNE: --prune "Unload the services that the stack file doesn't list")
+        (@arg DRY_RUN: --("dry-run") "Print the planned changes without making them")
+        (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+            "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+    )
+}
+
 pub fn sub_sup_depart() -> App<'static, 'static> {
     clap_app!(@subcommand depart =>
         (about: "Depart a Supervisor from the gossip ring; kicking and banning the target \
@@ -939,6 +1011,20 @@ pub fn sub_sup_secret() -> App<'static, 'static> {
         (@subcommand generate =>
             (about: "Generate a secret key to use as a Supervisor's Control Gateway secret")
         )
+        (@subcommand create =>
+            (about: "Create a named Control Gateway secret for the local Supervisor, limited to \
+                the given scopes, and print its key")
+            (@arg NAME: +required +takes_value "A name for the secret (ex: monitoring)")
+            (@arg SCOPE: --scope -s +required +takes_value +multiple {valid_ctl_secret_scope}
+                "What the secret allows [values: read, config, lifecycle, admin]")
+        )
+        (@subcommand revoke =>
+            (about: "Revoke a named Control Gateway secret of the local Supervisor")
+            (@arg NAME: +required +takes_value "The name of the secret")
+        )
+        (@subcommand list =>
+            (about: "List the named Control Gateway secrets of the local Supervisor")
+        )
     )
 }
 
@@ -1002,20 +1088,57 @@ pub fn sub_sup_run(feature_flags: FeatureFlag) -> App<'static, 'static> {
                              "Use package config from this path, rather than the package itself")
                             (@arg AUTO_UPDATE: --("auto-update") -A "Enable automatic updates for the Supervisor \
                                                                      itself")
-                            (@arg KEY_FILE: --key +takes_value {file_exists} requires[CERT_FILE]
-                             "Used for enabling TLS for the HTTP gateway. Read private key from KEY_FILE. \
-                              This should be a RSA private key or PKCS8-encoded private key, in PEM format.")
-                            (@arg CERT_FILE: --certs +takes_value {file_exists} requires[KEY_FILE]
-                             "Used for enabling TLS for the HTTP gateway. Read server certificates from CERT_FILE. \
+                            (@arg KEY_FILE: --("http-tls-key") +takes_value {file_exists} requires[CERT_FILE]
+                             (visible_alias: "key")
+                             "Serve the HTTP gateway over TLS, using the private key in KEY_FILE. \
+                              This should be a RSA private key or PKCS8-encoded private key, in PEM format. \
+                              The key is reloaded whenever the file changes.")
+                            (@arg CERT_FILE: --("http-tls-cert") +takes_value {file_exists} requires[KEY_FILE]
+                             (visible_alias: "certs")
+                             "Serve the HTTP gateway over TLS, using the certificates in CERT_FILE. \
                               This should contain PEM-format certificates in the right order (the first certificate \
-                              should certify KEY_FILE, the last should be a root CA).")
-                            (@arg CA_CERT_FILE: --("ca-certs") +takes_value {file_exists} requires[CERT_FILE] requires[KEY_FILE]
-                             "Used for enabling client-authentication with TLS for the HTTP gateway. Read CA certificate from CA_CERT_FILE. \
-                              This should contain PEM-format certificate that can be used to validate client requests.")
+                              should certify KEY_FILE, the last should be a root CA). The certificates are \
+                              reloaded whenever the file changes.")
+                            (@arg CA_CERT_FILE: --("http-tls-client-ca") +takes_value {file_exists} requires[CERT_FILE] requires[KEY_FILE]
+                             (visible_alias: "ca-certs")
+                             "Require clients of the HTTP gateway to present a certificate signed by the CA in \
+                              CA_CERT_FILE. This should contain PEM-format certificates. The file is reloaded \
+                              whenever it changes.")
+                            (@arg CTL_KEY_FILE: --("ctl-tls-key") +takes_value {file_exists} requires[CTL_CERT_FILE]
+                             "Serve the ctl gateway over TLS, using the private key in CTL_KEY_FILE. \
+                              This should be a RSA private key or PKCS8-encoded private key, in PEM format. \
+                              The key is reloaded whenever the file changes.")
+                            (@arg CTL_CERT_FILE: --("ctl-tls-cert") +takes_value {file_exists} requires[CTL_KEY_FILE]
+                             "Serve the ctl gateway over TLS, using the certificates in CTL_CERT_FILE. \
+                              This should contain PEM-format certificates in the right order (the first certificate \
+                              should certify CTL_KEY_FILE, the last should be a root CA). The certificates are \
+                              reloaded whenever the file changes.")
+                            (@arg CTL_CA_CERT_FILE: --("ctl-tls-client-ca") +takes_value {file_exists} requires[CTL_CERT_FILE] requires[CTL_KEY_FILE]
+                             "Require clients of the ctl gateway to present a certificate signed by the CA in \
+                              CTL_CA_CERT_FILE. This should contain PEM-format certificates. The file is reloaded \
+                              whenever it changes.")
+                            (@arg SERVICE_LOG_FILES: --("service-log-files")
+                             "Write the output of every service to a rotated file under /hab/svc/<name>/logs \
+                              unless its own settings say otherwise")
+                            (@arg SERVICE_LOG_MAX_SIZE: --("service-log-max-size") +takes_value {valid_memory_size}
+                             "The default size at which service log files are rotated, e.g. 10M \
+                              [default: 10M]")
+                            (@arg SERVICE_LOG_MAX_AGE: --("service-log-max-age") +takes_value {valid_numeric::<u64>}
+                             "The default age (seconds) at which service log files are rotated; 0 never \
+                              rotates on age [default: 86400]")
+                            (@arg SERVICE_LOG_RETAIN: --("service-log-retain") +takes_value {valid_numeric::<u32>}
+                             "The default number of rotated service log files to keep [default: 5]")
+                            (@arg SECRETS_URL: --("secrets-url") +takes_value {valid_url}
+                             "Fetch secrets that templates refer to, and that aren't kept under \
+                              /hab/sup/default/secrets, from SECRETS_URL/<service_group>/<name>; \
+                              must be an https URL")
+                            (@arg SECRETS_URL_ALLOW_HTTP: --("secrets-url-allow-http") requires[SECRETS_URL]
+                             "Allow SECRETS_URL to be a plain http URL, sending secrets in the clear")
                             // === Optional arguments to additionally load an initial service for the Supervisor
                             (@arg PKG_IDENT_OR_ARTIFACT: +takes_value "Load the given Habitat package as part of \
                                                                        the Supervisor startup specified by a package identifier \
-                                                                       (ex: core/redis) or filepath to a Habitat Artifact \
+                                                                       (ex: core/redis), optionally followed by a version \
+                                                                       constraint (ex: core/postgresql@~9.6), or filepath to a Habitat Artifact \
                                                                        (ex: /home/core-redis-3.0.7-21120102031201-x86_64-linux.hart).")
                             (@arg APPLICATION: --application -a +takes_value requires[ENVIRONMENT]
                              "Application name; [default: not set].")
@@ -1027,17 +1150,75 @@ pub fn sub_sup_run(feature_flags: FeatureFlag) -> App<'static, 'static> {
                              "Service topology; [default: none]")
                             (@arg STRATEGY: --strategy -s +takes_value {valid_update_strategy}
                              "The update strategy; [default: none] [values: none, at-once, rolling]")
+                            (@arg UPDATE_WINDOW: --("update-window") +takes_value +multiple number_of_values(1) {valid_update_window}
+                             "Only restart into an update during this maintenance window, given as days, \
+                              times and an optional UTC offset or time zone, e.g. \"mon-fri 02:00-04:00 \
+                              +01:00\" or \"mon-fri 02:00-04:00 Europe/Paris\". May be given more than once")
+                            (@arg NO_UPDATE_WINDOWS: --("no-update-windows") conflicts_with("UPDATE_WINDOW")
+                             "Restart into updates as soon as they're found")
+                            (@arg UPDATE_BATCH_SIZE: --("update-batch-size") +takes_value {valid_update_batch_size}
+                             "How many members of the service group a rolling update updates at once, \
+                              as a number or a percentage, e.g. 25% [default: 1]")
+                            (@arg UPDATE_HEALTH_TIMEOUT: --("update-health-timeout") +takes_value {valid_numeric::<u64>}
+                             "Pause a rolling update if an updated member fails its health check for \
+                              this many seconds [default: 600]")
+                            (@arg UPDATE_ROLLBACK: --("update-rollback")
+                             "Roll back an update that fails its health check within the grace period")
+                            (@arg NO_UPDATE_ROLLBACK: --("no-update-rollback") conflicts_with("UPDATE_ROLLBACK")
+                             "Keep updates that fail their health check")
+                            (@arg UPDATE_ROLLBACK_GRACE: --("update-rollback-grace") +takes_value {valid_numeric::<u64>}
+                             "How many seconds an update has to pass its health check before it's \
+                              rolled back [default: 300]")
                             (@arg BIND: --bind +takes_value +multiple
                              "One or more service groups to bind to a configuration")
                             (@arg BINDING_MODE: --("binding-mode") +takes_value {valid_binding_mode}
                              "Governs how the presence or absence of binds affects service startup. `strict` blocks \
                               startup until all binds are present. [default: strict] [values: relaxed, strict]")
+                            (@arg STRICT_TEMPLATES: --("strict-templates")
+                             "Refuse to render configuration and hook templates that refer to undefined \
+                              variables, even if the package doesn't ask for that")
                             (@arg VERBOSE: -v "Verbose output; shows file and line/column numbers")
                             (@arg NO_COLOR: --("no-color") "Turn ANSI color off")
                             (@arg JSON: --("json-logging") "Use structured JSON logging for the Supervisor. \
                                                             Implies NO_COLOR")
                             (@arg HEALTH_CHECK_INTERVAL: --("health-check-interval") -i +takes_value {valid_health_check_interval}
                              "The interval (seconds) on which to run health checks [default: 30]")
+                            (@arg RESTART_POLICY: --("restart-policy") +takes_value {valid_restart_policy}
+                             "Whether to restart the service when it exits; `on-failure` only restarts \
+                              it after a non-zero exit code. [default: always] [values: always, on-failure, never]")
+                            (@arg RESTART_DELAY: --("restart-delay") +takes_value {valid_numeric::<u64>}
+                             "Seconds to wait before restarting a service which was already restarted \
+                              within the restart window; doubled for each further restart [default: 2]")
+                            (@arg RESTART_MAX_DELAY: --("restart-max-delay") +takes_value {valid_numeric::<u64>}
+                             "The longest time (seconds) to wait between restarts [default: 120]")
+                            (@arg RESTART_LIMIT: --("restart-limit") +takes_value {valid_numeric::<u32>}
+                             "Stop restarting the service after this many restarts within the restart \
+                              window; 0 never stops [default: 0]")
+                            (@arg RESTART_WINDOW: --("restart-window") +takes_value {valid_numeric::<u64>}
+                             "The window (seconds) within which restarts are counted [default: 600]")
+                            (@arg CPU_WEIGHT: --("cpu-weight") +takes_value {valid_cpu_weight}
+                             "The service's share of CPU time relative to other services, from 1 to \
+                              10000 (Linux only) [default: 100]")
+                            (@arg CPU_QUOTA: --("cpu-quota") +takes_value {valid_cpu_quota}
+                             "The most CPU time the service may use, as a number of CPUs, e.g. 0.5 \
+                              (Linux only)")
+                            (@arg MEMORY_MAX: --("memory-max") +takes_value {valid_memory_size}
+                             "The most memory the service may use, e.g. 512M or 2G (Linux only)")
+                            (@arg PIDS_MAX: --("pids-max") +takes_value {valid_numeric::<u64>}
+                             "The most processes and threads the service may run (Linux only)")
+                            (@arg LOG_FILE: --("log-file")
+                             "Write the service's output to a rotated file under /hab/svc/<name>/logs")
+                            (@arg NO_LOG_FILE: --("no-log-file") conflicts_with("LOG_FILE")
+                             "Don't write the service's output to a file, even if the Supervisor does so \
+                              by default")
+                            (@arg LOG_MAX_SIZE: --("log-max-size") +takes_value {valid_memory_size}
+                             "Rotate the service's log file once it grows past this size, e.g. 10M \
+                              [default: 10M]")
+                            (@arg LOG_MAX_AGE: --("log-max-age") +takes_value {valid_numeric::<u64>}
+                             "Rotate the service's log file once it is this many seconds old; 0 never \
+                              rotates on age [default: 86400]")
+                            (@arg LOG_RETAIN: --("log-retain") +takes_value {valid_numeric::<u32>}
+                             "The number of rotated log files to keep [default: 5]")
     );
 
     if feature_flags.contains(FeatureFlag::EVENT_STREAM) {
@@ -1077,12 +1258,26 @@ fn sub_svc_start() -> App<'static, 'static> {
     )
 }
 
+fn sub_svc_health() -> App<'static, 'static> {
+    clap_app!(@subcommand health =>
+        (about: "Show the results of a loaded service's health checks.")
+        (@arg PKG_IDENT: +required +takes_value {valid_ident}
+            "A Habitat package identifier (ex: core/redis)")
+        (@arg HISTORY: --history
+            "Show every result the Supervisor has kept, oldest first, rather than only the latest")
+        (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+            "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+    )
+}
+
 // `hab svc status` is the canonical location for this command, but we
 // have historically used `hab sup status` as an alias.
 pub fn sub_svc_status() -> App<'static, 'static> {
     clap_app!(@subcommand status =>
         (about: "Query the status of Habitat services.")
         (@arg PKG_IDENT: +takes_value {valid_ident} "A Habitat package identifier (ex: core/redis)")
+        (@arg WATCH: --watch -w
+            "Keep printing the status of services whenever it changes, until interrupted")
         (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
         "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
     )
@@ -1104,8 +1299,9 @@ fn sub_svc_load() -> App<'static, 'static> {
         (about: "Load a service to be started and supervised by Habitat from a package \
             identifier. If an installed package doesn't satisfy the given package \
             identifier, a suitable package will be installed from Builder.")
-        (@arg PKG_IDENT: +required +takes_value {valid_ident}
-            "A Habitat package identifier (ex: core/redis)")
+        (@arg PKG_IDENT: +required +takes_value {valid_constrained_ident}
+            "A Habitat package identifier (ex: core/redis), optionally followed by a version \
+            constraint that updates are kept within (ex: core/postgresql@~9.6)")
         (@arg APPLICATION: --application -a +takes_value requires[ENVIRONMENT]
             "Application name; [default: not set].")
         (@arg ENVIRONMENT: --environment -e +takes_value requires[APPLICATION]
@@ -1122,17 +1318,72 @@ fn sub_svc_load() -> App<'static, 'static> {
             "Service topology; [default: none]")
         (@arg STRATEGY: --strategy -s +takes_value {valid_update_strategy}
             "The update strategy; [default: none] [values: none, at-once, rolling]")
+        (@arg UPDATE_WINDOW: --("update-window") +takes_value +multiple number_of_values(1) {valid_update_window}
+            "Only restart into an update during this maintenance window, given as days, times and \
+             an optional UTC offset or time zone, e.g. \"mon-fri 02:00-04:00 +01:00\" or \
+             \"mon-fri 02:00-04:00 Europe/Paris\". May be given more than once")
+        (@arg NO_UPDATE_WINDOWS: --("no-update-windows") conflicts_with("UPDATE_WINDOW")
+            "Restart into updates as soon as they're found")
+        (@arg UPDATE_BATCH_SIZE: --("update-batch-size") +takes_value {valid_update_batch_size}
+            "How many members of the service group a rolling update updates at once, as a number \
+             or a percentage, e.g. 25% [default: 1]")
+        (@arg UPDATE_HEALTH_TIMEOUT: --("update-health-timeout") +takes_value {valid_numeric::<u64>}
+            "Pause a rolling update if an updated member fails its health check for this many \
+             seconds [default: 600]")
+        (@arg UPDATE_ROLLBACK: --("update-rollback")
+            "Roll back an update that fails its health check within the grace period")
+        (@arg NO_UPDATE_ROLLBACK: --("no-update-rollback") conflicts_with("UPDATE_ROLLBACK")
+            "Keep updates that fail their health check")
+        (@arg UPDATE_ROLLBACK_GRACE: --("update-rollback-grace") +takes_value {valid_numeric::<u64>}
+            "How many seconds an update has to pass its health check before it's rolled back \
+             [default: 300]")
         (@arg BIND: --bind +takes_value +multiple
             "One or more service groups to bind to a configuration")
         (@arg BINDING_MODE: --("binding-mode") +takes_value {valid_binding_mode}
              "Governs how the presence or absence of binds affects service startup. `strict` blocks \
               startup until all binds are present. [default: strict] [values: relaxed, strict]")
+        (@arg STRICT_TEMPLATES: --("strict-templates")
+            "Refuse to render configuration and hook templates that refer to undefined variables, \
+             even if the package doesn't ask for that")
         (@arg FORCE: --force -f "Load or reload an already loaded service. If the service \
             was previously loaded and running this operation will also restart the service")
         (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
             "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
         (@arg HEALTH_CHECK_INTERVAL: --("health-check-interval") -i +takes_value {valid_health_check_interval}
             "The interval (seconds) on which to run health checks [default: 30]")
+        (@arg RESTART_POLICY: --("restart-policy") +takes_value {valid_restart_policy}
+            "Whether to restart the service when it exits; `on-failure` only restarts it after a \
+             non-zero exit code. [default: always] [values: always, on-failure, never]")
+        (@arg RESTART_DELAY: --("restart-delay") +takes_value {valid_numeric::<u64>}
+            "Seconds to wait before restarting a service which was already restarted within the \
+             restart window; doubled for each further restart [default: 2]")
+        (@arg RESTART_MAX_DELAY: --("restart-max-delay") +takes_value {valid_numeric::<u64>}
+            "The longest time (seconds) to wait between restarts [default: 120]")
+        (@arg RESTART_LIMIT: --("restart-limit") +takes_value {valid_numeric::<u32>}
+            "Stop restarting the service after this many restarts within the restart window; 0 \
+             never stops [default: 0]")
+        (@arg RESTART_WINDOW: --("restart-window") +takes_value {valid_numeric::<u64>}
+            "The window (seconds) within which restarts are counted [default: 600]")
+        (@arg CPU_WEIGHT: --("cpu-weight") +takes_value {valid_cpu_weight}
+            "The service's share of CPU time relative to other services, from 1 to 10000 (Linux \
+             only) [default: 100]")
+        (@arg CPU_QUOTA: --("cpu-quota") +takes_value {valid_cpu_quota}
+            "The most CPU time the service may use, as a number of CPUs, e.g. 0.5 (Linux only)")
+        (@arg MEMORY_MAX: --("memory-max") +takes_value {valid_memory_size}
+            "The most memory the service may use, e.g. 512M or 2G (Linux only)")
+        (@arg PIDS_MAX: --("pids-max") +takes_value {valid_numeric::<u64>}
+            "The most processes and threads the service may run (Linux only)")
+        (@arg LOG_FILE: --("log-file")
+            "Write the service's output to a rotated file under /hab/svc/<name>/logs")
+        (@arg NO_LOG_FILE: --("no-log-file") conflicts_with("LOG_FILE")
+            "Don't write the service's output to a file, even if the Supervisor does so by default")
+        (@arg LOG_MAX_SIZE: --("log-max-size") +takes_value {valid_memory_size}
+            "Rotate the service's log file once it grows past this size, e.g. 10M [default: 10M]")
+        (@arg LOG_MAX_AGE: --("log-max-age") +takes_value {valid_numeric::<u64>}
+            "Rotate the service's log file once it is this many seconds old; 0 never rotates on age \
+             [default: 86400]")
+        (@arg LOG_RETAIN: --("log-retain") +takes_value {valid_numeric::<u32>}
+            "The number of rotated log files to keep [default: 5]")
     );
 
     if cfg!(windows) {
@@ -1313,6 +1564,62 @@ fn valid_update_strategy(val: String) -> result::Result<(), String> {
     }
 }
 
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_update_batch_size(val: String) -> result::Result<(), String> {
+    match habitat_sup_protocol::types::UpdateRollout::default().set_batch_size(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.msg),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_update_window(val: String) -> result::Result<(), String> {
+    match habitat_sup_protocol::types::UpdateWindow::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.msg),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_ctl_secret_scope(val: String) -> result::Result<(), String> {
+    match habitat_sup_protocol::ctl_secrets::Scope::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("Scope: '{}' is not valid", &val)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_restart_policy(val: String) -> result::Result<(), String> {
+    match habitat_sup_protocol::types::RestartPolicy::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("Restart policy: '{}' is not valid", &val)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_cpu_weight(val: String) -> result::Result<(), String> {
+    match val.parse::<u32>() {
+        Ok(weight) if weight >= 1 && weight <= 10_000 => Ok(()),
+        _ => Err(format!("CPU weight: '{}' is not a number from 1 to 10000", &val)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_cpu_quota(val: String) -> result::Result<(), String> {
+    match CpuQuota::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("CPU quota: {}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_memory_size(val: String) -> result::Result<(), String> {
+    match MemorySize::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Memory size: {}", e)),
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
 fn valid_ident(val: String) -> result::Result<(), String> {
     match PackageIdent::from_str(&val) {
@@ -1325,6 +1632,14 @@ fn valid_ident(val: String) -> result::Result<(), String> {
     }
 }
 
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_constrained_ident(val: String) -> result::Result<(), String> {
+    match VersionConstraint::parse_ident(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
 fn valid_target(val: String) -> result::Result<(), String> {
     match PackageTarget::from_str(&val) {
@@ -0,0 +1,113 @@
+use crate::error::Result;
+use toml::{self,
+           value::Table,
+           Value};
+
+/// A difference between two configurations, at a key given as the dotted path of tables it's in.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    Added(String, Value),
+    Removed(String, Value),
+    Changed(String, Value, Value),
+}
+
+pub fn start(old_cfg: &str, new_cfg: &str) -> Result<()> {
+    let old_cfg: Table = toml::from_str(old_cfg)?;
+    let new_cfg: Table = toml::from_str(new_cfg)?;
+    for change in changes(&old_cfg, &new_cfg) {
+        match change {
+            Change::Added(key, value) => println!("+ {} = {}", key, value),
+            Change::Removed(key, value) => println!("- {} = {}", key, value),
+            Change::Changed(key, old, new) => {
+                println!("- {} = {}", key, old);
+                println!("+ {} = {}", key, new);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The differences between two configurations, ordered by key. Tables are compared key by key,
+/// while any other value, including an array, is compared as a whole.
+pub fn changes(old_cfg: &Table, new_cfg: &Table) -> Vec<Change> {
+    let old_cfg = flatten(old_cfg);
+    let mut new_cfg = flatten(new_cfg);
+    let mut changes = Vec::new();
+    for (key, old) in old_cfg {
+        match new_cfg.iter().position(|(k, _)| *k == key) {
+            Some(i) => {
+                let (_, new) = new_cfg.remove(i);
+                if new != old {
+                    changes.push(Change::Changed(key, old, new));
+                }
+            }
+            None => changes.push(Change::Removed(key, old)),
+        }
+    }
+    changes.extend(new_cfg.into_iter()
+                          .map(|(key, value)| Change::Added(key, value)));
+    changes.sort_by(|a, b| key_of(a).cmp(key_of(b)));
+    changes
+}
+
+fn key_of(change: &Change) -> &str {
+    match *change {
+        Change::Added(ref key, _)
+        | Change::Removed(ref key, _)
+        | Change::Changed(ref key, ..) => key,
+    }
+}
+
+fn flatten(cfg: &Table) -> Vec<(String, Value)> {
+    let mut values = Vec::new();
+    for (key, value) in cfg {
+        match *value {
+            Value::Table(ref table) => {
+                values.extend(flatten(table).into_iter()
+                                            .map(|(k, v)| (format!("{}.{}", key, k), v)))
+            }
+            _ => values.push((key.clone(), value.clone())),
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn changes_are_found_key_by_key() {
+        let old_cfg: Table = toml::from_str(r#"
+            port = 6379
+            tcp-backlog = 511
+            [tls]
+            enabled = false
+            ciphers = ["a", "b"]
+            "#).unwrap();
+        let new_cfg: Table = toml::from_str(r#"
+            port = 6380
+            [tls]
+            enabled = false
+            ciphers = ["a"]
+            [limits]
+            clients = 100
+            "#).unwrap();
+        assert_eq!(changes(&old_cfg, &new_cfg),
+                   vec![Change::Added("limits.clients".to_string(), Value::Integer(100)),
+                        Change::Changed("port".to_string(),
+                                        Value::Integer(6379),
+                                        Value::Integer(6380)),
+                        Change::Removed("tcp-backlog".to_string(), Value::Integer(511)),
+                        Change::Changed("tls.ciphers".to_string(),
+                                        Value::Array(vec![Value::String("a".to_string()),
+                                                          Value::String("b".to_string())]),
+                                        Value::Array(vec![Value::String("a".to_string())])),]);
+    }
+
+    #[test]
+    fn identical_configurations_have_no_changes() {
+        let cfg: Table = toml::from_str("port = 6379\n[tls]\nenabled = true\n").unwrap();
+        assert!(changes(&cfg, &cfg).is_empty());
+    }
+}
@@ -1 +1 @@
-
+pub mod diff;
@@ -1,5 +1,6 @@
 pub mod bldr;
 pub mod cli;
+pub mod config;
 pub mod launcher;
 pub mod origin;
 pub mod pkg;
@@ -20,10 +20,12 @@ pub fn start(ui: &mut UI,
              default_toml_path: &Path,
              user_toml_path: Option<&Path>,
              mock_data_path: Option<&Path>,
+             partials_path: &Path,
              print: bool,
              render: bool,
              render_dir: &Path,
-             quiet: bool)
+             quiet: bool,
+             strict: bool)
              -> Result<()> {
     // Strip the file name out of our passed template
     let file_name = Path::new(template_path.file_name().expect("valid template file"));
@@ -86,11 +88,23 @@ pub fn start(ui: &mut UI,
 
     // create a template renderer
     let mut renderer = TemplateRenderer::new();
+    renderer.set_strict(strict);
+    // register any partials the template might include
+    if partials_path.is_dir() && !quiet {
+        ui.begin(format!("Importing partials: {}", partials_path.display()))?;
+    }
+    renderer.register_partials(partials_path)?;
     // register our template
-    renderer.register_template_string(&template, &template)
+    let name = template_path.display().to_string();
+    renderer.register_template_string(&name, &template)
             .expect("Could not register template content");
+    // report every variable the template refers to that the data doesn't have, as a Supervisor
+    // rendering it in strict mode would refuse to
+    for variable in renderer.missing_variables(&name, &data)? {
+        ui.warn(format!("Undefined variable: {}", variable))?;
+    }
     // render our JSON override in our template.
-    let rendered_template = renderer.render(&template, &data)?;
+    let rendered_template = renderer.render(&name, &data)?;
 
     if print {
         if !quiet {
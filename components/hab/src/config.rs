@@ -17,10 +17,23 @@ const CLI_CONFIG_PATH: &str = "hab/etc/cli.toml";
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Config {
-    pub auth_token: Option<String>,
-    pub origin:     Option<String>,
-    pub ctl_secret: Option<String>,
-    pub bldr_url:   Option<String>,
+    pub auth_token:          Option<String>,
+    pub origin:              Option<String>,
+    pub ctl_secret:          Option<String>,
+    pub bldr_url:            Option<String>,
+    /// CA certificates to verify Supervisors' ctl gateway certificates with. Setting this
+    /// makes every connection to a Supervisor use TLS.
+    #[serde(default)]
+    pub ctl_tls_ca_cert:     Option<PathBuf>,
+    /// The certificate and key to present to Supervisors which require client certificates.
+    #[serde(default)]
+    pub ctl_tls_cert:        Option<PathBuf>,
+    #[serde(default)]
+    pub ctl_tls_key:         Option<PathBuf>,
+    /// The name Supervisors' certificates must be valid for, if not the host given with
+    /// `--remote-sup`.
+    #[serde(default)]
+    pub ctl_tls_server_name: Option<String>,
 }
 
 impl ConfigFile for Config {
@@ -29,10 +42,14 @@ impl ConfigFile for Config {
 
 impl Default for Config {
     fn default() -> Self {
-        Config { auth_token: None,
-                 origin:     None,
-                 ctl_secret: None,
-                 bldr_url:   None, }
+        Config { auth_token:          None,
+                 origin:              None,
+                 ctl_secret:          None,
+                 bldr_url:            None,
+                 ctl_tls_ca_cert:     None,
+                 ctl_tls_cert:        None,
+                 ctl_tls_key:         None,
+                 ctl_tls_server_name: None, }
     }
 }
 
@@ -31,6 +31,7 @@ pub enum Error {
     CannotRemoveFromChannel((String, String)),
     CannotRemovePackage(hcore::package::PackageIdent, usize),
     CommandNotFoundInPkg((String, String)),
+    ConfigVersionNotFound(String, u64),
     CryptoCLI(String),
     CtlClient(SrvClientError),
     DockerDaemonDown,
@@ -94,6 +95,11 @@ impl fmt::Display for Error {
                 format!("`{}' was not found under any 'PATH' directories in the {} package",
                         c, p)
             }
+            Error::ConfigVersionNotFound(ref service_group, version) => {
+                format!("Configuration version {} of {} isn't kept by the Supervisor; see `hab \
+                         config history {}` for the versions that are",
+                        version, service_group, service_group)
+            }
             Error::CryptoCLI(ref e) => e.to_string(),
             Error::CtlClient(ref e) => e.to_string(),
             Error::DockerDaemonDown => {
@@ -199,6 +205,7 @@ impl error::Error for Error {
             Error::CommandNotFoundInPkg(_) => {
                 "Command was not found under any 'PATH' directories in the package"
             }
+            Error::ConfigVersionNotFound(..) => "The configuration version isn't kept",
             Error::CryptoCLI(_) => "A cryptographic error has occurred",
             Error::CtlClient(ref err) => err.description(),
             Error::DockerDaemonDown => "The Docker daemon could not be found.",
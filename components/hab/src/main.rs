@@ -7,6 +7,8 @@ extern crate lazy_static;
 #[macro_use]
 extern crate log;
 
+use chrono::{TimeZone,
+             Utc};
 use clap::{ArgMatches,
            Shell};
 use env_logger;
@@ -44,7 +46,8 @@ use habitat_common::{self as common,
 #[cfg(windows)]
 use habitat_core::crypto::dpapi::encrypt;
 use habitat_core::{crypto::{init,
-                            keys::PairType,
+                            keys::{box_key_pair::WrappedSealedBox,
+                                   PairType},
                             BoxKeyPair,
                             SigKeyPair},
                    env as henv,
@@ -56,17 +59,23 @@ use habitat_core::{crypto::{init,
                                  ShutdownTimeout},
                    package::{target,
                              PackageIdent,
-                             PackageTarget},
-                   service::{HealthCheckInterval,
+                             PackageTarget,
+                             VersionConstraint},
+                   service::{CpuQuota,
+                             HealthCheckInterval,
+                             MemorySize,
                              ServiceGroup},
                    url::{bldr_url_from_env,
                          default_bldr_url},
                    ChannelIdent};
 use habitat_sup_client::{SrvClient,
-                         SrvClientError};
+                         SrvClientError,
+                         SrvClientTls};
 use habitat_sup_protocol::{self as sup_proto,
                            codec::*,
                            ctl::ServiceBindList,
+                           ctl_secrets::{CtlSecrets,
+                                         Scope},
                            net::ErrCode,
                            types::*};
 use pbr;
@@ -101,7 +110,11 @@ lazy_static! {
              "state",
              "elapsed (s)",
              "pid",
-             "group",]
+             "group",
+             "restarts",
+             "health",
+             "updating",
+             "templates",]
     };
 }
 
@@ -173,6 +186,9 @@ fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
         ("config", Some(m)) => {
             match m.subcommand() {
                 ("apply", Some(m)) => sub_svc_set(m)?,
+                ("diff", Some(m)) => sub_svc_cfg_diff(m)?,
+                ("history", Some(m)) => sub_svc_cfg_history(m)?,
+                ("rollback", Some(m)) => sub_svc_cfg_rollback(m)?,
                 ("show", Some(m)) => sub_svc_config(m)?,
                 _ => unreachable!(),
             }
@@ -288,6 +304,7 @@ fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
                         _ => unreachable!(),
                     }
                 }
+                ("health", Some(m)) => sub_svc_health(m)?,
                 ("load", Some(m)) => sub_svc_load(m)?,
                 ("unload", Some(m)) => sub_svc_unload(m, feature_flags)?,
                 ("start", Some(m)) => sub_svc_start(m)?,
@@ -298,10 +315,14 @@ fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
         }
         ("sup", Some(m)) => {
             match m.subcommand() {
+                ("apply", Some(m)) => sub_sup_apply(m)?,
                 ("depart", Some(m)) => sub_sup_depart(m)?,
                 ("secret", Some(m)) => {
                     match m.subcommand() {
                         ("generate", _) => sub_sup_secret_generate()?,
+                        ("create", Some(sc)) => sub_sup_secret_create(sc)?,
+                        ("revoke", Some(sc)) => sub_sup_secret_revoke(sc)?,
+                        ("list", _) => sub_sup_secret_list()?,
                         _ => unreachable!(),
                     }
                 }
@@ -689,9 +710,12 @@ fn sub_plan_render(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
 
     let mock_data_path = m.value_of("MOCK_DATA").map(Path::new);
 
+    let partials_path = Path::new(m.value_of("PARTIALS_DIR").unwrap());
+
     let print = m.is_present("PRINT");
     let render = !m.is_present("NO_RENDER");
     let quiet = m.is_present("QUIET");
+    let strict = m.is_present("STRICT");
 
     let render_dir = Path::new(m.value_of("RENDER_DIR").unwrap());
 
@@ -700,10 +724,12 @@ fn sub_plan_render(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
                                  default_toml_path,
                                  user_toml_path,
                                  mock_data_path,
+                                 partials_path,
                                  print,
                                  render,
                                  render_dir,
-                                 quiet)
+                                 quiet,
+                                 strict)
 }
 
 fn sub_pkg_install(ui: &mut UI, m: &ArgMatches<'_>, feature_flags: FeatureFlag) -> Result<()> {
@@ -733,12 +759,29 @@ fn sub_pkg_install(ui: &mut UI, m: &ArgMatches<'_>, feature_flags: FeatureFlag)
 
     init();
 
-    for install_source in install_sources.iter() {
+    for (install_source, constraint) in install_sources {
+        let install_source = match constraint {
+            Some(constraint) => {
+                let ident = common::command::package::install::latest_matching(
+                    ui,
+                    &url,
+                    &channel,
+                    (install_source.as_ref(), PackageTarget::active_target()),
+                    &constraint,
+                    PRODUCT,
+                    VERSION,
+                    &*FS_ROOT,
+                    token.as_ref().map(String::as_str),
+                )?;
+                InstallSource::from((ident, PackageTarget::active_target()))
+            }
+            None => install_source,
+        };
         let pkg_install =
             common::command::package::install::start(ui,
                                                      &url,
                                                      &channel,
-                                                     install_source,
+                                                     &install_source,
                                                      PRODUCT,
                                                      VERSION,
                                                      &*FS_ROOT,
@@ -891,13 +934,8 @@ fn sub_pkg_channels(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
 }
 
 fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
-    let cfg = config::load()?;
-    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
-    let secret_key = ctl_secret_key(&cfg)?;
     let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
     let mut ui = ui();
-    let mut validate = sup_proto::ctl::SvcValidateCfg::default();
-    validate.service_group = Some(service_group.clone().into());
     let mut buf = Vec::with_capacity(sup_proto::butterfly::MAX_SVC_CFG_SIZE);
     let cfg_len = match m.value_of("FILE") {
         Some("-") | None => io::stdin().read_to_end(&mut buf)?,
@@ -911,7 +949,29 @@ fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
                          sup_proto::butterfly::MAX_SVC_CFG_SIZE))?;
         process::exit(1);
     }
-    validate.cfg = Some(buf.clone());
+    if m.is_present("DRY_RUN") {
+        return dry_run_svc_cfg(&mut ui, m, service_group, buf);
+    }
+    let version = value_t!(m, "VERSION_NUMBER", u64).unwrap();
+    set_svc_cfg(&mut ui, m, service_group, buf, version)
+}
+
+/// Validates a configuration with the Supervisor, then has it shared with the members of the
+/// service group as the given version.
+fn set_svc_cfg(ui: &mut UI,
+               m: &ArgMatches<'_>,
+               service_group: ServiceGroup,
+               buf: Vec<u8>,
+               version: u64)
+               -> Result<()> {
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
+    let validate = sup_proto::ctl::SvcValidateCfg { service_group: Some(service_group.clone()
+                                                                                     .into()),
+                                                    cfg: Some(buf.clone()),
+                                                    ..Default::default() };
     let cache = cache_key_path_from_matches(&m);
     let mut set = sup_proto::ctl::SvcSetCfg::default();
     match (service_group.org(), user_param_or_env(&m)) {
@@ -925,10 +985,10 @@ fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
             set.cfg = Some(user_pair.encrypt(&buf, Some(&service_pair))?.into_bytes());
             set.is_encrypted = Some(true);
         }
-        _ => set.cfg = Some(buf.to_vec()),
+        _ => set.cfg = Some(buf),
     }
     set.service_group = Some(service_group.into());
-    set.version = Some(value_t!(m, "VERSION_NUMBER", u64).unwrap());
+    set.version = Some(version);
     ui.begin(format!("Setting new configuration version {} for {}",
                      set.version
                         .as_ref()
@@ -939,33 +999,12 @@ fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
                         .map(ToString::to_string)
                         .unwrap_or_else(|| "UNKNOWN".to_string()),))?;
     ui.status(Status::Creating, "service configuration")?;
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
-                                                         conn.call(validate)
-                .for_each(|reply| match reply.message_id() {
-                    "NetOk" => Ok(()),
-                    "NetErr" => {
-                        let m = reply
-                            .parse::<sup_proto::net::NetErr>()
-                            .map_err(SrvClientError::Decode)?;
-                        match ErrCode::from_i32(m.code) {
-                            Some(ErrCode::InvalidPayload) => {
-                                ui.warn(m)?;
-                                Ok(())
-                            }
-                            _ => Err(SrvClientError::from(m)),
-                        }
-                    }
-                    _ => Err(SrvClientError::from(io::Error::from(
-                        io::ErrorKind::UnexpectedEof,
-                    ))),
-                })
-                                                     })
-                                                     .wait()?;
+    validate_svc_cfg(ui, &listen_ctl_addr, &secret_key, ctl_tls.as_ref(), validate)?;
     ui.status(Status::Applying, format!("via peer {}", listen_ctl_addr))?;
     // JW: We should not need to make two connections here. I need a way to return the
     // SrvClient from a for_each iterator so we can chain upon a successful stream but I don't
     // know if it's possible with this version of futures.
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
                                                          conn.call(set).for_each(|reply| {
                           match reply.message_id() {
                 "NetOk" => Ok(()),
@@ -986,14 +1025,246 @@ fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
     Ok(())
 }
 
+/// Has the Supervisor render a configuration change and prints a diff of each rendered file it
+/// would change, without applying it.
+fn dry_run_svc_cfg(ui: &mut UI,
+                   m: &ArgMatches<'_>,
+                   service_group: ServiceGroup,
+                   buf: Vec<u8>)
+                   -> Result<()> {
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
+    let validate = sup_proto::ctl::SvcValidateCfg { service_group: Some(service_group.clone()
+                                                                                     .into()),
+                                                    cfg: Some(buf.clone()),
+                                                    ..Default::default() };
+    let dry_run = sup_proto::ctl::SvcDryRunCfg { service_group: Some(service_group.clone()
+                                                                                  .into()),
+                                                 cfg: Some(buf),
+                                                 ..Default::default() };
+    ui.begin(format!("Rendering new configuration for {} without applying it",
+                     service_group))?;
+    validate_svc_cfg(ui, &listen_ctl_addr, &secret_key, ctl_tls.as_ref(), validate)?;
+    let mut changed = 0;
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
+        conn.call(dry_run).for_each(|reply| {
+                              match reply.message_id() {
+                                  "ServiceCfgFileDiff" => {
+                                      let file =
+                                          reply.parse::<sup_proto::types::ServiceCfgFileDiff>()
+                                               .map_err(SrvClientError::Decode)?;
+                                      print!("{}", file.diff);
+                                      changed += 1;
+                                      Ok(())
+                                  }
+                                  "NetOk" => Ok(()),
+                                  "NetErr" => {
+                                      let err = reply.parse::<sup_proto::net::NetErr>()
+                                                     .map_err(SrvClientError::Decode)?;
+                                      Err(SrvClientError::from(err))
+                                  }
+                                  _ => {
+                                      Err(SrvClientError::from(io::Error::from(
+                                          io::ErrorKind::UnexpectedEof,
+                                      )))
+                                  }
+                              }
+                          })
+    })
+                                                     .wait()?;
+    if changed == 0 {
+        ui.end("No rendered files would change")?;
+    } else {
+        ui.end(format!("{} rendered file(s) would change; nothing was applied", changed))?;
+    }
+    Ok(())
+}
+
+/// Has the Supervisor check a configuration against the schema of the service group's package.
+/// A schema the Supervisor can't use only produces a warning.
+fn validate_svc_cfg(ui: &mut UI,
+                    listen_ctl_addr: &ListenCtlAddr,
+                    secret_key: &str,
+                    ctl_tls: Option<&SrvClientTls>,
+                    validate: sup_proto::ctl::SvcValidateCfg)
+                    -> Result<()> {
+    SrvClient::connect(listen_ctl_addr, secret_key, ctl_tls).and_then(|conn| {
+                                                                conn.call(validate)
+                .for_each(|reply| match reply.message_id() {
+                    "NetOk" => Ok(()),
+                    "NetErr" => {
+                        let m = reply
+                            .parse::<sup_proto::net::NetErr>()
+                            .map_err(SrvClientError::Decode)?;
+                        match ErrCode::from_i32(m.code) {
+                            Some(ErrCode::InvalidPayload) => {
+                                ui.warn(m)?;
+                                Ok(())
+                            }
+                            _ => Err(SrvClientError::from(m)),
+                        }
+                    }
+                    _ => Err(SrvClientError::from(io::Error::from(
+                        io::ErrorKind::UnexpectedEof,
+                    ))),
+                })
+                                                            })
+                                                            .wait()?;
+    Ok(())
+}
+
+fn sub_svc_cfg_history(m: &ArgMatches<'_>) -> Result<()> {
+    let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
+    let mut tw = TabWriter::new(io::stdout());
+    writeln!(tw, "VERSION\tAPPLIED")?;
+    for version in svc_cfg_history(m, &service_group)? {
+        writeln!(tw,
+                 "{}\t{}",
+                 version.incarnation,
+                 Utc.timestamp(version.applied_at, 0).to_rfc3339())?;
+    }
+    tw.flush()?;
+    Ok(())
+}
+
+fn sub_svc_cfg_diff(m: &ArgMatches<'_>) -> Result<()> {
+    let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
+    let old_version = value_t!(m, "OLD_VERSION_NUMBER", u64).unwrap();
+    let new_version = value_t!(m, "NEW_VERSION_NUMBER", u64).unwrap();
+    let history = svc_cfg_history(m, &service_group)?;
+    let cfg_for = |version| {
+        history.iter()
+               .find(|v| v.incarnation == version)
+               .ok_or_else(|| Error::ConfigVersionNotFound(service_group.to_string(), version))
+               .and_then(|v| svc_cfg_version_toml(m, v))
+    };
+    command::config::diff::start(&cfg_for(old_version)?, &cfg_for(new_version)?)
+}
+
+fn sub_svc_cfg_rollback(m: &ArgMatches<'_>) -> Result<()> {
+    let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
+    let version = value_t!(m, "VERSION_NUMBER", u64).unwrap();
+    let history = svc_cfg_history(m, &service_group)?;
+    let cfg = history.iter()
+                     .find(|v| v.incarnation == version)
+                     .ok_or_else(|| {
+                         Error::ConfigVersionNotFound(service_group.to_string(), version)
+                     })
+                     .and_then(|v| svc_cfg_version_toml(m, v))?;
+    // The old configuration has to go out with a newer incarnation than the current one, or
+    // the members of the service group would ignore it. The history can't tell what that is, as
+    // it only has the versions this Supervisor accepted.
+    let new_version = match value_t!(m, "NEW_VERSION_NUMBER", u64) {
+        Ok(new_version) => new_version,
+        Err(_) => svc_cfg_incarnation(m, &service_group)? + 1,
+    };
+    let mut ui = ui();
+    ui.status(Status::Applying,
+              format!("configuration version {} of {} again", version, service_group))?;
+    set_svc_cfg(&mut ui, m, service_group, cfg.into_bytes(), new_version)
+}
+
+/// The incarnation of the latest configuration applied to a service group, as the Supervisor's
+/// census has it, or 0 if none has been.
+fn svc_cfg_incarnation(m: &ArgMatches<'_>, service_group: &ServiceGroup) -> Result<u64> {
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
+    let msg = sup_proto::ctl::SvcStatus::default();
+    let mut incarnation = 0;
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
+        conn.call(msg).for_each(|reply| {
+                          match reply.message_id() {
+                              "ServiceStatus" => {
+                                  let status = reply.parse::<sup_proto::types::ServiceStatus>()
+                                                    .map_err(SrvClientError::Decode)?;
+                                  let group: ServiceGroup = status.service_group.into();
+                                  if group == *service_group {
+                                      incarnation = status.config_incarnation.unwrap_or(0);
+                                  }
+                                  Ok(())
+                              }
+                              "NetOk" => Ok(()),
+                              "NetErr" => {
+                                  let err = reply.parse::<sup_proto::net::NetErr>()
+                                                 .map_err(SrvClientError::Decode)?;
+                                  Err(SrvClientError::from(err))
+                              }
+                              _ => {
+                                  Err(SrvClientError::from(io::Error::from(
+                                      io::ErrorKind::UnexpectedEof,
+                                  )))
+                              }
+                          }
+                      })
+    })
+                                                     .wait()?;
+    Ok(incarnation)
+}
+
+/// A configuration that a Supervisor has kept, as TOML. One that was applied encrypted is
+/// decrypted with the service group's key, which has to be in the key cache.
+fn svc_cfg_version_toml(m: &ArgMatches<'_>,
+                        version: &sup_proto::types::ServiceCfgVersion)
+                        -> Result<String> {
+    if !version.encrypted.unwrap_or(false) {
+        return Ok(version.cfg.clone());
+    }
+    let cache = cache_key_path_from_matches(m);
+    let cfg = BoxKeyPair::decrypt_with_path(&WrappedSealedBox::from(version.cfg.as_str()),
+                                            &cache)?;
+    Ok(String::from_utf8_lossy(&cfg).into_owned())
+}
+
+/// The configurations that a Supervisor has kept that were applied to a service group, oldest
+/// first.
+fn svc_cfg_history(m: &ArgMatches<'_>,
+                   service_group: &ServiceGroup)
+                   -> Result<Vec<sup_proto::types::ServiceCfgVersion>> {
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
+    let msg = sup_proto::ctl::SvcCfgHistory { service_group: Some(service_group.clone().into()), };
+    let mut versions = vec![];
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
+        conn.call(msg).for_each(|reply| {
+                          match reply.message_id() {
+                              "ServiceCfgVersion" => {
+                                  let version = reply.parse::<sup_proto::types::ServiceCfgVersion>()
+                                                     .map_err(SrvClientError::Decode)?;
+                                  versions.push(version);
+                                  Ok(())
+                              }
+                              "NetErr" => {
+                                  let err = reply.parse::<sup_proto::net::NetErr>()
+                                                 .map_err(SrvClientError::Decode)?;
+                                  Err(SrvClientError::from(err))
+                              }
+                              _ => {
+                                  Err(SrvClientError::from(io::Error::from(
+                                      io::ErrorKind::UnexpectedEof,
+                                  )))
+                              }
+                          }
+                      })
+    })
+                                                     .wait()?;
+    Ok(versions)
+}
+
 fn sub_svc_config(m: &ArgMatches<'_>) -> Result<()> {
     let ident = PackageIdent::from_str(m.value_of("PKG_IDENT").unwrap())?;
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
     let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
     let mut msg = sup_proto::ctl::SvcGetDefaultCfg::default();
     msg.ident = Some(ident.into());
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
                                                          conn.call(msg).for_each(|reply| {
                           match reply.message_id() {
                 "ServiceCfg" => {
@@ -1023,11 +1294,13 @@ fn sub_svc_load(m: &ArgMatches<'_>) -> Result<()> {
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
     let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
     let mut msg = sup_proto::ctl::SvcLoad::default();
     update_svc_load_from_input(m, &mut msg)?;
-    let ident: PackageIdent = m.value_of("PKG_IDENT").unwrap().parse()?;
+    let (ident, constraint) = VersionConstraint::parse_ident(m.value_of("PKG_IDENT").unwrap())?;
     msg.ident = Some(ident.into());
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+    msg.version_constraint = constraint.as_ref().map(ToString::to_string);
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
                                                          conn.call(msg)
                                                              .for_each(|m| handle_ctl_reply(&m))
                                                      })
@@ -1040,6 +1313,7 @@ fn sub_svc_unload(m: &ArgMatches<'_>, feature_flags: FeatureFlag) -> Result<()>
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
     let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
 
     let timeout_in_seconds = maybe_get_shutdown_timeout(m, feature_flags)?.map(Into::into);
     let signal = maybe_get_shutdown_signal(m, feature_flags)?.map(|s| s.to_string());
@@ -1047,7 +1321,7 @@ fn sub_svc_unload(m: &ArgMatches<'_>, feature_flags: FeatureFlag) -> Result<()>
     let msg = sup_proto::ctl::SvcUnload { ident: Some(ident.into()),
                                           signal,
                                           timeout_in_seconds };
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
                                                          conn.call(msg)
                                                              .for_each(|m| handle_ctl_reply(&m))
                                                      })
@@ -1086,9 +1360,10 @@ fn sub_svc_start(m: &ArgMatches<'_>) -> Result<()> {
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
     let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
     let mut msg = sup_proto::ctl::SvcStart::default();
     msg.ident = Some(ident.into());
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
                                                          conn.call(msg)
                                                              .for_each(|m| handle_ctl_reply(&m))
                                                      })
@@ -1100,12 +1375,33 @@ fn sub_svc_status(m: &ArgMatches<'_>) -> Result<()> {
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
     let secret_key = ctl_secret_key(&cfg)?;
-    let mut msg = sup_proto::ctl::SvcStatus::default();
-    if let Some(pkg) = m.value_of("PKG_IDENT") {
-        msg.ident = Some(PackageIdent::from_str(pkg)?.into());
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
+    let ident = match m.value_of("PKG_IDENT") {
+        Some(pkg) => Some(PackageIdent::from_str(pkg)?.into()),
+        None => None,
+    };
+
+    if m.is_present("WATCH") {
+        // Rows arrive one at a time, so there's nothing to align them with.
+        let msg = sup_proto::ctl::SvcStatusWatch { ident };
+        let mut print_header = true;
+        SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
+                                                             conn.call(msg).for_each(move |reply| {
+                                                                 let mut out = io::stdout();
+                                                                 print_svc_status(&mut out,
+                                                                                  &reply,
+                                                                                  print_header)?;
+                                                                 print_header = false;
+                                                                 out.flush()?;
+                                                                 Ok(())
+                                                             })
+                                                         })
+                                                         .wait()?;
+        return Ok(());
     }
 
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+    let msg = sup_proto::ctl::SvcStatus { ident };
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
                                                          let mut out = TabWriter::new(io::stdout());
                                                          conn.call(msg)
                 .into_future()
@@ -1136,11 +1432,29 @@ fn sub_svc_status(m: &ArgMatches<'_>) -> Result<()> {
     Ok(())
 }
 
+fn sub_svc_health(m: &ArgMatches<'_>) -> Result<()> {
+    let ident = PackageIdent::from_str(m.value_of("PKG_IDENT").unwrap())?;
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
+    let msg = sup_proto::ctl::SvcHealth { ident:   Some(ident.into()),
+                                          history: Some(m.is_present("HISTORY")), };
+
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
+                                                         conn.call(msg)
+                                                             .for_each(|m| print_svc_health(&m))
+                                                     })
+                                                     .wait()?;
+    Ok(())
+}
+
 fn sub_svc_stop(m: &ArgMatches<'_>, feature_flags: FeatureFlag) -> Result<()> {
     let ident = PackageIdent::from_str(m.value_of("PKG_IDENT").unwrap())?;
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
     let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
 
     let timeout_in_seconds = maybe_get_shutdown_timeout(m, feature_flags)?.map(Into::into);
     let signal = maybe_get_shutdown_signal(m, feature_flags)?.map(|s| s.to_string());
@@ -1149,7 +1463,7 @@ fn sub_svc_stop(m: &ArgMatches<'_>, feature_flags: FeatureFlag) -> Result<()> {
                                         timeout_in_seconds,
                                         signal };
 
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
                                                          conn.call(msg)
                                                              .for_each(|m| handle_ctl_reply(&m))
                                                      })
@@ -1162,6 +1476,7 @@ fn sub_file_put(m: &ArgMatches<'_>) -> Result<()> {
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
     let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
     let mut ui = ui();
     let mut msg = sup_proto::ctl::SvcFilePut::default();
     let file = Path::new(m.value_of("FILE").unwrap());
@@ -1200,7 +1515,7 @@ fn sub_file_put(m: &ArgMatches<'_>) -> Result<()> {
         }
         _ => msg.content = Some(buf.to_vec()),
     }
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
                                                          ui.status(Status::Applying,
                                                                    format!("via peer {}",
                                                                            listen_ctl_addr))
@@ -1231,14 +1546,59 @@ fn sub_file_put(m: &ArgMatches<'_>) -> Result<()> {
     Ok(())
 }
 
+fn sub_sup_apply(m: &ArgMatches<'_>) -> Result<()> {
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
+    let stack_path = Path::new(m.value_of("FILE").unwrap());
+    let msg = sup_proto::ctl::SupApply { stack:   Some(std::fs::read_to_string(stack_path)?),
+                                         prune:   Some(m.is_present("PRUNE")),
+                                         dry_run: Some(m.is_present("DRY_RUN")), };
+    let mut ui = ui();
+    ui.begin(format!("Applying {} via {}", stack_path.display(), listen_ctl_addr))?;
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
+        conn.call(msg).for_each(|reply| {
+                          match reply.message_id() {
+                              "ServiceSpecChange" => {
+                                  let change = reply.parse::<ServiceSpecChange>()
+                                                    .map_err(SrvClientError::Decode)?;
+                                  print_spec_change(&change);
+                                  Ok(())
+                              }
+                              _ => handle_ctl_reply(&reply),
+                          }
+                      })
+    })
+    .wait()?;
+    Ok(())
+}
+
+/// Prints one line of the plan `hab sup apply` makes, followed by each setting an update changes.
+fn print_spec_change(change: &ServiceSpecChange) {
+    let ident = &change.ident;
+    match service_spec_change::Action::from_i32(change.action) {
+        Some(service_spec_change::Action::Load) => println!("+ {} (load)", ident),
+        Some(service_spec_change::Action::Update) => println!("~ {} (update)", ident),
+        Some(service_spec_change::Action::Unload) => println!("- {} (unload)", ident),
+        Some(service_spec_change::Action::Unchanged) | None => {
+            println!("  {} (unchanged)", ident)
+        }
+    }
+    for difference in &change.differences {
+        println!("      {}", difference);
+    }
+}
+
 fn sub_sup_depart(m: &ArgMatches<'_>) -> Result<()> {
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
     let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, m.value_of("REMOTE_SUP"))?;
     let mut ui = ui();
     let mut msg = sup_proto::ctl::SupDepart::default();
     msg.member_id = Some(m.value_of("MEMBER_ID").unwrap().to_string());
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
         ui.begin(format!("Permanently marking {} as departed",
                          msg.member_id
                             .as_ref()
@@ -1275,6 +1635,51 @@ fn sub_sup_secret_generate() -> Result<()> {
     Ok(())
 }
 
+fn sub_sup_secret_create(m: &ArgMatches<'_>) -> Result<()> {
+    let mut ui = ui();
+    let sup_root = sup_proto::sup_root(None);
+    let name = m.value_of("NAME").unwrap(); // Required via clap
+    let scopes = m.values_of("SCOPE")
+                  .unwrap() // Required via clap
+                  .map(Scope::from_str)
+                  .collect::<result::Result<Vec<_>, _>>()?;
+    let mut secrets = CtlSecrets::load(&sup_root)?;
+    let key = secrets.create(name, scopes)?.key.clone();
+    secrets.save(&sup_root)?;
+    ui.info(key)?;
+    Ok(())
+}
+
+fn sub_sup_secret_revoke(m: &ArgMatches<'_>) -> Result<()> {
+    let mut ui = ui();
+    let sup_root = sup_proto::sup_root(None);
+    let name = m.value_of("NAME").unwrap(); // Required via clap
+    let mut secrets = CtlSecrets::load(&sup_root)?;
+    if secrets.revoke(name).is_none() {
+        ui.fatal(format!("No ctl secret named {}", name))?;
+        process::exit(1);
+    }
+    secrets.save(&sup_root)?;
+    ui.status(Status::Deleted, format!("ctl secret {}", name))?;
+    Ok(())
+}
+
+fn sub_sup_secret_list() -> Result<()> {
+    let secrets = CtlSecrets::load(sup_proto::sup_root(None))?;
+    let mut tw = TabWriter::new(io::stdout());
+    writeln!(&mut tw, "NAME\tSCOPES")?;
+    for secret in secrets.iter() {
+        let scopes = secret.scopes
+                           .iter()
+                           .map(ToString::to_string)
+                           .collect::<Vec<_>>()
+                           .join(",");
+        writeln!(&mut tw, "{}\t{}", secret.name, scopes)?;
+    }
+    tw.flush()?;
+    Ok(())
+}
+
 fn sub_supportbundle(ui: &mut UI) -> Result<()> {
     init();
 
@@ -1421,6 +1826,36 @@ fn ctl_secret_key(config: &Config) -> Result<String> {
     }
 }
 
+/// TLS settings for connecting to the Supervisor at `remote_sup` (or the local one), if
+/// `cli.toml` sets a CA certificate to verify Supervisors with.
+fn ctl_tls_config(config: &Config, remote_sup: Option<&str>) -> Result<Option<SrvClientTls>> {
+    let ca_cert_path = match config.ctl_tls_ca_cert {
+        Some(ref path) => path.clone(),
+        None => return Ok(None),
+    };
+    let client_cert = match (&config.ctl_tls_cert, &config.ctl_tls_key) {
+        (Some(cert), Some(key)) => Some((cert.clone(), key.clone())),
+        (None, None) => None,
+        _ => {
+            return Err(Error::ArgumentError("Both ctl_tls_cert and ctl_tls_key must be set in \
+                                             cli.toml to present a client certificate"));
+        }
+    };
+    // Without a name in the config, use the host from `--remote-sup`. IP addresses can't be
+    // checked against a certificate, so they need the name set explicitly.
+    let server_name = match (&config.ctl_tls_server_name, remote_sup) {
+        (Some(name), _) => name.clone(),
+        (None, Some(remote_sup)) => {
+            let host = remote_sup.rsplitn(2, ':').last().unwrap_or(remote_sup);
+            host.trim_start_matches('[').trim_end_matches(']').to_string()
+        }
+        (None, None) => "localhost".to_string(),
+    };
+    Ok(Some(SrvClientTls { ca_cert_path,
+                           client_cert,
+                           server_name }))
+}
+
 /// Check to see if an auth token exists and convert it to a string slice if it does. Unlike
 /// auth_token_param_or_env, it's ok for no auth token to be present here. This is useful for
 /// commands that can optionally take an auth token for operating on private packages.
@@ -1530,11 +1965,21 @@ fn active_target() -> PackageTarget {
     }
 }
 
-fn install_sources_from_matches(matches: &ArgMatches<'_>) -> Result<Vec<InstallSource>> {
+/// Identifiers may be followed by a version constraint, e.g. `core/postgresql@~9.6`, in which
+/// case the latest release matching it is installed.
+fn install_sources_from_matches(matches: &ArgMatches<'_>)
+                                -> Result<Vec<(InstallSource, Option<VersionConstraint>)>> {
     matches
         .values_of("PKG_IDENT_OR_ARTIFACT")
         .unwrap() // Required via clap
-        .map(|t| t.parse().map_err(Error::from))
+        .map(|t| -> Result<_> {
+            if t.contains('@') && !Path::new(t).is_file() {
+                let (ident, constraint) = VersionConstraint::parse_ident(t)?;
+                Ok((InstallSource::from((ident, PackageTarget::active_target())), constraint))
+            } else {
+                Ok((t.parse()?, None))
+            }
+        })
         .collect()
 }
 
@@ -1594,6 +2039,14 @@ fn print_svc_status<T>(out: &mut T,
             reply.parse::<sup_proto::types::ServiceStatus>()
                  .map_err(SrvClientError::Decode)?
         }
+        "ServiceStatusRemoved" => {
+            let removed = reply.parse::<sup_proto::types::ServiceStatusRemoved>()
+                               .map_err(SrvClientError::Decode)?;
+            writeln!(out,
+                     "{} ({}) is no longer loaded",
+                     removed.ident, removed.service_group)?;
+            return Ok(());
+        }
         "NetOk" => {
             println!("No services loaded.");
             return Ok(());
@@ -1623,6 +2076,31 @@ fn print_svc_status<T>(out: &mut T,
             }
         }
     };
+    let svc_restarts = match status.restart {
+        Some(restart) if restart.gave_up => format!("{} (gave up)", restart.count),
+        Some(restart) => {
+            match restart.next_restart_in {
+                Some(secs) => format!("{} (next in {}s)", restart.count, secs),
+                None => restart.count.to_string(),
+            }
+        }
+        None => "<none>".to_string(),
+    };
+    let svc_health = status.health
+                           .and_then(sup_proto::types::HealthCheckStatus::from_i32)
+                           .map_or_else(|| "<none>".to_string(), |h| h.to_string());
+    let svc_updating = if status.update_in_progress.unwrap_or(false) {
+        "yes".to_string()
+    } else if let Some(until) = status.update_pending_until {
+        format!("pending until {}", Utc.timestamp(until, 0).to_rfc3339())
+    } else {
+        "no".to_string()
+    };
+    let svc_templates = match status.render_error {
+        // Tabs and newlines would break the columns up.
+        Some(ref err) => format!("refused: {}", err.replace(|c| c == '\t' || c == '\n', " ")),
+        None => "ok".to_string(),
+    };
     if print_header {
         writeln!(out, "{}", STATUS_HEADER.join("\t")).unwrap();
     }
@@ -1634,14 +2112,65 @@ fn print_svc_status<T>(out: &mut T,
     //
     // TODO: Remove this when we have a stable machine-readable alternative
     // that scripts could depend on
+    //
+    // New columns are only ever appended, for the same reason.
     writeln!(out,
-             "{}\tstandalone\t{}\t{}\t{}\t{}\t{}",
+             "{}\tstandalone\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
              status.ident,
              DesiredState::from_str(&svc_desired_state)?,
              ProcessState::from_str(&svc_state)?,
              svc_elapsed,
              svc_pid,
-             status.service_group,)?;
+             status.service_group,
+             svc_restarts,
+             svc_health,
+             svc_updating,
+             svc_templates)?;
+    Ok(())
+}
+
+fn print_svc_health(reply: &SrvMessage) -> result::Result<(), SrvClientError> {
+    let record = match reply.message_id() {
+        "HealthCheckRecord" => {
+            reply.parse::<sup_proto::types::HealthCheckRecord>()
+                 .map_err(SrvClientError::Decode)?
+        }
+        "NetErr" => {
+            let err = reply.parse::<sup_proto::net::NetErr>()
+                           .map_err(SrvClientError::Decode)?;
+            return Err(SrvClientError::from(err));
+        }
+        _ => {
+            warn!("Unexpected health message, {:?}", reply);
+            return Ok(());
+        }
+    };
+    let status = sup_proto::types::HealthCheckStatus::from_i32(record.status)
+        .unwrap_or(sup_proto::types::HealthCheckStatus::Unknown);
+    let mut details = vec![];
+    if let Some(code) = record.exit_code {
+        details.push(format!("exit code {}", code));
+    }
+    if let Some(ms) = record.duration_ms {
+        details.push(format!("{}ms", ms));
+    }
+    let details = if details.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", details.join(", "))
+    };
+    println!("{} {}: {}{}",
+             Utc.timestamp(record.timestamp, 0).to_rfc3339(),
+             record.service_group,
+             status,
+             details);
+    for (name, output) in &[("stdout", &record.stdout), ("stderr", &record.stderr)] {
+        if let Some(output) = output {
+            for line in output.lines() {
+                println!("    {}: {}", name, line);
+            }
+        }
+    }
     Ok(())
 }
 
@@ -1661,11 +2190,12 @@ fn supervisor_services() -> Result<Vec<PackageIdent>> {
 
     let cfg = config::load()?;
     let secret_key = ctl_secret_key(&cfg)?;
+    let ctl_tls = ctl_tls_config(&cfg, None)?;
     let listen_ctl_addr = ListenCtlAddr::default();
     let msg = sup_proto::ctl::SvcStatus::default();
 
     let mut out: Vec<PackageIdent> = vec![];
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+    SrvClient::connect(&listen_ctl_addr, &secret_key, ctl_tls.as_ref()).and_then(|conn| {
                                                          conn.call(msg).for_each(|reply| {
                           match reply.message_id() {
                               "ServiceStatus" => {
@@ -1742,6 +2272,112 @@ fn get_health_check_interval_from_input(m: &ArgMatches<'_>)
      .map(HealthCheckInterval::into)
 }
 
+fn get_restart_policy_from_input(m: &ArgMatches<'_>) -> Option<RestartPolicy> {
+    // There won't be errors, because we validate with `valid_restart_policy`
+    m.value_of("RESTART_POLICY")
+     .and_then(|p| RestartPolicy::from_str(p).ok())
+}
+
+fn get_restart_backoff_from_input(m: &ArgMatches<'_>) -> Option<RestartBackoff> {
+    // Values will have already been validated by `cli::valid_numeric`
+    let backoff = RestartBackoff { initial_delay: m.value_of("RESTART_DELAY")
+                                                   .and_then(|s| s.parse().ok()),
+                                   max_delay:     m.value_of("RESTART_MAX_DELAY")
+                                                   .and_then(|s| s.parse().ok()),
+                                   limit:         m.value_of("RESTART_LIMIT")
+                                                   .and_then(|s| s.parse().ok()),
+                                   window:        m.value_of("RESTART_WINDOW")
+                                                   .and_then(|s| s.parse().ok()), };
+    if backoff == RestartBackoff::default() {
+        None
+    } else {
+        Some(backoff)
+    }
+}
+
+fn get_update_windows_from_input(m: &ArgMatches<'_>) -> Option<UpdateWindows> {
+    if m.is_present("NO_UPDATE_WINDOWS") {
+        return Some(UpdateWindows::default());
+    }
+    // Values will have already been validated by their `cli::valid_*` functions
+    m.values_of("UPDATE_WINDOW")
+     .map(|values| UpdateWindows { windows: values.filter_map(|v| v.parse().ok()).collect(), })
+}
+
+fn get_update_rollout_from_input(m: &ArgMatches<'_>) -> Option<UpdateRollout> {
+    // Values will have already been validated by their `cli::valid_*` functions
+    let mut rollout = UpdateRollout::default();
+    if let Some(batch_size) = m.value_of("UPDATE_BATCH_SIZE") {
+        rollout.set_batch_size(batch_size).ok();
+    }
+    rollout.health_timeout = m.value_of("UPDATE_HEALTH_TIMEOUT")
+                              .and_then(|s| s.parse().ok());
+    if rollout == UpdateRollout::default() {
+        None
+    } else {
+        Some(rollout)
+    }
+}
+
+fn get_update_rollback_from_input(m: &ArgMatches<'_>) -> Option<UpdateRollback> {
+    if m.is_present("NO_UPDATE_ROLLBACK") {
+        return Some(UpdateRollback { enabled: Some(false),
+                                     ..Default::default() });
+    }
+    // Values will have already been validated by their `cli::valid_*` functions
+    let rollback = UpdateRollback { enabled:      None,
+                                    grace_period: m.value_of("UPDATE_ROLLBACK_GRACE")
+                                                   .and_then(|s| s.parse().ok()), };
+    if m.is_present("UPDATE_ROLLBACK") || rollback != UpdateRollback::default() {
+        Some(UpdateRollback { enabled: Some(true),
+                              ..rollback })
+    } else {
+        None
+    }
+}
+
+fn get_resource_limits_from_input(m: &ArgMatches<'_>) -> Option<ResourceLimits> {
+    // Values will have already been validated by their `cli::valid_*` functions
+    let limits = ResourceLimits { cpu_weight: m.value_of("CPU_WEIGHT")
+                                               .and_then(|s| s.parse().ok()),
+                                  cpu_quota:  m.value_of("CPU_QUOTA")
+                                               .and_then(|s| CpuQuota::from_str(s).ok())
+                                               .map(CpuQuota::into),
+                                  memory_max: m.value_of("MEMORY_MAX")
+                                               .and_then(|s| MemorySize::from_str(s).ok())
+                                               .map(MemorySize::into),
+                                  pids_max:   m.value_of("PIDS_MAX")
+                                               .and_then(|s| s.parse().ok()), };
+    if limits == ResourceLimits::default() {
+        None
+    } else {
+        Some(limits)
+    }
+}
+
+fn get_log_file_from_input(m: &ArgMatches<'_>) -> Option<LogFile> {
+    if m.is_present("NO_LOG_FILE") {
+        return Some(LogFile { enabled: Some(false),
+                              ..Default::default() });
+    }
+    // Tuning flags alone leave the Supervisor's default for writing the file
+    let enabled = if m.is_present("LOG_FILE") { Some(true) } else { None };
+    // Values will have already been validated by their `cli::valid_*` functions
+    let log_file = LogFile { enabled,
+                             max_size: m.value_of("LOG_MAX_SIZE")
+                                        .and_then(|s| MemorySize::from_str(s).ok())
+                                        .map(MemorySize::into),
+                             max_age:  m.value_of("LOG_MAX_AGE")
+                                        .and_then(|s| s.parse().ok()),
+                             retain:   m.value_of("LOG_RETAIN")
+                                        .and_then(|s| s.parse().ok()), };
+    if log_file == LogFile::default() {
+        None
+    } else {
+        Some(log_file)
+    }
+}
+
 #[cfg(target_os = "windows")]
 fn get_password_from_input(m: &ArgMatches) -> Result<Option<String>> {
     if let Some(password) = m.value_of("PASSWORD") {
@@ -1834,9 +2470,17 @@ fn update_svc_load_from_input(m: &ArgMatches<'_>, msg: &mut sup_proto::ctl::SvcL
     msg.group = get_group_from_input(m);
     msg.svc_encrypted_password = get_password_from_input(m)?;
     msg.health_check_interval = get_health_check_interval_from_input(m);
+    msg.restart_policy = get_restart_policy_from_input(m).map(|v| v as i32);
+    msg.restart_backoff = get_restart_backoff_from_input(m);
+    msg.resource_limits = get_resource_limits_from_input(m);
+    msg.log_file = get_log_file_from_input(m);
     msg.binding_mode = get_binding_mode_from_input(m).map(|v| v as i32);
     msg.topology = get_topology_from_input(m).map(|v| v as i32);
     msg.update_strategy = get_strategy_from_input(m).map(|v| v as i32);
+    msg.update_windows = get_update_windows_from_input(m);
+    msg.update_rollout = get_update_rollout_from_input(m);
+    msg.update_rollback = get_update_rollback_from_input(m);
+    msg.strict_templates = Some(m.is_present("STRICT_TEMPLATES"));
     Ok(())
 }
 
@@ -25,7 +25,8 @@ use url::Url;
 
 use crate::{error::{Error,
                     Result},
-            net::ProxyHttpsConnector,
+            net::{ProxyHttpsConnector,
+                  TimeoutConnector},
             proxy::{proxy_unless_domain_exempted,
                     ProxyInfo},
             ssl};
@@ -70,9 +71,30 @@ impl ApiClient {
                   fs_root_path: Option<&Path>)
                   -> Result<Self>
         where T: IntoUrl
+    {
+        Self::with_timeout(endpoint, product, version, fs_root_path, socket_timeout())
+    }
+
+    /// Creates and returns a new `ApiClient` instance which gives up on connecting to, reading
+    /// from or writing to its endpoint after `timeout`, rather than after the default socket
+    /// timeout.
+    ///
+    /// # Errors
+    ///
+    /// * If the underlying Hyper client cannot be created
+    /// * If a suitable SSL context cannot be established
+    /// * If an HTTP proxy cannot be correctly setup
+    /// * If a `User-Agent` HTTP header string cannot be constructed
+    pub fn with_timeout<T>(endpoint: T,
+                           product: &str,
+                           version: &str,
+                           fs_root_path: Option<&Path>,
+                           timeout: Duration)
+                           -> Result<Self>
+        where T: IntoUrl
     {
         let endpoint = endpoint.into_url().map_err(Error::UrlParseError)?;
-        Ok(ApiClient { inner: new_hyper_client(&endpoint, fs_root_path)?,
+        Ok(ApiClient { inner: new_hyper_client(&endpoint, fs_root_path, timeout)?,
                        proxy: proxy_unless_domain_exempted(Some(&endpoint))?,
                        target_scheme: endpoint.scheme().to_string(),
                        endpoint,
@@ -220,44 +242,52 @@ impl ApiClient {
 /// The Mac platform uses a Security Framework to store and find root certificates and the hyper
 /// library will default to using this on the Mac. Therefore the behavior on the Mac remains
 /// unchanged and will use the system's certificates.
-fn new_hyper_client(url: &Url, fs_root_path: Option<&Path>) -> Result<HyperClient> {
+fn new_hyper_client(url: &Url,
+                    fs_root_path: Option<&Path>,
+                    timeout: Duration)
+                    -> Result<HyperClient> {
     let connector = ssl_connector(fs_root_path)?;
     let ssl_client = OpensslClient::from(connector);
 
-    let timeout_in_secs = match env::var("HAB_CLIENT_SOCKET_TIMEOUT") {
-        Ok(t) => {
-            match t.parse::<u64>() {
-                Ok(n) => n,
-                Err(_) => CLIENT_SOCKET_RW_TIMEOUT_SEC,
-            }
-        }
-        Err(_) => CLIENT_SOCKET_RW_TIMEOUT_SEC,
-    };
-    debug!("Client socket timeout: {} secs", timeout_in_secs);
-
-    let timeout = Some(Duration::from_secs(timeout_in_secs));
+    debug!("Client socket timeout: {} secs", timeout.as_secs());
 
     match proxy_unless_domain_exempted(Some(url))? {
         Some(proxy) => {
             debug!("Using proxy {}:{}...", proxy.host(), proxy.port());
-            let connector = ProxyHttpsConnector::new(proxy, ssl_client)?;
+            let connector = ProxyHttpsConnector::new(proxy, ssl_client, timeout)?;
             let pool = Pool::with_connector(Config::default(), connector);
             let mut client = HyperClient::with_protocol(Http11Protocol::with_connector(pool));
-            client.set_read_timeout(timeout);
-            client.set_write_timeout(timeout);
+            client.set_read_timeout(Some(timeout));
+            client.set_write_timeout(Some(timeout));
             Ok(client)
         }
         None => {
-            let connector = HttpsConnector::new(ssl_client);
+            let connector =
+                HttpsConnector::with_connector(ssl_client, TimeoutConnector::new(timeout));
             let pool = Pool::with_connector(Config::default(), connector);
             let mut client = HyperClient::with_protocol(Http11Protocol::with_connector(pool));
-            client.set_read_timeout(timeout);
-            client.set_write_timeout(timeout);
+            client.set_read_timeout(Some(timeout));
+            client.set_write_timeout(Some(timeout));
             Ok(client)
         }
     }
 }
 
+/// The socket timeout for clients that don't ask for their own, which is
+/// `HAB_CLIENT_SOCKET_TIMEOUT` seconds if that's set.
+fn socket_timeout() -> Duration {
+    let timeout_in_secs = match env::var("HAB_CLIENT_SOCKET_TIMEOUT") {
+        Ok(t) => {
+            match t.parse::<u64>() {
+                Ok(n) => n,
+                Err(_) => CLIENT_SOCKET_RW_TIMEOUT_SEC,
+            }
+        }
+        Err(_) => CLIENT_SOCKET_RW_TIMEOUT_SEC,
+    };
+    Duration::from_secs(timeout_in_secs)
+}
+
 /// Returns an HTTP User-Agent string type for use by Hyper when making HTTP requests.
 ///
 /// The general form for Habitat-related clients are of the following form:
@@ -1,10 +1,14 @@
-use std::io::{Read,
-              Write};
+use std::{io::{self,
+               Read,
+               Write},
+          net::{TcpStream,
+                ToSocketAddrs},
+          time::Duration};
 
 use httparse;
 use hyper::{self,
             method::Method,
-            net::{HttpConnector,
+            net::{HttpStream,
                   HttpsStream,
                   NetworkConnector,
                   SslClient},
@@ -12,20 +16,55 @@ use hyper::{self,
 
 use crate::proxy::ProxyInfo;
 
+/// A connector for plain TCP connections, like hyper's `HttpConnector`, that gives up on a server
+/// which doesn't accept the connection within its timeout.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeoutConnector {
+    timeout: Duration,
+}
+
+impl TimeoutConnector {
+    pub fn new(timeout: Duration) -> Self { TimeoutConnector { timeout } }
+}
+
+impl NetworkConnector for TimeoutConnector {
+    type Stream = HttpStream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> hyper::Result<Self::Stream> {
+        if scheme != "http" {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "Invalid scheme for Http").into());
+        }
+        // Every address the host resolves to is tried in turn, as `TcpStream::connect` would.
+        let mut last_err = None;
+        for addr in (host, port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, self.timeout) {
+                Ok(stream) => return Ok(HttpStream(stream)),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+                        io::Error::new(io::ErrorKind::InvalidInput,
+                                       format!("{} didn't resolve to any addresses", host))
+                    })
+                    .into())
+    }
+}
+
 /// A connector that uses an HTTP proxy server (pass-through for plaintext and tunneled for SSL
 /// sessions).
 pub struct ProxyHttpsConnector<S: SslClient> {
     proxy:           ProxyInfo,
-    proxy_connector: HttpConnector,
+    proxy_connector: TimeoutConnector,
     ssl_client:      S,
 }
 
 impl<S: SslClient> ProxyHttpsConnector<S> {
     /// Creates a new connection using the provided proxy server configuration and SSL
-    /// implementation.
-    pub fn new(proxy: ProxyInfo, ssl_client: S) -> hyper::Result<Self> {
+    /// implementation, which gives up on connecting to the proxy server after `timeout`.
+    pub fn new(proxy: ProxyInfo, ssl_client: S, timeout: Duration) -> hyper::Result<Self> {
         Ok(ProxyHttpsConnector { proxy,
-                                 proxy_connector: HttpConnector,
+                                 proxy_connector: TimeoutConnector::new(timeout),
                                  ssl_client })
     }
 }
@@ -34,7 +73,7 @@ impl<S: SslClient> NetworkConnector for ProxyHttpsConnector<S> {
     type Stream = HttpsStream<S::Stream>;
 
     fn connect(&self, host: &str, port: u16, scheme: &str) -> hyper::Result<Self::Stream> {
-        // Initial connection to the proxy server, using an `HttpConnector`
+        // Initial connection to the proxy server, using a `TimeoutConnector`
         let mut stream = self.proxy_connector
                              .connect(self.proxy.host(), self.proxy.port(), "http")?;
         match scheme {
@@ -1,22 +1,45 @@
 use crate::error::{Error,
                    Result};
 use habitat_common::types::UserInfo;
-use habitat_core::os::process::Pid;
+use habitat_core::{os::process::Pid,
+                   service::{LogFileConfig,
+                             ResourceLimits}};
 use habitat_launcher_protocol::{self as protocol,
-                                Error as ProtocolError};
+                                Error as ProtocolError,
+                                LauncherMessage};
 use ipc_channel::ipc::{IpcOneShotServer,
                        IpcReceiver,
                        IpcSender};
 use std::{collections::HashMap,
           io,
-          path::Path};
+          path::Path,
+          sync::atomic::{AtomicBool,
+                         Ordering},
+          thread,
+          time::{Duration,
+                 Instant}};
 
 type Env = HashMap<String, String>;
 type IpcServer = IpcOneShotServer<Vec<u8>>;
 
+/// How long to wait for the Launcher to answer a request it may not
+/// understand. Launchers older than the `UnknownMessage` error never
+/// reply to messages they predate.
+const OPTIONAL_REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+/// The period, in microseconds, CPU quotas are enforced over.
+const CPU_PERIOD_US: u64 = 100_000;
+
 pub struct LauncherCli {
     tx: IpcSender<Vec<u8>>,
     rx: IpcReceiver<Vec<u8>>,
+    // Cleared once the Launcher replies that it doesn't know an
+    // `ExitCode` or `ServiceUsage` request so we stop sending them.
+    // Not cleared when a reply is slow, as it may only be busy.
+    exit_code_supported: AtomicBool,
+    usage_supported: AtomicBool,
+    // Set if the Launcher asks us to shut down while we're waiting on
+    // the reply to a request.
+    stopping: AtomicBool,
     // We persist the pipe identifier so we can delete the file on drop.
     // This is not necessary on Windows because named pipes are removed
     // upon releasing the last handle to the pipe. The ipc-channel crate
@@ -48,6 +71,9 @@ impl LauncherCli {
         Self::read::<protocol::NetOk>(&raw)?;
         Ok(LauncherCli { tx,
                          rx,
+                         exit_code_supported: AtomicBool::new(true),
+                         usage_supported: AtomicBool::new(true),
+                         stopping: AtomicBool::new(false),
                          #[cfg(not(windows))]
                          pipe: pipe_to_sup })
     }
@@ -65,13 +91,62 @@ impl LauncherCli {
         Ok(msg)
     }
 
-    /// Receive and read protocol message from an IpcReceiver
-    fn recv<T>(rx: &IpcReceiver<Vec<u8>>) -> Result<T>
+    /// Read a protocol message from a byte array if it's the one we're
+    /// waiting on (or an error in its place), the reply to `request`
+    /// about `pid`.
+    ///
+    /// Any other message is dropped, as it's the late reply to a
+    /// request we stopped waiting on, unless it's a request to shut
+    /// down, which is kept for `is_stopping`.
+    fn read_expected<T>(&self, bytes: &[u8], request: &str, pid: Option<i64>) -> Result<Option<T>>
+        where T: protocol::LauncherMessage
+    {
+        let txn = protocol::NetTxn::from_bytes(bytes)?;
+        match txn.message_id() {
+            "NetErr" => {
+                let err = txn.decode::<protocol::NetErr>()?;
+                if Self::answers(&err, request, pid) {
+                    Err(Error::Protocol(ProtocolError::NetErr(err)))
+                } else {
+                    debug!("Dropping an error from the Launcher for an earlier {} while waiting \
+                            on a {}: {}",
+                           err.message_id.as_ref().map_or("request", String::as_str),
+                           request,
+                           err);
+                    Ok(None)
+                }
+            }
+            id if id == T::MESSAGE_ID => Ok(Some(txn.decode::<T>()?)),
+            "Shutdown" => {
+                self.stopping.store(true, Ordering::Relaxed);
+                Ok(None)
+            }
+            id => {
+                debug!("Dropping a {} from the Launcher while waiting on a {}",
+                       id,
+                       T::MESSAGE_ID);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Whether `err` is the Launcher's reply to `request` about `pid`.
+    /// Older Launchers don't say what their errors reply to, so theirs
+    /// are taken to be.
+    fn answers(err: &protocol::NetErr, request: &str, pid: Option<i64>) -> bool {
+        err.message_id.as_ref().map_or(true, |id| id == request)
+        && err.pid.map_or(true, |p| Some(p) == pid)
+    }
+
+    /// Receive and read the protocol message we're waiting on
+    fn recv<T>(&self, request: &str, pid: Option<i64>) -> Result<T>
         where T: protocol::LauncherMessage
     {
-        match rx.recv() {
-            Ok(bytes) => Self::read(&bytes),
-            Err(err) => Err(Error::from(*err)),
+        loop {
+            let bytes = self.rx.recv().map_err(|err| Error::from(*err))?;
+            if let Some(msg) = self.read_expected(&bytes, request, pid)? {
+                return Ok(msg);
+            }
         }
     }
 
@@ -85,33 +160,120 @@ impl LauncherCli {
         Ok(())
     }
 
-    /// Receive and read protocol message from an IpcReceiver
-    fn try_recv<T>(rx: &IpcReceiver<Vec<u8>>) -> Result<Option<T>>
+    /// Receive and read the protocol message we're waiting on, if it
+    /// has arrived
+    fn try_recv<T>(&self, request: &str, pid: Option<i64>) -> Result<Option<T>>
         where T: protocol::LauncherMessage
     {
-        match rx.try_recv().map_err(|err| Error::from(*err)) {
-            Ok(bytes) => {
-                let msg = Self::read::<T>(&bytes)?;
-                Ok(Some(msg))
+        loop {
+            match self.rx.try_recv().map_err(|err| Error::from(*err)) {
+                Ok(bytes) => {
+                    if let Some(msg) = self.read_expected(&bytes, request, pid)? {
+                        return Ok(Some(msg));
+                    }
+                }
+                Err(Error::IPCIO(io::ErrorKind::WouldBlock)) => return Ok(None),
+                Err(err) => return Err(err),
             }
-            Err(Error::IPCIO(io::ErrorKind::WouldBlock)) => Ok(None),
-            Err(err) => Err(err),
         }
     }
 
     pub fn is_stopping(&self) -> bool {
-        match Self::try_recv::<protocol::Shutdown>(&self.rx) {
+        if self.stopping.load(Ordering::Relaxed) {
+            return true;
+        }
+        // No request is waiting on a reply here, so no error is ours.
+        match self.try_recv::<protocol::Shutdown>(protocol::Shutdown::MESSAGE_ID, None) {
             Ok(Some(_)) | Err(Error::IPCIO(_)) => true,
             Ok(None) => false,
             Err(err) => panic!("Unexpected error checking for shutdown request, {}", err),
         }
     }
 
+    /// Send a request about `pid` that older Launchers may not
+    /// understand, waiting a short while for the reply, which
+    /// `is_reply` tells apart from late replies to earlier requests.
+    ///
+    /// If none arrives in time, `None` is returned. If the Launcher
+    /// replies that it doesn't know the request, `supported` is also
+    /// cleared and `None` is returned for any later request without
+    /// asking.
+    fn request_optional<T, R, F>(&self,
+                                 message: &T,
+                                 pid: i64,
+                                 supported: &AtomicBool,
+                                 is_reply: F)
+                                 -> Result<Option<R>>
+        where T: protocol::LauncherMessage,
+              R: protocol::LauncherMessage,
+              F: Fn(&R) -> bool
+    {
+        if !supported.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        Self::send(&self.tx, message)?;
+        let deadline = Instant::now() + OPTIONAL_REPLY_TIMEOUT;
+        loop {
+            match self.try_recv::<R>(T::MESSAGE_ID, Some(pid)) {
+                Ok(Some(reply)) => {
+                    if is_reply(&reply) {
+                        return Ok(Some(reply));
+                    }
+                    debug!("Dropping a late {} from the Launcher", R::MESSAGE_ID);
+                    continue;
+                }
+                Ok(None) => {}
+                Err(Error::Protocol(ProtocolError::NetErr(ref err)))
+                    if err.code == protocol::ErrCode::UnknownMessage =>
+                {
+                    warn!("Launcher does not understand {} requests; no longer sending them",
+                          T::MESSAGE_ID);
+                    supported.store(false, Ordering::Relaxed);
+                    return Ok(None);
+                }
+                Err(err) => return Err(err),
+            }
+            if Instant::now() >= deadline {
+                warn!("Launcher did not answer a {} request in time",
+                      T::MESSAGE_ID);
+                return Ok(None);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Retrieve the exit code of a service process which the Launcher
+    /// has reaped.
+    ///
+    /// Returns `None` if the Launcher has no record of the process
+    /// exiting, or is too old to understand the request.
+    pub fn exit_code(&self, pid: Pid) -> Result<Option<i32>> {
+        let msg = protocol::ExitCode { pid: pid.into() };
+        let reply: Option<protocol::ExitCodeOk> =
+            self.request_optional(&msg, msg.pid, &self.exit_code_supported, |reply| {
+                    reply.pid.map_or(true, |p| p == msg.pid)
+                })?;
+        Ok(reply.and_then(|r| r.exit_code))
+    }
+
+    /// Retrieve the resource usage of a service process spawned with
+    /// resource limits.
+    ///
+    /// Returns `None` if the Launcher is too old to understand the
+    /// request; fields of the reply are unset where the usage couldn't
+    /// be read (including when the process has no limits).
+    pub fn usage(&self, pid: Pid) -> Result<Option<protocol::ServiceUsageOk>> {
+        let msg = protocol::ServiceUsage { pid: pid.into() };
+        self.request_optional(&msg, msg.pid, &self.usage_supported, |reply| {
+                reply.pid.map_or(true, |p| p == msg.pid)
+            })
+    }
+
     /// Restart a running process with the same arguments
     pub fn restart(&self, pid: Pid) -> Result<Pid> {
         let msg = protocol::Restart { pid: pid.into() };
         Self::send(&self.tx, &msg)?;
-        let reply = Self::recv::<protocol::SpawnOk>(&self.rx)?;
+        let reply = self.recv::<protocol::SpawnOk>(protocol::Restart::MESSAGE_ID, Some(msg.pid))?;
         Ok(reply.pid as Pid)
     }
 
@@ -120,6 +282,9 @@ impl LauncherCli {
     /// `username` and `groupname` are string names, while `uid` and
     /// `gid` are numeric IDs. Newer versions of the Launcher can
     /// accept either, but prefer numeric IDs.
+    ///
+    /// If `log_file` is given, the Launcher also writes the process's
+    /// output to that path, rotating it as configured.
     pub fn spawn(&self,
                  id: &str,
                  bin: &Path,
@@ -128,7 +293,9 @@ impl LauncherCli {
                             groupname,
                             gid, }: UserInfo,
                  password: Option<&str>,
-                 env: Env)
+                 env: Env,
+                 limits: &ResourceLimits,
+                 log_file: Option<(&Path, &LogFileConfig)>)
                  -> Result<Pid> {
         // On Windows, we only expect user to be Some.
         //
@@ -143,17 +310,44 @@ impl LauncherCli {
                                     svc_group_id: gid,
                                     svc_password: password.map(str::to_string),
                                     env,
-                                    id: id.to_string() };
+                                    id: id.to_string(),
+                                    limits: Self::launcher_limits(limits),
+                                    log_file: log_file.map(|(path, config)| {
+                                                          Self::launcher_log_file(path, config)
+                                                      }) };
 
         Self::send(&self.tx, &msg)?;
-        let reply = Self::recv::<protocol::SpawnOk>(&self.rx)?;
+        let reply = self.recv::<protocol::SpawnOk>(protocol::Spawn::MESSAGE_ID, None)?;
         Ok(reply.pid as Pid)
     }
 
+    fn launcher_limits(limits: &ResourceLimits) -> Option<protocol::ResourceLimits> {
+        if limits.is_empty() {
+            return None;
+        }
+        // Quotas are in thousandths of a CPU; the Launcher wants
+        // microseconds per period.
+        let cpu_quota_us = limits.cpu_quota
+                                 .map(|millis| millis.saturating_mul(CPU_PERIOD_US) / 1000);
+        Some(protocol::ResourceLimits { cpu_weight: limits.cpu_weight,
+                                        cpu_quota_us,
+                                        cpu_period_us: cpu_quota_us.map(|_| CPU_PERIOD_US),
+                                        memory_max: limits.memory_max,
+                                        pids_max: limits.pids_max })
+    }
+
+    fn launcher_log_file(path: &Path, config: &LogFileConfig) -> protocol::LogFile {
+        protocol::LogFile { path:     Some(path.to_string_lossy().into_owned()),
+                            max_size: Some(config.max_size),
+                            max_age:  Some(config.max_age),
+                            retain:   Some(config.retain), }
+    }
+
     pub fn terminate(&self, pid: Pid) -> Result<i32> {
         let msg = protocol::Terminate { pid: pid.into() };
         Self::send(&self.tx, &msg)?;
-        let reply = Self::recv::<protocol::TerminateOk>(&self.rx)?;
+        let reply =
+            self.recv::<protocol::TerminateOk>(protocol::Terminate::MESSAGE_ID, Some(msg.pid))?;
         Ok(reply.exit_code)
     }
 }
@@ -55,6 +55,7 @@ impl NetTxn {
 pub fn error<T>(err: T) -> NetErr
     where T: ToString + Into<ErrCode>
 {
-    NetErr { msg:  err.to_string(),
-             code: err.into(), }
+    NetErr { msg: err.to_string(),
+             code: err.into(),
+             ..Default::default() }
 }
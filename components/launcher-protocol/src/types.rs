@@ -27,6 +27,8 @@ pub trait LauncherMessage
 }
 
 pub use generated::{ErrCode,
+                    LogFile,
+                    ResourceLimits,
                     ShutdownMethod};
 
 // Now we're going to define our own set of structs to use internally, as well as conversion
@@ -34,8 +36,10 @@ pub use generated::{ErrCode,
 // simplify or eliminate a lot of this boilerplate, but I'm not sure if there is or not.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct NetErr {
-    pub code: generated::ErrCode,
-    pub msg:  String,
+    pub code:       generated::ErrCode,
+    pub msg:        String,
+    pub message_id: Option<String>,
+    pub pid:        Option<i64>,
 }
 
 impl LauncherMessage for NetErr {
@@ -48,14 +52,18 @@ impl LauncherMessage for NetErr {
             code: generated::ErrCode::from_i32(proto.code.ok_or(Error::ProtocolMismatch("code"))?)
                 .ok_or(Error::ProtocolMismatch("code"))?,
             msg: proto.msg.ok_or(Error::ProtocolMismatch("msg"))?,
+            message_id: proto.message_id,
+            pid: proto.pid,
         })
     }
 }
 
 impl From<NetErr> for generated::NetErr {
     fn from(value: NetErr) -> Self {
-        generated::NetErr { code: Some(value.code as i32),
-                            msg:  Some(value.msg), }
+        generated::NetErr { code:       Some(value.code as i32),
+                            msg:        Some(value.msg),
+                            message_id: value.message_id,
+                            pid:        value.pid, }
     }
 }
 
@@ -132,6 +140,8 @@ pub struct Spawn {
     pub env:          HashMap<String, String>,
     pub svc_user_id:  Option<u32>,
     pub svc_group_id: Option<u32>,
+    pub limits:       Option<ResourceLimits>,
+    pub log_file:     Option<LogFile>,
 }
 
 impl LauncherMessage for Spawn {
@@ -147,7 +157,9 @@ impl LauncherMessage for Spawn {
                    svc_password: proto.svc_password,
                    env:          proto.env,
                    svc_user_id:  proto.svc_user_id,
-                   svc_group_id: proto.svc_group_id, })
+                   svc_group_id: proto.svc_group_id,
+                   limits:       proto.limits,
+                   log_file:     proto.log_file, })
     }
 }
 
@@ -160,7 +172,9 @@ impl From<Spawn> for generated::Spawn {
                            svc_password: value.svc_password,
                            env:          value.env,
                            svc_user_id:  value.svc_user_id,
-                           svc_group_id: value.svc_group_id, }
+                           svc_group_id: value.svc_group_id,
+                           limits:       value.limits,
+                           log_file:     value.log_file, }
     }
 }
 
@@ -235,6 +249,104 @@ impl From<TerminateOk> for generated::TerminateOk {
     }
 }
 
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExitCode {
+    pub pid: i64,
+}
+
+impl LauncherMessage for ExitCode {
+    type Generated = generated::ExitCode;
+
+    const MESSAGE_ID: &'static str = "ExitCode";
+
+    fn from_proto(proto: generated::ExitCode) -> Result<Self> {
+        Ok(ExitCode { pid: proto.pid.ok_or(Error::ProtocolMismatch("pid"))?, })
+    }
+}
+
+impl From<ExitCode> for generated::ExitCode {
+    fn from(value: ExitCode) -> Self { generated::ExitCode { pid: Some(value.pid), } }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExitCodeOk {
+    pub exit_code: Option<i32>,
+    pub pid:       Option<i64>,
+}
+
+impl LauncherMessage for ExitCodeOk {
+    type Generated = generated::ExitCodeOk;
+
+    const MESSAGE_ID: &'static str = "ExitCodeOk";
+
+    fn from_proto(proto: generated::ExitCodeOk) -> Result<Self> {
+        Ok(ExitCodeOk { exit_code: proto.exit_code,
+                        pid:       proto.pid, })
+    }
+}
+
+impl From<ExitCodeOk> for generated::ExitCodeOk {
+    fn from(value: ExitCodeOk) -> Self {
+        generated::ExitCodeOk { exit_code: value.exit_code,
+                                pid:       value.pid, }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServiceUsage {
+    pub pid: i64,
+}
+
+impl LauncherMessage for ServiceUsage {
+    type Generated = generated::ServiceUsage;
+
+    const MESSAGE_ID: &'static str = "ServiceUsage";
+
+    fn from_proto(proto: generated::ServiceUsage) -> Result<Self> {
+        Ok(ServiceUsage { pid: proto.pid.ok_or(Error::ProtocolMismatch("pid"))?, })
+    }
+}
+
+impl From<ServiceUsage> for generated::ServiceUsage {
+    fn from(value: ServiceUsage) -> Self { generated::ServiceUsage { pid: Some(value.pid), } }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServiceUsageOk {
+    pub cpu_usage_usec:     Option<u64>,
+    pub cpu_throttled_usec: Option<u64>,
+    pub memory_current:     Option<u64>,
+    pub pids_current:       Option<u64>,
+    pub oom_kills:          Option<u64>,
+    pub pid:                Option<i64>,
+}
+
+impl LauncherMessage for ServiceUsageOk {
+    type Generated = generated::ServiceUsageOk;
+
+    const MESSAGE_ID: &'static str = "ServiceUsageOk";
+
+    fn from_proto(proto: generated::ServiceUsageOk) -> Result<Self> {
+        Ok(ServiceUsageOk { cpu_usage_usec:     proto.cpu_usage_usec,
+                            cpu_throttled_usec: proto.cpu_throttled_usec,
+                            memory_current:     proto.memory_current,
+                            pids_current:       proto.pids_current,
+                            oom_kills:          proto.oom_kills,
+                            pid:                proto.pid, })
+    }
+}
+
+impl From<ServiceUsageOk> for generated::ServiceUsageOk {
+    fn from(value: ServiceUsageOk) -> Self {
+        generated::ServiceUsageOk { cpu_usage_usec:     value.cpu_usage_usec,
+                                    cpu_throttled_usec: value.cpu_throttled_usec,
+                                    memory_current:     value.memory_current,
+                                    pids_current:       value.pids_current,
+                                    oom_kills:          value.oom_kills,
+                                    pid:                value.pid, }
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Envelope {
     pub message_id: String,
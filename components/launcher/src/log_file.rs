@@ -0,0 +1,240 @@
+//! Writes a service's output to a file, rotating it once it grows too
+//! large or too old.
+//!
+//! Rotation renames `<file>` to `<file>.1`, `<file>.1` to `<file>.2`,
+//! and so on, dropping whatever falls past the number of files to keep.
+
+use crate::protocol;
+use std::{ffi::OsString,
+          fs::{self,
+               File,
+               OpenOptions},
+          io::{self,
+               Write},
+          path::{Path,
+                 PathBuf},
+          sync::{Arc,
+                 Mutex},
+          time::{Duration,
+                 SystemTime}};
+
+/// A log file shared by the threads reading a service's stdout and
+/// stderr.
+pub type SharedLogFile = Arc<Mutex<LogFile>>;
+
+pub struct LogFile {
+    path:     PathBuf,
+    // Only `None` while rotating, or if reopening the file failed.
+    file:     Option<File>,
+    size:     u64,
+    started:  SystemTime,
+    max_size: u64,
+    max_age:  Option<Duration>,
+    retain:   u32,
+    // Set after a failed write, so a broken disk doesn't log a warning
+    // for every line of output.
+    failing:  bool,
+}
+
+impl LogFile {
+    pub fn open(config: &protocol::LogFile) -> io::Result<Self> {
+        let path = match config.path {
+            Some(ref path) => PathBuf::from(path),
+            None => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          "no log file path given"));
+            }
+        };
+        let mut log_file = LogFile { path,
+                                     file: None,
+                                     size: 0,
+                                     started: SystemTime::now(),
+                                     max_size: config.max_size.unwrap_or(0),
+                                     max_age: config.max_age
+                                                    .filter(|secs| *secs > 0)
+                                                    .map(Duration::from_secs),
+                                     retain: config.retain.unwrap_or(0),
+                                     failing: false };
+        log_file.reopen()?;
+        Ok(log_file)
+    }
+
+    pub fn share(self) -> SharedLogFile { Arc::new(Mutex::new(self)) }
+
+    /// Append a line of output from the given stream ("O" or "E").
+    pub fn write_line(&mut self, stream: &str, line: &str) {
+        let entry = format!("{} {}: {}\n", time::now_utc().rfc3339(), stream, line);
+        match self.write_entry(&entry) {
+            Ok(()) => self.failing = false,
+            Err(err) => {
+                if !self.failing {
+                    warn!("Unable to write to log file {}, {}",
+                          self.path.display(),
+                          err);
+                }
+                self.failing = true;
+            }
+        }
+    }
+
+    fn write_entry(&mut self, entry: &str) -> io::Result<()> {
+        if self.needs_rotation(entry.len() as u64) {
+            self.rotate()?;
+        }
+        if self.file.is_none() {
+            self.reopen()?;
+        }
+        if let Some(ref mut file) = self.file {
+            file.write_all(entry.as_bytes())?;
+            self.size += entry.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn needs_rotation(&self, incoming: u64) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+        let too_large = self.max_size > 0 && self.size + incoming > self.max_size;
+        let age = self.started.elapsed().unwrap_or_default();
+        let too_old = self.max_age.map_or(false, |max_age| age >= max_age);
+        too_large || too_old
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // Close the file first; Windows won't rename a file that's open.
+        self.file = None;
+        if self.retain == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let oldest = self.rotated_path(self.retain);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+            for n in (1..self.retain).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.reopen()
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true)
+                                     .append(true)
+                                     .open(&self.path)?;
+        let metadata = file.metadata()?;
+        self.size = metadata.len();
+        // Measure age from when the file was created, where the
+        // filesystem records it, so restarting a service doesn't put
+        // off rotation.
+        self.started = metadata.created().unwrap_or_else(|_| SystemTime::now());
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn rotated_path(&self, n: u32) -> PathBuf {
+        let mut name = OsString::from(self.path.as_os_str());
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config(path: &Path, max_size: u64, retain: u32) -> protocol::LogFile {
+        protocol::LogFile { path: Some(path.to_string_lossy().into_owned()),
+                            max_size: Some(max_size),
+                            max_age: None,
+                            retain: Some(retain) }
+    }
+
+    fn lines(path: &Path) -> Vec<String> {
+        fs::read_to_string(path).unwrap()
+                                .lines()
+                                .map(|line| line.rsplit(": ").next().unwrap().to_string())
+                                .collect()
+    }
+
+    #[test]
+    fn rotates_once_the_file_would_grow_past_its_max_size() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("logs").join("redis.log");
+        let mut log_file = LogFile::open(&config(&path, 100, 5)).unwrap();
+        for n in 0..5 {
+            log_file.write_line("O", &n.to_string());
+        }
+
+        assert!(fs::metadata(&path).unwrap().len() <= 100);
+        assert!(fs::metadata(log_file.rotated_path(1)).unwrap().len() <= 100);
+        let mut written = lines(&log_file.rotated_path(1));
+        written.extend(lines(&path));
+        assert_eq!(written, vec!["0", "1", "2", "3", "4"]);
+    }
+
+    #[test]
+    fn keeps_only_the_given_number_of_rotated_files() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("redis.log");
+        // Every entry is larger than this, so each write rotates
+        let mut log_file = LogFile::open(&config(&path, 1, 2)).unwrap();
+        for n in 0..5 {
+            log_file.write_line("O", &n.to_string());
+        }
+
+        assert_eq!(lines(&path), vec!["4"]);
+        assert_eq!(lines(&log_file.rotated_path(1)), vec!["3"]);
+        assert_eq!(lines(&log_file.rotated_path(2)), vec!["2"]);
+        assert!(!log_file.rotated_path(3).exists());
+    }
+
+    #[test]
+    fn keeping_no_rotated_files_truncates() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("redis.log");
+        let mut log_file = LogFile::open(&config(&path, 1, 0)).unwrap();
+        log_file.write_line("O", "first");
+        log_file.write_line("E", "second");
+
+        assert_eq!(lines(&path), vec!["second"]);
+        assert!(!log_file.rotated_path(1).exists());
+    }
+
+    #[test]
+    fn reopening_appends_and_counts_what_is_already_there() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("redis.log");
+        let mut log_file = LogFile::open(&config(&path, 1024, 5)).unwrap();
+        log_file.write_line("O", "before");
+        let size = log_file.size;
+        drop(log_file);
+
+        let mut log_file = LogFile::open(&config(&path, 1024, 5)).unwrap();
+        assert_eq!(log_file.size, size);
+        log_file.write_line("O", "after");
+        assert_eq!(lines(&path), vec!["before", "after"]);
+    }
+
+    #[test]
+    fn reopens_the_file_if_it_was_closed() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("redis.log");
+        let mut log_file = LogFile::open(&config(&path, 1024, 5)).unwrap();
+        log_file.write_line("O", "before");
+        // As after a failed rotation
+        log_file.file = None;
+        log_file.write_line("O", "after");
+
+        assert_eq!(lines(&path), vec!["before", "after"]);
+        assert!(!log_file.rotated_path(1).exists());
+    }
+}
@@ -0,0 +1,20 @@
+use crate::protocol;
+
+use super::{HandleResult,
+            Handler};
+use crate::server::ServiceTable;
+
+pub struct ExitCodeHandler;
+impl Handler for ExitCodeHandler {
+    type Message = protocol::ExitCode;
+    type Reply = protocol::ExitCodeOk;
+
+    fn handle(msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        let mut reply = protocol::ExitCodeOk::default();
+        reply.exit_code = services.take_exit_code(msg.pid as u32);
+        reply.pid = Some(msg.pid);
+        Ok(reply)
+    }
+
+    fn pid(msg: &Self::Message) -> Option<i64> { Some(msg.pid) }
+}
@@ -1,8 +1,12 @@
+mod exit_code;
 mod restart;
+mod service_usage;
 mod spawn;
 mod terminate;
 
-pub use self::{restart::*,
+pub use self::{exit_code::*,
+               restart::*,
+               service_usage::*,
                spawn::*,
                terminate::*};
 
@@ -19,6 +23,9 @@ pub trait Handler {
 
     fn handle(msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply>;
 
+    /// The process `msg` concerns, if any, which an error reply names.
+    fn pid(_msg: &Self::Message) -> Option<i64> { None }
+
     fn run(tx: &Sender, txn: protocol::NetTxn, services: &mut ServiceTable) {
         let msg = match txn.decode::<Self::Message>() {
             Ok(msg) => msg,
@@ -28,13 +35,16 @@ pub trait Handler {
             }
         };
         trace!("{}, {:?}, {:?}", txn.message_id(), msg, services);
+        let pid = Self::pid(&msg);
         match Self::handle(msg, services) {
             Ok(reply) => {
                 if let Err(err) = super::send(tx, &reply) {
                     error!("{}: replying, {}", txn.message_id(), err);
                 }
             }
-            Err(reply) => {
+            Err(mut reply) => {
+                reply.message_id = Some(txn.message_id().to_string());
+                reply.pid = pid;
                 if let Err(err) = super::send(tx, &reply) {
                     error!("{}: replying, {}", txn.message_id(), err);
                 }
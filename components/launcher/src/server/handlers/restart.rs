@@ -39,4 +39,6 @@ impl Handler for RestartHandler {
             }
         }
     }
+
+    fn pid(msg: &Self::Message) -> Option<i64> { Some(msg.pid) }
 }
@@ -0,0 +1,28 @@
+use crate::protocol;
+
+use super::{HandleResult,
+            Handler};
+use crate::server::ServiceTable;
+
+pub struct ServiceUsageHandler;
+impl Handler for ServiceUsageHandler {
+    type Message = protocol::ServiceUsage;
+    type Reply = protocol::ServiceUsageOk;
+
+    fn handle(msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        match services.get(msg.pid as u32) {
+            Some(service) => {
+                let mut reply = service.usage().unwrap_or_default();
+                reply.pid = Some(msg.pid);
+                Ok(reply)
+            }
+            None => {
+                let mut reply = protocol::NetErr::default();
+                reply.code = protocol::ErrCode::NoPid;
+                Err(reply)
+            }
+        }
+    }
+
+    fn pid(msg: &Self::Message) -> Option<i64> { Some(msg.pid) }
+}
@@ -35,4 +35,6 @@ impl Handler for TerminateHandler {
             }
         }
     }
+
+    fn pid(msg: &Self::Message) -> Option<i64> { Some(msg.pid) }
 }
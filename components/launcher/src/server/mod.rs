@@ -28,7 +28,8 @@ use ipc_channel::ipc::{IpcOneShotServer,
 use libc;
 use semver::{Version,
              VersionReq};
-use std::{collections::HashMap,
+use std::{collections::{HashMap,
+                        VecDeque},
           fs,
           io::Write,
           path::PathBuf,
@@ -56,6 +57,14 @@ const SUP_VERSION_CHECK_DISABLE: &str = "HAB_LAUNCH_NO_SUP_VERSION_CHECK";
 // always existed such as https://github.com/habitat-sh/habitat/issues/5380
 const SUP_VERSION_REQ: &str = ">= 0.56";
 
+/// How many exit codes of reaped services we hold on to for the
+/// Supervisor to ask about.
+const EXIT_CODE_HISTORY: usize = 64;
+
+/// Recorded in place of an exit code when a service was terminated by
+/// a signal rather than exiting on its own.
+const SIGNALED_EXIT_CODE: i32 = -1;
+
 type Receiver = IpcReceiver<Vec<u8>>;
 type Sender = IpcSender<Vec<u8>>;
 
@@ -367,19 +376,36 @@ impl Server {
 }
 
 #[derive(Debug, Default)]
-pub struct ServiceTable(HashMap<u32, Service>);
+pub struct ServiceTable {
+    services:   HashMap<u32, Service>,
+    exit_codes: VecDeque<(u32, i32)>,
+}
 
 impl ServiceTable {
-    pub fn get(&self, pid: u32) -> Option<&Service> { self.0.get(&pid) }
+    pub fn get(&self, pid: u32) -> Option<&Service> { self.services.get(&pid) }
 
-    pub fn get_mut(&mut self, pid: u32) -> Option<&mut Service> { self.0.get_mut(&pid) }
+    pub fn get_mut(&mut self, pid: u32) -> Option<&mut Service> { self.services.get_mut(&pid) }
 
-    pub fn insert(&mut self, service: Service) { self.0.insert(service.id(), service); }
+    pub fn insert(&mut self, service: Service) { self.services.insert(service.id(), service); }
 
-    pub fn remove(&mut self, pid: u32) -> Option<Service> { self.0.remove(&pid) }
+    pub fn remove(&mut self, pid: u32) -> Option<Service> { self.services.remove(&pid) }
+
+    /// Return (and forget) the exit code of a service that has been
+    /// reaped, if we still have a record of it.
+    pub fn take_exit_code(&mut self, pid: u32) -> Option<i32> {
+        let index = self.exit_codes.iter().position(|(p, _)| *p == pid)?;
+        self.exit_codes.remove(index).map(|(_, code)| code)
+    }
+
+    fn record_exit_code(&mut self, pid: u32, code: i32) {
+        if self.exit_codes.len() >= EXIT_CODE_HISTORY {
+            self.exit_codes.pop_front();
+        }
+        self.exit_codes.push_back((pid, code));
+    }
 
     fn kill_all(&mut self) {
-        for service in self.0.values_mut() {
+        for service in self.services.values_mut() {
             outputln!(preamble service.name(), "Stopping...");
             let shutdown_method = service.kill();
             outputln!(preamble service.name(), "Shutdown OK: {}", shutdown_method);
@@ -387,8 +413,8 @@ impl ServiceTable {
     }
 
     fn reap_services(&mut self) {
-        let mut dead: Vec<u32> = vec![];
-        for service in self.0.values_mut() {
+        let mut dead: Vec<(u32, i32)> = vec![];
+        for service in self.services.values_mut() {
             match service.try_wait() {
                 Ok(None) => (),
                 Ok(Some(code)) => {
@@ -396,16 +422,17 @@ impl ServiceTable {
                               service.name(),
                               service.id(),
                               code);
-                    dead.push(service.id());
+                    dead.push((service.id(), code.code().unwrap_or(SIGNALED_EXIT_CODE)));
                 }
                 Err(err) => {
                     warn!("Error waiting for child, {}, {}", service.id(), err);
-                    dead.push(service.id());
+                    dead.push((service.id(), SIGNALED_EXIT_CODE));
                 }
             }
         }
-        for pid in dead {
-            self.0.remove(&pid);
+        for (pid, code) in dead {
+            self.services.remove(&pid);
+            self.record_exit_code(pid, code);
         }
     }
 }
@@ -456,11 +483,21 @@ fn dispatch(tx: &Sender, bytes: &[u8], services: &mut ServiceTable) {
         }
     };
     let func = match msg.message_id() {
+        "ExitCode" => handlers::ExitCodeHandler::run,
         "Restart" => handlers::RestartHandler::run,
+        "ServiceUsage" => handlers::ServiceUsageHandler::run,
         "Spawn" => handlers::SpawnHandler::run,
         "Terminate" => handlers::TerminateHandler::run,
         unknown => {
             warn!("Received unknown message from Supervisor, {}", unknown);
+            // Say so, rather than leave the Supervisor waiting on a reply.
+            let reply = protocol::NetErr { code:       protocol::ErrCode::UnknownMessage,
+                                           msg:        format!("Unknown message, {}", unknown),
+                                           message_id: Some(unknown.to_string()),
+                                           pid:        None, };
+            if let Err(err) = send(tx, &reply) {
+                error!("{}: replying, {}", unknown, err);
+            }
             return;
         }
     };
@@ -1,4 +1,6 @@
-use crate::protocol;
+use crate::{log_file::{LogFile,
+                      SharedLogFile},
+            protocol};
 #[cfg(windows)]
 use core::os::process::windows_child::{ChildStderr,
                                        ChildStdout,
@@ -29,16 +31,26 @@ impl Service {
                stdout: Option<ChildStdout>,
                stderr: Option<ChildStderr>)
                -> Self {
+        let log_file = spawn.log_file.as_ref().and_then(|config| {
+                           match LogFile::open(config) {
+                               Ok(log_file) => Some(log_file.share()),
+                               Err(err) => {
+                                   warn!("Unable to open log file for {}, {}", spawn.id, err);
+                                   None
+                               }
+                           }
+                       });
         if let Some(stdout) = stdout {
             let id = spawn.id.to_string();
+            let log_file = log_file.clone();
             thread::Builder::new().name(format!("{}-out", spawn.id))
-                                  .spawn(move || pipe_stdout(stdout, &id))
+                                  .spawn(move || pipe_stdout(stdout, &id, log_file))
                                   .ok();
         }
         if let Some(stderr) = stderr {
             let id = spawn.id.to_string();
             thread::Builder::new().name(format!("{}-err", spawn.id))
-                                  .spawn(move || pipe_stderr(stderr, &id))
+                                  .spawn(move || pipe_stderr(stderr, &id, log_file))
                                   .ok();
         }
         Service { args: spawn,
@@ -59,6 +71,8 @@ impl Service {
 
     pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> { self.process.try_wait() }
 
+    pub fn usage(&self) -> Option<protocol::ServiceUsageOk> { self.process.usage() }
+
     pub fn wait(&mut self) -> io::Result<ExitStatus> { self.process.wait() }
 }
 
@@ -69,7 +83,7 @@ impl fmt::Debug for Service {
 }
 
 /// Consume output from a child process until EOF, then finish
-fn pipe_stdout<T>(out: T, id: &str)
+fn pipe_stdout<T>(out: T, id: &str, log_file: Option<SharedLogFile>)
     where T: Read
 {
     let mut reader = BufReader::new(out);
@@ -81,12 +95,17 @@ fn pipe_stdout<T>(out: T, id: &str)
             println!("printing output: '{}' to stdout resulted in error: {}",
                      content, e);
         }
+        if let Some(ref log_file) = log_file {
+            log_file.lock()
+                    .expect("Log file lock poisoned")
+                    .write_line("O", content);
+        }
         buffer.clear();
     }
 }
 
 /// Consume standard error from a child process until EOF, then finish
-fn pipe_stderr<T>(err: T, id: &str)
+fn pipe_stderr<T>(err: T, id: &str, log_file: Option<SharedLogFile>)
     where T: Read
 {
     let mut reader = BufReader::new(err);
@@ -98,6 +117,11 @@ fn pipe_stderr<T>(err: T, id: &str)
             eprintln!("printing output: '{}' to stderr resulted in error: {}",
                       content, e);
         }
+        if let Some(ref log_file) = log_file {
+            log_file.lock()
+                    .expect("Log file lock poisoned")
+                    .write_line("E", content);
+        }
         buffer.clear();
     }
 }
@@ -0,0 +1,138 @@
+//! Resource limits for services, applied through the unified (v2) cgroup
+//! hierarchy.
+//!
+//! Each service gets its own cgroup, named after the service, beneath a
+//! common parent (`/sys/fs/cgroup/habitat` unless `HAB_LAUNCH_CGROUP_ROOT`
+//! says otherwise). The Launcher must be able to write to that parent; if
+//! it can't, or the host doesn't use cgroup v2, services run without
+//! limits.
+
+use crate::{core,
+            protocol};
+use std::{fs::{self,
+               File,
+               OpenOptions},
+          io,
+          path::PathBuf};
+
+const CGROUP_ROOT_ENVVAR: &str = "HAB_LAUNCH_CGROUP_ROOT";
+const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup/habitat";
+const CONTROLLERS: &str = "+cpu +memory +pids";
+/// Used when a CPU quota is given without a period.
+const DEFAULT_CPU_PERIOD_US: u64 = 100_000;
+const DEFAULT_CPU_WEIGHT: u32 = 100;
+
+pub struct Cgroup {
+    path: PathBuf,
+}
+
+impl Cgroup {
+    /// Create (or reuse) the cgroup for the named service and write the
+    /// given limits to it. Limits which aren't set are reset, so a
+    /// service reloaded without them doesn't inherit old values.
+    pub fn create(name: &str, limits: &protocol::ResourceLimits) -> io::Result<Self> {
+        let root = cgroup_root();
+        if !root.exists() {
+            fs::create_dir_all(&root)?;
+        }
+        if !root.join("cgroup.controllers").is_file() {
+            return Err(io::Error::new(io::ErrorKind::NotFound,
+                                      format!("{} is not part of a cgroup v2 hierarchy",
+                                              root.display())));
+        }
+        // The controllers must be enabled in every ancestor for them to
+        // show up in a service's cgroup. The host (or its init system)
+        // normally enables them at the top; we only try the levels
+        // directly above the service, ignoring failures from the
+        // parent that we may not own.
+        if let Some(parent) = root.parent() {
+            fs::write(parent.join("cgroup.subtree_control"), CONTROLLERS).ok();
+        }
+        fs::write(root.join("cgroup.subtree_control"), CONTROLLERS)?;
+
+        let path = root.join(name);
+        if !path.exists() {
+            fs::create_dir(&path)?;
+        }
+        let cgroup = Cgroup { path };
+        cgroup.apply(limits)?;
+        Ok(cgroup)
+    }
+
+    /// Open the list of this cgroup's processes for writing. A process
+    /// joins the cgroup by writing its pid (or "0", meaning itself) to
+    /// it, and children it forks afterwards are accounted to the cgroup
+    /// too.
+    pub fn procs(&self) -> io::Result<File> {
+        OpenOptions::new().write(true)
+                          .open(self.path.join("cgroup.procs"))
+    }
+
+    pub fn usage(&self) -> protocol::ServiceUsageOk {
+        let cpu_stat = self.read("cpu.stat").unwrap_or_default();
+        let memory_events = self.read("memory.events").unwrap_or_default();
+        protocol::ServiceUsageOk { cpu_usage_usec:     keyed(&cpu_stat, "usage_usec"),
+                                   cpu_throttled_usec: keyed(&cpu_stat, "throttled_usec"),
+                                   memory_current:     self.read_u64("memory.current"),
+                                   pids_current:       self.read_u64("pids.current"),
+                                   oom_kills:          keyed(&memory_events, "oom_kill"),
+                                   pid:                None, }
+    }
+
+    fn apply(&self, limits: &protocol::ResourceLimits) -> io::Result<()> {
+        let weight = limits.cpu_weight.unwrap_or(DEFAULT_CPU_WEIGHT);
+        self.write("cpu.weight", &weight.to_string())?;
+        let period = limits.cpu_period_us.unwrap_or(DEFAULT_CPU_PERIOD_US);
+        let quota = limits.cpu_quota_us
+                          .map_or_else(|| "max".to_string(), |q| q.to_string());
+        self.write("cpu.max", &format!("{} {}", quota, period))?;
+        self.write("memory.max", &max_or(limits.memory_max))?;
+        self.write("pids.max", &max_or(limits.pids_max))
+    }
+
+    fn write(&self, file: &str, value: &str) -> io::Result<()> {
+        fs::write(self.path.join(file), value)
+            .map_err(|e| io::Error::new(e.kind(), format!("writing {}, {}", file, e)))
+    }
+
+    fn read(&self, file: &str) -> Option<String> {
+        fs::read_to_string(self.path.join(file)).ok()
+    }
+
+    fn read_u64(&self, file: &str) -> Option<u64> {
+        self.read(file).and_then(|s| s.trim().parse().ok())
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        // This fails if any processes are still in the cgroup (for
+        // instance, when the service has already been restarted into
+        // it), which is what we want.
+        fs::remove_dir(&self.path).ok();
+    }
+}
+
+fn cgroup_root() -> PathBuf {
+    core::env::var(CGROUP_ROOT_ENVVAR).map(PathBuf::from)
+                                      .unwrap_or_else(|_| PathBuf::from(DEFAULT_CGROUP_ROOT))
+}
+
+fn max_or(value: Option<u64>) -> String {
+    value.map_or_else(|| "max".to_string(), |v| v.to_string())
+}
+
+/// Find the value for `key` in a flat-keyed cgroup file such as
+/// `cpu.stat`.
+fn keyed(contents: &str, key: &str) -> Option<u64> {
+    contents.lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                match (fields.next(), fields.next()) {
+                    (Some(k), Some(v)) if k == key => v.parse().ok(),
+                    _ => None,
+                }
+            })
+            .next()
+}
+
@@ -1 +1,2 @@
+mod cgroup;
 pub mod service;
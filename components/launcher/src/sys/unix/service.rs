@@ -1,10 +1,14 @@
-use std::{io,
+use std::{fs::File,
+          io,
           ops::Neg,
-          os::unix::process::CommandExt,
+          os::unix::{io::{AsRawFd,
+                          RawFd},
+                     process::CommandExt},
           process::{Child,
                     Command,
                     ExitStatus,
                     Stdio},
+          ptr,
           result};
 
 use crate::{core::os::{self,
@@ -16,19 +20,23 @@ use libc;
 use time::{Duration,
            SteadyTime};
 
+use super::cgroup::Cgroup;
 use crate::{error::{Error,
                     Result},
             service::Service};
 
-pub struct Process(Child);
+pub struct Process {
+    child:  Child,
+    cgroup: Option<Cgroup>,
+}
 
 impl Process {
-    pub fn id(&self) -> u32 { self.0.id() }
+    pub fn id(&self) -> u32 { self.child.id() }
 
     /// Attempt to gracefully terminate a process and then forcefully kill it after
     /// 8 seconds if it has not terminated.
     pub fn kill(&mut self) -> ShutdownMethod {
-        let mut pid_to_kill = self.0.id() as i32;
+        let mut pid_to_kill = self.child.id() as i32;
         // check the group of the process being killed
         // if it is the root process of the process group
         // we send our signals to the entire process group
@@ -64,9 +72,15 @@ impl Process {
         }
     }
 
-    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> { self.0.try_wait() }
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> { self.child.try_wait() }
+
+    /// Resource usage of the process's cgroup, if it was given resource
+    /// limits.
+    pub fn usage(&self) -> Option<protocol::ServiceUsageOk> {
+        self.cgroup.as_ref().map(Cgroup::usage)
+    }
 
-    pub fn wait(&mut self) -> io::Result<ExitStatus> { self.0.wait() }
+    pub fn wait(&mut self) -> io::Result<ExitStatus> { self.child.wait() }
 }
 
 pub fn run(msg: protocol::Spawn) -> Result<Service> {
@@ -90,22 +104,78 @@ pub fn run(msg: protocol::Spawn) -> Result<Service> {
         return Err(Error::GroupNotFound(String::from("")));
     };
 
-    cmd.before_exec(owned_pgid);
     cmd.stdin(Stdio::null())
        .stdout(Stdio::piped())
-       .stderr(Stdio::piped())
-       .uid(uid)
-       .gid(gid);
+       .stderr(Stdio::piped());
     for (key, val) in msg.env.iter() {
         cmd.env(key, val);
     }
+    let cgroup = match msg.limits {
+        Some(ref limits) => {
+            match open_cgroup(&msg.id, limits) {
+                Ok(cgroup) => Some(cgroup),
+                Err(err) => {
+                    warn!("Unable to apply resource limits to {}, running without them: {}",
+                          msg.id, err);
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+    let procs = cgroup.as_ref().map(|(_, procs)| procs.as_raw_fd());
+    // The child joins its cgroup before it execs, so nothing it runs
+    // escapes the limits. It must do so before giving up root, which is
+    // why it switches to the service user itself rather than leaving
+    // that to `Command::uid` and `Command::gid`, which run first.
+    unsafe {
+        cmd.pre_exec(owned_pgid);
+        cmd.pre_exec(move || {
+               if let Some(procs) = procs {
+                   join_cgroup(procs)?;
+               }
+               set_identity(uid, gid)
+           });
+    }
     let mut child = cmd.spawn().map_err(Error::Spawn)?;
     let stdout = child.stdout.take();
     let stderr = child.stderr.take();
-    let process = Process(child);
+    let cgroup = cgroup.map(|(cgroup, _)| cgroup);
+    let process = Process { child, cgroup };
     Ok(Service::new(msg, process, stdout, stderr))
 }
 
+fn open_cgroup(name: &str, limits: &protocol::ResourceLimits) -> io::Result<(Cgroup, File)> {
+    let cgroup = Cgroup::create(name, limits)?;
+    let procs = cgroup.procs()?;
+    Ok((cgroup, procs))
+}
+
+// Runs in the child between fork and exec, so it may only make
+// async-signal-safe calls; "0" stands for the writing process, which
+// saves formatting its pid.
+fn join_cgroup(procs: RawFd) -> io::Result<()> {
+    let written = unsafe { libc::write(procs, b"0".as_ptr() as *const libc::c_void, 1) };
+    if written < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// Does what `Command::uid` and `Command::gid` would, dropping any
+// supplementary groups of the Launcher when it runs as root.
+fn set_identity(uid: libc::uid_t, gid: libc::gid_t) -> io::Result<()> {
+    unsafe {
+        if libc::getuid() == 0 {
+            libc::setgroups(0, ptr::null());
+        }
+        if libc::setgid(gid) != 0 || libc::setuid(uid) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
 // we want the command to spawn processes in their own process group
 // and not the same group as the Launcher. Otherwise if a child process
 // sends SIGTERM to the group, the Launcher could be terminated.
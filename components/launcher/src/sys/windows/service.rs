@@ -47,6 +47,10 @@ impl Process {
 
     pub fn id(&self) -> u32 { unsafe { processthreadsapi::GetProcessId(self.handle.raw()) as u32 } }
 
+    /// Resource limits are not supported on Windows, so there is no usage
+    /// to report.
+    pub fn usage(&self) -> Option<protocol::ServiceUsageOk> { None }
+
     /// Attempt to gracefully terminate a process and then forcefully kill it after
     /// 8 seconds if it has not terminated.
     pub fn kill(&mut self) -> ShutdownMethod {
@@ -135,6 +139,10 @@ pub fn run(msg: protocol::Spawn) -> Result<Service> {
 
 fn spawn_pwsh(ps_binary_name: &str, msg: protocol::Spawn) -> Result<Service> {
     debug!("launcher is spawning {}", msg.binary);
+    if msg.limits.is_some() {
+        warn!("Resource limits are not supported on Windows, ignoring them for {}",
+              msg.id);
+    }
     let ps_cmd = format!("iex $(gc {} | out-string)", &msg.binary);
     let password = msg.svc_password.clone();
 
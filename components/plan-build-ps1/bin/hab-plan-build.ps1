@@ -118,6 +118,8 @@ $script:pkg_svc_run = ''
 $script:pkg_expose = @()
 # An associative array representing configuration data which should be gossiped to peers.
 $script:pkg_exports = @{}
+# Whether templates referring to undefined variables fail to render
+$script:pkg_strict_templates = $false
 # The user to run the service as
 $script:pkg_svc_user = "hab"
 # The group to run the service as
@@ -1731,7 +1733,7 @@ function Invoke-DefaultInstall {
 }
 
 # Copy the `./config` directory, relative to the Plan, to `$pkg_prefix\config`.
-# Do the same with `default.toml`. Delegates most of the implementation to the
+# Do the same with `default.toml` and `default.schema.json`. Delegates most of the implementation to the
 # `Invoke-DefaultBuildConfig` function.
 function Invoke-BuildConfig {
     Invoke-DefaultBuildConfig
@@ -1755,6 +1757,10 @@ function Invoke-DefaultBuildConfig {
         Write-BuildLine "Writing default.toml"
         Copy-Item "$PLAN_CONTEXT/default.toml" $pkg_prefix
     }
+    if (Test-Path "$PLAN_CONTEXT/default.schema.json") {
+        Write-BuildLine "Writing default.schema.json"
+        Copy-Item "$PLAN_CONTEXT/default.schema.json" $pkg_prefix
+    }
 }
 
 # Write out the `$pkg_prefix\run` file. If a file named `hooks\run`
@@ -1912,6 +1918,10 @@ function _Write-Metadata {
         }
     }
 
+    if ($pkg_strict_templates) {
+        "true" | Out-File "$pkg_prefix\STRICT_TEMPLATES" -Encoding ascii
+    }
+
     $runtime_path = _Assemble-RuntimePath
     if ($runtime_path) {
       "$runtime_path" | Out-File "$pkg_prefix\RUNTIME_PATH" -Encoding ascii
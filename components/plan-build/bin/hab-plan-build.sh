@@ -211,6 +211,14 @@
 #   [storage]="port host"
 # )
 #
+# ### pkg_strict_templates
+# Whether the Supervisor should refuse to render the package's `config/` and `hooks/` templates
+# when they refer to variables that aren't defined, e.g. a misspelled `{{cfg.port}}`, instead of
+# rendering them as empty strings.
+# ```
+# pkg_strict_templates=true
+# ```
+#
 # ### pkg_origin
 # A string to use for the origin. The origin is used to denote a particular upstream of a
 # package; when we resolve dependencies, we consider a version of a package to be equal
@@ -388,6 +396,8 @@ pkg_exposes=()
 declare -A pkg_exports
 declare -A pkg_binds
 declare -A pkg_binds_optional
+# Whether templates referring to undefined variables fail to render
+pkg_strict_templates=false
 # The user to run the service as
 pkg_svc_user=hab
 # The group to run the service as
@@ -1939,6 +1949,7 @@ _build_metadata() {
   _render_metadata_BINDS
   _render_metadata_BINDS_OPTIONAL
   _render_metadata_EXPOSES
+  _render_metadata_STRICT_TEMPLATES
   _render_metadata_INTERPRETERS
   _render_metadata_BUILD_DEPS
   _render_metadata_BUILD_TDEPS
@@ -1962,7 +1973,7 @@ _build_metadata() {
 }
 
 # Copy the `./config` directory, relative to the Plan, to `$pkg_prefix/config`.
-# Do the same with `default.toml`. Delegates most of the implementation to the
+# Do the same with `default.toml` and `default.schema.json`. Delegates most of the implementation to the
 # `do_default_build_config()` function.
 do_build_config() {
   do_default_build_config
@@ -1981,6 +1992,9 @@ do_default_build_config() {
   if [[ -f "$PLAN_CONTEXT/default.toml" ]]; then
     cp "$PLAN_CONTEXT/default.toml" "$pkg_prefix"
   fi
+  if [[ -f "$PLAN_CONTEXT/default.schema.json" ]]; then
+    cp "$PLAN_CONTEXT/default.schema.json" "$pkg_prefix"
+  fi
   return 0
 }
 
@@ -232,6 +232,14 @@ _render_metadata_RUNTIME_PATH(){
   fi
 }
 
+_render_metadata_STRICT_TEMPLATES() {
+  # shellcheck disable=2154
+  if [[ "$pkg_strict_templates" == "true" ]]; then
+    debug "Rendering STRICT_TEMPLATES metadata file"
+    echo "true" > "$pkg_prefix"/STRICT_TEMPLATES
+  fi
+}
+
 _render_metadata_SVC_GROUP() {
   debug "Rendering SVC_GROUP metadata file"
   # shellcheck disable=2154
@@ -6,7 +6,7 @@
 //! # RPC Call Example
 //!
 //! ```ignore
-//! let conn = SrvClient::connect(&listen_addr, secret_key).wait()?;
+//! let conn = SrvClient::connect(&listen_addr, secret_key, None).wait()?;
 //! let msg = protocols::ctl::ServiceGetDefaultCfg::new();
 //! conn.call(msg).for_each(|reply| {
 //!     match reply.message_id() {
@@ -33,15 +33,28 @@ use habitat_common as common;
 
 use std::{error,
           fmt,
-          io,
-          path::PathBuf};
+          fs::File,
+          io::{self,
+               BufReader},
+          path::{Path,
+                 PathBuf},
+          sync::Arc};
 
 use crate::protocol::{codec::*,
                       net::NetErr};
-use futures::{prelude::*,
+use futures::{future::{self,
+                       Either},
+              prelude::*,
               sink};
+use rustls::{internal::pemfile,
+             Certificate,
+             ClientConfig,
+             PrivateKey};
 use tokio::net::TcpStream;
 use tokio_codec::Framed;
+use tokio_rustls::TlsConnector;
+use webpki::{DNSName,
+             DNSNameRef};
 
 use crate::common::types::ListenCtlAddr;
 
@@ -56,6 +69,12 @@ pub enum SrvClientError {
     CtlSecretNotFound(PathBuf),
     /// Decoding a message from the remote failed.
     Decode(prost::DecodeError),
+    /// A certificate file given for TLS couldn't be read as certificates.
+    InvalidCertFile(PathBuf),
+    /// A key file given for TLS couldn't be read as a private key.
+    InvalidKeyFile(PathBuf),
+    /// The name to verify the Supervisor's certificate against isn't a valid DNS name.
+    InvalidServerName(String),
     /// An Os level IO error occurred.
     Io(io::Error),
     /// An RPC call to the remote was received but failed.
@@ -70,6 +89,9 @@ impl error::Error for SrvClientError {
             SrvClientError::ConnectionClosed => "Connection closed",
             SrvClientError::CtlSecretNotFound(_) => "Ctl secret key not found",
             SrvClientError::Decode(ref err) => err.description(),
+            SrvClientError::InvalidCertFile(_) => "Invalid TLS certificate file",
+            SrvClientError::InvalidKeyFile(_) => "Invalid TLS key file",
+            SrvClientError::InvalidServerName(_) => "Invalid TLS server name",
             SrvClientError::Io(ref err) => err.description(),
             SrvClientError::NetErr(ref err) => err.description(),
             SrvClientError::ParseColor(ref err) => err.description(),
@@ -93,6 +115,22 @@ impl fmt::Display for SrvClientError {
                         path.display())
             }
             SrvClientError::Decode(ref err) => format!("{}", err),
+            SrvClientError::InvalidCertFile(ref path) => {
+                format!("Unable to read TLS certificates from {}. It should contain \
+                         PEM-format certificates.",
+                        path.display())
+            }
+            SrvClientError::InvalidKeyFile(ref path) => {
+                format!("Unable to read a TLS private key from {}. It should contain a RSA or \
+                         PKCS8-encoded private key, in PEM format.",
+                        path.display())
+            }
+            SrvClientError::InvalidServerName(ref name) => {
+                format!("Unable to verify the Supervisor's TLS certificate against '{}', \
+                         which is not a DNS name. Set `ctl_tls_server_name` in `cli.toml` to \
+                         the name in the certificate.",
+                        name)
+            }
             SrvClientError::Io(ref err) => {
                 format!("Unable to contact the Supervisor.\n\nIf the Supervisor you are \
                          contacting is local, this probably means it is not running. You can run \
@@ -124,6 +162,33 @@ impl From<termcolor::ParseColorError> for SrvClientError {
     fn from(err: termcolor::ParseColorError) -> Self { SrvClientError::ParseColor(err) }
 }
 
+/// TLS settings for connecting to a CtlGateway which requires it.
+#[derive(Clone, Debug)]
+pub struct SrvClientTls {
+    /// CA certificates to verify the Supervisor's certificate with, in PEM format.
+    pub ca_cert_path: PathBuf,
+    /// A certificate and private key to present to Supervisors which require clients to
+    /// authenticate, in PEM format.
+    pub client_cert: Option<(PathBuf, PathBuf)>,
+    /// The DNS name the Supervisor's certificate must be valid for.
+    pub server_name: String,
+}
+
+impl SrvClientTls {
+    fn client_config(&self) -> Result<ClientConfig, SrvClientError> {
+        let mut config = ClientConfig::new();
+        let ca_file = &mut BufReader::new(File::open(&self.ca_cert_path)?);
+        match config.root_store.add_pem_file(ca_file) {
+            Ok((added, _)) if added > 0 => {}
+            _ => return Err(SrvClientError::InvalidCertFile(self.ca_cert_path.clone())),
+        }
+        if let Some((ref cert_path, ref key_path)) = self.client_cert {
+            config.set_single_client_cert(certificates(cert_path)?, private_key(key_path)?);
+        }
+        Ok(config)
+    }
+}
+
 /// Client for connecting and communicating with a server listener which speaks SrvProtocol.
 ///
 /// See module doc for usage.
@@ -139,12 +204,22 @@ pub struct SrvClient {
 }
 
 impl SrvClient {
-    /// Connect to the given remote server and authenticate with the given secret_key.
+    /// Connect to the given remote server and authenticate with the given secret_key. If `tls`
+    /// is given, the connection is made over TLS.
     pub fn connect(addr: &ListenCtlAddr,
-                   secret_key: &str)
+                   secret_key: &str,
+                   tls: Option<&SrvClientTls>)
                    -> Box<dyn Future<Item = SrvClient, Error = SrvClientError> + 'static> {
         let secret_key = secret_key.to_string();
+        let tls = match tls.map(Self::tls_connector) {
+            Some(Ok(tls)) => Some(tls),
+            Some(Err(err)) => return Box::new(future::err(err)),
+            None => None,
+        };
         let conn = TcpStream::connect(addr.as_ref()).map_err(SrvClientError::from)
+                                                    .and_then(move |socket| {
+                                                        Self::start_tls(socket, tls)
+                                                    })
                                                     .and_then(move |socket| {
                                                         let client = Self::new(socket, None);
                                                         let mut request =
@@ -174,7 +249,29 @@ impl SrvClient {
         Ok(buf)
     }
 
-    fn new(socket: TcpStream, current_txn: Option<SrvTxn>) -> Self {
+    fn tls_connector(tls: &SrvClientTls) -> Result<(TlsConnector, DNSName), SrvClientError> {
+        let server_name =
+            DNSNameRef::try_from_ascii_str(&tls.server_name)
+                .map_err(|_| SrvClientError::InvalidServerName(tls.server_name.clone()))?;
+        let config = tls.client_config()?;
+        Ok((TlsConnector::from(Arc::new(config)), server_name.to_owned()))
+    }
+
+    /// Start TLS on a newly connected socket, if we're using it.
+    fn start_tls(socket: TcpStream,
+                 tls: Option<(TlsConnector, DNSName)>)
+                 -> impl Future<Item = Box<dyn SrvIo>, Error = SrvClientError> {
+        match tls {
+            Some((connector, server_name)) => {
+                Either::A(connector.connect(server_name.as_ref(), socket)
+                                   .map(|socket| Box::new(socket) as Box<dyn SrvIo>)
+                                   .map_err(SrvClientError::from))
+            }
+            None => Either::B(future::ok(Box::new(socket) as Box<dyn SrvIo>)),
+        }
+    }
+
+    fn new(socket: Box<dyn SrvIo>, current_txn: Option<SrvTxn>) -> Self {
         SrvClient { socket:      Framed::new(socket, SrvCodec::new()),
                     current_txn: current_txn.unwrap_or_default(), }
     }
@@ -253,6 +350,25 @@ impl Stream for SrvReply {
     }
 }
 
+/// Read the PEM-format certificates in a file.
+fn certificates(path: &Path) -> Result<Vec<Certificate>, SrvClientError> {
+    let file = &mut BufReader::new(File::open(path)?);
+    pemfile::certs(file).ok()
+                        .filter(|certs| !certs.is_empty())
+                        .ok_or_else(|| SrvClientError::InvalidCertFile(path.to_path_buf()))
+}
+
+/// Read the first RSA or PKCS8 private key from a PEM file.
+fn private_key(path: &Path) -> Result<PrivateKey, SrvClientError> {
+    let rsa_keys = pemfile::rsa_private_keys(&mut BufReader::new(File::open(path)?));
+    let pkcs8_keys = pemfile::pkcs8_private_keys(&mut BufReader::new(File::open(path)?));
+    rsa_keys.into_iter()
+            .chain(pkcs8_keys)
+            .flatten()
+            .next()
+            .ok_or_else(|| SrvClientError::InvalidKeyFile(path.to_path_buf()))
+}
+
 enum SrvReplyState {
     /// Request is sending.
     Sending,
@@ -0,0 +1,287 @@
+//! Named CtlGateway secrets, each limited to some scopes.
+//!
+//! The key in `CTL_SECRET` is the Supervisor's admin secret and allows every request. Alongside
+//! it, `CTL_SECRETS.toml` holds any number of named secrets, each of which only allows the
+//! requests its scopes cover. The Supervisor reads the file on every handshake, so a secret that
+//! is created or revoked takes effect for the next connection.
+
+use crate::{core::{self,
+                   crypto},
+            net::{self,
+                  ErrCode,
+                  NetResult}};
+use std::{fmt,
+          fs::{self,
+               File},
+          io::{Read,
+               Write},
+          path::{Path,
+                 PathBuf},
+          str::FromStr};
+
+/// Name of the file containing the named CtlGateway secrets.
+const CTL_SECRETS_FILENAME: &str = "CTL_SECRETS.toml";
+
+/// What a CtlGateway secret allows.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Querying the status, health and default configuration of services.
+    Read,
+    /// Applying configuration and files to service groups.
+    Config,
+    /// Loading, unloading, starting and stopping services.
+    Lifecycle,
+    /// Everything, including departing Supervisors from the ring.
+    Admin,
+}
+
+impl Scope {
+    /// The scope needed to make a request, given its message id. Requests we don't know about
+    /// need `Admin`, so new requests aren't allowed by narrower secrets until they're added here.
+    pub fn required_for(message_id: &str) -> Scope {
+        match message_id {
+            "SvcGetDefaultCfg" | "SvcHealth" | "SvcStatus" | "SvcStatusWatch" => Scope::Read,
+            "SvcCfgHistory" | "SvcDryRunCfg" | "SvcFilePut" | "SvcSetCfg" | "SvcValidateCfg" => {
+                Scope::Config
+            }
+            "SupApply" | "SvcLoad" | "SvcStart" | "SvcStop" | "SvcUnload" => Scope::Lifecycle,
+            _ => Scope::Admin,
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match *self {
+            Scope::Read => "read",
+            Scope::Config => "config",
+            Scope::Lifecycle => "lifecycle",
+            Scope::Admin => "admin",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl FromStr for Scope {
+    type Err = net::NetErr;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_ref() {
+            "read" | "status" => Ok(Scope::Read),
+            "config" => Ok(Scope::Config),
+            "lifecycle" => Ok(Scope::Lifecycle),
+            "admin" => Ok(Scope::Admin),
+            _ => {
+                Err(net::err(ErrCode::InvalidPayload,
+                             format!("Invalid scope, {}. Expected one of read, config, \
+                                      lifecycle or admin",
+                                     value)))
+            }
+        }
+    }
+}
+
+/// Returns true if a secret with the given scopes allows a request that needs `required`.
+/// `Admin` allows everything, and any scope allows reading.
+pub fn permits(scopes: &[Scope], required: Scope) -> bool {
+    scopes.iter()
+          .any(|s| *s == Scope::Admin || *s == required || required == Scope::Read)
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CtlSecret {
+    pub name:   String,
+    pub key:    String,
+    pub scopes: Vec<Scope>,
+}
+
+/// The contents of `CTL_SECRETS.toml`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct CtlSecrets {
+    #[serde(default, rename = "secret")]
+    secrets: Vec<CtlSecret>,
+}
+
+impl CtlSecrets {
+    /// Read the named secrets for the given Supervisor root. A missing file has no secrets.
+    pub fn load<T>(sup_root: T) -> NetResult<Self>
+        where T: AsRef<Path>
+    {
+        let path = secrets_path(sup_root);
+        if !path.exists() {
+            return Ok(CtlSecrets::default());
+        }
+        let mut contents = String::new();
+        File::open(&path).and_then(|mut f| f.read_to_string(&mut contents))
+                         .map_err(|e| io_err(&path, e))?;
+        toml::from_str(&contents).map_err(|e| {
+                                     net::err(ErrCode::Io,
+                                              format!("Unable to parse ctl secrets, {}, {}",
+                                                      path.display(),
+                                                      e))
+                                 })
+    }
+
+    /// Write the named secrets for the given Supervisor root. The file is replaced rather than
+    /// rewritten, so a Supervisor reading it never sees half of it.
+    pub fn save<T>(&self, sup_root: T) -> NetResult<()>
+        where T: AsRef<Path>
+    {
+        let path = secrets_path(&sup_root);
+        let tmp_path = path.with_extension("toml.tmp");
+        let contents = toml::to_string(self).expect("Ctl secrets always serialize");
+        fs::create_dir_all(sup_root.as_ref()).map_err(|e| io_err(sup_root.as_ref(), e))?;
+        {
+            let mut f = File::create(&tmp_path).map_err(|e| io_err(&tmp_path, e))?;
+            set_permissions(&tmp_path).map_err(|e| {
+                                           net::err(ErrCode::Io,
+                                                    format!("Unable to set permissions on {}, \
+                                                             {}",
+                                                            tmp_path.display(),
+                                                            e))
+                                       })?;
+            f.write_all(contents.as_bytes())
+             .and_then(|_| f.sync_all())
+             .map_err(|e| io_err(&tmp_path, e))?;
+        }
+        fs::rename(&tmp_path, &path).map_err(|e| io_err(&path, e))
+    }
+
+    /// Add a new secret with a freshly generated key.
+    pub fn create(&mut self, name: &str, scopes: Vec<Scope>) -> NetResult<&CtlSecret> {
+        if name.is_empty()
+           || !name.chars()
+                   .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(net::err(ErrCode::InvalidPayload,
+                                format!("Invalid secret name, {}. Names may only contain \
+                                         letters, numbers, '-' and '_'",
+                                        name)));
+        }
+        if scopes.is_empty() {
+            return Err(net::err(ErrCode::InvalidPayload,
+                                "A secret needs at least one scope"));
+        }
+        if self.get(name).is_some() {
+            return Err(net::err(ErrCode::Conflict,
+                                format!("A secret named {} already exists", name)));
+        }
+        let mut key = String::new();
+        crate::generate_secret_key(&mut key);
+        self.secrets.push(CtlSecret { name: name.to_string(),
+                                      key,
+                                      scopes });
+        Ok(self.secrets.last().expect("A secret was just added"))
+    }
+
+    /// Remove the named secret, returning it if there was one.
+    pub fn revoke(&mut self, name: &str) -> Option<CtlSecret> {
+        let index = self.secrets.iter().position(|s| s.name == name)?;
+        Some(self.secrets.remove(index))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CtlSecret> {
+        self.secrets.iter().find(|s| s.name == name)
+    }
+
+    /// Find the secret with the given key.
+    pub fn authenticate(&self, key: &str) -> Option<&CtlSecret> {
+        self.secrets
+            .iter()
+            .find(|s| crypto::secure_eq(&s.key, key))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CtlSecret> { self.secrets.iter() }
+}
+
+/// Returns the location of the named CtlGateway secrets on disk for the given Supervisor root.
+pub fn secrets_path<T>(sup_root: T) -> PathBuf
+    where T: AsRef<Path>
+{
+    sup_root.as_ref().join(CTL_SECRETS_FILENAME)
+}
+
+fn io_err(path: &Path, err: std::io::Error) -> net::NetErr {
+    net::err(ErrCode::Io,
+             format!("IoError while reading or writing ctl secrets, {}, {}",
+                     path.display(),
+                     err))
+}
+
+#[cfg(not(windows))]
+fn set_permissions(path: &Path) -> core::error::Result<()> {
+    core::util::posix_perm::set_permissions(path, 0o600)
+}
+
+#[cfg(windows)]
+fn set_permissions(path: &Path) -> core::error::Result<()> {
+    core::util::win_perm::harden_path(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn scopes_permit_their_own_requests_and_reading() {
+        let config = [Scope::Config];
+        assert!(permits(&config, Scope::required_for("SvcStatus")));
+        assert!(permits(&config, Scope::required_for("SvcSetCfg")));
+        assert!(!permits(&config, Scope::required_for("SvcUnload")));
+        assert!(!permits(&config, Scope::required_for("SupDepart")));
+
+        let lifecycle = [Scope::Lifecycle];
+        assert!(permits(&lifecycle, Scope::required_for("SupApply")));
+        assert!(!permits(&lifecycle, Scope::required_for("SvcSetCfg")));
+
+        let read = [Scope::Read];
+        assert!(permits(&read, Scope::required_for("SvcHealth")));
+        assert!(!permits(&read, Scope::required_for("SvcStop")));
+
+        let admin = [Scope::Admin];
+        assert!(permits(&admin, Scope::required_for("SupDepart")));
+        assert!(permits(&admin, Scope::required_for("SomethingNew")));
+        assert!(!permits(&[], Scope::required_for("SvcStatus")));
+    }
+
+    #[test]
+    fn secrets_round_trip_through_the_file() {
+        let tmpdir = TempDir::new().unwrap();
+        let mut secrets = CtlSecrets::load(tmpdir.path()).unwrap();
+        assert_eq!(secrets.iter().count(), 0);
+
+        let key = secrets.create("monitoring", vec![Scope::Read])
+                         .unwrap()
+                         .key
+                         .clone();
+        secrets.create("deploy", vec![Scope::Config, Scope::Lifecycle])
+               .unwrap();
+        secrets.save(tmpdir.path()).unwrap();
+
+        let mut secrets = CtlSecrets::load(tmpdir.path()).unwrap();
+        let found = secrets.authenticate(&key).unwrap();
+        assert_eq!(found.name, "monitoring");
+        assert_eq!(found.scopes, vec![Scope::Read]);
+        assert!(secrets.authenticate("not-a-key").is_none());
+
+        assert_eq!(secrets.revoke("monitoring").unwrap().key, key);
+        assert!(secrets.revoke("monitoring").is_none());
+        secrets.save(tmpdir.path()).unwrap();
+        let secrets = CtlSecrets::load(tmpdir.path()).unwrap();
+        assert!(secrets.authenticate(&key).is_none());
+        assert!(secrets.get("deploy").is_some());
+    }
+
+    #[test]
+    fn create_rejects_bad_names_and_duplicates() {
+        let mut secrets = CtlSecrets::default();
+        assert!(secrets.create("", vec![Scope::Read]).is_err());
+        assert!(secrets.create("has space", vec![Scope::Read]).is_err());
+        assert!(secrets.create("no-scopes", vec![]).is_err());
+        secrets.create("ci", vec![Scope::Admin]).unwrap();
+        assert_eq!(secrets.create("ci", vec![Scope::Read]).unwrap_err().code,
+                   ErrCode::Conflict as i32);
+    }
+}
@@ -12,6 +12,9 @@ impl message::MessageStatic for ServiceBindList {
 impl message::MessageStatic for SupDepart {
     const MESSAGE_ID: &'static str = "SupDepart";
 }
+impl message::MessageStatic for SupApply {
+    const MESSAGE_ID: &'static str = "SupApply";
+}
 impl message::MessageStatic for SvcFilePut {
     const MESSAGE_ID: &'static str = "SvcFilePut";
 }
@@ -21,9 +24,15 @@ impl message::MessageStatic for SvcGetDefaultCfg {
 impl message::MessageStatic for SvcValidateCfg {
     const MESSAGE_ID: &'static str = "SvcValidateCfg";
 }
+impl message::MessageStatic for SvcDryRunCfg {
+    const MESSAGE_ID: &'static str = "SvcDryRunCfg";
+}
 impl message::MessageStatic for SvcSetCfg {
     const MESSAGE_ID: &'static str = "SvcSetCfg";
 }
+impl message::MessageStatic for SvcCfgHistory {
+    const MESSAGE_ID: &'static str = "SvcCfgHistory";
+}
 impl message::MessageStatic for SvcLoad {
     const MESSAGE_ID: &'static str = "SvcLoad";
 }
@@ -39,6 +48,12 @@ impl message::MessageStatic for SvcStop {
 impl message::MessageStatic for SvcStatus {
     const MESSAGE_ID: &'static str = "SvcStatus";
 }
+impl message::MessageStatic for SvcStatusWatch {
+    const MESSAGE_ID: &'static str = "SvcStatusWatch";
+}
+impl message::MessageStatic for SvcHealth {
+    const MESSAGE_ID: &'static str = "SvcHealth";
+}
 impl message::MessageStatic for ConsoleLine {
     const MESSAGE_ID: &'static str = "ConsoleLine";
 }
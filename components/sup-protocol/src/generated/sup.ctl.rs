@@ -38,6 +38,22 @@ pub struct SupDepart {
     #[prost(string, optional, tag="1")]
     pub member_id: ::std::option::Option<std::string::String>,
 }
+/// Request to reconcile the services a Supervisor runs with a stack file. The reply is the change
+/// planned for each service, followed by the output of making those changes.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SupApply {
+    /// The stack file, as TOML.
+    #[prost(string, optional, tag="1")]
+    pub stack: ::std::option::Option<std::string::String>,
+    /// Unload the services that the stack doesn't list.
+    #[prost(bool, optional, tag="2")]
+    pub prune: ::std::option::Option<bool>,
+    /// Only reply with the planned changes, without making them.
+    #[prost(bool, optional, tag="3")]
+    pub dry_run: ::std::option::Option<bool>,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -77,6 +93,22 @@ pub struct SvcValidateCfg {
     #[prost(bytes, optional, tag="3")]
     pub cfg: ::std::option::Option<std::vec::Vec<u8>>,
 }
+/// Request to render a running service's configuration files and hooks with a configuration
+/// change, without applying it. The reply is a diff of each file that would change.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcDryRunCfg {
+    /// Service group of a running service to render with the configuration change.
+    #[prost(message, optional, tag="1")]
+    pub service_group: ::std::option::Option<super::types::ServiceGroup>,
+    /// Structured and self-describing string format contained in the configuration string.
+    #[prost(enumeration="super::types::service_cfg::Format", optional, tag="2", default="Toml")]
+    pub format: ::std::option::Option<i32>,
+    /// Unencrypted configuration to render with.
+    #[prost(bytes, optional, tag="3")]
+    pub cfg: ::std::option::Option<std::vec::Vec<u8>>,
+}
 /// Request to set a running service's configuration to the given values.
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -97,6 +129,15 @@ pub struct SvcSetCfg {
     #[prost(bool, optional, tag="4", default="false")]
     pub is_encrypted: ::std::option::Option<bool>,
 }
+/// Request for the configurations that have been applied to a service group, oldest first.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcCfgHistory {
+    /// Service group of a running service to get the configurations of.
+    #[prost(message, optional, tag="1")]
+    pub service_group: ::std::option::Option<super::types::ServiceGroup>,
+}
 /// Request to load a new service.
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -148,6 +189,33 @@ pub struct SvcLoad {
     /// Health Check interval for the service
     #[prost(message, optional, tag="15")]
     pub health_check_interval: ::std::option::Option<super::types::HealthCheckInterval>,
+    /// How the Supervisor reacts when the service's process exits.
+    #[prost(enumeration="super::types::RestartPolicy", optional, tag="16")]
+    pub restart_policy: ::std::option::Option<i32>,
+    /// Delays and limits applied when restarting the service.
+    #[prost(message, optional, tag="17")]
+    pub restart_backoff: ::std::option::Option<super::types::RestartBackoff>,
+    /// Limits on the resources the service may consume.
+    #[prost(message, optional, tag="18")]
+    pub resource_limits: ::std::option::Option<super::types::ResourceLimits>,
+    /// Where and how to write the service's output to a file.
+    #[prost(message, optional, tag="19")]
+    pub log_file: ::std::option::Option<super::types::LogFile>,
+    /// How a rolling update of the service is rolled out across its service group.
+    #[prost(message, optional, tag="20")]
+    pub update_rollout: ::std::option::Option<super::types::UpdateRollout>,
+    /// Whether to roll back updates of the service that fail their health checks.
+    #[prost(message, optional, tag="21")]
+    pub update_rollback: ::std::option::Option<super::types::UpdateRollback>,
+    /// The maintenance windows to hold updates of the service for. An empty list removes them.
+    #[prost(message, optional, tag="22")]
+    pub update_windows: ::std::option::Option<super::types::UpdateWindows>,
+    /// A constraint on the versions the service is updated to, e.g. "~9.6" or ">=1.2,<2.0".
+    #[prost(string, optional, tag="23")]
+    pub version_constraint: ::std::option::Option<std::string::String>,
+    /// Whether rendering the service's templates fails when they refer to undefined variables.
+    #[prost(bool, optional, tag="24")]
+    pub strict_templates: ::std::option::Option<bool>,
 }
 /// Request to unload a loaded service.
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -197,6 +265,29 @@ pub struct SvcStatus {
     #[prost(message, optional, tag="1")]
     pub ident: ::std::option::Option<super::types::PackageIdent>,
 }
+/// Request to watch the service status of one or all services. The reply starts with the status
+/// of every requested service, followed by a service's status whenever it changes, or a
+/// `ServiceStatusRemoved` when it's unloaded. The reply never completes; the client ends it by
+/// disconnecting.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcStatusWatch {
+    #[prost(message, optional, tag="1")]
+    pub ident: ::std::option::Option<super::types::PackageIdent>,
+}
+/// Request for the results of a loaded service's health checks.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcHealth {
+    #[prost(message, optional, tag="1")]
+    pub ident: ::std::option::Option<super::types::PackageIdent>,
+    /// If true, the reply contains every result the Supervisor has kept, oldest first, rather than
+    /// only the latest.
+    #[prost(bool, optional, tag="2", default="false")]
+    pub history: ::std::option::Option<bool>,
+}
 /// A reply to various requests which contains a pre-formatted console line.
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -21,6 +21,45 @@ impl message::MessageStatic for ServiceGroup {
 impl message::MessageStatic for ServiceStatus {
     const MESSAGE_ID: &'static str = "ServiceStatus";
 }
+impl message::MessageStatic for ServiceStatusRemoved {
+    const MESSAGE_ID: &'static str = "ServiceStatusRemoved";
+}
 impl message::MessageStatic for HealthCheckInterval {
     const MESSAGE_ID: &'static str = "HealthCheckInterval";
 }
+impl message::MessageStatic for RestartBackoff {
+    const MESSAGE_ID: &'static str = "RestartBackoff";
+}
+impl message::MessageStatic for RestartStatus {
+    const MESSAGE_ID: &'static str = "RestartStatus";
+}
+impl message::MessageStatic for ResourceLimits {
+    const MESSAGE_ID: &'static str = "ResourceLimits";
+}
+impl message::MessageStatic for LogFile {
+    const MESSAGE_ID: &'static str = "LogFile";
+}
+impl message::MessageStatic for UpdateRollout {
+    const MESSAGE_ID: &'static str = "UpdateRollout";
+}
+impl message::MessageStatic for UpdateRollback {
+    const MESSAGE_ID: &'static str = "UpdateRollback";
+}
+impl message::MessageStatic for UpdateWindow {
+    const MESSAGE_ID: &'static str = "UpdateWindow";
+}
+impl message::MessageStatic for UpdateWindows {
+    const MESSAGE_ID: &'static str = "UpdateWindows";
+}
+impl message::MessageStatic for HealthCheckRecord {
+    const MESSAGE_ID: &'static str = "HealthCheckRecord";
+}
+impl message::MessageStatic for ServiceCfgVersion {
+    const MESSAGE_ID: &'static str = "ServiceCfgVersion";
+}
+impl message::MessageStatic for ServiceCfgFileDiff {
+    const MESSAGE_ID: &'static str = "ServiceCfgFileDiff";
+}
+impl message::MessageStatic for ServiceSpecChange {
+    const MESSAGE_ID: &'static str = "ServiceSpecChange";
+}
@@ -86,6 +86,36 @@ pub struct ServiceStatus {
     pub service_group: ServiceGroup,
     #[prost(enumeration="DesiredState", optional, tag="5")]
     pub desired_state: ::std::option::Option<i32>,
+    #[prost(message, optional, tag="6")]
+    pub restart: ::std::option::Option<RestartStatus>,
+    /// The result of the service's latest health check, if one has run.
+    #[prost(enumeration="HealthCheckStatus", optional, tag="7")]
+    pub health: ::std::option::Option<i32>,
+    /// True while the service is being restarted with an updated package.
+    #[prost(bool, optional, tag="8")]
+    pub update_in_progress: ::std::option::Option<bool>,
+    /// If an update has been found but is held until the service's next maintenance window, when
+    /// that window opens, in seconds since the Unix epoch.
+    #[prost(int64, optional, tag="9")]
+    pub update_pending_until: ::std::option::Option<i64>,
+    /// Why the latest render of the service's templates was refused, keeping the files rendered
+    /// before, if it was.
+    #[prost(string, optional, tag="10")]
+    pub render_error: ::std::option::Option<std::string::String>,
+    /// Incarnation of the latest configuration applied to the service group in the census, whether
+    /// or not the service accepted it.
+    #[prost(uint64, optional, tag="11")]
+    pub config_incarnation: ::std::option::Option<u64>,
+}
+/// Sent by a status watch when a service is no longer loaded.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ServiceStatusRemoved {
+    #[prost(message, required, tag="1")]
+    pub ident: PackageIdent,
+    #[prost(message, required, tag="2")]
+    pub service_group: ServiceGroup,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -94,6 +124,227 @@ pub struct HealthCheckInterval {
     #[prost(uint64, required, tag="1")]
     pub seconds: u64,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RestartBackoff {
+    /// Seconds to wait before the second restart within `window`. Doubled for every restart after
+    /// that, up to `max_delay`.
+    #[prost(uint64, optional, tag="1")]
+    pub initial_delay: ::std::option::Option<u64>,
+    /// Upper bound, in seconds, on the delay between restarts.
+    #[prost(uint64, optional, tag="2")]
+    pub max_delay: ::std::option::Option<u64>,
+    /// Give up restarting the service after this many restarts within `window`. Zero never gives up.
+    #[prost(uint32, optional, tag="3")]
+    pub limit: ::std::option::Option<u32>,
+    /// Length, in seconds, of the sliding window restarts are counted in.
+    #[prost(uint64, optional, tag="4")]
+    pub window: ::std::option::Option<u64>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RestartStatus {
+    #[prost(enumeration="RestartPolicy", required, tag="1")]
+    pub policy: i32,
+    /// Number of restarts within the current window.
+    #[prost(uint32, required, tag="2")]
+    pub count: u32,
+    /// Seconds until the next restart attempt, if one is scheduled.
+    #[prost(uint64, optional, tag="3")]
+    pub next_restart_in: ::std::option::Option<u64>,
+    /// Set once the Supervisor has stopped restarting the service.
+    #[prost(bool, required, tag="4")]
+    pub gave_up: bool,
+    #[prost(int32, optional, tag="5")]
+    pub last_exit_code: ::std::option::Option<i32>,
+}
+/// Limits on the resources a service may consume, applied through a cgroup on Linux. Unset
+/// fields are unlimited.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ResourceLimits {
+    /// Relative share of CPU time, from 1 to 10000.
+    #[prost(uint32, optional, tag="1")]
+    pub cpu_weight: ::std::option::Option<u32>,
+    /// Maximum CPU time, in thousandths of a CPU.
+    #[prost(uint64, optional, tag="2")]
+    pub cpu_quota: ::std::option::Option<u64>,
+    /// Maximum memory, in bytes.
+    #[prost(uint64, optional, tag="3")]
+    pub memory_max: ::std::option::Option<u64>,
+    /// Maximum number of processes and threads.
+    #[prost(uint64, optional, tag="4")]
+    pub pids_max: ::std::option::Option<u64>,
+}
+/// Settings for writing a service's output to a file under its `logs` directory. Unset fields take
+/// their default values.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LogFile {
+    /// Set to false to turn off a log file the Supervisor would otherwise write by default.
+    #[prost(bool, optional, tag="1")]
+    pub enabled: ::std::option::Option<bool>,
+    /// Rotate the file once it grows past this many bytes; zero never rotates on size.
+    #[prost(uint64, optional, tag="2")]
+    pub max_size: ::std::option::Option<u64>,
+    /// Rotate the file once it is this many seconds old; zero never rotates on age.
+    #[prost(uint64, optional, tag="3")]
+    pub max_age: ::std::option::Option<u64>,
+    /// The number of rotated files to keep.
+    #[prost(uint32, optional, tag="4")]
+    pub retain: ::std::option::Option<u32>,
+}
+/// How a rolling update is rolled out across a service group. Unset fields take their default
+/// values. At most one of `batch_size` and `batch_percent` is set.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct UpdateRollout {
+    /// Update this many followers at a time.
+    #[prost(uint32, optional, tag="1")]
+    pub batch_size: ::std::option::Option<u32>,
+    /// Update this percentage of the followers at a time, rounded up.
+    #[prost(uint32, optional, tag="2")]
+    pub batch_percent: ::std::option::Option<u32>,
+    /// Pause the rollout if an updated member is still failing its health check after this many
+    /// seconds.
+    #[prost(uint64, optional, tag="3")]
+    pub health_timeout: ::std::option::Option<u64>,
+}
+/// Whether, and after how long, a service that was updated goes back to the release it ran before
+/// if the update doesn't pass its health checks. Unset fields take their default values.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct UpdateRollback {
+    /// Set to false to stop rolling back failed updates of a service that did so before.
+    #[prost(bool, optional, tag="1")]
+    pub enabled: ::std::option::Option<bool>,
+    /// Seconds after an updated service starts that its health check must be passing.
+    #[prost(uint64, optional, tag="2")]
+    pub grace_period: ::std::option::Option<u64>,
+}
+/// A recurring period in which a service may restart into an update, e.g. "mon-fri 02:00-04:00
+/// +01:00". A window that ends at or before the time it starts runs past midnight.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct UpdateWindow {
+    /// The days the window opens on, as a bit mask with Monday as the lowest bit.
+    #[prost(uint32, required, tag="1")]
+    pub days: u32,
+    /// Minutes past midnight that the window opens, in its time zone.
+    #[prost(uint32, required, tag="2")]
+    pub start: u32,
+    /// Minutes past midnight that the window closes, in its time zone.
+    #[prost(uint32, required, tag="3")]
+    pub end: u32,
+    /// The window's time zone, as an offset from UTC in seconds. Unset is UTC.
+    #[prost(int32, optional, tag="4")]
+    pub utc_offset: ::std::option::Option<i32>,
+    /// The window's time zone by its IANA name, e.g. "Europe/Paris", whose offset from UTC is
+    /// worked out whenever the window is checked. Takes the place of utc_offset when set.
+    #[prost(string, optional, tag="5")]
+    pub time_zone: ::std::option::Option<String>,
+}
+/// The maintenance windows that updates of a service are held for. Updates of a service without
+/// any are applied as soon as they're found.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct UpdateWindows {
+    #[prost(message, repeated, tag="1")]
+    pub windows: ::std::vec::Vec<UpdateWindow>,
+}
+/// The outcome of one run of a service's health check.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HealthCheckRecord {
+    #[prost(message, required, tag="1")]
+    pub service_group: ServiceGroup,
+    /// When the check finished, in seconds since the Unix epoch.
+    #[prost(int64, required, tag="2")]
+    pub timestamp: i64,
+    #[prost(enumeration="HealthCheckStatus", required, tag="3")]
+    pub status: i32,
+    /// Exit code of the health check hook. Absent if the service has no hook, or the hook was
+    /// killed by a signal.
+    #[prost(int32, optional, tag="4")]
+    pub exit_code: ::std::option::Option<i32>,
+    /// How long the hook ran, in milliseconds. Absent if the service has no hook.
+    #[prost(uint64, optional, tag="5")]
+    pub duration_ms: ::std::option::Option<u64>,
+    /// The end of the hook's output, if it was longer than the Supervisor keeps.
+    #[prost(string, optional, tag="6")]
+    pub stdout: ::std::option::Option<std::string::String>,
+    #[prost(string, optional, tag="7")]
+    pub stderr: ::std::option::Option<std::string::String>,
+}
+/// A configuration that was applied to a service group with `hab config apply`.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ServiceCfgVersion {
+    #[prost(message, required, tag="1")]
+    pub service_group: ServiceGroup,
+    /// Incarnation the configuration was applied with.
+    #[prost(uint64, required, tag="2")]
+    pub incarnation: u64,
+    /// When the Supervisor applied it, in seconds since the Unix epoch.
+    #[prost(int64, required, tag="3")]
+    pub applied_at: i64,
+    /// The configuration, as TOML, or as it was sent if it was encrypted.
+    #[prost(string, required, tag="4")]
+    pub cfg: std::string::String,
+    /// Whether `cfg` is encrypted for the service group's key.
+    #[prost(bool, optional, tag="5")]
+    pub encrypted: ::std::option::Option<bool>,
+}
+/// A rendered configuration file or hook that a configuration change would modify.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ServiceCfgFileDiff {
+    #[prost(message, required, tag="1")]
+    pub service_group: ServiceGroup,
+    /// Where the file is rendered to.
+    #[prost(string, required, tag="2")]
+    pub path: std::string::String,
+    /// Unified diff from the file as it's currently rendered.
+    #[prost(string, required, tag="3")]
+    pub diff: std::string::String,
+}
+/// A change that `hab sup apply` makes to the services a Supervisor runs.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ServiceSpecChange {
+    #[prost(message, required, tag="1")]
+    pub ident: PackageIdent,
+    #[prost(enumeration="service_spec_change::Action", required, tag="2")]
+    pub action: i32,
+    /// Each setting of the service's spec that an update changes, as `name: old -> new`.
+    #[prost(string, repeated, tag="3")]
+    pub differences: ::std::vec::Vec<std::string::String>,
+}
+pub mod service_spec_change {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum Action {
+        /// The service is loaded with the spec the stack describes.
+        Unchanged = 0,
+        Load = 1,
+        Update = 2,
+        Unload = 3,
+    }
+}
 /// Encapsulate all possible sources we can install packages from.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -152,3 +403,27 @@ pub enum BindingMode {
     /// Service start-up is blocked until all binds are available
     Strict = 1,
 }
+/// How the Supervisor reacts when a service's process exits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    /// Restart the service whenever it exits
+    Always = 0,
+    /// Restart the service only if it exited with a non-zero exit code
+    OnFailure = 1,
+    /// Leave the service down once it exits
+    Never = 2,
+}
+/// Result of a service's health check, numbered as the health check hook's exit codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HealthCheckStatus {
+    Ok = 0,
+    Warning = 1,
+    Critical = 2,
+    Unknown = 3,
+}
@@ -42,6 +42,7 @@ extern crate tempfile;
 pub mod butterfly;
 pub mod codec;
 pub mod ctl;
+pub mod ctl_secrets;
 pub mod message;
 pub mod net;
 pub mod types;
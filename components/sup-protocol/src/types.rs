@@ -8,6 +8,7 @@
 include!("generated/sup.types.rs");
 include!("generated/sup.types.impl.rs");
 
+use chrono_tz::Tz;
 use std::{fmt,
           str::FromStr};
 
@@ -204,6 +205,42 @@ impl From<core::service::HealthCheckInterval> for HealthCheckInterval {
     fn from(h: core::service::HealthCheckInterval) -> Self { Self { seconds: h.into() } }
 }
 
+impl From<core::service::ResourceLimits> for ResourceLimits {
+    fn from(limits: core::service::ResourceLimits) -> Self {
+        Self { cpu_weight: limits.cpu_weight,
+               cpu_quota:  limits.cpu_quota,
+               memory_max: limits.memory_max,
+               pids_max:   limits.pids_max, }
+    }
+}
+
+impl Into<core::service::ResourceLimits> for ResourceLimits {
+    fn into(self) -> core::service::ResourceLimits {
+        core::service::ResourceLimits { cpu_weight: self.cpu_weight,
+                                        cpu_quota:  self.cpu_quota,
+                                        memory_max: self.memory_max,
+                                        pids_max:   self.pids_max, }
+    }
+}
+
+impl From<core::service::LogFileConfig> for LogFile {
+    fn from(config: core::service::LogFileConfig) -> Self {
+        Self { enabled:  Some(config.enabled),
+               max_size: Some(config.max_size),
+               max_age:  Some(config.max_age),
+               retain:   Some(config.retain), }
+    }
+}
+
+impl Into<core::service::LogFileOverrides> for LogFile {
+    fn into(self) -> core::service::LogFileOverrides {
+        core::service::LogFileOverrides { enabled:  self.enabled,
+                                          max_size: self.max_size,
+                                          max_age:  self.max_age,
+                                          retain:   self.retain, }
+    }
+}
+
 impl From<package::PackageIdent> for PackageIdent {
     fn from(ident: package::PackageIdent) -> Self {
         Self { origin:  ident.origin,
@@ -338,6 +375,230 @@ impl fmt::Display for UpdateStrategy {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}", self.as_str()) }
 }
 
+impl RestartPolicy {
+    fn as_str(&self) -> &str {
+        match *self {
+            RestartPolicy::Always => "always",
+            RestartPolicy::OnFailure => "on-failure",
+            RestartPolicy::Never => "never",
+        }
+    }
+}
+
+impl FromStr for RestartPolicy {
+    type Err = NetErr;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "always" => Ok(RestartPolicy::Always),
+            "on-failure" => Ok(RestartPolicy::OnFailure),
+            "never" => Ok(RestartPolicy::Never),
+            _ => Err(net::err(ErrCode::InvalidPayload, "Invalid restart policy.")),
+        }
+    }
+}
+
+impl fmt::Display for RestartPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}", self.as_str()) }
+}
+
+impl fmt::Display for HealthCheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match *self {
+            HealthCheckStatus::Ok => "OK",
+            HealthCheckStatus::Warning => "WARNING",
+            HealthCheckStatus::Critical => "CRITICAL",
+            HealthCheckStatus::Unknown => "UNKNOWN",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl UpdateRollout {
+    /// Set the batch size from either a number of followers, e.g. "2", or a percentage of them,
+    /// e.g. "25%".
+    pub fn set_batch_size(&mut self, value: &str) -> Result<(), NetErr> {
+        let invalid = || {
+            net::err(ErrCode::InvalidPayload,
+                     format!("Invalid update batch size, {}. Expected a number of members, \
+                              e.g. 2, or a percentage of them, e.g. 25%",
+                             value))
+        };
+        if value.ends_with('%') {
+            let percent = value.trim_end_matches('%')
+                               .parse::<u32>()
+                               .map_err(|_| invalid())?;
+            if percent == 0 || percent > 100 {
+                return Err(invalid());
+            }
+            self.batch_size = None;
+            self.batch_percent = Some(percent);
+        } else {
+            let size = value.parse::<u32>().map_err(|_| invalid())?;
+            if size == 0 {
+                return Err(invalid());
+            }
+            self.batch_size = Some(size);
+            self.batch_percent = None;
+        }
+        Ok(())
+    }
+}
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const EVERY_DAY: u32 = 0b111_1111;
+
+impl UpdateWindow {
+    fn parse_days(value: &str) -> Option<u32> {
+        if value == "*" {
+            return Some(EVERY_DAY);
+        }
+        let day = |name: &str| WEEKDAYS.iter().position(|d| name.eq_ignore_ascii_case(d));
+        let mut days = 0;
+        for part in value.split(',') {
+            let mut range = part.splitn(2, '-');
+            let first = day(range.next()?)?;
+            let last = match range.next() {
+                Some(last) => day(last)?,
+                None => first,
+            };
+            // A range like "sat-mon" wraps around the end of the week
+            let mut current = first;
+            loop {
+                days |= 1 << current;
+                if current == last {
+                    break;
+                }
+                current = (current + 1) % 7;
+            }
+        }
+        Some(days)
+    }
+
+    fn parse_time(value: &str) -> Option<u32> {
+        let mut parts = value.splitn(2, ':');
+        let hours = parts.next()?.parse::<u32>().ok()?;
+        let minutes = parts.next()?.parse::<u32>().ok()?;
+        let time = hours * 60 + minutes;
+        if minutes < 60 && time <= 24 * 60 {
+            Some(time)
+        } else {
+            None
+        }
+    }
+
+    fn parse_utc_offset(value: &str) -> Option<i32> {
+        if value.eq_ignore_ascii_case("utc") || value == "Z" {
+            return Some(0);
+        }
+        let sign = match value.chars().next()? {
+            '+' => 1,
+            '-' => -1,
+            _ => return None,
+        };
+        let offset = Self::parse_time(&value[1..])?;
+        if offset <= 14 * 60 {
+            Some(sign * offset as i32 * 60)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for UpdateWindow {
+    type Err = NetErr;
+
+    /// Parse a window from the days it opens on, its opening and closing times, and optionally
+    /// its time zone as an offset from UTC or an IANA name, e.g. "mon-fri 02:00-04:00 +01:00",
+    /// "sat,sun 22:00-06:00" or "mon 02:00-04:00 Europe/Paris".
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            net::err(ErrCode::InvalidPayload,
+                     format!("Invalid update window, {}. Expected days, times and an optional \
+                              UTC offset or time zone, e.g. mon-fri 02:00-04:00 +01:00 or \
+                              mon-fri 02:00-04:00 Europe/Paris",
+                             value))
+        };
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        if fields.len() < 2 || fields.len() > 3 {
+            return Err(invalid());
+        }
+        let days = Self::parse_days(fields[0]).ok_or_else(invalid)?;
+        let mut times = fields[1].splitn(2, '-');
+        let start = times.next()
+                         .and_then(Self::parse_time)
+                         .ok_or_else(invalid)?;
+        let end = times.next()
+                       .and_then(Self::parse_time)
+                       .ok_or_else(invalid)?;
+        if start == end || start == 24 * 60 {
+            return Err(invalid());
+        }
+        let (utc_offset, time_zone) = match fields.get(2) {
+            Some(zone) => {
+                match Self::parse_utc_offset(zone) {
+                    Some(offset) => (Some(offset), None),
+                    None => {
+                        let tz = zone.parse::<Tz>().map_err(|_| invalid())?;
+                        (None, Some(tz.name().to_string()))
+                    }
+                }
+            }
+            None => (None, None),
+        };
+        Ok(UpdateWindow { days,
+                          start,
+                          end,
+                          utc_offset,
+                          time_zone })
+    }
+}
+
+impl fmt::Display for UpdateWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.days & EVERY_DAY == EVERY_DAY {
+            write!(f, "*")?;
+        } else {
+            let mut runs = Vec::new();
+            let mut day = 0;
+            while day < 7 {
+                if self.days & (1 << day) == 0 {
+                    day += 1;
+                    continue;
+                }
+                let first = day;
+                while day < 6 && self.days & (1 << (day + 1)) != 0 {
+                    day += 1;
+                }
+                if first == day {
+                    runs.push(WEEKDAYS[first].to_string());
+                } else {
+                    runs.push(format!("{}-{}", WEEKDAYS[first], WEEKDAYS[day]));
+                }
+                day += 1;
+            }
+            write!(f, "{}", runs.join(","))?;
+        }
+        write!(f,
+               " {:02}:{:02}-{:02}:{:02}",
+               self.start / 60,
+               self.start % 60,
+               self.end / 60,
+               self.end % 60)?;
+        if let Some(ref time_zone) = self.time_zone {
+            return write!(f, " {}", time_zone);
+        }
+        match self.utc_offset.unwrap_or(0) {
+            0 => write!(f, " UTC"),
+            offset => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let minutes = offset.abs() / 60;
+                write!(f, " {}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use toml;
@@ -453,4 +714,93 @@ mod test {
 
         assert!(toml.starts_with(r#"key = "at-once""#));
     }
+
+    #[test]
+    fn restart_policy_default() {
+        // Services have always been restarted unconditionally; keep that the default
+        assert_eq!(RestartPolicy::default(), RestartPolicy::Always);
+    }
+
+    #[test]
+    fn restart_policy_from_str() {
+        assert_eq!(RestartPolicy::from_str("always").unwrap(),
+                   RestartPolicy::Always);
+        assert_eq!(RestartPolicy::from_str("on-failure").unwrap(),
+                   RestartPolicy::OnFailure);
+        assert_eq!(RestartPolicy::from_str("never").unwrap(), RestartPolicy::Never);
+    }
+
+    #[test]
+    fn restart_policy_from_str_invalid() {
+        assert!(RestartPolicy::from_str("sometimes").is_err());
+    }
+
+    #[test]
+    fn restart_policy_to_string() {
+        assert_eq!("on-failure", RestartPolicy::OnFailure.to_string());
+    }
+
+    #[test]
+    fn restart_policy_toml_deserialize() {
+        #[derive(Deserialize)]
+        struct Data {
+            key: RestartPolicy,
+        }
+        let toml = r#"
+            key = "on-failure"
+            "#;
+        let data: Data = toml::from_str(toml).unwrap();
+
+        assert_eq!(data.key, RestartPolicy::OnFailure);
+    }
+
+    #[test]
+    fn update_rollout_batch_size_from_str() {
+        let mut rollout = UpdateRollout::default();
+        rollout.set_batch_size("3").unwrap();
+        assert_eq!(rollout.batch_size, Some(3));
+        assert_eq!(rollout.batch_percent, None);
+
+        rollout.set_batch_size("25%").unwrap();
+        assert_eq!(rollout.batch_size, None);
+        assert_eq!(rollout.batch_percent, Some(25));
+
+        assert!(rollout.set_batch_size("0").is_err());
+        assert!(rollout.set_batch_size("0%").is_err());
+        assert!(rollout.set_batch_size("101%").is_err());
+        assert!(rollout.set_batch_size("some").is_err());
+    }
+
+    #[test]
+    fn update_window_from_str() {
+        let window = UpdateWindow::from_str("mon-fri 02:00-04:30 +01:00").unwrap();
+        assert_eq!(window.days, 0b001_1111);
+        assert_eq!(window.start, 120);
+        assert_eq!(window.end, 270);
+        assert_eq!(window.utc_offset, Some(3600));
+        assert_eq!(window.to_string(), "mon-fri 02:00-04:30 +01:00");
+
+        let window = UpdateWindow::from_str("Sat,Sun,wed 22:00-06:00").unwrap();
+        assert_eq!(window.days, 0b110_0100);
+        assert_eq!(window.utc_offset, None);
+        assert_eq!(window.to_string(), "wed,sat-sun 22:00-06:00 UTC");
+
+        let window = UpdateWindow::from_str("sat-mon 00:00-24:00 -05:30").unwrap();
+        assert_eq!(window.days, 0b110_0001);
+        assert_eq!(window.utc_offset, Some(-19_800));
+        assert_eq!(UpdateWindow::from_str("* 01:00-02:00").unwrap().to_string(),
+                   "* 01:00-02:00 UTC");
+
+        assert!(UpdateWindow::from_str("weekdays 02:00-04:00").is_err());
+        assert!(UpdateWindow::from_str("mon 02:00").is_err());
+        assert!(UpdateWindow::from_str("mon 02:00-02:00").is_err());
+        assert!(UpdateWindow::from_str("mon 02:60-04:00").is_err());
+        let window = UpdateWindow::from_str("mon 02:00-04:00 Europe/Paris").unwrap();
+        assert_eq!(window.utc_offset, None);
+        assert_eq!(window.time_zone, Some("Europe/Paris".to_string()));
+        assert_eq!(window.to_string(), "mon 02:00-04:00 Europe/Paris");
+
+        assert!(UpdateWindow::from_str("mon 02:00-04:00 Europe/Atlantis").is_err());
+        assert!(UpdateWindow::from_str("mon 02:00-04:00 +15:00").is_err());
+    }
 }
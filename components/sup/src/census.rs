@@ -21,8 +21,10 @@ use serde::{ser::SerializeStruct,
 use std::{borrow::Cow,
           collections::{BTreeMap,
                         HashMap,
-                        HashSet},
+                        HashSet,
+                        VecDeque},
           fmt,
+          mem,
           path::Path,
           result,
           str::FromStr};
@@ -32,9 +34,15 @@ static LOGKEY: &'static str = "CE";
 
 pub type MemberId = String;
 
+/// How many census events the HTTP gateway keeps for clients that
+/// reconnect to `/census/events`.
+const CENSUS_EVENT_LOG_SIZE: usize = 1000;
+
 #[derive(Debug, Serialize)]
 pub struct CensusRing {
     changed: bool,
+    #[serde(skip)]
+    events: Vec<CensusEvent>,
     census_groups: HashMap<ServiceGroup, CensusGroup>,
     local_member_id: MemberId,
     last_service_counter: usize,
@@ -54,6 +62,7 @@ impl CensusRing {
         where I: Into<MemberId>
     {
         CensusRing { changed: false,
+                     events: Vec::new(),
                      census_groups: HashMap::new(),
                      local_member_id: local_member_id.into(),
                      last_service_counter: 0,
@@ -84,6 +93,7 @@ impl CensusRing {
         {
             self.changed = true;
 
+            let before = self.snapshot();
             self.populate_census(service_rumors, member_list);
             self.update_from_election_store(election_rumors);
             self.update_from_election_update_store(election_update_rumors);
@@ -97,17 +107,101 @@ impl CensusRing {
             self.last_election_update_counter = election_update_rumors.get_update_counter();
             self.last_service_config_counter = service_config_rumors.get_update_counter();
             self.last_service_file_counter = service_file_rumors.get_update_counter();
+
+            let events = self.events_since(&before);
+            self.events.extend(events);
         } else {
             self.changed = false;
         }
     }
 
+    /// Returns the events produced by `update_from_rumors` since the
+    /// last call.
+    pub fn take_events(&mut self) -> Vec<CensusEvent> { mem::replace(&mut self.events, Vec::new()) }
+
     pub fn census_group_for(&self, sg: &ServiceGroup) -> Option<&CensusGroup> {
         self.census_groups.get(sg)
     }
 
     pub fn groups(&self) -> Vec<&CensusGroup> { self.census_groups.values().map(|cg| cg).collect() }
 
+    /// Record the progress of the rolling update the given service group is in, if any. Returns
+    /// true if it changed.
+    pub fn set_rollout(&mut self, sg: &ServiceGroup, rollout: Option<RolloutStatus>) -> bool {
+        match self.census_groups.get_mut(sg) {
+            Some(group) if group.rollout != rollout => {
+                group.rollout = rollout;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn snapshot(&self) -> HashMap<ServiceGroup, GroupSnapshot> {
+        self.census_groups
+            .iter()
+            .map(|(sg, group)| (sg.clone(), GroupSnapshot::new(group)))
+            .collect()
+    }
+
+    /// Compare the census against a snapshot taken before it was
+    /// rebuilt. Groups and members never leave the census, so
+    /// everything in the snapshot is still here.
+    fn events_since(&self, before: &HashMap<ServiceGroup, GroupSnapshot>) -> Vec<CensusEvent> {
+        let mut events = Vec::new();
+        for (sg, group) in self.census_groups.iter() {
+            let previous = before.get(sg);
+            for member in group.population.values() {
+                let was = previous.and_then(|p| p.members.get(&member.member_id))
+                                  .and_then(|health| *health);
+                let now = member.health();
+                if was == now {
+                    continue;
+                }
+                let service_group = sg.clone();
+                let member_id = member.member_id.clone();
+                events.push(match (was, now) {
+                                (None, Some(Health::Alive)) => {
+                                    CensusEvent::MemberJoined { service_group,
+                                                                member_id }
+                                }
+                                (_, Some(Health::Alive)) => {
+                                    CensusEvent::MemberAlive { service_group,
+                                                               member_id }
+                                }
+                                (_, Some(Health::Suspect)) => {
+                                    CensusEvent::MemberSuspect { service_group,
+                                                                 member_id }
+                                }
+                                (_, Some(Health::Confirmed)) => {
+                                    CensusEvent::MemberConfirmed { service_group,
+                                                                   member_id }
+                                }
+                                (_, Some(Health::Departed)) => {
+                                    CensusEvent::MemberLeft { service_group,
+                                                              member_id }
+                                }
+                                (_, None) => continue,
+                            });
+            }
+            let (leader_id, incarnation) = match previous {
+                Some(p) => (p.leader_id.as_ref(), p.config_incarnation),
+                None => (None, None),
+            };
+            if group.leader_id.as_ref() != leader_id {
+                events.push(CensusEvent::LeaderChanged { service_group: sg.clone(),
+                                                         leader_id:     group.leader_id.clone(), });
+            }
+            if let Some(ref config) = group.service_config {
+                if Some(config.incarnation) != incarnation {
+                    events.push(CensusEvent::ConfigChanged { service_group: sg.clone(),
+                                                             incarnation:   config.incarnation, });
+                }
+            }
+        }
+        events
+    }
+
     /// Populates the census from `ServiceRumor`s and Butterfly-level
     /// membership lists.
     ///
@@ -235,6 +329,114 @@ impl<'a> Serialize for CensusRingProxy<'a> {
     }
 }
 
+/// A change to the census, as served from the HTTP gateway's
+/// `/census/events` endpoint.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CensusEvent {
+    MemberJoined {
+        service_group: ServiceGroup,
+        member_id:     MemberId,
+    },
+    /// A suspect or confirmed member is alive again.
+    MemberAlive {
+        service_group: ServiceGroup,
+        member_id:     MemberId,
+    },
+    MemberSuspect {
+        service_group: ServiceGroup,
+        member_id:     MemberId,
+    },
+    MemberConfirmed {
+        service_group: ServiceGroup,
+        member_id:     MemberId,
+    },
+    MemberLeft {
+        service_group: ServiceGroup,
+        member_id:     MemberId,
+    },
+    LeaderChanged {
+        service_group: ServiceGroup,
+        leader_id:     Option<MemberId>,
+    },
+    ConfigChanged {
+        service_group: ServiceGroup,
+        incarnation:   u64,
+    },
+}
+
+impl CensusEvent {
+    pub fn service_group(&self) -> &ServiceGroup {
+        match *self {
+            CensusEvent::MemberJoined { ref service_group, .. }
+            | CensusEvent::MemberAlive { ref service_group, .. }
+            | CensusEvent::MemberSuspect { ref service_group, .. }
+            | CensusEvent::MemberConfirmed { ref service_group, .. }
+            | CensusEvent::MemberLeft { ref service_group, .. }
+            | CensusEvent::LeaderChanged { ref service_group, .. }
+            | CensusEvent::ConfigChanged { ref service_group, .. } => service_group,
+        }
+    }
+
+    /// The name of the event, as used for the SSE `event` field.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            CensusEvent::MemberJoined { .. } => "member_joined",
+            CensusEvent::MemberAlive { .. } => "member_alive",
+            CensusEvent::MemberSuspect { .. } => "member_suspect",
+            CensusEvent::MemberConfirmed { .. } => "member_confirmed",
+            CensusEvent::MemberLeft { .. } => "member_left",
+            CensusEvent::LeaderChanged { .. } => "leader_changed",
+            CensusEvent::ConfigChanged { .. } => "config_changed",
+        }
+    }
+}
+
+/// The most recent census events, numbered so that a client can pick
+/// up where it left off.
+#[derive(Debug, Default)]
+pub struct CensusEventLog {
+    last_id: u64,
+    events:  VecDeque<(u64, CensusEvent)>,
+}
+
+impl CensusEventLog {
+    pub fn push(&mut self, event: CensusEvent) {
+        self.last_id += 1;
+        if self.events.len() == CENSUS_EVENT_LOG_SIZE {
+            self.events.pop_front();
+        }
+        self.events.push_back((self.last_id, event));
+    }
+
+    /// The id of the most recent event, or 0 if there hasn't been one.
+    pub fn last_id(&self) -> u64 { self.last_id }
+
+    /// The events that came after the given id, oldest first. Events
+    /// which have fallen out of the log are skipped.
+    pub fn since(&self, id: u64) -> impl Iterator<Item = &(u64, CensusEvent)> {
+        self.events.iter().filter(move |(event_id, _)| *event_id > id)
+    }
+}
+
+/// The parts of a census group that events are produced from.
+struct GroupSnapshot {
+    members:            HashMap<MemberId, Option<Health>>,
+    leader_id:          Option<MemberId>,
+    config_incarnation: Option<u64>,
+}
+
+impl GroupSnapshot {
+    fn new(group: &CensusGroup) -> Self {
+        GroupSnapshot { members:            group.population
+                                                 .values()
+                                                 .map(|m| (m.member_id.clone(), m.health()))
+                                                 .collect(),
+                        leader_id:          group.leader_id.clone(),
+                        config_incarnation: group.service_config.as_ref().map(|c| c.incarnation), }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum ElectionStatus {
     None,
@@ -294,6 +496,29 @@ pub struct ServiceFile {
 pub struct ServiceConfig {
     pub incarnation: u64,
     pub value:       toml::value::Table,
+    /// Whether the configuration was encrypted when it was gossiped.
+    #[serde(skip)]
+    pub encrypted:   bool,
+    /// The configuration as it was gossiped, so that an encrypted one can be kept encrypted.
+    #[serde(skip)]
+    pub payload:     Vec<u8>,
+}
+
+/// The progress of a rolling update through a service group, as seen by the local Supervisor.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RolloutStatus {
+    /// The package the update leader is rolling out.
+    pub package:    String,
+    /// How many followers update at once.
+    pub batch_size: usize,
+    /// How many alive members run the package, including the update leader.
+    pub updated:    usize,
+    /// How many of the updated members pass their health check.
+    pub healthy:    usize,
+    /// How many members are alive.
+    pub total:      usize,
+    /// The updated member whose failing health check has paused the rollout, if any.
+    pub paused_by:  Option<MemberId>,
 }
 
 #[derive(Debug)]
@@ -303,6 +528,8 @@ pub struct CensusGroup {
     pub update_election_status: ElectionStatus,
     pub leader_id:              Option<MemberId>,
     pub service_config:         Option<ServiceConfig>,
+    /// Set while a rolling update is in progress.
+    pub rollout:                Option<RolloutStatus>,
 
     local_member_id:       MemberId,
     population:            BTreeMap<MemberId, CensusMember>,
@@ -321,6 +548,7 @@ impl CensusGroup {
                       leader_id:              None,
                       update_leader_id:       None,
                       service_config:         None,
+                      rollout:                None,
                       service_files:          HashMap::new(),
                       changed_service_files:  Vec::new(), }
     }
@@ -454,7 +682,12 @@ impl CensusGroup {
                 {
                     self.service_config = Some(ServiceConfig { incarnation:
                                                                    service_config.incarnation,
-                                                               value:       config, });
+                                                               value:       config,
+                                                               encrypted:
+                                                                   service_config.encrypted,
+                                                               payload:
+                                                                   service_config.config
+                                                                                 .clone(), });
                 }
             }
             Err(err) => warn!("{}", err),
@@ -518,7 +751,7 @@ impl Serialize for CensusGroup {
     fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
         where S: Serializer
     {
-        let mut strukt = serializer.serialize_struct("census_group", 10)?;
+        let mut strukt = serializer.serialize_struct("census_group", 11)?;
         strukt.serialize_field("service_group", &self.service_group)?;
         strukt.serialize_field("election_status", &self.election_status)?;
         strukt.serialize_field("update_election_status", &self.update_election_status)?;
@@ -536,6 +769,7 @@ impl Serialize for CensusGroup {
         strukt.serialize_field("update_leader_id", &self.update_leader_id)?;
         strukt.serialize_field("changed_service_files", &self.changed_service_files)?;
         strukt.serialize_field("service_files", &self.service_files)?;
+        strukt.serialize_field("rollout", &self.rollout)?;
         strukt.end()
     }
 }
@@ -566,6 +800,8 @@ pub struct CensusMember {
     pub update_election_is_no_quorum: bool,
     pub update_election_is_finished: bool,
     pub sys: SysInfo,
+    /// The result of the member's latest health check, if it reports one.
+    pub health: Option<String>,
 
     alive:     bool,
     suspect:   bool,
@@ -595,6 +831,7 @@ impl CensusMember {
             Err(err) => warn!("Received a bad package ident from gossip data, err={}", err),
         };
         self.sys = rumor.sys.clone();
+        self.health = rumor.health.clone();
         self.cfg = toml::from_slice(&rumor.cfg).unwrap_or_default();
     }
 
@@ -657,6 +894,21 @@ impl CensusMember {
     pub fn confirmed(&self) -> bool { self.confirmed }
 
     pub fn departed(&self) -> bool { self.departed }
+
+    /// The member's health, if we've heard it from the member list yet.
+    fn health(&self) -> Option<Health> {
+        if self.alive {
+            Some(Health::Alive)
+        } else if self.suspect {
+            Some(Health::Suspect)
+        } else if self.confirmed {
+            Some(Health::Confirmed)
+        } else if self.departed {
+            Some(Health::Departed)
+        } else {
+            None
+        }
+    }
 }
 
 /// This data structure just wraps the CensusMember and allows us to tweak the serialization logic.
@@ -670,7 +922,7 @@ impl<'a> Serialize for CensusMemberProxy<'a> {
     fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
         where S: Serializer
     {
-        let mut strukt = serializer.serialize_struct("census_member", 24)?;
+        let mut strukt = serializer.serialize_struct("census_member", 25)?;
         strukt.serialize_field("member_id", &self.0.member_id)?;
         strukt.serialize_field("pkg", &self.0.pkg)?;
 
@@ -700,6 +952,7 @@ impl<'a> Serialize for CensusMemberProxy<'a> {
         strukt.serialize_field("update_election_is_finished",
                                &self.0.update_election_is_finished)?;
         strukt.serialize_field("sys", &self.0.sys)?;
+        strukt.serialize_field("health", &self.0.health)?;
         strukt.serialize_field("alive", &self.0.alive)?;
         strukt.serialize_field("suspect", &self.0.suspect)?;
         strukt.serialize_field("confirmed", &self.0.confirmed)?;
@@ -757,6 +1010,77 @@ mod tests {
         assert_eq!(members.next().unwrap().member_id, "member-b");
     }
 
+    #[test]
+    fn update_from_rumors_records_events() {
+        let (mut ring, sg_one, sg_two) = test_census_ring();
+        let events = ring.take_events();
+        assert_eq!(events.len(), 2);
+        assert!(events.contains(&CensusEvent::MemberJoined { service_group: sg_two,
+                                                             member_id:     "member-b".into(), }));
+        assert!(events.contains(&CensusEvent::LeaderChanged { service_group: sg_one,
+                                                              leader_id:
+                                                                  Some("member-a".into()), }));
+        assert!(ring.take_events().is_empty());
+    }
+
+    #[test]
+    fn events_since_reports_health_changes() {
+        let sg: ServiceGroup = "test-service.default".parse().unwrap();
+        let mut ring = CensusRing::new("me");
+        let mut census_group = CensusGroup::new(sg.clone(), "me");
+        for member in vec![test_census_member("me", Health::Alive),
+                           test_census_member("going", Health::Alive),
+                           test_census_member("wobbly", Health::Suspect),]
+        {
+            census_group.population
+                        .insert(member.member_id.clone(), member);
+        }
+        ring.census_groups.insert(sg.clone(), census_group);
+        let before = ring.snapshot();
+
+        let census_group = ring.census_groups.get_mut(&sg).unwrap();
+        census_group.population
+                    .get_mut("going")
+                    .unwrap()
+                    .update_from_health(Health::Departed);
+        census_group.population
+                    .get_mut("wobbly")
+                    .unwrap()
+                    .update_from_health(Health::Alive);
+        let events = ring.events_since(&before);
+
+        assert_eq!(events,
+                   vec![CensusEvent::MemberLeft { service_group: sg.clone(),
+                                                  member_id:     "going".into(), },
+                        CensusEvent::MemberAlive { service_group: sg,
+                                                   member_id:     "wobbly".into(), },]);
+    }
+
+    #[test]
+    fn census_event_log_drops_the_oldest_events() {
+        let sg: ServiceGroup = "test-service.default".parse().unwrap();
+        let mut log = CensusEventLog::default();
+        for incarnation in 0..(CENSUS_EVENT_LOG_SIZE as u64 + 5) {
+            log.push(CensusEvent::ConfigChanged { service_group: sg.clone(),
+                                                  incarnation });
+        }
+        assert_eq!(log.last_id(), CENSUS_EVENT_LOG_SIZE as u64 + 5);
+        assert_eq!(log.since(0).count(), CENSUS_EVENT_LOG_SIZE);
+        assert_eq!(log.since(0).next().unwrap().0, 6);
+        let newest: Vec<u64> = log.since(log.last_id() - 2).map(|(id, _)| *id).collect();
+        assert_eq!(newest, vec![log.last_id() - 1, log.last_id()]);
+    }
+
+    #[test]
+    fn census_events_serialize_with_their_type() {
+        let event = CensusEvent::MemberSuspect { service_group: "redis.default".parse().unwrap(),
+                                                 member_id:     "abc".into(), };
+        let json: serde_json::Value = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], event.name());
+        assert_eq!(json["service_group"], "redis.default");
+        assert_eq!(json["member_id"], "abc");
+    }
+
     #[test]
     fn census_ring_proxy_conforms_to_the_schema() {
         let (ring, ..) = test_census_ring();
@@ -856,6 +1180,7 @@ mod tests {
                        update_election_is_no_quorum: false,
                        update_election_is_finished: false,
                        sys: SysInfo::default(),
+                       health: None,
                        alive: health == Health::Alive,
                        suspect: health == Health::Suspect,
                        confirmed: health == Health::Confirmed,
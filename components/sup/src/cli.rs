@@ -65,6 +65,105 @@ mod test {
                         "BIND" => ["service.group1", "service.group2"],
                         "PKG_IDENT_OR_ARTIFACT" => "core/redis");
 
+        assert_cli_cmd!(should_handle_restart_flags,
+                        no_feature_flags(),
+                        "hab-sup run --restart-policy on-failure --restart-limit 5 \
+                         --restart-window 300 core/redis",
+                        "RESTART_POLICY" => "on-failure",
+                        "RESTART_LIMIT" => "5",
+                        "RESTART_WINDOW" => "300",
+                        "PKG_IDENT_OR_ARTIFACT" => "core/redis");
+
+        #[test]
+        fn invalid_restart_policy_is_rejected() {
+            let cmd_vec =
+                Vec::from_iter("hab-sup run --restart-policy sometimes core/redis".split_whitespace());
+            assert!(cli(no_feature_flags()).get_matches_from_safe(cmd_vec)
+                                           .is_err());
+        }
+
+        assert_cli_cmd!(should_handle_resource_limit_flags,
+                        no_feature_flags(),
+                        "hab-sup run --cpu-weight 200 --cpu-quota 1.5 --memory-max 512M \
+                         --pids-max 100 core/redis",
+                        "CPU_WEIGHT" => "200",
+                        "CPU_QUOTA" => "1.5",
+                        "MEMORY_MAX" => "512M",
+                        "PIDS_MAX" => "100",
+                        "PKG_IDENT_OR_ARTIFACT" => "core/redis");
+
+        #[test]
+        fn invalid_resource_limits_are_rejected() {
+            for args in &["--cpu-weight 0", "--cpu-weight 10001", "--cpu-quota none",
+                          "--memory-max 12X", "--pids-max -1"]
+            {
+                let cmd = format!("hab-sup run {} core/redis", args);
+                let cmd_vec = Vec::from_iter(cmd.split_whitespace());
+                assert!(cli(no_feature_flags()).get_matches_from_safe(cmd_vec)
+                                               .is_err(),
+                        "{} should be rejected",
+                        args);
+            }
+        }
+
+        assert_cli_cmd!(should_handle_log_file_flags,
+                        no_feature_flags(),
+                        "hab-sup run --service-log-files --service-log-retain 10 --log-file \
+                         --log-max-size 1M core/redis",
+                        "SERVICE_LOG_FILES" => true,
+                        "SERVICE_LOG_RETAIN" => "10",
+                        "LOG_FILE" => true,
+                        "LOG_MAX_SIZE" => "1M",
+                        "PKG_IDENT_OR_ARTIFACT" => "core/redis");
+
+        #[test]
+        fn log_file_and_no_log_file_are_mutually_exclusive() {
+            let cmd_vec =
+                Vec::from_iter("hab-sup run --log-file --no-log-file core/redis".split_whitespace());
+            assert!(cli(no_feature_flags()).get_matches_from_safe(cmd_vec)
+                                           .is_err());
+        }
+
+        // The TLS flags only accept files which exist, so borrow some
+        // of ours.
+        assert_cli_cmd!(should_handle_http_tls_flags,
+                        no_feature_flags(),
+                        "hab-sup run --http-tls-key Cargo.toml --http-tls-cert build.rs \
+                         --http-tls-client-ca Cargo.toml",
+                        "KEY_FILE" => "Cargo.toml",
+                        "CERT_FILE" => "build.rs",
+                        "CA_CERT_FILE" => "Cargo.toml");
+
+        assert_cli_cmd!(should_accept_old_names_for_http_tls_flags,
+                        no_feature_flags(),
+                        "hab-sup run --key Cargo.toml --certs build.rs --ca-certs Cargo.toml",
+                        "KEY_FILE" => "Cargo.toml",
+                        "CERT_FILE" => "build.rs",
+                        "CA_CERT_FILE" => "Cargo.toml");
+
+        assert_cli_cmd!(should_handle_ctl_tls_flags,
+                        no_feature_flags(),
+                        "hab-sup run --ctl-tls-key Cargo.toml --ctl-tls-cert build.rs \
+                         --ctl-tls-client-ca Cargo.toml",
+                        "CTL_KEY_FILE" => "Cargo.toml",
+                        "CTL_CERT_FILE" => "build.rs",
+                        "CTL_CA_CERT_FILE" => "Cargo.toml");
+
+        #[test]
+        fn ctl_tls_client_ca_requires_key_and_cert() {
+            let cmd_vec =
+                Vec::from_iter("hab-sup run --ctl-tls-client-ca Cargo.toml".split_whitespace());
+            assert!(cli(no_feature_flags()).get_matches_from_safe(cmd_vec)
+                                           .is_err());
+        }
+
+        #[test]
+        fn http_tls_key_requires_cert() {
+            let cmd_vec = Vec::from_iter("hab-sup run --http-tls-key Cargo.toml".split_whitespace());
+            assert!(cli(no_feature_flags()).get_matches_from_safe(cmd_vec)
+                                           .is_err());
+        }
+
         #[test]
         fn local_gossip_mode_and_listen_gossip_are_mutually_exclusive() {
             let cmd_vec = Vec::from_iter("hab-sup run --listen-gossip 1.1.1.1:1111 \
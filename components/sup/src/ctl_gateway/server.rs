@@ -22,8 +22,12 @@ use habitat_core::crypto;
 use habitat_sup_protocol::{self as protocol,
                            codec::{SrvCodec,
                                    SrvMessage,
+                                   SrvIo,
                                    SrvStream,
                                    SrvTxn},
+                           ctl_secrets::{self,
+                                         CtlSecrets,
+                                         Scope},
                            net::{self,
                                  ErrCode,
                                  NetErr,
@@ -32,18 +36,25 @@ use prometheus::{HistogramTimer,
                  HistogramVec,
                  IntCounterVec};
 use prost;
+use rustls::ServerConfig;
 use std::{cell::RefCell,
+          collections::VecDeque,
           error,
           fmt,
           io,
           net::SocketAddr,
+          path::{Path,
+                 PathBuf},
           rc::Rc,
+          sync::Arc,
           thread,
           time::Duration};
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener,
+                 TcpStream};
 use tokio_codec::Decoder;
 use tokio_core::{reactor,
                  try_nb};
+use tokio_rustls::TlsAcceptor;
 
 lazy_static! {
     static ref RPC_CALLS: IntCounterVec = register_int_counter_vec!("hab_sup_rpc_call_total",
@@ -154,13 +165,17 @@ impl Client {
     pub fn serve(self, socket: SrvStream) -> impl Future<Item = (), Error = HandlerError> {
         let mgr_sender = self.state.borrow().mgr_sender.clone();
         self.handshake(socket)
-            .and_then(|socket| SrvHandler::new(socket, mgr_sender))
+            .and_then(|(socket, scopes)| SrvHandler::new(socket, mgr_sender, scopes))
     }
 
     /// Initiate a handshake with the connected client before allowing future requests. A failed
-    /// handshake will close the connection.
-    fn handshake(&self, socket: SrvStream) -> impl Future<Item = SrvStream, Error = HandlerError> {
+    /// handshake will close the connection; a successful one yields the scopes of the secret the
+    /// client authenticated with.
+    fn handshake(&self,
+                 socket: SrvStream)
+                 -> impl Future<Item = (SrvStream, Vec<Scope>), Error = HandlerError> {
         let secret_key = self.state.borrow().secret_key.to_string();
+        let sup_root = self.state.borrow().sup_root.clone();
         let handshake = socket.into_future()
                               .map_err(|(err, _)| HandlerError::from(err))
                               .and_then(move |(m, io)| {
@@ -185,7 +200,8 @@ impl Client {
                                 Ok(decoded) => {
                                     trace!("Received handshake, {:?}", decoded);
                                     let decoded_key = decoded.secret_key.unwrap_or_default();
-                                    Ok((m, crypto::secure_eq(decoded_key, secret_key), io))
+                                    let scopes = authenticate(&decoded_key, &secret_key, &sup_root);
+                                    Ok((m, scopes, io))
                                 }
                                 Err(err) => {
                                     warn!("Handshake error, {:?}", err);
@@ -198,8 +214,8 @@ impl Client {
                     },
                 )
                               })
-                              .and_then(|(msg, success, socket)| {
-                                  let mut reply = if success {
+                              .and_then(|(msg, scopes, socket)| {
+                                  let mut reply = if scopes.is_some() {
                                       SrvMessage::from(net::ok())
                                   } else {
                                       SrvMessage::from(net::err(ErrCode::Unauthorized,
@@ -208,12 +224,12 @@ impl Client {
                                   reply.reply_for(msg.transaction().unwrap(), true);
                                   socket.send(reply)
                                         .map_err(HandlerError::from)
-                                        .and_then(move |io| Ok((io, success)))
+                                        .and_then(move |io| Ok((io, scopes)))
                               });
         handshake.select2(self.timeout(REQ_TIMEOUT)).then(|res| {
                                                         match res {
-                Ok(Either::A(((io, true), _to))) => future::ok(io),
-                Ok(Either::A(((_, false), _to))) => future::err(HandlerError::from(
+                Ok(Either::A(((io, Some(scopes)), _to))) => future::ok((io, scopes)),
+                Ok(Either::A(((_, None), _to))) => future::err(HandlerError::from(
                     io::Error::new(io::ErrorKind::ConnectionAborted, "handshake failed"),
                 )),
                 Ok(Either::B((_to, _hs))) => future::err(HandlerError::from(io::Error::new(
@@ -233,6 +249,27 @@ impl Client {
     }
 }
 
+/// The scopes of the secret matching `key`, if any. The Supervisor's own secret allows
+/// everything; otherwise the key is looked up in the named secrets, which are read afresh so
+/// that revoking one takes effect straight away.
+fn authenticate(key: &str, secret_key: &str, sup_root: &Path) -> Option<Vec<Scope>> {
+    if crypto::secure_eq(key, secret_key) {
+        return Some(vec![Scope::Admin]);
+    }
+    match CtlSecrets::load(sup_root) {
+        Ok(secrets) => {
+            secrets.authenticate(key).map(|secret| {
+                                         debug!("Authenticated with ctl secret {}", secret.name);
+                                         secret.scopes.clone()
+                                     })
+        }
+        Err(err) => {
+            warn!("Unable to read ctl secrets, {}", err);
+            None
+        }
+    }
+}
+
 /// A `Future` that will resolve into a stream of one or more `SrvMessage` replies.
 #[must_use = "futures do nothing unless polled"]
 struct SrvHandler {
@@ -242,10 +279,15 @@ struct SrvHandler {
     ctl_receiver: CtlReceiver,
     ctl_sender:   CtlSender,
     timer:        Option<HistogramTimer>,
+    /// What the client's secret allows it to do.
+    scopes:       Vec<Scope>,
+    /// Messages the client sent while we were replying to an earlier one, handled in turn once
+    /// the reply is sent.
+    queued:       VecDeque<SrvMessage>,
 }
 
 impl SrvHandler {
-    fn new(io: SrvStream, mgr_sender: MgrSender) -> Self {
+    fn new(io: SrvStream, mgr_sender: MgrSender, scopes: Vec<Scope>) -> Self {
         let (ctl_sender, ctl_receiver) = mpsc::unbounded();
 
         SrvHandler { io,
@@ -253,7 +295,26 @@ impl SrvHandler {
                      mgr_sender,
                      ctl_receiver,
                      ctl_sender,
-                     timer: None }
+                     timer: None,
+                     scopes,
+                     queued: VecDeque::new() }
+    }
+
+    /// Reply to the client that its secret doesn't allow the given request. Returns false if
+    /// there's no way to reply, because the request isn't transactional.
+    fn reply_unauthorized(&self, msg: &SrvMessage) -> bool {
+        let txn = match msg.transaction() {
+            Some(txn) => txn,
+            None => return false,
+        };
+        let required = Scope::required_for(msg.message_id());
+        let mut reply = SrvMessage::from(net::err(ErrCode::Unauthorized,
+                                                  format!("{} requires a ctl secret with the \
+                                                           {} scope",
+                                                          msg.message_id(),
+                                                          required)));
+        reply.reply_for(txn, true);
+        self.ctl_sender.unbounded_send(reply).is_ok()
     }
 
     fn command_from_message(msg: &SrvMessage,
@@ -287,6 +348,26 @@ impl SrvHandler {
                                        commands::service_cfg_set(state, req, m.clone())
                                    }))
             }
+            "SvcCfgHistory" => {
+                let m = msg.parse::<protocol::ctl::SvcCfgHistory>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, _action_sender| {
+                                       commands::service_cfg_history(state, req, m.clone())
+                                   }))
+            }
+            "SvcDryRunCfg" => {
+                let m = msg.parse::<protocol::ctl::SvcDryRunCfg>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |_state, req, action_sender| {
+                                       commands::service_cfg_dry_run(req,
+                                                                     m.clone(),
+                                                                     &action_sender)
+                                   }))
+            }
             "SvcValidateCfg" => {
                 let m = msg.parse::<protocol::ctl::SvcValidateCfg>()
                            .map_err(HandlerError::from)?;
@@ -344,6 +425,24 @@ impl SrvHandler {
                                        commands::service_status(state, req, m.clone())
                                    }))
             }
+            "SvcStatusWatch" => {
+                let m = msg.parse::<protocol::ctl::SvcStatusWatch>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, _action_sender| {
+                                       commands::service_status_watch(state, req, m.clone())
+                                   }))
+            }
+            "SvcHealth" => {
+                let m = msg.parse::<protocol::ctl::SvcHealth>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, _action_sender| {
+                                       commands::service_health(state, req, m.clone())
+                                   }))
+            }
             "SupDepart" => {
                 let m = msg.parse::<protocol::ctl::SupDepart>()
                            .map_err(HandlerError::from)?;
@@ -353,6 +452,18 @@ impl SrvHandler {
                                        commands::supervisor_depart(state, req, m.clone())
                                    }))
             }
+            "SupApply" => {
+                let m = msg.parse::<protocol::ctl::SupApply>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, action_sender| {
+                                       commands::supervisor_apply(state,
+                                                                  req,
+                                                                  m.clone(),
+                                                                  &action_sender)
+                                   }))
+            }
             _ => {
                 warn!("Unhandled message, {}", msg.message_id());
                 Err(HandlerError::from(io::Error::from(io::ErrorKind::InvalidData)))
@@ -379,12 +490,28 @@ impl Future for SrvHandler {
 
             match self.state {
                 SrvHandlerState::Receiving => {
-                    match try_ready!(self.io.poll()) {
+                    let next = match self.queued.pop_front() {
+                        Some(msg) => Some(msg),
+                        None => try_ready!(self.io.poll()),
+                    };
+                    match next {
                         None => break,
                         Some(msg) => {
                             self.start_timer(&msg.message_id());
                             trace!("OnMessage, {}", msg.message_id());
 
+                            let required = Scope::required_for(msg.message_id());
+                            if !ctl_secrets::permits(&self.scopes, required) {
+                                warn!("Rejected {} from a client without the {} scope",
+                                      msg.message_id(),
+                                      required);
+                                if self.reply_unauthorized(&msg) {
+                                    self.state = SrvHandlerState::Sending;
+                                    continue;
+                                }
+                                break;
+                            }
+
                             let cmd =
                                 match Self::command_from_message(&msg, self.ctl_sender.clone()) {
                                     Ok(cmd) => cmd,
@@ -427,7 +554,20 @@ impl Future for SrvHandler {
                             continue;
                         }
                         Ok(Async::Ready(None)) => self.state = SrvHandlerState::Sent,
-                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Ok(Async::NotReady) => {
+                            // Watch for the client going away while we wait, so that replies
+                            // which never complete, like a status watch, stop being sent. Any
+                            // messages it sends meanwhile are kept until the reply is done.
+                            match self.io.poll() {
+                                Ok(Async::Ready(Some(msg))) => {
+                                    trace!("Queueing {} while replying", msg.message_id());
+                                    self.queued.push_back(msg);
+                                    continue;
+                                }
+                                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                                Ok(Async::Ready(None)) | Err(_) => break,
+                            }
+                        }
                         Err(()) => break,
                     }
                 }
@@ -436,7 +576,10 @@ impl Future for SrvHandler {
                         timer.observe_duration();
                     }
                     trace!("OnMessage complete");
-                    break;
+                    if self.queued.is_empty() {
+                        break;
+                    }
+                    self.state = SrvHandlerState::Receiving;
                 }
             }
         }
@@ -456,21 +599,44 @@ enum SrvHandlerState {
 
 struct SrvState {
     secret_key: String,
+    sup_root:   PathBuf,
     mgr_sender: MgrSender,
 }
 
+/// Accept TLS on a new connection, if we're using it.
+fn start_tls(tcp_stream: TcpStream,
+             tls_acceptor: Option<&TlsAcceptor>)
+             -> impl Future<Item = Box<dyn SrvIo>, Error = HandlerError> {
+    match tls_acceptor {
+        Some(acceptor) => {
+            Either::A(acceptor.accept(tcp_stream)
+                              .map(|stream| Box::new(stream) as Box<dyn SrvIo>)
+                              .map_err(HandlerError::from))
+        }
+        None => Either::B(future::ok(Box::new(tcp_stream) as Box<dyn SrvIo>)),
+    }
+}
+
 /// Start a new thread which will run the CtlGateway server.
 ///
-/// New connections will be authenticated using `secret_key`. Messages from the main thread
-/// will be sent over the channel `mgr_sender`.
-pub fn run(listen_addr: SocketAddr, secret_key: String, mgr_sender: MgrSender) {
+/// If `tls_config` is given, connections must be made over TLS, and with a client certificate if
+/// it requires one. New connections will be authenticated using `secret_key`, or one of the named
+/// secrets under `sup_root`. Messages from the main thread will be sent over the channel
+/// `mgr_sender`.
+pub fn run(listen_addr: SocketAddr,
+           tls_config: Option<Arc<ServerConfig>>,
+           secret_key: String,
+           sup_root: PathBuf,
+           mgr_sender: MgrSender) {
     let tb = thread::Builder::new().name("ctl-gateway".to_string());
     tb.spawn(move || {
           let mut core = reactor::Core::new().unwrap();
           let handle = core.handle();
           let state = SrvState { secret_key,
+                                 sup_root,
                                  mgr_sender };
           let state = Rc::new(RefCell::new(state));
+          let tls_acceptor = tls_config.map(TlsAcceptor::from);
           let server =
               TcpListener::bind(&listen_addr).expect("Could not bind ctl gateway listen address!")
                                              .incoming()
@@ -478,10 +644,14 @@ pub fn run(listen_addr: SocketAddr, secret_key: String, mgr_sender: MgrSender) {
                                                  let addr =
                                                      tcp_stream.peer_addr().expect("Couldn't get \
                                                                                     peer address!");
-                                                 let io = SrvCodec::new().framed(tcp_stream);
                                                  let client = Client { handle: handle.clone(),
                                                                        state:  state.clone(), };
-                                                 (client.serve(io), addr)
+                                                 let serve =
+                                                     start_tls(tcp_stream, tls_acceptor.as_ref())
+                                                     .and_then(move |io| {
+                                                         client.serve(SrvCodec::new().framed(io))
+                                                     });
+                                                 (serve, addr)
                                              })
                                              .for_each(|(client, addr)| {
                                                  handle.spawn(client.then(move |res| {
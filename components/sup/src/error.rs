@@ -106,6 +106,7 @@ pub enum Error {
     ButterflyError(habitat_butterfly::error::Error),
     CtlSecretIo(PathBuf, io::Error),
     APIClient(habitat_api_client::Error),
+    DuplicateStackService(String),
     EnvJoinPathsError(env::JoinPathsError),
     ExecCommandNotFound(String),
     EventError(event::Error),
@@ -219,6 +220,9 @@ impl fmt::Display for SupError {
             Error::Permissions(ref err) => err.to_string(),
             Error::HabitatCommon(ref err) => err.to_string(),
             Error::HabitatCore(ref err) => err.to_string(),
+            Error::DuplicateStackService(ref name) => {
+                format!("The stack lists the {} service more than once", name)
+            }
             Error::EnvJoinPathsError(ref err) => err.to_string(),
             Error::FileNotFound(ref e) => format!("File not found at: {}", e),
             Error::FileWatcherFileIsRoot => "Watched file is root".to_string(),
@@ -368,6 +372,7 @@ impl error::Error for SupError {
             Error::GroupNotFound(_) => "No matching GID for group found",
             Error::HabitatCommon(ref err) => err.description(),
             Error::HabitatCore(ref err) => err.description(),
+            Error::DuplicateStackService(_) => "The stack lists a service more than once",
             Error::EnvJoinPathsError(ref err) => err.description(),
             Error::FileNotFound(_) => "File not found",
             Error::FileWatcherFileIsRoot => "Watched file is root",
@@ -26,8 +26,10 @@ pub(crate) use self::types::ServiceMetadata;
 use self::types::{EventMessage,
                   EventMetadata,
                   HealthCheckEvent,
+                  ServiceRenderFailedEvent,
                   ServiceStartedEvent,
-                  ServiceStoppedEvent};
+                  ServiceStoppedEvent,
+                  ServiceUpdateRolledBackEvent};
 use crate::manager::{service::{HealthCheckResult,
                                Service},
                      sys::Sys};
@@ -37,7 +39,8 @@ pub use error::{Error,
 use futures::sync::mpsc::UnboundedSender;
 use habitat_common::types::{AutomateAuthToken,
                             EventStreamMetadata};
-use habitat_core::env::Config as EnvConfig;
+use habitat_core::{env::Config as EnvConfig,
+                   package::PackageIdent};
 use state::Container;
 use std::{net::SocketAddr,
           num::ParseIntError,
@@ -194,6 +197,28 @@ pub fn health_check(metadata: ServiceMetadata,
     }
 }
 
+/// Send an event for a Service going back to the release it ran
+/// before an update that failed its health check.
+pub fn service_update_rolled_back(service: &Service, rolled_back_to: &PackageIdent) {
+    if stream_initialized() {
+        let service_metadata = Some(service.to_service_metadata());
+        publish(ServiceUpdateRolledBackEvent { service_metadata,
+                                               event_metadata: None,
+                                               rolled_back_to: rolled_back_to.to_string() });
+    }
+}
+
+/// Send an event for a Service whose templates couldn't be rendered,
+/// e.g. because they refer to undefined variables in strict mode.
+pub fn service_render_failed(service: &Service, error: &str) {
+    if stream_initialized() {
+        let service_metadata = Some(service.to_service_metadata());
+        publish(ServiceRenderFailedEvent { service_metadata,
+                                           event_metadata: None,
+                                           error: error.to_string() });
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////
 
 /// Internal helper function to know whether or not to go to the trouble of
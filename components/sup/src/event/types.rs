@@ -110,3 +110,15 @@ impl EventMessage for HealthCheckEvent {
         self.event_metadata = Some(event_metadata);
     }
 }
+
+impl EventMessage for ServiceUpdateRolledBackEvent {
+    fn event_metadata(&mut self, event_metadata: EventMetadata) {
+        self.event_metadata = Some(event_metadata);
+    }
+}
+
+impl EventMessage for ServiceRenderFailedEvent {
+    fn event_metadata(&mut self, event_metadata: EventMetadata) {
+        self.event_metadata = Some(event_metadata);
+    }
+}
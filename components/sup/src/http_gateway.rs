@@ -1,4 +1,11 @@
-use crate::{error::{Result,
+mod census_events;
+mod commands;
+mod tls;
+
+pub use self::tls::GatewayTls;
+use self::commands::CommandBody;
+use crate::{ctl_gateway::server::MgrSender,
+            error::{Result,
                     SupError},
             manager::{self,
                       service::{HealthCheckHook,
@@ -164,19 +171,22 @@ struct AppState {
     authentication_token: Option<String>,
     timer:                Cell<Option<HistogramTimer>>,
     feature_flags:        FeatureFlag,
+    mgr_sender:           MgrSender,
 }
 
 impl AppState {
     fn new(gs: Arc<RwLock<manager::GatewayState>>,
            authentication_token: GatewayAuthenticationToken,
-           feature_flags: FeatureFlag)
+           feature_flags: FeatureFlag,
+           mgr_sender: MgrSender)
            -> Self {
         AppState { gateway_state: gs,
                    // We'll unwrap to the inner type, since the
                    // GatewayAuthenticationToken type has done its job by this point.
                    authentication_token: authentication_token.0,
                    timer: Cell::new(None),
-                   feature_flags }
+                   feature_flags,
+                   mgr_sender }
     }
 }
 
@@ -189,6 +199,13 @@ impl Middleware<AppState> for Authentication {
         let current_token = match current_token {
             Some(t) => t,
             None => {
+                // Anyone who can reach the gateway could change what it runs, so the write
+                // endpoints are only available once a token has been set.
+                if *req.method() != http::Method::GET && *req.method() != http::Method::HEAD {
+                    let body = CommandBody::error("The HTTP gateway only accepts changes when \
+                                                   HAB_SUP_GATEWAY_AUTH_TOKEN is set");
+                    return Ok(Started::Response(HttpResponse::Forbidden().json(body)));
+                }
                 debug!("No authentication token present. HTTP gateway starting in \
                         unauthenticated mode.");
                 return Ok(Started::Done);
@@ -266,6 +283,7 @@ impl Server {
                gateway_state: Arc<RwLock<manager::GatewayState>>,
                authentication_token: GatewayAuthenticationToken,
                feature_flags: FeatureFlag,
+               mgr_sender: MgrSender,
                control: Arc<(Mutex<ServerStartup>, Condvar)>) {
         thread::spawn(move || {
             let &(ref lock, ref cvar) = &*control;
@@ -283,7 +301,8 @@ impl Server {
             let mut server = server::new(move || {
                                  let app_state = AppState::new(gateway_state.clone(),
                                                                authentication_token.clone(),
-                                                               feature_flags);
+                                                               feature_flags,
+                                                               mgr_sender.clone());
                                  App::with_state(app_state).middleware(Authentication)
                                                            .middleware(Metrics)
                                                            .configure(routes)
@@ -335,27 +354,49 @@ impl Predicate<AppState> for RedactHTTP {
 
 fn routes(app: App<AppState>) -> App<AppState> {
     app.resource("/", |r| r.get().f(doc))
-       .resource("/services", |r| r.get().f(services))
+       .resource("/services", |r| {
+           r.get().f(services);
+           r.post().f(commands::load);
+       })
        .resource("/services/{svc}/{group}", |r| {
-           r.get().f(service_without_org)
+           r.get().f(service_without_org);
+           r.delete().f(commands::unload);
        })
        .resource("/services/{svc}/{group}/config", |r| {
-           r.get().f(config_without_org)
+           r.get().f(config_without_org);
+           r.put().f(commands::set_config);
        })
        .resource("/services/{svc}/{group}/health", |r| {
            r.get().f(health_without_org)
        })
+       .resource("/services/{svc}/{group}/health/history", |r| {
+           r.get().f(health_history_without_org)
+       })
        .resource("/services/{svc}/{group}/{org}", |r| {
-           r.get().f(service_with_org)
+           r.get().f(service_with_org);
+           r.delete().f(commands::unload);
        })
        .resource("/services/{svc}/{group}/{org}/config", |r| {
-           r.get().f(config_with_org)
+           r.get().f(config_with_org);
+           r.put().f(commands::set_config);
        })
        .resource("/services/{svc}/{group}/{org}/health", |r| {
            r.get().f(health_with_org)
        })
+       .resource("/services/{svc}/{group}/{org}/health/history", |r| {
+           r.get().f(health_history_with_org)
+       })
+       // Outside of /services so that they can't be mistaken for a service in an organization
+       // named "start" or "stop".
+       .resource("/start/{svc}/{group}", |r| r.post().f(commands::start))
+       .resource("/start/{svc}/{group}/{org}", |r| r.post().f(commands::start))
+       .resource("/stop/{svc}/{group}", |r| r.post().f(commands::stop))
+       .resource("/stop/{svc}/{group}/{org}", |r| r.post().f(commands::stop))
        .resource("/butterfly", |r| r.get().filter(RedactHTTP).f(butterfly))
        .resource("/census", |r| r.get().filter(RedactHTTP).f(census))
+       .resource("/census/events", |r| {
+           r.get().filter(RedactHTTP).f(census_events::census_events)
+       })
        .resource("/metrics", |r| r.get().f(metrics))
 }
 
@@ -477,6 +518,38 @@ fn health(req: &HttpRequest<AppState>,
     }
 }
 
+fn health_history_with_org(req: &HttpRequest<AppState>) -> HttpResponse {
+    let (svc, group, org) = Path::<(String, String, String)>::extract(&req).unwrap()
+                                                                           .into_inner();
+    health_history(req, svc, group, Some(&org))
+}
+
+fn health_history_without_org(req: &HttpRequest<AppState>) -> HttpResponse {
+    let (svc, group) = Path::<(String, String)>::extract(&req).unwrap()
+                                                              .into_inner();
+    health_history(req, svc, group, None)
+}
+
+fn health_history(req: &HttpRequest<AppState>,
+                  svc: String,
+                  group: String,
+                  org: Option<&str>)
+                  -> HttpResponse {
+    let service_group = match ServiceGroup::new(None, svc, group, org) {
+        Ok(sg) => sg,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    let gateway_state = &req.state()
+                            .gateway_state
+                            .read()
+                            .expect("GatewayState lock is poisoned");
+    match gateway_state.health_check_history.get(&service_group) {
+        Some(history) => HttpResponse::Ok().json(history),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
 fn service_with_org(req: &HttpRequest<AppState>) -> HttpResponse {
     let (svc, group, org) = Path::<(String, String, String)>::extract(&req).unwrap()
                                                                            .into_inner();
@@ -0,0 +1,159 @@
+//! The `/census/events` endpoint: a stream of changes to the census, as server-sent events.
+//!
+//! The Manager appends events to the `CensusEventLog` in the gateway state whenever it rebuilds
+//! the census, and each connection polls the log for events newer than the last one it saw. A
+//! client that reconnects with a `Last-Event-ID` header picks up after that event, provided it's
+//! still in the log.
+
+use super::AppState;
+use crate::census::CensusEventLog;
+use actix_web::{error::ErrorInternalServerError,
+                http::header,
+                FromRequest,
+                HttpRequest,
+                HttpResponse,
+                Query};
+use bytes::Bytes;
+use futures::Stream;
+use habitat_core::service::ServiceGroup;
+use serde_json;
+use std::{fmt::Write,
+          result,
+          str::FromStr,
+          sync::Arc,
+          time::{Duration,
+                 Instant}};
+use tokio_timer::Interval;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// After this many polls with nothing to send, we send a comment instead. This keeps proxies from
+/// timing out the connection and lets us notice clients that have gone away.
+const KEEPALIVE_POLLS: u32 = 30;
+
+#[derive(Deserialize)]
+struct EventsQuery {
+    /// Comma-separated service groups to limit the stream to.
+    service_group: Option<String>,
+}
+
+pub(super) fn census_events(req: &HttpRequest<AppState>) -> HttpResponse {
+    let filter = match service_group_filter(req) {
+        Ok(filter) => filter,
+        Err(resp) => return resp,
+    };
+    let gateway_state = Arc::clone(&req.state().gateway_state);
+    let current_id = gateway_state.read()
+                                  .expect("GatewayState lock is poisoned")
+                                  .census_events
+                                  .last_id();
+    let mut last_id = match last_event_id(req) {
+        Some(id) if id <= current_id => id,
+        // The log has been reset since the client last connected, so it has missed nothing
+        // that's still there.
+        Some(_) => 0,
+        None => current_id,
+    };
+    // Start with a keepalive, so the client gets the response headers straight away.
+    let mut idle_polls = KEEPALIVE_POLLS;
+
+    let stream = Interval::new(Instant::now(), POLL_INTERVAL)
+        .map_err(ErrorInternalServerError)
+        .filter_map(move |_| {
+            let mut body = {
+                let gs = gateway_state.read().expect("GatewayState lock is poisoned");
+                format_events(&gs.census_events, &mut last_id, &filter)
+            };
+            if body.is_empty() {
+                idle_polls += 1;
+                if idle_polls < KEEPALIVE_POLLS {
+                    return None;
+                }
+                body.push_str(":\n\n");
+            }
+            idle_polls = 0;
+            Some(Bytes::from(body))
+        });
+
+    HttpResponse::Ok().content_type("text/event-stream")
+                      .header(header::CACHE_CONTROL, "no-cache")
+                      .streaming(stream)
+}
+
+/// Format the events in the log after `last_id` that belong to one of the filtered service
+/// groups (or to any group, if the filter is empty), moving `last_id` past all of them.
+fn format_events(log: &CensusEventLog, last_id: &mut u64, filter: &[ServiceGroup]) -> String {
+    let mut body = String::new();
+    for (id, event) in log.since(*last_id) {
+        *last_id = *id;
+        if !filter.is_empty() && !filter.contains(event.service_group()) {
+            continue;
+        }
+        let data = serde_json::to_string(event).expect("Census events always serialize");
+        write!(body, "id: {}\nevent: {}\ndata: {}\n\n", id, event.name(), data)
+            .expect("Writing to a String can't fail");
+    }
+    body
+}
+
+fn service_group_filter(req: &HttpRequest<AppState>)
+                        -> result::Result<Vec<ServiceGroup>, HttpResponse> {
+    let query = Query::<EventsQuery>::extract(req).map_err(|_| HttpResponse::BadRequest().finish())?
+                                                  .into_inner();
+    match query.service_group {
+        Some(groups) => {
+            groups.split(',')
+                  .map(|sg| ServiceGroup::from_str(sg.trim()))
+                  .collect::<result::Result<_, _>>()
+                  .map_err(|_| HttpResponse::BadRequest().finish())
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+fn last_event_id(req: &HttpRequest<AppState>) -> Option<u64> {
+    req.headers()
+       .get("Last-Event-ID")
+       .and_then(|value| value.to_str().ok())
+       .and_then(|value| value.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::census::CensusEvent;
+
+    fn test_log() -> CensusEventLog {
+        let mut log = CensusEventLog::default();
+        log.push(CensusEvent::MemberJoined { service_group: "redis.default".parse().unwrap(),
+                                             member_id:     "a".into(), });
+        log.push(CensusEvent::LeaderChanged { service_group: "nginx.default".parse().unwrap(),
+                                              leader_id:     Some("b".into()), });
+        log
+    }
+
+    #[test]
+    fn format_events_writes_server_sent_events() {
+        let log = test_log();
+        let mut last_id = 0;
+        let body = format_events(&log, &mut last_id, &[]);
+        assert_eq!(body,
+                   "id: 1\nevent: member_joined\ndata: \
+                    {\"type\":\"member_joined\",\"service_group\":\"redis.default\",\
+                    \"member_id\":\"a\"}\n\n\
+                    id: 2\nevent: leader_changed\ndata: \
+                    {\"type\":\"leader_changed\",\"service_group\":\"nginx.default\",\
+                    \"leader_id\":\"b\"}\n\n");
+        assert_eq!(last_id, 2);
+        assert!(format_events(&log, &mut last_id, &[]).is_empty());
+    }
+
+    #[test]
+    fn format_events_skips_filtered_groups() {
+        let log = test_log();
+        let mut last_id = 0;
+        let body = format_events(&log, &mut last_id, &["nginx.default".parse().unwrap()]);
+        assert!(body.starts_with("id: 2\n"));
+        assert!(!body.contains("redis.default"));
+        assert_eq!(last_id, 2);
+    }
+}
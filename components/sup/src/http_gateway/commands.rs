@@ -0,0 +1,589 @@
+//! Handlers for the gateway's write endpoints.
+//!
+//! Each request is turned into the same `CtlCommand` that the ctl-gateway would build for
+//! `hab svc load`, `hab svc stop`, etc. and is sent to the Manager over its command channel. The
+//! replies are collected and returned as a single JSON response.
+
+use super::{service_from_services,
+            AppState};
+use crate::{ctl_gateway::{server::{CtlCommand,
+                                   MgrSender},
+                          CtlRequest},
+            manager::{action::ActionSender,
+                      commands,
+                      ManagerState}};
+use actix_web::{error::ErrorInternalServerError,
+                http::StatusCode,
+                AsyncResponder,
+                FromRequest,
+                FutureResponse,
+                HttpMessage,
+                HttpRequest,
+                HttpResponse,
+                Query};
+use futures::{future,
+              sync::mpsc,
+              Future,
+              Stream};
+use habitat_core::{package::{PackageIdent,
+                             VersionConstraint},
+                   service::{ApplicationEnvironment,
+                             CpuQuota,
+                             MemorySize,
+                             ServiceGroup}};
+use habitat_sup_protocol::{self as protocol,
+                           codec::{SrvMessage,
+                                   SrvTxn},
+                           ctl::ServiceBindList,
+                           net::{self,
+                                 ErrCode,
+                                 NetErr,
+                                 NetResult},
+                           types::{BindingMode,
+                                   HealthCheckInterval,
+                                   LogFile,
+                                   ResourceLimits,
+                                   RestartBackoff,
+                                   RestartPolicy,
+                                   ServiceBind,
+                                   Topology,
+                                   UpdateRollback,
+                                   UpdateRollout,
+                                   UpdateStrategy,
+                                   UpdateWindows}};
+use serde_json::{self,
+                 Value as Json};
+use std::{result,
+          str::FromStr};
+use toml;
+
+/// Body of every response from a write endpoint: the output the command produced and, if it
+/// failed, why.
+#[derive(Default, Serialize)]
+pub(super) struct CommandBody {
+    output: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error:  Option<String>,
+}
+
+impl CommandBody {
+    pub(super) fn error<T: ToString>(msg: T) -> Self {
+        CommandBody { output: Vec::new(),
+                      error:  Some(msg.to_string()), }
+    }
+}
+
+/// Body of a request to load a service. Only `ident` is required, and it may end in a version
+/// constraint, e.g. `core/postgresql@~9.6`, as it can for `hab svc load`. The other fields take
+/// the same values as the `hab svc load` options of the same name.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LoadBody {
+    ident:                 String,
+    group:                 Option<String>,
+    application:           Option<String>,
+    environment:           Option<String>,
+    bldr_url:              Option<String>,
+    channel:               Option<String>,
+    topology:              Option<String>,
+    strategy:              Option<String>,
+    binds:                 Option<Vec<String>>,
+    binding_mode:          Option<String>,
+    config_from:           Option<String>,
+    password:              Option<String>,
+    health_check_interval: Option<u64>,
+    restart_policy:        Option<String>,
+    restart_backoff:       Option<RestartBackoffBody>,
+    resource_limits:       Option<ResourceLimitsBody>,
+    log_file:              Option<LogFileBody>,
+    /// An empty list removes the service's update windows.
+    update_windows:        Option<Vec<String>>,
+    update_rollout:        Option<UpdateRolloutBody>,
+    update_rollback:       Option<UpdateRollbackBody>,
+    strict_templates:      Option<bool>,
+    force:                 Option<bool>,
+}
+
+/// The `hab svc load` restart options, in seconds.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RestartBackoffBody {
+    initial_delay: Option<u64>,
+    max_delay:     Option<u64>,
+    limit:         Option<u32>,
+    window:        Option<u64>,
+}
+
+impl From<RestartBackoffBody> for RestartBackoff {
+    fn from(body: RestartBackoffBody) -> Self {
+        RestartBackoff { initial_delay: body.initial_delay,
+                         max_delay:     body.max_delay,
+                         limit:         body.limit,
+                         window:        body.window, }
+    }
+}
+
+/// The `hab svc load` resource limit options. `cpu_quota` and `memory_max` may be given as
+/// numbers or as strings in the same form as the options, e.g. `"0.5"` and `"512M"`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ResourceLimitsBody {
+    cpu_weight: Option<u32>,
+    cpu_quota:  Option<Json>,
+    memory_max: Option<Json>,
+    pids_max:   Option<u64>,
+}
+
+/// The `hab svc load` log file options. The file is written if `enabled` is true, or if it's
+/// left out and the Supervisor writes log files by default.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LogFileBody {
+    enabled:  Option<bool>,
+    max_size: Option<Json>,
+    max_age:  Option<u64>,
+    retain:   Option<u32>,
+}
+
+/// The `hab svc load` rolling update options. `batch_size` may be a number of members or a
+/// string giving a percentage of them, e.g. `"25%"`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UpdateRolloutBody {
+    batch_size:     Option<Json>,
+    health_timeout: Option<u64>,
+}
+
+/// The `hab svc load` rollback options. Rolling back is turned on unless `enabled` is false.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UpdateRollbackBody {
+    enabled:      Option<bool>,
+    grace_period: Option<u64>,
+}
+
+impl From<UpdateRollbackBody> for UpdateRollback {
+    fn from(body: UpdateRollbackBody) -> Self {
+        UpdateRollback { enabled:      Some(body.enabled.unwrap_or(true)),
+                         grace_period: body.grace_period, }
+    }
+}
+
+impl LoadBody {
+    fn into_msg(self) -> NetResult<protocol::ctl::SvcLoad> {
+        let (ident, constraint) = VersionConstraint::parse_ident(&self.ident)?;
+        let mut msg = protocol::ctl::SvcLoad::default();
+        msg.ident = Some(ident.into());
+        msg.version_constraint = constraint.as_ref().map(ToString::to_string);
+        msg.group = self.group;
+        msg.application_environment = match (self.application, self.environment) {
+            (Some(application), Some(environment)) => {
+                Some(ApplicationEnvironment::new(application, environment)?.into())
+            }
+            (None, None) => None,
+            _ => {
+                return Err(net::err(ErrCode::InvalidPayload,
+                                    "application and environment must be given \
+                                     together"));
+            }
+        };
+        msg.bldr_url = self.bldr_url;
+        msg.bldr_channel = self.channel;
+        msg.topology = parse::<Topology>(self.topology)?.map(|v| v as i32);
+        msg.update_strategy = parse::<UpdateStrategy>(self.strategy)?.map(|v| v as i32);
+        msg.binding_mode = parse::<BindingMode>(self.binding_mode)?.map(|v| v as i32);
+        msg.restart_policy = parse::<RestartPolicy>(self.restart_policy)?.map(|v| v as i32);
+        msg.restart_backoff = self.restart_backoff.map(RestartBackoffBody::into);
+        if let Some(limits) = self.resource_limits {
+            msg.resource_limits =
+                Some(ResourceLimits { cpu_weight: limits.cpu_weight,
+                                      cpu_quota:  parse_json::<CpuQuota>(limits.cpu_quota)?
+                                                      .map(CpuQuota::into),
+                                      memory_max: parse_json::<MemorySize>(limits.memory_max)?
+                                                      .map(MemorySize::into),
+                                      pids_max:   limits.pids_max, });
+        }
+        if let Some(log_file) = self.log_file {
+            msg.log_file = Some(LogFile { enabled:  log_file.enabled,
+                                          max_size: parse_json::<MemorySize>(log_file.max_size)?
+                                                        .map(MemorySize::into),
+                                          max_age:  log_file.max_age,
+                                          retain:   log_file.retain, });
+        }
+        msg.config_from = self.config_from;
+        msg.svc_encrypted_password = self.password.map(encrypt_password).transpose()?;
+        msg.health_check_interval = self.health_check_interval
+                                        .map(|seconds| HealthCheckInterval { seconds });
+        msg.update_windows = match self.update_windows {
+            Some(windows) => {
+                let windows = windows.iter()
+                                     .map(|w| w.parse())
+                                     .collect::<NetResult<_>>()?;
+                Some(UpdateWindows { windows })
+            }
+            None => None,
+        };
+        if let Some(rollout) = self.update_rollout {
+            let mut update_rollout = UpdateRollout::default();
+            if let Some(batch_size) = rollout.batch_size {
+                match batch_size {
+                    Json::String(s) => update_rollout.set_batch_size(&s)?,
+                    other => update_rollout.set_batch_size(&other.to_string())?,
+                }
+            }
+            update_rollout.health_timeout = rollout.health_timeout;
+            msg.update_rollout = Some(update_rollout);
+        }
+        msg.update_rollback = self.update_rollback.map(UpdateRollbackBody::into);
+        msg.strict_templates = self.strict_templates;
+        msg.force = self.force;
+        if let Some(binds) = self.binds {
+            let mut list = ServiceBindList::default();
+            for bind in binds {
+                list.binds.push(ServiceBind::from_str(&bind)?);
+            }
+            msg.binds = Some(list);
+        }
+        Ok(msg)
+    }
+}
+
+/// Services only run as another user with a password on Windows, where the password is kept
+/// encrypted for this machine, as `hab svc load --password` does.
+#[cfg(windows)]
+fn encrypt_password(password: String) -> NetResult<String> {
+    habitat_core::crypto::dpapi::encrypt(password).map_err(NetErr::from)
+}
+
+#[cfg(not(windows))]
+fn encrypt_password(_password: String) -> NetResult<String> {
+    Err(net::err(ErrCode::NotSupported,
+                 "A password can only be given for services on \
+                  Windows"))
+}
+
+/// Body of a request to set a service group's configuration.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigBody {
+    /// Defaults to one more than the service group's current version.
+    version: Option<u64>,
+    config:  Json,
+}
+
+/// Query parameters for unloading or stopping a service.
+#[derive(Deserialize)]
+struct ShutdownQuery {
+    signal:  Option<String>,
+    timeout: Option<u32>,
+}
+
+pub(super) fn load(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let mgr_sender = req.state().mgr_sender.clone();
+    req.json()
+       .from_err()
+       .and_then(move |body: LoadBody| -> FutureResponse<HttpResponse> {
+           match body.into_msg() {
+               Ok(msg) => {
+                   send(&mgr_sender, move |state, req, _action_sender| {
+                       commands::service_load(state, req, &msg)
+                   })
+               }
+               Err(err) => {
+                   Box::new(future::ok(HttpResponse::BadRequest().json(CommandBody::error(err.msg))))
+               }
+           }
+       })
+       .responder()
+}
+
+pub(super) fn unload(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let ident = match loaded_ident(req) {
+        Ok(ident) => ident,
+        Err(resp) => return Box::new(future::ok(resp)),
+    };
+    let query = match shutdown_query(req) {
+        Ok(query) => query,
+        Err(resp) => return Box::new(future::ok(resp)),
+    };
+    let msg = protocol::ctl::SvcUnload { ident:              Some(ident.into()),
+                                         signal:             query.signal,
+                                         timeout_in_seconds: query.timeout, };
+    send(&req.state().mgr_sender, move |state, req, action_sender| {
+        commands::service_unload(state, req, msg.clone(), &action_sender)
+    })
+}
+
+pub(super) fn start(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let ident = match loaded_ident(req) {
+        Ok(ident) => ident,
+        Err(resp) => return Box::new(future::ok(resp)),
+    };
+    let msg = protocol::ctl::SvcStart { ident: Some(ident.into()), };
+    send(&req.state().mgr_sender, move |state, req, _action_sender| {
+        commands::service_start(state, req, msg.clone())
+    })
+}
+
+pub(super) fn stop(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let ident = match loaded_ident(req) {
+        Ok(ident) => ident,
+        Err(resp) => return Box::new(future::ok(resp)),
+    };
+    let query = match shutdown_query(req) {
+        Ok(query) => query,
+        Err(resp) => return Box::new(future::ok(resp)),
+    };
+    let msg = protocol::ctl::SvcStop { ident:              Some(ident.into()),
+                                       signal:             query.signal,
+                                       timeout_in_seconds: query.timeout, };
+    send(&req.state().mgr_sender, move |state, req, action_sender| {
+        commands::service_stop(state, req, msg.clone(), &action_sender)
+    })
+}
+
+pub(super) fn set_config(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let service_group = match service_group_from_path(req) {
+        Some(sg) => sg,
+        None => return Box::new(future::ok(HttpResponse::BadRequest().finish())),
+    };
+    let next_version = req.state()
+                          .gateway_state
+                          .read()
+                          .expect("GatewayState lock is poisoned")
+                          .config_incarnations
+                          .get(&service_group)
+                          .map_or(1, |incarnation| incarnation + 1);
+    let mgr_sender = req.state().mgr_sender.clone();
+    req.json()
+       .from_err()
+       .and_then(move |body: ConfigBody| -> FutureResponse<HttpResponse> {
+           let cfg = match toml::Value::try_from(body.config).and_then(|v| toml::to_vec(&v)) {
+               Ok(cfg) => cfg,
+               Err(err) => {
+                   let msg = format!("Unable to convert configuration to TOML, {}", err);
+                   return Box::new(future::ok(HttpResponse::BadRequest().json(CommandBody::error(msg))));
+               }
+           };
+           let version = body.version.unwrap_or(next_version);
+           let msg = protocol::ctl::SvcSetCfg { service_group: Some(service_group.into()),
+                                                cfg:           Some(cfg),
+                                                version:       Some(version),
+                                                is_encrypted:  Some(false), };
+           send(&mgr_sender, move |state, req, _action_sender| {
+               commands::service_cfg_set(state, req, msg.clone())
+           })
+       })
+       .responder()
+}
+
+/// Send a command to the Manager, resolving to a response once it has finished.
+fn send<F>(mgr_sender: &MgrSender, fun: F) -> FutureResponse<HttpResponse>
+    where F: Fn(&ManagerState, &mut CtlRequest, ActionSender) -> NetResult<()> + Send + 'static
+{
+    let (ctl_sender, ctl_receiver) = mpsc::unbounded();
+    let cmd = CtlCommand::new(ctl_sender, Some(SrvTxn::default()), fun);
+    if let Err(err) = mgr_sender.unbounded_send(cmd) {
+        // The Manager has stopped taking commands, most likely because it's shutting down.
+        warn!("ManagerReceiver err: {}", err);
+        return Box::new(future::ok(HttpResponse::ServiceUnavailable().finish()));
+    }
+    // The channel closes once the Manager has run the command and dropped it.
+    Box::new(ctl_receiver.collect()
+                         .map(command_response)
+                         .map_err(|()| ErrorInternalServerError("command reply channel failed")))
+}
+
+fn command_response(replies: Vec<SrvMessage>) -> HttpResponse {
+    let mut body = CommandBody::default();
+    let mut status = StatusCode::OK;
+    // Output arrives in fragments wherever its color changes, so join it all up before
+    // splitting it into lines.
+    let mut output = String::new();
+    for reply in replies {
+        match reply.message_id() {
+            "ConsoleLine" => {
+                if let Ok(line) = reply.parse::<protocol::ctl::ConsoleLine>() {
+                    output.push_str(&line.line);
+                }
+            }
+            "NetErr" => {
+                if let Ok(err) = reply.parse::<NetErr>() {
+                    status = status_for(&err);
+                    body.error = Some(err.msg);
+                }
+            }
+            _ => (),
+        }
+    }
+    body.output = output.lines().map(String::from).collect();
+    HttpResponse::build(status).json(&body)
+}
+
+fn status_for(err: &NetErr) -> StatusCode {
+    match ErrCode::from_i32(err.code).unwrap_or_default() {
+        ErrCode::NotFound => StatusCode::NOT_FOUND,
+        ErrCode::Conflict => StatusCode::CONFLICT,
+        ErrCode::Unauthorized => StatusCode::FORBIDDEN,
+        ErrCode::NotSupported
+        | ErrCode::BadPayload
+        | ErrCode::InvalidPayload
+        | ErrCode::UpdateClient => StatusCode::BAD_REQUEST,
+        ErrCode::EntityTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+        ErrCode::Internal | ErrCode::Io => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn parse<T>(value: Option<String>) -> NetResult<Option<T>>
+    where T: FromStr<Err = NetErr>
+{
+    value.map(|v| v.parse()).transpose()
+}
+
+/// Parse a value that may be given as a JSON string or as a number.
+fn parse_json<T>(value: Option<Json>) -> NetResult<Option<T>>
+    where T: FromStr<Err = habitat_core::Error>
+{
+    value.map(|v| {
+             match v {
+                 Json::String(s) => s.parse(),
+                 other => other.to_string().parse(),
+             }
+         })
+         .transpose()
+         .map_err(NetErr::from)
+}
+
+fn service_group_from_path(req: &HttpRequest<AppState>) -> Option<ServiceGroup> {
+    let params = req.match_info();
+    let svc = params.get("svc")?;
+    let group = params.get("group")?;
+    ServiceGroup::new(None, svc, group, params.get("org")).ok()
+}
+
+/// Find the package identifier the service in the request path was loaded with.
+fn loaded_ident(req: &HttpRequest<AppState>) -> result::Result<PackageIdent, HttpResponse> {
+    let service_group =
+        service_group_from_path(req).ok_or_else(|| HttpResponse::BadRequest().finish())?;
+    let data = &req.state()
+                   .gateway_state
+                   .read()
+                   .expect("GatewayState lock is poisoned")
+                   .services_data;
+    service_from_services(&service_group, &data).and_then(|mut s| {
+                                                    serde_json::from_value(s["spec_ident"].take())
+                                                        .ok()
+                                                })
+                                                .ok_or_else(|| {
+                                                    let msg = format!("Service not loaded, {}",
+                                                                      service_group);
+                                                    HttpResponse::NotFound().json(CommandBody::error(msg))
+                                                })
+}
+
+fn shutdown_query(req: &HttpRequest<AppState>) -> result::Result<ShutdownQuery, HttpResponse> {
+    Query::<ShutdownQuery>::extract(req).map(Query::into_inner)
+                                        .map_err(|err| {
+                                            HttpResponse::BadRequest().json(CommandBody::error(err))
+                                        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_body(json: &str) -> NetResult<protocol::ctl::SvcLoad> {
+        serde_json::from_str::<LoadBody>(json).expect("valid load body")
+                                              .into_msg()
+    }
+
+    #[test]
+    fn load_body_sets_svc_load_fields() {
+        let msg = load_body(r#"{"ident": "core/redis", "group": "prod", "topology": "leader",
+                                "strategy": "at-once", "binds": ["db:postgresql.default"]}"#).unwrap();
+        let ident: PackageIdent = msg.ident.unwrap().into();
+        assert_eq!(ident, PackageIdent::from_str("core/redis").unwrap());
+        assert_eq!(msg.group, Some("prod".to_string()));
+        assert_eq!(msg.topology, Some(Topology::Leader as i32));
+        assert_eq!(msg.update_strategy, Some(UpdateStrategy::AtOnce as i32));
+        assert_eq!(msg.binds.unwrap().binds.len(), 1);
+        assert_eq!(msg.force, None);
+        assert_eq!(msg.restart_backoff, None);
+        assert_eq!(msg.resource_limits, None);
+        assert_eq!(msg.log_file, None);
+    }
+
+    #[test]
+    fn load_body_sets_restart_backoff_resource_limits_and_log_file() {
+        let msg = load_body(r#"{"ident": "core/redis",
+                                "restart_backoff": {"limit": 5, "window": 300},
+                                "resource_limits": {"cpu_quota": 0.5, "memory_max": "512M"},
+                                "log_file": {"enabled": true, "max_size": "10M"}}"#).unwrap();
+        let backoff = msg.restart_backoff.unwrap();
+        assert_eq!(backoff.limit, Some(5));
+        assert_eq!(backoff.window, Some(300));
+        assert_eq!(backoff.initial_delay, None);
+        let limits = msg.resource_limits.unwrap();
+        assert_eq!(limits.cpu_quota, Some(500));
+        assert_eq!(limits.memory_max, Some(512 << 20));
+        assert_eq!(limits.cpu_weight, None);
+        let log_file = msg.log_file.unwrap();
+        assert_eq!(log_file.enabled, Some(true));
+        assert_eq!(log_file.max_size, Some(10 << 20));
+
+        assert!(load_body(r#"{"ident": "core/redis", "resource_limits": {"memory_max": "lots"}}"#)
+                    .is_err());
+    }
+
+    #[test]
+    fn load_body_sets_update_and_application_environment_fields() {
+        let msg = load_body(r#"{"ident": "core/postgresql@~9.6",
+                                "application": "myapp", "environment": "prod",
+                                "health_check_interval": 60,
+                                "update_windows": ["mon-fri 02:00-04:00 UTC"],
+                                "update_rollout": {"batch_size": "25%", "health_timeout": 300},
+                                "update_rollback": {"grace_period": 120},
+                                "strict_templates": true}"#).unwrap();
+        let ident: PackageIdent = msg.ident.unwrap().into();
+        assert_eq!(ident, PackageIdent::from_str("core/postgresql").unwrap());
+        assert_eq!(msg.version_constraint, Some("~9.6".to_string()));
+        let app_env = msg.application_environment.unwrap();
+        assert_eq!(app_env.application, "myapp");
+        assert_eq!(app_env.environment, "prod");
+        assert_eq!(msg.health_check_interval.unwrap().seconds, 60);
+        assert_eq!(msg.update_windows.unwrap().windows.len(), 1);
+        let rollout = msg.update_rollout.unwrap();
+        assert_eq!(rollout.batch_percent, Some(25));
+        assert_eq!(rollout.health_timeout, Some(300));
+        let rollback = msg.update_rollback.unwrap();
+        assert_eq!(rollback.enabled, Some(true));
+        assert_eq!(rollback.grace_period, Some(120));
+        assert_eq!(msg.strict_templates, Some(true));
+
+        let msg = load_body(r#"{"ident": "core/redis", "update_windows": []}"#).unwrap();
+        assert!(msg.update_windows.unwrap().windows.is_empty());
+        assert!(load_body(r#"{"ident": "core/redis", "application": "myapp"}"#).is_err());
+        assert!(load_body(r#"{"ident": "core/redis", "update_rollout": {"batch_size": 0}}"#)
+                    .is_err());
+    }
+
+    #[test]
+    fn load_body_rejects_invalid_values() {
+        assert!(load_body(r#"{"ident": "core/redis", "topology": "sideways"}"#).is_err());
+        assert!(load_body(r#"{"ident": "core/redis", "binds": ["nope"]}"#).is_err());
+    }
+
+    #[test]
+    fn load_body_rejects_unknown_fields() {
+        assert!(serde_json::from_str::<LoadBody>(r#"{"ident": "core/redis", "lulz": true}"#).is_err());
+    }
+
+    #[test]
+    fn command_errors_map_to_http_statuses() {
+        let status = |code| status_for(&protocol::net::err(code, "oops"));
+        assert_eq!(status(ErrCode::NotFound), StatusCode::NOT_FOUND);
+        assert_eq!(status(ErrCode::Conflict), StatusCode::CONFLICT);
+        assert_eq!(status(ErrCode::BadPayload), StatusCode::BAD_REQUEST);
+        assert_eq!(status(ErrCode::Internal), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
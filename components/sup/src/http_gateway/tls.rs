@@ -0,0 +1,177 @@
+//! TLS for the HTTP gateway, also used by the ctl gateway.
+//!
+//! The gateway's `ServerConfig` is built once, when the listener is
+//! bound, so rather than giving it a fixed certificate we give it a
+//! resolver (and, for client authentication, a verifier) that we can
+//! swap the contents of. Reloading then takes effect for the next
+//! connection, without restarting the gateway.
+
+use crate::{error::{Error,
+                    Result},
+            manager::TLSConfig};
+use rustls::{internal::pemfile,
+             sign::{self,
+                    CertifiedKey},
+             AllowAnyAuthenticatedClient,
+             Certificate,
+             ClientCertVerified,
+             ClientCertVerifier,
+             DistinguishedNames,
+             NoClientAuth,
+             PrivateKey,
+             ResolvesServerCert,
+             RootCertStore,
+             ServerConfig,
+             SignatureScheme,
+             TLSError};
+use std::{fs::File,
+          io::BufReader,
+          path::{Path,
+                 PathBuf},
+          result,
+          sync::{Arc,
+                 RwLock}};
+use webpki;
+
+pub struct GatewayTls {
+    config:          TLSConfig,
+    cert_resolver:   Arc<CertResolver>,
+    client_verifier: Option<Arc<ClientVerifier>>,
+}
+
+impl GatewayTls {
+    pub fn new(config: TLSConfig) -> Result<Self> {
+        let cert_resolver = Arc::new(CertResolver(RwLock::new(certified_key(&config)?)));
+        let client_verifier = match config.ca_cert_path {
+            Some(ref path) => Some(Arc::new(ClientVerifier(RwLock::new(client_verifier(path)?)))),
+            None => None,
+        };
+        Ok(GatewayTls { config,
+                        cert_resolver,
+                        client_verifier })
+    }
+
+    /// The config to bind the gateway with. It keeps referring to this
+    /// `GatewayTls`, so later reloads apply to it.
+    pub fn server_config(&self) -> ServerConfig {
+        let verifier: Arc<dyn ClientCertVerifier> = match self.client_verifier {
+            Some(ref verifier) => verifier.clone(),
+            None => NoClientAuth::new(),
+        };
+        let mut server_config = ServerConfig::new(verifier);
+        server_config.cert_resolver = self.cert_resolver.clone();
+        server_config.ignore_client_order = true;
+        server_config
+    }
+
+    /// Read the certificate, key and client CA again. If any of them
+    /// can't be loaded, nothing is replaced and the gateway carries on
+    /// with what it had.
+    pub fn reload(&self) -> Result<()> {
+        let key = certified_key(&self.config)?;
+        let verifier = match self.config.ca_cert_path {
+            Some(ref path) => Some(client_verifier(path)?),
+            None => None,
+        };
+        *self.cert_resolver
+             .0
+             .write()
+             .expect("CertResolver lock is poisoned") = key;
+        if let (Some(current), Some(verifier)) = (&self.client_verifier, verifier) {
+            *current.0.write().expect("ClientVerifier lock is poisoned") = verifier;
+        }
+        Ok(())
+    }
+
+    /// The files to watch for changes.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        let mut paths = vec![self.config.cert_path.clone(), self.config.key_path.clone()];
+        if let Some(ref path) = self.config.ca_cert_path {
+            paths.push(path.clone());
+        }
+        paths
+    }
+}
+
+struct CertResolver(RwLock<CertifiedKey>);
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self,
+               _server_name: Option<webpki::DNSNameRef<'_>>,
+               _sigschemes: &[SignatureScheme])
+               -> Option<CertifiedKey> {
+        Some(self.0
+                 .read()
+                 .expect("CertResolver lock is poisoned")
+                 .clone())
+    }
+}
+
+struct ClientVerifier(RwLock<Arc<dyn ClientCertVerifier>>);
+
+impl ClientVerifier {
+    fn current(&self) -> Arc<dyn ClientCertVerifier> {
+        self.0
+            .read()
+            .expect("ClientVerifier lock is poisoned")
+            .clone()
+    }
+}
+
+impl ClientCertVerifier for ClientVerifier {
+    fn client_auth_root_subjects(&self) -> DistinguishedNames {
+        self.current().client_auth_root_subjects()
+    }
+
+    fn verify_client_cert(&self,
+                          presented_certs: &[Certificate])
+                          -> result::Result<ClientCertVerified, TLSError> {
+        self.current().verify_client_cert(presented_certs)
+    }
+}
+
+fn certified_key(config: &TLSConfig) -> Result<CertifiedKey> {
+    let cert_file = &mut BufReader::new(File::open(&config.cert_path)?);
+
+    // Note that we must explicitly map these errors because rustls returns () as the error from
+    // both pemfile::certs() as well as the private key readers and we want to return different
+    // errors for each.
+    let cert_chain =
+        pemfile::certs(cert_file).and_then(|c| if c.is_empty() { Err(()) } else { Ok(c) })
+                                 .map_err(|_| {
+                                     sup_error!(Error::InvalidCertFile(config.cert_path.clone()))
+                                 })?;
+
+    let key = private_key(&config.key_path)?;
+    let signing_key =
+        sign::any_supported_type(&key).map_err(|_| {
+                                          sup_error!(Error::InvalidKeyFile(config.key_path
+                                                                                 .clone()))
+                                      })?;
+    Ok(CertifiedKey::new(cert_chain, Arc::new(signing_key)))
+}
+
+/// Read the first RSA or PKCS8 private key from a PEM file.
+fn private_key(path: &Path) -> Result<PrivateKey> {
+    let rsa_keys = pemfile::rsa_private_keys(&mut BufReader::new(File::open(path)?));
+    let pkcs8_keys = pemfile::pkcs8_private_keys(&mut BufReader::new(File::open(path)?));
+    rsa_keys.into_iter()
+            .chain(pkcs8_keys)
+            .flatten()
+            .next()
+            .ok_or_else(|| sup_error!(Error::InvalidKeyFile(path.to_path_buf())))
+}
+
+fn client_verifier(path: &Path) -> Result<Arc<dyn ClientCertVerifier>> {
+    let mut root_store = RootCertStore::empty();
+    let ca_file = &mut BufReader::new(File::open(path)?);
+    root_store.add_pem_file(ca_file)
+              .and_then(|(added, _)| {
+                  if added < 1 {
+                      Err(())
+                  } else {
+                      Ok(AllowAnyAuthenticatedClient::new(root_store))
+                  }
+              })
+              .map_err(|_| sup_error!(Error::InvalidCertFile(path.to_path_buf())))
+}
@@ -50,6 +50,7 @@ extern crate serde_derive;
 #[macro_use]
 extern crate serde_json;
 extern crate time as time_crate;
+extern crate webpki;
 
 #[cfg(test)]
 extern crate json;
@@ -42,6 +42,11 @@ use habitat_common::{cli::{cache_key_path_from_matches,
 use habitat_core::crypto::dpapi::encrypt;
 use habitat_core::{crypto::{self,
                             SymKey},
+                   package::{PackageTarget,
+                             VersionConstraint},
+                   service::{CpuQuota,
+                             LogFileConfig,
+                             MemorySize},
                    url::{bldr_url_from_env,
                          default_bldr_url},
                    ChannelIdent};
@@ -50,9 +55,16 @@ use habitat_launcher_client::{LauncherCli,
 use habitat_sup_protocol::{ctl::ServiceBindList,
                            types::{ApplicationEnvironment,
                                    BindingMode,
+                                   LogFile,
+                                   ResourceLimits,
+                                   RestartBackoff,
+                                   RestartPolicy,
                                    ServiceBind,
                                    Topology,
-                                   UpdateStrategy}};
+                                   UpdateRollback,
+                                   UpdateRollout,
+                                   UpdateStrategy,
+                                   UpdateWindows}};
 use std::{env,
           io::{self,
                Write},
@@ -163,7 +175,15 @@ fn sub_run(m: &ArgMatches, launcher: LauncherCli, feature_flags: FeatureFlag) ->
         // don't care if a service was loaded previously or not and with what options. You
         // want one loaded right now and in this way.
         msg.force = Some(true);
-        let ident = match pkg.parse::<InstallSource>()? {
+        let source = if pkg.contains('@') && !Path::new(pkg).is_file() {
+            // An identifier followed by a version constraint, e.g. `core/postgresql@~9.6`
+            let (ident, constraint) = VersionConstraint::parse_ident(pkg)?;
+            msg.version_constraint = constraint.as_ref().map(ToString::to_string);
+            InstallSource::from((ident, PackageTarget::active_target()))
+        } else {
+            pkg.parse::<InstallSource>()?
+        };
+        let ident = match source {
             source @ InstallSource::Archive(_) => {
                 // Install the archive manually then explicitly set the pkg ident to the
                 // version found in the archive. This will lock the software to this
@@ -286,8 +306,23 @@ fn mgrcfg_from_sup_run_matches(m: &ArgMatches,
                 ca_cert_path,
             }
         }),
+        ctl_tls_config: m.value_of("CTL_KEY_FILE").map(|kf| {
+            let cert_path = m
+                .value_of("CTL_CERT_FILE")
+                .map(PathBuf::from)
+                .expect("CTL_CERT_FILE should always have a value if CTL_KEY_FILE has a value.");
+            let ca_cert_path = m.value_of("CTL_CA_CERT_FILE").map(PathBuf::from);
+            TLSConfig {
+                key_path: PathBuf::from(kf),
+                cert_path,
+                ca_cert_path,
+            }
+        }),
         feature_flags,
         event_stream_config,
+        service_log_file: get_service_log_file_from_input(m),
+        secrets_url: m.value_of("SECRETS_URL").map(str::to_string),
+        secrets_url_allow_http: m.is_present("SECRETS_URL_ALLOW_HTTP"),
     };
 
     Ok(cfg)
@@ -417,6 +452,137 @@ fn get_binding_mode_from_input(m: &ArgMatches) -> Option<BindingMode> {
      .and_then(|b| BindingMode::from_str(b).ok())
 }
 
+fn get_restart_policy_from_input(m: &ArgMatches) -> Option<RestartPolicy> {
+    // There won't be errors, because we validate with `valid_restart_policy`
+    m.value_of("RESTART_POLICY")
+     .and_then(|p| RestartPolicy::from_str(p).ok())
+}
+
+fn get_restart_backoff_from_input(m: &ArgMatches) -> Option<RestartBackoff> {
+    // Values will have already been validated by `valid_numeric`
+    let backoff = RestartBackoff { initial_delay: m.value_of("RESTART_DELAY")
+                                                   .and_then(|s| s.parse().ok()),
+                                   max_delay:     m.value_of("RESTART_MAX_DELAY")
+                                                   .and_then(|s| s.parse().ok()),
+                                   limit:         m.value_of("RESTART_LIMIT")
+                                                   .and_then(|s| s.parse().ok()),
+                                   window:        m.value_of("RESTART_WINDOW")
+                                                   .and_then(|s| s.parse().ok()), };
+    if backoff == RestartBackoff::default() {
+        None
+    } else {
+        Some(backoff)
+    }
+}
+
+fn get_update_windows_from_input(m: &ArgMatches) -> Option<UpdateWindows> {
+    if m.is_present("NO_UPDATE_WINDOWS") {
+        return Some(UpdateWindows::default());
+    }
+    // Values will have already been validated by their `cli::valid_*` functions
+    m.values_of("UPDATE_WINDOW")
+     .map(|values| UpdateWindows { windows: values.filter_map(|v| v.parse().ok()).collect(), })
+}
+
+fn get_update_rollout_from_input(m: &ArgMatches) -> Option<UpdateRollout> {
+    // Values will have already been validated by their `cli::valid_*` functions
+    let mut rollout = UpdateRollout::default();
+    if let Some(batch_size) = m.value_of("UPDATE_BATCH_SIZE") {
+        rollout.set_batch_size(batch_size).ok();
+    }
+    rollout.health_timeout = m.value_of("UPDATE_HEALTH_TIMEOUT")
+                              .and_then(|s| s.parse().ok());
+    if rollout == UpdateRollout::default() {
+        None
+    } else {
+        Some(rollout)
+    }
+}
+
+fn get_update_rollback_from_input(m: &ArgMatches) -> Option<UpdateRollback> {
+    if m.is_present("NO_UPDATE_ROLLBACK") {
+        return Some(UpdateRollback { enabled: Some(false),
+                                     ..Default::default() });
+    }
+    // Values will have already been validated by their `cli::valid_*` functions
+    let rollback = UpdateRollback { enabled:      None,
+                                    grace_period: m.value_of("UPDATE_ROLLBACK_GRACE")
+                                                   .and_then(|s| s.parse().ok()), };
+    if m.is_present("UPDATE_ROLLBACK") || rollback != UpdateRollback::default() {
+        Some(UpdateRollback { enabled: Some(true),
+                              ..rollback })
+    } else {
+        None
+    }
+}
+
+fn get_resource_limits_from_input(m: &ArgMatches) -> Option<ResourceLimits> {
+    // Values will have already been validated by their `cli::valid_*` functions
+    let limits = ResourceLimits { cpu_weight: m.value_of("CPU_WEIGHT")
+                                               .and_then(|s| s.parse().ok()),
+                                  cpu_quota:  m.value_of("CPU_QUOTA")
+                                               .and_then(|s| CpuQuota::from_str(s).ok())
+                                               .map(CpuQuota::into),
+                                  memory_max: m.value_of("MEMORY_MAX")
+                                               .and_then(|s| MemorySize::from_str(s).ok())
+                                               .map(MemorySize::into),
+                                  pids_max:   m.value_of("PIDS_MAX")
+                                               .and_then(|s| s.parse().ok()), };
+    if limits == ResourceLimits::default() {
+        None
+    } else {
+        Some(limits)
+    }
+}
+
+fn get_log_file_from_input(m: &ArgMatches) -> Option<LogFile> {
+    if m.is_present("NO_LOG_FILE") {
+        return Some(LogFile { enabled: Some(false),
+                              ..Default::default() });
+    }
+    // Tuning flags alone leave the Supervisor's default for writing the file
+    let enabled = if m.is_present("LOG_FILE") { Some(true) } else { None };
+    // Values will have already been validated by their `cli::valid_*` functions
+    let log_file = LogFile { enabled,
+                             max_size: m.value_of("LOG_MAX_SIZE")
+                                        .and_then(|s| MemorySize::from_str(s).ok())
+                                        .map(MemorySize::into),
+                             max_age:  m.value_of("LOG_MAX_AGE")
+                                        .and_then(|s| s.parse().ok()),
+                             retain:   m.value_of("LOG_RETAIN")
+                                        .and_then(|s| s.parse().ok()), };
+    if log_file == LogFile::default() {
+        None
+    } else {
+        Some(log_file)
+    }
+}
+
+fn get_service_log_file_from_input(m: &ArgMatches) -> Option<LogFileConfig> {
+    // The tuning flags only apply once log files are turned on
+    if !m.is_present("SERVICE_LOG_FILES") {
+        return None;
+    }
+    // Values will have already been validated by their `valid_*` functions
+    let mut config = LogFileConfig::default();
+    if let Some(size) = m.value_of("SERVICE_LOG_MAX_SIZE")
+                         .and_then(|s| MemorySize::from_str(s).ok())
+    {
+        config.max_size = size.into();
+    }
+    if let Some(age) = m.value_of("SERVICE_LOG_MAX_AGE")
+                        .and_then(|s| s.parse().ok())
+    {
+        config.max_age = age;
+    }
+    if let Some(retain) = m.value_of("SERVICE_LOG_RETAIN")
+                           .and_then(|s| s.parse().ok())
+    {
+        config.retain = retain;
+    }
+    Some(config)
+}
+
 fn get_config_from_input(m: &ArgMatches) -> Option<String> {
     if let Some(ref config_from) = m.value_of("CONFIG_DIR") {
         warn!("");
@@ -488,6 +654,14 @@ fn update_svc_load_from_input(m: &ArgMatches,
     msg.binding_mode = get_binding_mode_from_input(m).map(|v| v as i32);
     msg.topology = get_topology_from_input(m).map(|v| v as i32);
     msg.update_strategy = get_strategy_from_input(m).map(|v| v as i32);
+    msg.update_windows = get_update_windows_from_input(m);
+    msg.update_rollout = get_update_rollout_from_input(m);
+    msg.update_rollback = get_update_rollback_from_input(m);
+    msg.restart_policy = get_restart_policy_from_input(m).map(|v| v as i32);
+    msg.restart_backoff = get_restart_backoff_from_input(m);
+    msg.resource_limits = get_resource_limits_from_input(m);
+    msg.log_file = get_log_file_from_input(m);
+    msg.strict_templates = Some(m.is_present("STRICT_TEMPLATES"));
     Ok(())
 }
 
@@ -2,10 +2,14 @@
 //! part of the Supervisor to another.
 
 use super::service::ServiceSpec;
+use futures::sync::oneshot;
 #[cfg(unix)]
 use habitat_core::os::process::ShutdownSignal;
-use habitat_core::os::process::ShutdownTimeout;
-use std::sync::mpsc;
+use habitat_core::{os::process::ShutdownTimeout,
+                   service::ServiceGroup};
+use habitat_sup_protocol::net::NetResult;
+use std::{path::PathBuf,
+          sync::mpsc};
 
 /// Defines the parameters by which a service process is to be shut
 /// down cleanly.
@@ -33,7 +37,7 @@ pub struct ShutdownSpec {
 /// Supervisor itself can understand and operate on.
 // TODO (CM): More actions will be added to this with future
 // refactorings
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub enum SupervisorAction {
     StopService {
         service_spec:  ServiceSpec,
@@ -43,6 +47,14 @@ pub enum SupervisorAction {
         service_spec:  ServiceSpec,
         shutdown_spec: ShutdownSpec,
     },
+    /// Render a service group's configuration files and hooks as they would be with the given
+    /// configuration applied, and send back a diff for each that would change. The census is
+    /// needed for rendering, which only the main loop has.
+    DryRunConfig {
+        service_group: ServiceGroup,
+        cfg:           toml::value::Table,
+        reply:         oneshot::Sender<NetResult<Vec<(PathBuf, String)>>>,
+    },
 }
 
 pub type ActionSender = mpsc::Sender<SupervisorAction>;
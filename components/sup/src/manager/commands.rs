@@ -3,13 +3,23 @@
 use crate::{ctl_gateway::CtlRequest,
             error::Error,
             manager::{action::{ActionSender,
+                               ShutdownSpec,
                                SupervisorAction},
                       service::{spec::{IntoServiceSpec,
                                        ServiceSpec},
                                 DesiredState,
-                                ProcessState},
+                                HealthCheckRecord,
+                                HealthCheckResult,
+                                ProcessState,
+                                RestartPolicy},
+                      stack::{SpecChange,
+                              Stack},
+                      GatewayState,
                       ManagerState},
             util};
+use futures::{sync::oneshot,
+              Future,
+              Stream};
 use habitat_butterfly as butterfly;
 use habitat_common::{command::package::install::InstallSource,
                      outputln,
@@ -26,14 +36,28 @@ use habitat_sup_protocol::{self as protocol,
                                  NetResult}};
 use serde_json;
 use std::{fmt,
-          result};
+          result,
+          sync::RwLock,
+          time::{Duration,
+                 Instant}};
 use time::{self,
            Duration as TimeDuration,
            Timespec};
+use tokio::executor::{DefaultExecutor,
+                      Executor};
+use tokio_timer::{timeout,
+                  Interval,
+                  Timeout};
 use toml;
 
 static LOGKEY: &'static str = "CMD";
 
+/// How often a status watch looks for changes to the services it's watching.
+const STATUS_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a dry run waits for the Supervisor's main loop to render a configuration change.
+const DRY_RUN_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub fn service_cfg(mgr: &ManagerState,
                    req: &mut CtlRequest,
                    opts: protocol::ctl::SvcGetDefaultCfg)
@@ -60,14 +84,75 @@ pub fn service_cfg(mgr: &ManagerState,
     Err(net::err(ErrCode::NotFound, format!("Service not loaded, {}", ident)))
 }
 
-pub fn service_cfg_validate(_mgr: &ManagerState,
+pub fn service_cfg_validate(mgr: &ManagerState,
                             req: &mut CtlRequest,
                             opts: protocol::ctl::SvcValidateCfg)
                             -> NetResult<()> {
-    let cfg = opts.cfg.ok_or_else(err_update_client)?;
-    let format = opts.format
-                     .and_then(protocol::types::service_cfg::Format::from_i32)
-                     .unwrap_or_default();
+    let new_cfg = decode_cfg(opts.cfg, opts.format)?;
+    // Services which aren't loaded on this Supervisor, but are known through rumor propagation,
+    // can't be validated, since we don't have their package's schema.
+    if let Some(service_group) = opts.service_group {
+        validate_cfg_against_schema(mgr, req, &service_group.into(), &new_cfg)?;
+    }
+    req.reply_complete(net::ok());
+    Ok(())
+}
+
+pub fn service_cfg_dry_run(req: &mut CtlRequest,
+                           opts: protocol::ctl::SvcDryRunCfg,
+                           action_sender: &ActionSender)
+                           -> NetResult<()> {
+    let service_group: ServiceGroup = opts.service_group.ok_or_else(err_update_client)?.into();
+    let cfg = decode_cfg(opts.cfg, opts.format)?;
+    let (reply, changes) = oneshot::channel();
+    send_action(SupervisorAction::DryRunConfig { service_group: service_group.clone(),
+                                                 cfg,
+                                                 reply },
+                action_sender)?;
+
+    // The main loop renders the changes, so wait for them off the ctl gateway's thread.
+    let mut req = req.clone();
+    let dry_run = Timeout::new(changes, DRY_RUN_TIMEOUT).then(move |changes| {
+        match changes.map_err(err_dry_run).and_then(|changes| changes) {
+            Ok(changes) => {
+                for (path, diff) in changes {
+                    let msg = protocol::types::ServiceCfgFileDiff { service_group:
+                                                                         service_group.clone()
+                                                                                      .into(),
+                                                                     path:
+                                                                         path.display()
+                                                                             .to_string(),
+                                                                     diff };
+                    req.reply_partial(msg);
+                }
+                req.reply_complete(net::ok());
+            }
+            Err(err) => req.reply_complete(err),
+        }
+        Ok(())
+    });
+    DefaultExecutor::current().spawn(Box::new(dry_run))
+                              .map_err(|err| {
+                                  net::err(ErrCode::Internal,
+                                           format!("Unable to start dry run, {}", err))
+                              })
+}
+
+/// The error for a dry run whose changes never arrived.
+fn err_dry_run(err: timeout::Error<oneshot::Canceled>) -> net::NetErr {
+    if err.is_elapsed() {
+        net::err(ErrCode::Internal, "Timed out rendering the configuration")
+    } else {
+        net::err(ErrCode::Internal,
+                 "The Supervisor stopped before rendering the configuration")
+    }
+}
+
+/// Decodes a configuration sent to be applied to a service group.
+fn decode_cfg(cfg: Option<Vec<u8>>, format: Option<i32>) -> NetResult<toml::value::Table> {
+    let cfg = cfg.ok_or_else(err_update_client)?;
+    let format = format.and_then(protocol::types::service_cfg::Format::from_i32)
+                       .unwrap_or_default();
     if cfg.len() > protocol::butterfly::MAX_SVC_CFG_SIZE {
         return Err(net::err(ErrCode::EntityTooLarge, "Configuration too large."));
     }
@@ -76,44 +161,38 @@ pub fn service_cfg_validate(_mgr: &ManagerState,
                             format!("Configuration format {} not available.",
                                     format)));
     }
-    let _new_cfg: toml::value::Table = toml::from_slice(&cfg).map_err(|e| {
-                                                                 net::err(
-            ErrCode::BadPayload,
-            format!("Unable to decode configuration as {}, {}", format, e),
-        )
-                                                             })?;
-    req.reply_complete(net::ok());
+    toml::from_slice(&cfg).map_err(|e| {
+                              net::err(ErrCode::BadPayload,
+                                       format!("Unable to decode configuration as {}, {}",
+                                               format, e))
+                          })
+}
+
+/// Validates a configuration that would be applied to a service group against the schema of
+/// the package each of its services on this Supervisor runs.
+///
+/// A configuration that doesn't match a schema is rejected, while a schema that can't be used
+/// only produces a warning.
+fn validate_cfg_against_schema(mgr: &ManagerState,
+                               req: &mut CtlRequest,
+                               service_group: &ServiceGroup,
+                               cfg: &toml::value::Table)
+                               -> NetResult<()> {
+    for service in mgr.services
+                      .read()
+                      .expect("Services lock is poisoned")
+                      .values()
+                      .filter(|service| service.service_group == *service_group)
+    {
+        match service.cfg.validate_gossip(cfg) {
+            Ok(()) => {}
+            Err(e @ habitat_common::Error::ConfigInvalid(_)) => {
+                return Err(net::err(ErrCode::BadPayload, e.to_string()));
+            }
+            Err(e) => req.reply_partial(net::err(ErrCode::InvalidPayload, e.to_string())),
+        }
+    }
     Ok(())
-    // JW TODO: Hold off on validation until we can validate services which aren't currently
-    // loaded in the Supervisor but are known through rumor propagation.
-    // let service_group: ServiceGroup = opts.service_group.into();
-    // for service in mgr.services.read().unwrap().iter() {
-    //     if service.service_group != service_group {
-    //         continue;
-    //     }
-    //     if let Some(interface) = service.cfg.interface() {
-    //         match Cfg::validate(interface, &new_cfg) {
-    //             None => req.reply_complete(net::ok()),
-    //             Some(errors) => {
-    //                 for error in errors {
-    //                     req.reply_partial(net::err(ErrCode::InvalidPayload, error));
-    //                 }
-    //                 req.reply_complete(net::ok());
-    //             }
-    //         }
-    //         return Ok(());
-    //     } else {
-    //         // No interface, this service can't be configured.
-    //         return Err(net::err(
-    //             ErrCode::NotFound,
-    //             "Service has no configurable attributes.",
-    //         ));
-    //     }
-    // }
-    // Err(net::err(
-    //     ErrCode::NotFound,
-    //     format!("Service not loaded, {}", service_group),
-    // ))
 }
 
 pub fn service_cfg_set(mgr: &ManagerState,
@@ -127,6 +206,12 @@ pub fn service_cfg_set(mgr: &ManagerState,
     if cfg.len() > protocol::butterfly::MAX_SVC_CFG_SIZE {
         return Err(net::err(ErrCode::EntityTooLarge, "Configuration too large."));
     }
+    // An encrypted configuration was already validated by the client before it encrypted it.
+    if !is_encrypted {
+        if let Ok(new_cfg) = toml::from_slice::<toml::value::Table>(&cfg) {
+            validate_cfg_against_schema(mgr, req, &service_group, &new_cfg)?;
+        }
+    }
     outputln!("Setting new configuration version {} for {}",
               version,
               service_group,);
@@ -147,6 +232,45 @@ pub fn service_cfg_set(mgr: &ManagerState,
           })
 }
 
+pub fn service_cfg_history(mgr: &ManagerState,
+                           req: &mut CtlRequest,
+                           opts: protocol::ctl::SvcCfgHistory)
+                           -> NetResult<()> {
+    let service_group: ServiceGroup = opts.service_group.ok_or_else(err_update_client)?.into();
+    let versions = mgr.services
+                      .read()
+                      .expect("Services lock is poisoned")
+                      .values()
+                      .find(|service| service.service_group == service_group)
+                      .ok_or_else(|| {
+                          net::err(ErrCode::NotFound,
+                                   format!("Service group not loaded, {}", service_group))
+                      })?
+                      .config_history()
+                      .map_err(|e| net::err(ErrCode::Internal, e.to_string()))?;
+    if versions.is_empty() {
+        return Err(net::err(ErrCode::NotFound,
+                            format!("No configuration has been applied to {} yet",
+                                    service_group)));
+    }
+
+    let mut versions = versions.into_iter().peekable();
+    while let Some(version) = versions.next() {
+        let msg = protocol::types::ServiceCfgVersion { service_group: service_group.clone()
+                                                                                   .into(),
+                                                       incarnation:   version.incarnation,
+                                                       applied_at:    version.applied_at,
+                                                       cfg:           version.cfg,
+                                                       encrypted:     Some(version.encrypted), };
+        if versions.peek().is_some() {
+            req.reply_partial(msg);
+        } else {
+            req.reply_complete(msg);
+        }
+    }
+    Ok(())
+}
+
 pub fn service_file_put(mgr: &ManagerState,
                         req: &mut CtlRequest,
                         opts: protocol::ctl::SvcFilePut)
@@ -197,7 +321,8 @@ pub fn service_load(mgr: &ManagerState,
     match mgr.cfg.spec_for_ident(source.as_ref()) {
         None => {
             let mut spec = ServiceSpec::default();
-            opts.into_spec(&mut spec);
+            opts.into_spec(&mut spec)
+                .map_err(|e| net::err(ErrCode::InvalidPayload, e.to_string()))?;
 
             // We don't have any record of this thing; let's set it up!
             //
@@ -205,7 +330,11 @@ pub fn service_load(mgr: &ManagerState,
             // desired package identifier, it will be used;
             // otherwise, we'll install the latest suitable
             // version from the specified Builder channel.
-            util::pkg::satisfy_or_install(req, &source, &bldr_url, &bldr_channel)?;
+            util::pkg::satisfy_or_install(req,
+                                          &source,
+                                          spec.version_constraint.as_ref(),
+                                          &bldr_url,
+                                          &bldr_channel)?;
 
             mgr.cfg.save_spec_for(&spec)?;
             req.info(format!("The {} service was successfully loaded", spec.ident))?;
@@ -225,7 +354,8 @@ pub fn service_load(mgr: &ManagerState,
                                             ident)));
             }
 
-            opts.into_spec(&mut spec);
+            opts.into_spec(&mut spec)
+                .map_err(|e| net::err(ErrCode::InvalidPayload, e.to_string()))?;
 
             // Only install if we don't have something
             // locally; otherwise you could potentially
@@ -233,7 +363,11 @@ pub fn service_load(mgr: &ManagerState,
             //
             // Also make sure you're pulling from where you're
             // supposed to be pulling from!
-            util::pkg::satisfy_or_install(req, &source, &spec.bldr_url, &spec.channel)?;
+            util::pkg::satisfy_or_install(req,
+                                          &source,
+                                          spec.version_constraint.as_ref(),
+                                          &spec.bldr_url,
+                                          &spec.channel)?;
 
             mgr.cfg.save_spec_for(&spec)?;
             req.info(format!("The {} service was successfully loaded", spec.ident))?;
@@ -243,6 +377,99 @@ pub fn service_load(mgr: &ManagerState,
     Ok(())
 }
 
+/// Reconciles the Supervisor's services with a stack: replies with the change planned for each
+/// service, then loads, updates and unloads services to match, unless it's a dry run. Every
+/// package is installed before any spec is saved, and applying stops at the first change that
+/// fails, with an error naming the changes made before it.
+pub fn supervisor_apply(mgr: &ManagerState,
+                        req: &mut CtlRequest,
+                        opts: protocol::ctl::SupApply,
+                        action_sender: &ActionSender)
+                        -> NetResult<()> {
+    let stack: Stack = opts.stack
+                           .ok_or_else(err_update_client)?
+                           .parse()
+                           .map_err(|e| net::err(ErrCode::BadPayload, e))?;
+    let changes = stack.plan(mgr.cfg.specs(), opts.prune.unwrap_or(false));
+    for change in &changes {
+        req.reply_partial(protocol::types::ServiceSpecChange::from(change));
+    }
+    if opts.dry_run.unwrap_or(false) {
+        req.reply_complete(net::ok());
+        return Ok(());
+    }
+
+    // Install every package before saving any spec, so a failed install leaves the Supervisor's
+    // services as they were.
+    for change in &changes {
+        match *change {
+            SpecChange::Load(ref spec) | SpecChange::Update { desired: ref spec, .. } => {
+                let source =
+                    InstallSource::Ident(spec.ident.clone(), PackageTarget::active_target());
+                if let Err(err) = util::pkg::satisfy_or_install(req,
+                                                                &source,
+                                                                spec.version_constraint.as_ref(),
+                                                                &spec.bldr_url,
+                                                                &spec.channel)
+                {
+                    return Err(err_apply(err.into(), &[]));
+                }
+            }
+            SpecChange::Unload(_) | SpecChange::Unchanged(_) => {}
+        }
+    }
+
+    let mut applied = Vec::new();
+    for change in changes {
+        if let Some((ident, action)) =
+            apply_change(mgr, change, action_sender).map_err(|err| err_apply(err, &applied))?
+        {
+            req.info(format!("The {} service was successfully {}", ident, action))?;
+            applied.push(format!("{} ({})", ident, action));
+        }
+    }
+    req.reply_complete(net::ok());
+    Ok(())
+}
+
+/// Saves or unloads the spec for one change of a stack, returning the service's identifier and
+/// what was done to it, or `None` if the service was unchanged.
+fn apply_change(mgr: &ManagerState,
+                change: SpecChange,
+                action_sender: &ActionSender)
+                -> NetResult<Option<(PackageIdent, &'static str)>> {
+    match change {
+        SpecChange::Load(spec) => {
+            mgr.cfg.save_spec_for(&spec)?;
+            Ok(Some((spec.ident, "loaded")))
+        }
+        SpecChange::Update { desired: spec, .. } => {
+            mgr.cfg.save_spec_for(&spec)?;
+            Ok(Some((spec.ident, "updated")))
+        }
+        SpecChange::Unload(service_spec) => {
+            let ident = service_spec.ident.clone();
+            send_action(SupervisorAction::UnloadService { service_spec,
+                                                          shutdown_spec: ShutdownSpec::default() },
+                        action_sender)?;
+            Ok(Some((ident, "unloaded")))
+        }
+        SpecChange::Unchanged(_) => Ok(None),
+    }
+}
+
+/// The error for a stack that failed to apply, naming the changes made before the failure.
+fn err_apply(err: net::NetErr, applied: &[String]) -> net::NetErr {
+    let applied = if applied.is_empty() {
+        "none".to_string()
+    } else {
+        applied.join(", ")
+    };
+    net::err(ErrCode::from_i32(err.code).unwrap_or_default(),
+             format!("{}. Changes applied before the failure: {}",
+                     err.msg, applied))
+}
+
 pub fn service_unload(mgr: &ManagerState,
                       req: &mut CtlRequest,
                       opts: protocol::ctl::SvcUnload,
@@ -351,32 +578,25 @@ pub fn service_status(mgr: &ManagerState,
                       req: &mut CtlRequest,
                       opts: protocol::ctl::SvcStatus)
                       -> NetResult<()> {
-    let services_data = &mgr.gateway_state
-                            .read()
-                            .expect("GatewayState lock is poisoned")
-                            .services_data;
-    let statuses: Vec<ServiceStatus> = serde_json::from_str(&services_data).map_err(|e| {
-                                           sup_error!(Error::ServiceDeserializationError(e))
-                                       })?;
-
     if let Some(ident) = opts.ident {
-        for status in statuses {
-            if status.pkg.ident.satisfies(&ident) {
-                let msg: protocol::types::ServiceStatus = status.into();
+        return match service_statuses(&mgr.gateway_state, Some(&ident))?.into_iter()
+                                                                         .next()
+        {
+            Some(msg) => {
                 req.reply_complete(msg);
-                return Ok(());
+                Ok(())
             }
-        }
-        return Err(net::err(ErrCode::NotFound, format!("Service not loaded, {}", ident)));
+            None => Err(net::err(ErrCode::NotFound, format!("Service not loaded, {}", ident))),
+        };
     }
 
     // We're not dealing with a single service, but with all of them.
+    let statuses = service_statuses(&mgr.gateway_state, None)?;
     if statuses.is_empty() {
         req.reply_complete(net::ok());
     } else {
         let mut list = statuses.into_iter().peekable();
-        while let Some(status) = list.next() {
-            let msg: protocol::types::ServiceStatus = status.into();
+        while let Some(msg) = list.next() {
             if list.peek().is_some() {
                 req.reply_partial(msg);
             } else {
@@ -387,16 +607,206 @@ pub fn service_status(mgr: &ManagerState,
     Ok(())
 }
 
+/// Reply with the status of the requested services, then keep replying whenever one of them
+/// changes until the client disconnects. Changes are picked up by a task on the Supervisor's
+/// runtime, so the watch doesn't hold up the CtlGateway.
+pub fn service_status_watch(mgr: &ManagerState,
+                            req: &mut CtlRequest,
+                            opts: protocol::ctl::SvcStatusWatch)
+                            -> NetResult<()> {
+    let mut last = service_statuses(&mgr.gateway_state, opts.ident.as_ref())?;
+    for status in &last {
+        req.reply_partial(status.clone());
+    }
+
+    let mut req = req.clone();
+    let gateway_state = mgr.gateway_state.clone();
+    let ident = opts.ident;
+    let watch = Interval::new(Instant::now() + STATUS_WATCH_INTERVAL, STATUS_WATCH_INTERVAL)
+        .map_err(|err| warn!("Ending status watch, {}", err))
+        .for_each(move |_| {
+            // Ending the stream with an error is the only way to stop it early
+            if req.is_closed() {
+                return Err(());
+            }
+            let current = service_statuses(&gateway_state, ident.as_ref())
+                .map_err(|err| warn!("Ending status watch, {}", err))?;
+            for change in status_changes(&last, &current) {
+                match change {
+                    StatusChange::Changed(msg) => req.reply_partial(msg),
+                    StatusChange::Removed(msg) => req.reply_partial(msg),
+                }
+            }
+            last = current;
+            Ok(())
+        })
+        .then(|_| {
+            debug!("Status watch finished");
+            Ok(())
+        });
+    DefaultExecutor::current().spawn(Box::new(watch))
+                              .map_err(|err| {
+                                  net::err(ErrCode::Internal,
+                                           format!("Unable to start status watch, {}", err))
+                              })
+}
+
+pub fn service_health(mgr: &ManagerState,
+                      req: &mut CtlRequest,
+                      opts: protocol::ctl::SvcHealth)
+                      -> NetResult<()> {
+    let ident: PackageIdent = opts.ident.ok_or_else(err_update_client)?.into();
+    let service_group = mgr.services
+                           .read()
+                           .expect("Services lock is poisoned")
+                           .values()
+                           .find(|service| service.pkg.ident.satisfies(&ident))
+                           .map(|service| service.service_group.clone())
+                           .ok_or_else(|| {
+                               net::err(ErrCode::NotFound, format!("Service not loaded, {}", ident))
+                           })?;
+    let gateway_state = mgr.gateway_state
+                           .read()
+                           .expect("GatewayState lock is poisoned");
+    let history = gateway_state.health_check_history
+                               .get(&service_group)
+                               .filter(|history| history.latest().is_some())
+                               .ok_or_else(|| {
+                                   net::err(ErrCode::NotFound,
+                                            format!("No health checks have run yet for {}",
+                                                    service_group))
+                               })?;
+
+    if opts.history.unwrap_or(false) {
+        let mut records = history.records().peekable();
+        while let Some(record) = records.next() {
+            let msg = health_check_record_msg(&service_group, record);
+            if records.peek().is_some() {
+                req.reply_partial(msg);
+            } else {
+                req.reply_complete(msg);
+            }
+        }
+    } else if let Some(record) = history.latest() {
+        req.reply_complete(health_check_record_msg(&service_group, record));
+    }
+    Ok(())
+}
+
 ////////////////////////////////////////////////////////////////////////
 // Private helper functions
 fn err_update_client() -> net::NetErr { net::err(ErrCode::UpdateClient, "client out of date") }
 
+fn health_check_status(result: HealthCheckResult) -> protocol::types::HealthCheckStatus {
+    match result {
+        HealthCheckResult::Ok => protocol::types::HealthCheckStatus::Ok,
+        HealthCheckResult::Warning => protocol::types::HealthCheckStatus::Warning,
+        HealthCheckResult::Critical => protocol::types::HealthCheckStatus::Critical,
+        HealthCheckResult::Unknown => protocol::types::HealthCheckStatus::Unknown,
+    }
+}
+
+fn health_check_record_msg(service_group: &ServiceGroup,
+                           record: &HealthCheckRecord)
+                           -> protocol::types::HealthCheckRecord {
+    protocol::types::HealthCheckRecord { service_group: service_group.clone().into(),
+                                         timestamp:     record.timestamp,
+                                         status:        health_check_status(record.status) as i32,
+                                         exit_code:     record.exit_code,
+                                         duration_ms:   record.duration_ms,
+                                         stdout:        Some(record.stdout.clone()),
+                                         stderr:        Some(record.stderr.clone()), }
+}
+
+/// The status of the services matching `ident`, or of every service, along with the result of
+/// their latest health check, whether they're being updated or have an update held for a
+/// maintenance window, and why the latest render of their templates was refused, if it was.
+fn service_statuses(gateway_state: &RwLock<GatewayState>,
+                    ident: Option<&protocol::types::PackageIdent>)
+                    -> NetResult<Vec<protocol::types::ServiceStatus>> {
+    let gateway_state = gateway_state.read()
+                                     .expect("GatewayState lock is poisoned");
+    let services_data = &gateway_state.services_data;
+    let statuses: Vec<ServiceStatus> = serde_json::from_str(&services_data).map_err(|e| {
+                                           sup_error!(Error::ServiceDeserializationError(e))
+                                       })?;
+    Ok(statuses.into_iter()
+               .filter(|status| ident.map_or(true, |ident| status.pkg.ident.satisfies(ident)))
+               .map(|status| {
+                   let health = gateway_state.health_check_data
+                                             .get(&status.service_group)
+                                             .map(|result| health_check_status(*result) as i32);
+                   let update_in_progress =
+                       gateway_state.updating_services
+                                    .contains(&status.service_group);
+                   let update_pending_until = gateway_state.held_updates
+                                                           .get(&status.service_group)
+                                                           .cloned();
+                   let config_incarnation = gateway_state.config_incarnations
+                                                         .get(&status.service_group)
+                                                         .cloned();
+                   let mut msg: protocol::types::ServiceStatus = status.into();
+                   msg.health = health;
+                   msg.update_in_progress = Some(update_in_progress);
+                   msg.update_pending_until = update_pending_until;
+                   msg.config_incarnation = config_incarnation;
+                   msg
+               })
+               .collect())
+}
+
+enum StatusChange {
+    Changed(protocol::types::ServiceStatus),
+    Removed(protocol::types::ServiceStatusRemoved),
+}
+
+/// What a status watch has to tell its client about, going from the `previous` statuses to the
+/// `current` ones. The time spent in the current process state is ignored, as it changes every
+/// second.
+fn status_changes(previous: &[protocol::types::ServiceStatus],
+                  current: &[protocol::types::ServiceStatus])
+                  -> Vec<StatusChange> {
+    fn without_elapsed(status: &protocol::types::ServiceStatus) -> protocol::types::ServiceStatus {
+        let mut status = status.clone();
+        if let Some(ref mut process) = status.process {
+            process.elapsed = None;
+        }
+        status
+    }
+
+    let mut changes: Vec<StatusChange> =
+        current.iter()
+               .filter(|status| {
+                   previous.iter()
+                           .find(|p| p.service_group == status.service_group)
+                           .map_or(true, |p| without_elapsed(p) != without_elapsed(status))
+               })
+               .cloned()
+               .map(StatusChange::Changed)
+               .collect();
+    changes.extend(previous.iter()
+                           .filter(|p| {
+                               !current.iter()
+                                       .any(|status| status.service_group == p.service_group)
+                           })
+                           .map(|p| {
+                               StatusChange::Removed(protocol::types::ServiceStatusRemoved {
+                                   ident: p.ident.clone(),
+                                   service_group: p.service_group.clone(),
+                               })
+                           }));
+    changes
+}
+
 #[derive(Deserialize)]
 struct ServiceStatus {
     pkg:           Pkg,
     process:       ProcessStatus,
     service_group: ServiceGroup,
     desired_state: DesiredState,
+    // Absent from the data of Supervisors predating strict rendering
+    #[serde(default)]
+    render_error: Option<String>,
 }
 
 impl fmt::Display for ServiceStatus {
@@ -411,9 +821,11 @@ impl From<ServiceStatus> for protocol::types::ServiceStatus {
     fn from(other: ServiceStatus) -> Self {
         let mut proto = protocol::types::ServiceStatus::default();
         proto.ident = other.pkg.ident.into();
+        proto.restart = other.process.restart.as_ref().map(Into::into);
         proto.process = Some(other.process.into());
         proto.service_group = other.service_group.into();
         proto.desired_state = Some(other.desired_state.into());
+        proto.render_error = other.render_error;
         proto
     }
 }
@@ -424,6 +836,9 @@ struct ProcessStatus {
     elapsed: TimeDuration,
     pid: Option<u32>,
     state: ProcessState,
+    // Absent from the data of Supervisors predating restart policies
+    #[serde(default)]
+    restart: Option<RestartStatus>,
 }
 
 impl fmt::Display for ProcessStatus {
@@ -451,6 +866,29 @@ impl From<ProcessStatus> for protocol::types::ProcessStatus {
     }
 }
 
+#[derive(Deserialize)]
+struct RestartStatus {
+    policy:         RestartPolicy,
+    count:          u32,
+    next_restart:   Option<i64>,
+    gave_up:        bool,
+    last_exit_code: Option<i32>,
+}
+
+impl<'a> From<&'a RestartStatus> for protocol::types::RestartStatus {
+    fn from(other: &'a RestartStatus) -> Self {
+        let mut proto = protocol::types::RestartStatus::default();
+        proto.policy = other.policy as i32;
+        proto.count = other.count;
+        proto.next_restart_in =
+            other.next_restart
+                 .map(|sec| (sec - time::get_time().sec).max(0) as u64);
+        proto.gave_up = other.gave_up;
+        proto.last_exit_code = other.last_exit_code;
+        proto
+    }
+}
+
 fn deserialize_time<'de, D>(d: D) -> result::Result<TimeDuration, D::Error>
     where D: serde::Deserializer<'de>
 {
@@ -485,3 +923,50 @@ fn send_action(action: SupervisorAction, sender: &ActionSender) -> NetResult<()>
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(group: &str,
+              state: protocol::types::ProcessState,
+              elapsed: i64)
+              -> protocol::types::ServiceStatus {
+        let mut status = protocol::types::ServiceStatus::default();
+        status.ident = "core/redis".parse::<PackageIdent>().unwrap().into();
+        status.service_group = ServiceGroup::new(None, "redis", group, None).unwrap().into();
+        status.process = Some(protocol::types::ProcessStatus { elapsed: Some(elapsed),
+                                                               pid:     None,
+                                                               state:   state as i32, });
+        status
+    }
+
+    #[test]
+    fn status_changes_ignore_elapsed_time() {
+        let previous = vec![status("default", protocol::types::ProcessState::Up, 10)];
+        let current = vec![status("default", protocol::types::ProcessState::Up, 11)];
+        assert!(status_changes(&previous, &current).is_empty());
+    }
+
+    #[test]
+    fn status_changes_report_changed_added_and_removed_services() {
+        let previous = vec![status("default", protocol::types::ProcessState::Up, 10),
+                            status("old", protocol::types::ProcessState::Up, 10)];
+        let current = vec![status("default", protocol::types::ProcessState::Down, 0),
+                           status("new", protocol::types::ProcessState::Up, 0)];
+        let changes = status_changes(&previous, &current);
+        assert_eq!(changes.len(), 3);
+        match changes[0] {
+            StatusChange::Changed(ref s) => assert_eq!(s.service_group.group, "default"),
+            _ => panic!("Expected the default group to change"),
+        }
+        match changes[1] {
+            StatusChange::Changed(ref s) => assert_eq!(s.service_group.group, "new"),
+            _ => panic!("Expected the new group to be added"),
+        }
+        match changes[2] {
+            StatusChange::Removed(ref s) => assert_eq!(s.service_group.group, "old"),
+            _ => panic!("Expected the old group to be removed"),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! The order in which the services loaded on one Supervisor start and stop.
+//!
+//! A service that binds to another service loaded on the same Supervisor depends on it. It's
+//! started after the service it binds to, and if its binds are strict, only once that service is
+//! running and passing its health check. When the Supervisor shuts down, services are stopped in
+//! the reverse order, so no service loses something it binds to while it's still running.
+//!
+//! Services whose binds form a cycle can't each wait for the others, so they're started in no
+//! particular order, and don't wait on each other's health even if their binds are strict.
+
+use super::service::spec::{DesiredState,
+                           ServiceSpec};
+use habitat_common::outputln;
+use habitat_core::{package::PackageIdent,
+                   service::{BindingMode,
+                             ServiceGroup}};
+use std::collections::{HashMap,
+                       HashSet};
+
+static LOGKEY: &str = "DG";
+
+/// A local service that another local service binds to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Dependency {
+    pub ident:  PackageIdent,
+    /// True if the dependent's binds are strict, so it can't start until this service is healthy.
+    pub strict: bool,
+}
+
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct DependencyGraph {
+    dependencies: HashMap<PackageIdent, Vec<Dependency>>,
+}
+
+impl DependencyGraph {
+    /// Build the graph of the given specs, connecting each to the specs its binds are satisfied
+    /// by. Specs that should be down are left out, as nothing can wait on them.
+    pub fn new<'a, I>(specs: I) -> Self
+        where I: IntoIterator<Item = &'a ServiceSpec>
+    {
+        let specs: Vec<&ServiceSpec> = specs.into_iter()
+                                            .filter(|spec| spec.desired_state == DesiredState::Up)
+                                            .collect();
+        let mut dependencies = HashMap::new();
+        for spec in &specs {
+            let mut deps: Vec<Dependency> = Vec::new();
+            for bind in &spec.binds {
+                let found = specs.iter().find(|other| {
+                                            other.ident != spec.ident
+                                            && satisfies(other, bind.service_group())
+                                        });
+                if let Some(other) = found {
+                    if !deps.iter().any(|d| d.ident == other.ident) {
+                        deps.push(Dependency { ident:  other.ident.clone(),
+                                               strict: spec.binding_mode == BindingMode::Strict, });
+                    }
+                }
+            }
+            dependencies.insert(spec.ident.clone(), deps);
+        }
+        DependencyGraph { dependencies }
+    }
+
+    /// The local services the given service binds to.
+    pub fn dependencies(&self, ident: &PackageIdent) -> &[Dependency] {
+        self.dependencies
+            .get(ident)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// The local services that bind to the given service.
+    pub fn dependents<'a>(&'a self,
+                          ident: &'a PackageIdent)
+                          -> impl Iterator<Item = &'a PackageIdent> + 'a {
+        self.dependencies
+            .iter()
+            .filter(move |(_, deps)| deps.iter().any(|d| d.ident == *ident))
+            .map(|(dependent, _)| dependent)
+    }
+
+    /// True if `dependent` binds to `dependency`, either directly or through other local services.
+    pub fn depends_on(&self, dependent: &PackageIdent, dependency: &PackageIdent) -> bool {
+        let mut visited = HashSet::new();
+        let mut to_visit = vec![dependent];
+        while let Some(ident) = to_visit.pop() {
+            for dep in self.dependencies(ident) {
+                if dep.ident == *dependency {
+                    return true;
+                }
+                if visited.insert(&dep.ident) {
+                    to_visit.push(&dep.ident);
+                }
+            }
+        }
+        false
+    }
+
+    /// The local services that end up depending on themselves through a cycle of binds.
+    pub fn cycle_members(&self) -> Vec<&PackageIdent> {
+        let mut members: Vec<&PackageIdent> = self.dependencies
+                                                  .keys()
+                                                  .filter(|ident| self.depends_on(ident, ident))
+                                                  .collect();
+        members.sort();
+        members
+    }
+
+    /// Let the user know about any cycles of binds, as their services won't wait on each other.
+    pub fn warn_about_cycles(&self) {
+        let members = self.cycle_members();
+        if !members.is_empty() {
+            outputln!("{} are part of a cycle of binds between local services; they'll be \
+                       started in no particular order, without waiting on each other",
+                      members.iter()
+                             .map(ToString::to_string)
+                             .collect::<Vec<_>>()
+                             .join(", "));
+        }
+    }
+
+    /// Sort `idents` so each service comes after every service it depends on, keeping the
+    /// given order otherwise. Services that depend on each other in a cycle can't all come
+    /// first, so the cycle is broken where it's found.
+    pub fn start_order(&self, idents: &[PackageIdent]) -> Vec<PackageIdent> {
+        let mut ordered = Vec::with_capacity(idents.len());
+        let mut visited = HashSet::new();
+        for ident in idents {
+            self.visit(ident, idents, &mut visited, &mut ordered);
+        }
+        ordered
+    }
+
+    fn visit(&self,
+             ident: &PackageIdent,
+             idents: &[PackageIdent],
+             visited: &mut HashSet<PackageIdent>,
+             ordered: &mut Vec<PackageIdent>) {
+        if ordered.contains(ident) {
+            return;
+        }
+        if !visited.insert(ident.clone()) {
+            // A cycle, which `warn_about_cycles` tells the user about
+            return;
+        }
+        for dep in self.dependencies(ident) {
+            if idents.contains(&dep.ident) {
+                self.visit(&dep.ident, idents, visited, ordered);
+            }
+        }
+        ordered.push(ident.clone());
+    }
+}
+
+/// Returns true if the service with the given spec is a member of `service_group`, as far as can
+/// be told without knowing the Supervisor's organization.
+fn satisfies(spec: &ServiceSpec, service_group: &ServiceGroup) -> bool {
+    service_group.service() == spec.ident.name
+    && service_group.group() == spec.group
+    && service_group.application_environment()
+                    .map_or(true, |env| Some(env) == spec.application_environment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use habitat_core::service::ServiceBind;
+    use std::str::FromStr;
+
+    fn spec(ident: &str, binds: &[&str], binding_mode: BindingMode) -> ServiceSpec {
+        let mut spec = ServiceSpec::default_for(PackageIdent::from_str(ident).unwrap());
+        spec.binds = binds.iter()
+                          .map(|b| ServiceBind::from_str(b).unwrap())
+                          .collect();
+        spec.binding_mode = binding_mode;
+        spec
+    }
+
+    fn idents(specs: &[&ServiceSpec]) -> Vec<PackageIdent> {
+        specs.iter().map(|s| s.ident.clone()).collect()
+    }
+
+    #[test]
+    fn dependencies_are_local_services_named_by_binds() {
+        let db = spec("core/postgresql", &[], BindingMode::Strict);
+        let app = spec("core/app",
+                       &["database:postgresql.default", "cache:redis.default"],
+                       BindingMode::Strict);
+        let web = spec("core/nginx", &["backend:app.default"], BindingMode::Relaxed);
+        let graph = DependencyGraph::new(vec![&db, &app, &web]);
+
+        assert!(graph.dependencies(&db.ident).is_empty());
+        assert_eq!(graph.dependencies(&app.ident),
+                   &[Dependency { ident:  db.ident.clone(),
+                                  strict: true, }]);
+        assert_eq!(graph.dependencies(&web.ident),
+                   &[Dependency { ident:  app.ident.clone(),
+                                  strict: false, }]);
+        assert_eq!(graph.dependents(&db.ident).collect::<Vec<_>>(),
+                   vec![&app.ident]);
+    }
+
+    #[test]
+    fn down_services_are_not_dependencies() {
+        let mut db = spec("core/postgresql", &[], BindingMode::Strict);
+        db.desired_state = DesiredState::Down;
+        let app = spec("core/app", &["database:postgresql.default"], BindingMode::Strict);
+        let graph = DependencyGraph::new(vec![&db, &app]);
+        assert!(graph.dependencies(&app.ident).is_empty());
+    }
+
+    #[test]
+    fn start_order_puts_dependencies_first() {
+        let db = spec("core/postgresql", &[], BindingMode::Strict);
+        let app = spec("core/app", &["database:postgresql.default"], BindingMode::Strict);
+        let web = spec("core/nginx", &["backend:app.default"], BindingMode::Relaxed);
+        let graph = DependencyGraph::new(vec![&db, &app, &web]);
+
+        assert_eq!(graph.start_order(&idents(&[&web, &app, &db])),
+                   idents(&[&db, &app, &web]));
+        // Services that aren't being started don't come into it
+        assert_eq!(graph.start_order(&idents(&[&web, &db])),
+                   idents(&[&web, &db]));
+    }
+
+    #[test]
+    fn start_order_breaks_cycles() {
+        let a = spec("core/a", &["b:b.default"], BindingMode::Strict);
+        let b = spec("core/b", &["a:a.default"], BindingMode::Strict);
+        let graph = DependencyGraph::new(vec![&a, &b]);
+
+        assert_eq!(graph.start_order(&idents(&[&a, &b])), idents(&[&b, &a]));
+    }
+
+    #[test]
+    fn cycle_members_depend_on_themselves() {
+        let a = spec("core/a", &["b:b.default"], BindingMode::Strict);
+        let b = spec("core/b", &["c:c.default"], BindingMode::Strict);
+        let c = spec("core/c", &["a:a.default"], BindingMode::Strict);
+        let app = spec("core/app", &["a:a.default"], BindingMode::Strict);
+        let graph = DependencyGraph::new(vec![&a, &b, &c, &app]);
+
+        assert!(graph.depends_on(&a.ident, &c.ident));
+        assert!(graph.depends_on(&c.ident, &a.ident));
+        assert!(graph.depends_on(&app.ident, &c.ident));
+        assert!(!graph.depends_on(&a.ident, &app.ident));
+        assert_eq!(graph.cycle_members(), vec![&a.ident, &b.ident, &c.ident]);
+    }
+}
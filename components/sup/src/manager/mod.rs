@@ -3,6 +3,7 @@ pub mod service;
 #[macro_use]
 mod debug;
 pub mod commands;
+mod dependency_graph;
 mod file_watcher;
 mod peer_watcher;
 mod periodic;
@@ -10,16 +11,20 @@ mod self_updater;
 mod service_updater;
 mod spec_dir;
 mod spec_watcher;
+pub(crate) mod stack;
 pub(crate) mod sys;
+mod tls_watcher;
 mod user_config_watcher;
 
 use self::{action::{ShutdownSpec,
                     SupervisorAction},
+           dependency_graph::DependencyGraph,
            peer_watcher::PeerWatcher,
            self_updater::{SelfUpdater,
                           SUP_PKG_IDENT},
            service::{ConfigRendering,
                      DesiredState,
+                     HealthCheckHistory,
                      HealthCheckResult,
                      Service,
                      ServiceProxy,
@@ -29,8 +34,10 @@ use self::{action::{ShutdownSpec,
            spec_dir::SpecDir,
            spec_watcher::SpecWatcher,
            sys::Sys,
+           tls_watcher::TlsWatcher,
            user_config_watcher::UserConfigWatcher};
-use crate::{census::{CensusRing,
+use crate::{census::{CensusEventLog,
+                     CensusRing,
                      CensusRingProxy},
             config::GossipListenAddr,
             ctl_gateway::{self,
@@ -43,6 +50,7 @@ use crate::{census::{CensusRing,
                     EventCore,
                     EventStreamConfig},
             http_gateway,
+            PRODUCT,
             VERSION};
 use cpu_time::ProcessTime;
 use futures::{future,
@@ -55,6 +63,10 @@ use habitat_butterfly::{member::Member,
                                  Suitability},
                         trace::Trace};
 use habitat_common::{outputln,
+                     templating::secrets::{self,
+                                           FileSecretProvider,
+                                           HttpSecretProvider,
+                                           SecretProvider},
                      types::ListenCtlAddr,
                      FeatureFlag};
 #[cfg(unix)]
@@ -70,24 +82,23 @@ use habitat_core::{crypto::SymKey,
                    package::{Identifiable,
                              PackageIdent,
                              PackageInstall},
-                   service::ServiceGroup,
+                   service::{LogFileConfig,
+                             ServiceGroup},
                    util::ToI64,
                    ChannelIdent};
 use habitat_launcher_client::{LauncherCli,
                               LAUNCHER_LOCK_CLEAN_ENV,
                               LAUNCHER_PID_ENV};
-use habitat_sup_protocol;
+use habitat_sup_protocol::{self,
+                           net::{self,
+                                 ErrCode,
+                                 NetResult}};
 use num_cpus;
 #[cfg(unix)]
 use palaver;
 use prometheus::{HistogramVec,
                  IntGauge,
                  IntGaugeVec};
-use rustls::{internal::pemfile,
-             AllowAnyAuthenticatedClient,
-             NoClientAuth,
-             RootCertStore,
-             ServerConfig};
 use serde_json;
 use std::{collections::{HashMap,
                         HashSet},
@@ -184,6 +195,7 @@ pub struct FsCfg {
 
     data_path:      PathBuf,
     specs_path:     PathBuf,
+    secrets_path:   PathBuf,
     member_id_file: PathBuf,
     proc_lock_file: PathBuf,
 }
@@ -195,6 +207,7 @@ impl FsCfg {
         let sup_root = sup_root.into();
         FsCfg { specs_path: sup_root.join("specs"),
                 data_path: sup_root.join("data"),
+                secrets_path: sup_root.join("secrets"),
                 member_id_file: sup_root.join(MEMBER_ID_FILE),
                 proc_lock_file: sup_root.join(PROC_LOCK_FILE),
                 sup_root }
@@ -203,23 +216,31 @@ impl FsCfg {
 
 #[derive(Clone, Debug)]
 pub struct ManagerConfig {
-    pub auto_update:         bool,
-    pub custom_state_path:   Option<PathBuf>,
-    pub cache_key_path:      PathBuf,
-    pub update_url:          String,
-    pub update_channel:      ChannelIdent,
-    pub gossip_listen:       GossipListenAddr,
-    pub ctl_listen:          ListenCtlAddr,
-    pub http_listen:         http_gateway::ListenAddr,
-    pub http_disable:        bool,
-    pub gossip_peers:        Vec<SocketAddr>,
-    pub gossip_permanent:    bool,
-    pub ring_key:            Option<SymKey>,
-    pub organization:        Option<String>,
-    pub watch_peer_file:     Option<String>,
-    pub tls_config:          Option<TLSConfig>,
-    pub feature_flags:       FeatureFlag,
-    pub event_stream_config: Option<EventStreamConfig>,
+    pub auto_update:            bool,
+    pub custom_state_path:      Option<PathBuf>,
+    pub cache_key_path:         PathBuf,
+    pub update_url:             String,
+    pub update_channel:         ChannelIdent,
+    pub gossip_listen:          GossipListenAddr,
+    pub ctl_listen:             ListenCtlAddr,
+    pub http_listen:            http_gateway::ListenAddr,
+    pub http_disable:           bool,
+    pub gossip_peers:           Vec<SocketAddr>,
+    pub gossip_permanent:       bool,
+    pub ring_key:               Option<SymKey>,
+    pub organization:           Option<String>,
+    pub watch_peer_file:        Option<String>,
+    pub tls_config:             Option<TLSConfig>,
+    /// TLS for the ctl gateway. Without it, the ctl gateway speaks plain TCP.
+    pub ctl_tls_config:         Option<TLSConfig>,
+    pub feature_flags:          FeatureFlag,
+    pub event_stream_config:    Option<EventStreamConfig>,
+    /// Log file settings for services whose spec doesn't give any.
+    pub service_log_file:       Option<LogFileConfig>,
+    /// Where secrets that aren't kept locally are fetched from.
+    pub secrets_url:            Option<String>,
+    /// Whether `secrets_url` may be a plain HTTP URL.
+    pub secrets_url_allow_http: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -244,6 +265,12 @@ impl ManagerConfig {
         spec.to_file(self.spec_path_for(spec))
     }
 
+    /// Every spec in the specs directory.
+    pub fn specs(&self) -> Vec<ServiceSpec> {
+        SpecDir::new(self.sup_root().join("specs")).map(|dir| dir.specs())
+                                                   .unwrap_or_default()
+    }
+
     /// Given a `PackageIdent`, return current spec if it exists.
     pub fn spec_for_ident(&self, ident: &PackageIdent) -> Option<ServiceSpec> {
         let default_spec = ServiceSpec::default_for(ident.clone());
@@ -339,6 +366,22 @@ pub struct GatewayState {
     /// Data returned by /services/<SERVICE_NAME>/<GROUP_NAME>/health
     /// endpoint
     pub health_check_data: HashMap<ServiceGroup, HealthCheckResult>,
+    /// Data returned by /services/<SERVICE_NAME>/<GROUP_NAME>/health/history
+    /// endpoint
+    pub health_check_history: HashMap<ServiceGroup, HealthCheckHistory>,
+    /// Recent changes to the census, streamed by the /census/events
+    /// endpoint
+    pub census_events: CensusEventLog,
+    /// Service groups that have been stopped to be restarted with an
+    /// updated package
+    pub updating_services: HashSet<ServiceGroup>,
+    /// When the updates held for service groups until their next
+    /// maintenance window will be applied, in seconds since the Unix
+    /// epoch
+    pub held_updates: HashMap<ServiceGroup, i64>,
+    /// The incarnation of the latest configuration applied to each
+    /// service group in the census
+    pub config_incarnations: HashMap<ServiceGroup, u64>,
 }
 
 pub struct Manager {
@@ -350,6 +393,7 @@ pub struct Manager {
     updater:      Arc<Mutex<ServiceUpdater>>,
     peer_watcher: Option<PeerWatcher>,
     spec_watcher: SpecWatcher,
+    tls_watchers: Vec<TlsWatcher>,
     // This Arc<RwLock<>> business is a potentially temporary
     // change. Right now, in order to asynchronously shut down
     // services, we need to be able to have a safe reference to this
@@ -382,6 +426,14 @@ pub struct Manager {
     // the different operations.
     busy_services: Arc<Mutex<HashSet<PackageIdent>>>,
     services_need_reconciliation: ReconciliationFlag,
+    /// The spec files as of the last time the specs directory
+    /// changed, and the graph of the binds between them.
+    specs: Vec<ServiceSpec>,
+    dependency_graph: DependencyGraph,
+    /// Services that aren't started yet because a service they
+    /// strictly bind to isn't up and healthy. We take another look
+    /// at them once that service is.
+    waiting_for_dependencies: HashSet<PackageIdent>,
 
     feature_flags: FeatureFlag,
 }
@@ -465,6 +517,17 @@ impl Manager {
             None
         };
 
+        let mut secret_providers: Vec<Box<dyn SecretProvider>> =
+            vec![Box::new(FileSecretProvider::new(&fs_cfg.secrets_path, &cfg.cache_key_path))];
+        if let Some(ref url) = cfg.secrets_url {
+            outputln!("Fetching secrets that aren't kept locally from {}", url);
+            secret_providers.push(Box::new(HttpSecretProvider::new(url,
+                                                                   cfg.secrets_url_allow_http,
+                                                                   PRODUCT,
+                                                                   VERSION)?));
+        }
+        secrets::set_providers(secret_providers);
+
         let spec_dir = SpecDir::new(&fs_cfg.specs_path)?;
         spec_dir.migrate_specs();
 
@@ -503,6 +566,7 @@ impl Manager {
                      launcher,
                      peer_watcher,
                      spec_watcher,
+                     tls_watchers: Vec::new(),
                      user_config_watcher: Arc::new(RwLock::new(UserConfigWatcher::new())),
                      spec_dir,
                      fs_cfg: Arc::new(fs_cfg),
@@ -512,6 +576,9 @@ impl Manager {
                      http_disable: cfg.http_disable,
                      busy_services: Arc::new(Mutex::new(HashSet::new())),
                      services_need_reconciliation: ReconciliationFlag::new(false),
+                     specs: Vec::new(),
+                     dependency_graph: DependencyGraph::default(),
+                     waiting_for_dependencies: HashSet::new(),
                      feature_flags: cfg.feature_flags })
     }
 
@@ -556,6 +623,26 @@ impl Manager {
         Ok(member)
     }
 
+    /// The files that rendering a service group with the given configuration applied would
+    /// change, along with a diff of each.
+    fn dry_run_config(&self,
+                      service_group: &ServiceGroup,
+                      cfg: toml::value::Table)
+                      -> NetResult<Vec<(PathBuf, String)>> {
+        self.state
+            .services
+            .read()
+            .expect("Services lock is poisoned")
+            .values()
+            .find(|service| service.service_group == *service_group)
+            .ok_or_else(|| {
+                net::err(ErrCode::NotFound,
+                         format!("Service group not loaded, {}", service_group))
+            })?
+            .cfg_changes(&self.census_ring, cfg)
+            .map_err(|e| net::err(ErrCode::Internal, e.to_string()))
+    }
+
     fn clean_dirty_state(fs_cfg: &FsCfg) -> Result<()> {
         let data_path = &fs_cfg.data_path;
         debug!("Cleaning cached health checks");
@@ -595,6 +682,24 @@ impl Manager {
     }
 
     fn add_service(&mut self, spec: &ServiceSpec) {
+        self.start_service(spec);
+        // A service stopped to restart into an update is no longer being
+        // updated once it has started again, or failed to.
+        if let Ok(service_group) = ServiceGroup::new(spec.application_environment.as_ref(),
+                                                     &spec.ident.name,
+                                                     &spec.group,
+                                                     self.organization.as_ref().map(|org| &**org))
+        {
+            self.state
+                .gateway_state
+                .write()
+                .expect("GatewayState lock is poisoned")
+                .updating_services
+                .remove(&service_group);
+        }
+    }
+
+    fn start_service(&mut self, spec: &ServiceSpec) {
         // JW TODO: This clone sucks, but our data structures are a bit messy here. What we really
         // want is the service to hold the spec and, on failure, return an error with the spec
         // back to us. Since we consume and deconstruct the spec in `Service::new()` which
@@ -604,7 +709,8 @@ impl Manager {
                                           spec.clone(),
                                           self.fs_cfg.clone(),
                                           self.organization.as_ref().map(|org| &**org),
-                                          self.state.gateway_state.clone())
+                                          self.state.gateway_state.clone(),
+                                          self.state.cfg.service_log_file)
         {
             Ok(service) => {
                 outputln!("Starting {} ({})", &spec.ident, service.pkg.ident);
@@ -711,7 +817,7 @@ impl Manager {
 
         // This serves to start up any services that need starting
         // (which will be all of them at this point!)
-        self.maybe_spawn_service_futures(&mut runtime);
+        self.maybe_spawn_service_futures(&mut runtime, true);
 
         outputln!("Starting gossip-listener on {}",
                   self.butterfly.gossip_addr());
@@ -721,8 +827,22 @@ impl Manager {
         let http_listen_addr = self.sys.http_listen();
         let ctl_listen_addr = self.sys.ctl_listen();
         let ctl_secret_key = ctl_gateway::readgen_secret_key(&self.fs_cfg.sup_root)?;
+        let ctl_tls_server_config = match &self.state.cfg.ctl_tls_config {
+            Some(c) => {
+                let tls = http_gateway::GatewayTls::new(c.clone())?;
+                let server_config = tls.server_config();
+                self.tls_watchers
+                    .push(TlsWatcher::run(tls, "ctl-gateway")?);
+                Some(Arc::new(server_config))
+            }
+            None => None,
+        };
         outputln!("Starting ctl-gateway on {}", &ctl_listen_addr);
-        ctl_gateway::server::run(ctl_listen_addr, ctl_secret_key, mgr_sender);
+        ctl_gateway::server::run(ctl_listen_addr,
+                                 ctl_tls_server_config,
+                                 ctl_secret_key,
+                                 self.fs_cfg.sup_root.clone(),
+                                 mgr_sender.clone());
         debug!("ctl-gateway started");
 
         if self.http_disable {
@@ -734,10 +854,11 @@ impl Manager {
 
             let tls_server_config = match &self.state.cfg.tls_config {
                 Some(c) => {
-                    match tls_config(c) {
-                        Ok(c) => Some(c),
-                        Err(e) => return Err(e),
-                    }
+                    let tls = http_gateway::GatewayTls::new(c.clone())?;
+                    let server_config = tls.server_config();
+                    self.tls_watchers
+                        .push(TlsWatcher::run(tls, "http-gateway")?);
+                    Some(server_config)
                 }
                 None => None,
             };
@@ -754,6 +875,7 @@ impl Manager {
                                       self.state.gateway_state.clone(),
                                       http_gateway::GatewayAuthenticationToken::configured_value(),
                                       self.feature_flags,
+                                      mgr_sender,
                                       pair.clone());
 
             let &(ref lock, ref cvar) = &*pair;
@@ -910,6 +1032,13 @@ impl Manager {
                                   service_spec.ident);
                         }
                     }
+                    SupervisorAction::DryRunConfig { service_group,
+                                                     cfg,
+                                                     reply, } => {
+                        let changes = self.dry_run_config(&service_group, cfg);
+                        // The request is gone if nobody's waiting for the reply anymore.
+                        reply.send(changes).ok();
+                    }
                 }
             }
 
@@ -920,8 +1049,13 @@ impl Manager {
             // Takes into account filesystem events in the specs
             // directory, as well as whether or not we need to
             // reexamine specs after finishing some asynchronous
-            // operation on a service.
-            if self.spec_watcher.has_events() || self.services_need_reconciliation.is_set() {
+            // operation on a service, or for a service that strictly
+            // binds to another to become able to start.
+            let specs_changed = self.spec_watcher.has_events();
+            if specs_changed
+               || self.services_need_reconciliation.is_set()
+               || self.waiting_service_can_start()
+            {
                 // This call *must* come first. If some other future
                 // happens to complete before we get done spawning our
                 // current batch of futures, it could set the flag to
@@ -935,10 +1069,13 @@ impl Manager {
                 // event in the specs directory is registered, or
                 // another service finishes shutting down).
                 self.services_need_reconciliation.toggle_if_set();
-                self.maybe_spawn_service_futures(&mut runtime);
+                self.maybe_spawn_service_futures(&mut runtime, specs_changed);
             }
 
             self.update_peers_from_watch_file()?;
+            for watcher in &self.tls_watchers {
+                watcher.reload_if_changed();
+            }
             self.update_running_services_from_user_config_watcher();
 
             for f in self.stop_services_with_updates() {
@@ -960,6 +1097,7 @@ impl Manager {
             }
 
             if self.census_ring.changed() {
+                self.publish_census_events();
                 self.persist_state();
             }
 
@@ -977,6 +1115,7 @@ impl Manager {
                     self.gossip_latest_service_rumor(&service);
                 }
             }
+            self.roll_back_failed_updates();
 
             // This is really only needed until everything is running
             // in futures.
@@ -1017,13 +1156,16 @@ impl Manager {
                 outputln!("Gracefully departing from butterfly network.");
                 self.butterfly.set_departed();
 
-                let mut svcs = self.state
-                                   .services
-                                   .write()
-                                   .expect("Services lock is poisoned!");
+                let svcs: Vec<Service> = self.state
+                                             .services
+                                             .write()
+                                             .expect("Services lock is poisoned!")
+                                             .drain()
+                                             .map(|(_ident, svc)| svc)
+                                             .collect();
 
-                for (_ident, svc) in svcs.drain() {
-                    runtime.spawn(self.stop(svc));
+                for f in self.stop_in_dependency_order(svcs) {
+                    runtime.spawn(f);
                 }
             }
         }
@@ -1065,6 +1207,7 @@ impl Manager {
                     updater.check_for_updated_package(&service, &self.census_ring)
                 {
                     outputln!("Updating from {} to {}", current_ident, new_ident);
+                    updater.start_probation(&service, &new_ident);
                     Some(current_ident.clone())
                 } else {
                     trace!("No update found for {}", current_ident);
@@ -1073,15 +1216,78 @@ impl Manager {
                                                       })
                                                       .collect();
 
+        let mut rollouts_changed = false;
+        let mut held_updates = HashMap::new();
+        for service in state_services.values() {
+            let rollout = updater.rollout_status(&service.service_group);
+            rollouts_changed |= self.census_ring
+                                    .set_rollout(&service.service_group, rollout);
+            if let Some(until) = updater.held_until(&service.service_group) {
+                held_updates.insert(service.service_group.clone(), until.timestamp());
+            }
+        }
+
         let mut services_to_restart = Vec::with_capacity(idents_to_restart.len());
+        let mut gateway_state = self.state
+                                    .gateway_state
+                                    .write()
+                                    .expect("GatewayState lock is poisoned");
+        gateway_state.held_updates = held_updates;
         for current_ident in idents_to_restart {
             // unwrap is safe because we've to the write lock, and we
             // know there's a value present at this key.
-            services_to_restart.push(state_services.remove(&current_ident).unwrap());
+            let service = state_services.remove(&current_ident).unwrap();
+            gateway_state.updating_services
+                         .insert(service.service_group.clone());
+            services_to_restart.push(service);
+        }
+        drop(gateway_state);
+        drop(state_services);
+        drop(updater);
+
+        if rollouts_changed {
+            self.persist_census_state();
         }
         services_to_restart
     }
 
+    /// Pin any service whose update failed its health check within its
+    /// grace period to the release it ran before. Saving the spec
+    /// restarts the service through the usual reconciliation.
+    fn roll_back_failed_updates(&mut self) {
+        let mut rollbacks = Vec::new();
+        {
+            let services = self.state
+                               .services
+                               .read()
+                               .expect("Services lock is poisoned!");
+            let gateway_state = self.state
+                                    .gateway_state
+                                    .read()
+                                    .expect("GatewayState lock is poisoned");
+            let mut updater = self.updater.lock().expect("Updater lock poisoned");
+            for service in services.values() {
+                let health = gateway_state.health_check_data
+                                          .get(&service.service_group)
+                                          .cloned();
+                if let Some(rollback) = updater.check_probation(service, health) {
+                    event::service_update_rolled_back(service, &rollback.to);
+                    rollbacks.push((service.to_spec(), rollback));
+                }
+            }
+        }
+
+        for (mut spec, rollback) in rollbacks {
+            outputln!("Rolling back {} to {}; the update failed its health check",
+                      rollback.from,
+                      rollback.to);
+            spec.ident = rollback.to;
+            if let Err(err) = self.state.cfg.save_spec_for(&spec) {
+                outputln!("Unable to roll back {}: {}", rollback.from, err);
+            }
+        }
+    }
+
     /// Returns a Vec of futures for shutting down those services that
     /// need to be updated.
     // TODO (CM): In the future, when service start up is
@@ -1161,11 +1367,35 @@ impl Manager {
     fn persist_census_state(&self) {
         let crp = CensusRingProxy::new(&self.census_ring);
         let json = serde_json::to_string(&crp).unwrap();
-        self.state
-            .gateway_state
-            .write()
-            .expect("GatewayState lock is poisoned")
-            .census_data = json;
+        let config_incarnations = self.census_ring
+                                      .groups()
+                                      .into_iter()
+                                      .filter_map(|group| {
+                                          group.service_config.as_ref().map(|config| {
+                                              (group.service_group.clone(), config.incarnation)
+                                          })
+                                      })
+                                      .collect();
+        let mut gateway_state = self.state
+                                    .gateway_state
+                                    .write()
+                                    .expect("GatewayState lock is poisoned");
+        gateway_state.census_data = json;
+        gateway_state.config_incarnations = config_incarnations;
+    }
+
+    fn publish_census_events(&mut self) {
+        let events = self.census_ring.take_events();
+        if events.is_empty() {
+            return;
+        }
+        let mut gateway_state = self.state
+                                    .gateway_state
+                                    .write()
+                                    .expect("GatewayState lock is poisoned");
+        for event in events {
+            gateway_state.census_events.push(event);
+        }
     }
 
     fn persist_butterfly_state(&self) {
@@ -1205,7 +1435,8 @@ impl Manager {
                                   spec.clone(),
                                   self.fs_cfg.clone(),
                                   self.organization.as_ref().map(|org| &**org),
-                                  self.state.gateway_state.clone()).into_iter()
+                                  self.state.gateway_state.clone(),
+                                  self.state.cfg.service_log_file).into_iter()
                 })
                 .collect();
         let watched_service_proxies: Vec<ServiceProxy<'_>> =
@@ -1264,6 +1495,53 @@ impl Manager {
                                   self.services_need_reconciliation.clone())
     }
 
+    /// Create futures for stopping the given Services, where each
+    /// Service only starts stopping once the Services that bind to
+    /// it have stopped.
+    fn stop_in_dependency_order(&self,
+                                services: Vec<Service>)
+                                -> Vec<impl Future<Item = (), Error = ()>> {
+        let specs: Vec<ServiceSpec> = services.iter().map(Service::to_spec).collect();
+        let graph = DependencyGraph::new(&specs);
+        let idents: Vec<PackageIdent> = specs.into_iter().map(|spec| spec.ident).collect();
+        let mut services: HashMap<PackageIdent, Service> =
+            services.into_iter()
+                    .map(|service| (service.spec_ident.clone(), service))
+                    .collect();
+
+        let mut stops: HashMap<PackageIdent,
+                               future::Shared<Box<dyn Future<Item = (), Error = ()> + Send>>> =
+            HashMap::new();
+        // Dependents come last in the start order, so they're the
+        // first to be stopped, and their stops are there to wait on
+        // by the time we get to the services they bind to.
+        for ident in graph.start_order(&idents).into_iter().rev() {
+            let service = services.remove(&ident)
+                                  .expect("Every ident came from a service");
+            let dependents_stopped: Vec<_> = graph.dependents(&ident)
+                                                  .filter_map(|dependent| stops.get(dependent))
+                                                  .cloned()
+                                                  .collect();
+            let user_config_watcher = Arc::clone(&self.user_config_watcher);
+            let updater = Arc::clone(&self.updater);
+            let busy_services = Arc::clone(&self.busy_services);
+            let services_need_reconciliation = self.services_need_reconciliation.clone();
+            let stop = future::join_all(dependents_stopped).then(move |_| {
+                           Self::service_stop_future(service,
+                                                     ShutdownSpec::default(),
+                                                     user_config_watcher,
+                                                     updater,
+                                                     busy_services,
+                                                     services_need_reconciliation)
+                       });
+            let stop: Box<dyn Future<Item = (), Error = ()> + Send> = Box::new(stop);
+            stops.insert(ident, stop.shared());
+        }
+        stops.into_iter()
+             .map(|(_ident, stop)| stop.then(|_| Ok(())))
+             .collect()
+    }
+
     /// Remove the given service from the manager.
     fn service_stop_future(mut service: Service,
                            shutdown_spec: ShutdownSpec,
@@ -1345,19 +1623,47 @@ impl Manager {
     /// NOTE: Service start is currently synchronous, so any start
     /// operations will be performed directly as a consequence of
     /// calling this method.
-    fn maybe_spawn_service_futures(&mut self, runtime: &mut Runtime) {
-        let ops = self.compute_service_operations();
+    ///
+    /// The spec files are only read again if `specs_changed`;
+    /// otherwise, the ones read last time are used.
+    fn maybe_spawn_service_futures(&mut self, runtime: &mut Runtime, specs_changed: bool) {
+        if specs_changed {
+            self.specs = self.spec_dir.specs();
+            let graph = DependencyGraph::new(&self.specs);
+            if graph != self.dependency_graph {
+                graph.warn_about_cycles();
+                self.dependency_graph = graph;
+            }
+        }
+        let ops = Self::order_operations(self.compute_service_operations(),
+                                         &self.dependency_graph);
+        // Anything no longer to be started has nothing left to wait for
+        let starts: HashSet<&PackageIdent> = ops.iter()
+                                                .filter_map(|op| {
+                                                    match op {
+                                                        ServiceOperation::Start(spec) => {
+                                                            Some(&spec.ident)
+                                                        }
+                                                        _ => None,
+                                                    }
+                                                })
+                                                .collect();
+        self.waiting_for_dependencies.retain(|ident| starts.contains(ident));
         for f in self.operations_into_futures(ops) {
             runtime.spawn(f);
         }
     }
 
     fn remove_service_from_state(&mut self, spec: &ServiceSpec) -> Option<Service> {
-        self.state
-            .services
-            .write()
-            .expect("Services lock is poisoned")
-            .remove(&spec.ident)
+        let service = self.state
+                          .services
+                          .write()
+                          .expect("Services lock is poisoned")
+                          .remove(&spec.ident);
+        if let Some(ref service) = service {
+            secrets::forget(&service.service_group.to_string());
+        }
+        service
     }
 
     /// Start, stop, or restart services to bring what's running in
@@ -1368,6 +1674,10 @@ impl Manager {
     /// services is made asynchronous, however, it performs a mix of
     /// operations; starts are performed synchronously, while
     /// shutdowns and restarts are turned into futures.
+    ///
+    /// A service that strictly binds to another service on this
+    /// Supervisor isn't started until that service is up and
+    /// healthy; we'll take another look at it once it is.
     fn operations_into_futures<O>(&mut self, ops: O) -> Vec<impl Future<Item = (), Error = ()>>
         where O: IntoIterator<Item = ServiceOperation>
     {
@@ -1397,7 +1707,16 @@ impl Manager {
                        f
                    }
                    ServiceOperation::Start(spec) => {
-                       self.add_service(&spec);
+                       if let Some(dependency) = self.unready_dependency(&spec.ident) {
+                           if self.waiting_for_dependencies.insert(spec.ident.clone()) {
+                               outputln!("Waiting for {} to be up and healthy before starting {}",
+                                         dependency,
+                                         &spec.ident);
+                           }
+                       } else {
+                           self.waiting_for_dependencies.remove(&spec.ident);
+                           self.add_service(&spec);
+                       }
                        None // No future to return (currently synchronous!)
                    }
                }
@@ -1424,14 +1743,74 @@ impl Manager {
         let busy_services = self.busy_services
                                 .lock()
                                 .expect("busy_services lock is poisoned");
-        let on_disk_specs = self.spec_dir
-                                .specs()
-                                .into_iter()
-                                .filter(|s| !busy_services.contains(&s.ident));
+        let on_disk_specs = self.specs
+                                .iter()
+                                .filter(|s| !busy_services.contains(&s.ident))
+                                .cloned();
 
         Self::specs_to_operations(currently_running_specs, on_disk_specs)
     }
 
+    /// Returns true if a service that was waiting on a service it
+    /// strictly binds to no longer has anything to wait for.
+    fn waiting_service_can_start(&self) -> bool {
+        self.waiting_for_dependencies
+            .iter()
+            .any(|ident| self.unready_dependency(ident).is_none())
+    }
+
+    /// Returns the first service that the given service strictly
+    /// binds to which isn't yet running with a passing health check,
+    /// if there is one. Services in a cycle of binds don't wait on
+    /// each other, as none of them would ever start.
+    fn unready_dependency(&self, ident: &PackageIdent) -> Option<PackageIdent> {
+        let services = self.state
+                           .services
+                           .read()
+                           .expect("Services lock is poisoned");
+        let gateway_state = self.state
+                                .gateway_state
+                                .read()
+                                .expect("GatewayState lock is poisoned");
+        let graph = &self.dependency_graph;
+        graph.dependencies(ident)
+             .iter()
+             .filter(|dependency| dependency.strict && !graph.depends_on(&dependency.ident, ident))
+             .find(|dependency| {
+                 services.get(&dependency.ident)
+                         .and_then(|service| {
+                             gateway_state.health_check_data.get(&service.service_group)
+                         })
+                 != Some(&HealthCheckResult::Ok)
+             })
+             .map(|dependency| dependency.ident.clone())
+    }
+
+    /// Pure utility function to put service starts after every other
+    /// operation, with each service starting after the services it
+    /// binds to.
+    fn order_operations(ops: Vec<ServiceOperation>,
+                        graph: &DependencyGraph)
+                        -> Vec<ServiceOperation> {
+        let mut ordered = Vec::with_capacity(ops.len());
+        let mut starts = HashMap::new();
+        let mut start_idents = Vec::new();
+        for op in ops {
+            match op {
+                ServiceOperation::Start(spec) => {
+                    start_idents.push(spec.ident.clone());
+                    starts.insert(spec.ident.clone(), spec);
+                }
+                other => ordered.push(other),
+            }
+        }
+        ordered.extend(graph.start_order(&start_idents)
+                            .into_iter()
+                            .filter_map(|ident| starts.remove(&ident))
+                            .map(ServiceOperation::Start));
+        ordered
+    }
+
     /// Pure utility function to generate a list of operations to
     /// perform to bring what's currently running with what _should_ be
     /// running, based on the current on-disk spec files.
@@ -1550,49 +1929,6 @@ impl Manager {
 
 ////////////////////////////////////////////////////////////////////////
 
-fn tls_config(config: &TLSConfig) -> Result<rustls::ServerConfig> {
-    let client_auth = match &config.ca_cert_path {
-        Some(path) => {
-            let mut root_store = RootCertStore::empty();
-            let ca_file = &mut BufReader::new(File::open(path)?);
-            root_store.add_pem_file(ca_file)
-                      .and_then(|(added, _)| {
-                          if added < 1 {
-                              Err(())
-                          } else {
-                              Ok(AllowAnyAuthenticatedClient::new(root_store))
-                          }
-                      })
-                      .map_err(|_| sup_error!(Error::InvalidCertFile(path.clone())))?
-        }
-        None => NoClientAuth::new(),
-    };
-
-    let mut server_config = ServerConfig::new(client_auth);
-    let key_file = &mut BufReader::new(File::open(&config.key_path)?);
-    let cert_file = &mut BufReader::new(File::open(&config.cert_path)?);
-
-    // Note that we must explicitly map these errors because rustls returns () as the error from
-    // both pemfile::certs() as well as pemfile::rsa_private_keys() and we want to return
-    // different errors for each.
-    let cert_chain =
-        pemfile::certs(cert_file).and_then(|c| if c.is_empty() { Err(()) } else { Ok(c) })
-                                 .map_err(|_| {
-                                     sup_error!(Error::InvalidCertFile(config.cert_path.clone()))
-                                 })?;
-
-    let key =
-        pemfile::rsa_private_keys(key_file).and_then(|mut k| k.pop().ok_or(()))
-                                           .map_err(|_| {
-                                               sup_error!(Error::InvalidKeyFile(config.key_path
-                                                                                      .clone()))
-                                           })?;
-
-    server_config.set_single_cert(cert_chain, key)?;
-    server_config.ignore_client_order = true;
-    Ok(server_config)
-}
-
 /// Represents how many threads to start for our main Tokio runtime
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq)]
 struct TokioThreadCount(usize);
@@ -1800,23 +2136,27 @@ mod test {
     // code, so only implement it under test configuration.
     impl Default for ManagerConfig {
         fn default() -> Self {
-            ManagerConfig { auto_update:         false,
-                            custom_state_path:   None,
-                            cache_key_path:      cache_key_path(Some(&*FS_ROOT)),
-                            update_url:          "".to_string(),
-                            update_channel:      ChannelIdent::default(),
-                            gossip_listen:       GossipListenAddr::default(),
-                            ctl_listen:          ListenCtlAddr::default(),
-                            http_listen:         http_gateway::ListenAddr::default(),
-                            http_disable:        false,
-                            gossip_peers:        vec![],
-                            gossip_permanent:    false,
-                            ring_key:            None,
-                            organization:        None,
-                            watch_peer_file:     None,
-                            tls_config:          None,
-                            feature_flags:       FeatureFlag::empty(),
-                            event_stream_config: None, }
+            ManagerConfig { auto_update:            false,
+                            custom_state_path:      None,
+                            cache_key_path:         cache_key_path(Some(&*FS_ROOT)),
+                            update_url:             "".to_string(),
+                            update_channel:         ChannelIdent::default(),
+                            gossip_listen:          GossipListenAddr::default(),
+                            ctl_listen:             ListenCtlAddr::default(),
+                            http_listen:            http_gateway::ListenAddr::default(),
+                            http_disable:           false,
+                            gossip_peers:           vec![],
+                            gossip_permanent:       false,
+                            ring_key:               None,
+                            organization:           None,
+                            watch_peer_file:        None,
+                            tls_config:             None,
+                            ctl_tls_config:         None,
+                            feature_flags:          FeatureFlag::empty(),
+                            event_stream_config:    None,
+                            service_log_file:       None,
+                            secrets_url:            None,
+                            secrets_url_allow_http: false, }
         }
     }
 
@@ -2062,5 +2402,26 @@ mod test {
                         op);
             }
         }
+
+        #[test]
+        fn starts_come_last_and_after_the_services_they_bind_to() {
+            let db = new_spec("core/postgresql");
+            let app = {
+                let mut s = new_spec("core/app");
+                s.binds = vec!["database:postgresql.default".parse().unwrap()];
+                s
+            };
+            let old = new_spec("core/old");
+            let graph = DependencyGraph::new(vec![&db, &app, &old]);
+
+            let operations = vec![ServiceOperation::Start(app.clone()),
+                                  ServiceOperation::Stop(old.clone()),
+                                  ServiceOperation::Start(db.clone()),];
+
+            assert_eq!(Manager::order_operations(operations, &graph),
+                       vec![ServiceOperation::Stop(old),
+                            ServiceOperation::Start(db),
+                            ServiceOperation::Start(app),]);
+        }
     }
 }
@@ -0,0 +1,208 @@
+//! The configurations that have been applied to a service group with `hab config apply`.
+//!
+//! Each is kept as it was gossiped in a file named after its incarnation, in a directory per
+//! service group under the Supervisor's data path, so that an earlier one can be compared against
+//! the current one or applied again. A configuration that was sent encrypted stays encrypted, and
+//! only whoever has the service group's key can read it. Only the latest `CONFIG_HISTORY_LIMIT`
+//! are kept.
+
+use crate::error::{Error,
+                   Result};
+use habitat_core::service::ServiceGroup;
+use std::{fs,
+          path::{Path,
+                 PathBuf},
+          time::UNIX_EPOCH};
+
+/// How many applied configurations are kept for each service group.
+pub const CONFIG_HISTORY_LIMIT: usize = 10;
+
+const CONFIG_HISTORY_DIR: &str = "config_history";
+
+/// Only the Supervisor may read the configurations it keeps.
+#[cfg(not(windows))]
+const CONFIG_HISTORY_PERMISSIONS: u32 = 0o600;
+
+/// The extensions of the files that plain and encrypted configurations are kept in.
+const PLAIN_EXTENSION: &str = "toml";
+const ENCRYPTED_EXTENSION: &str = "box";
+
+/// A configuration that was applied to a service group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigVersion {
+    pub incarnation: u64,
+    /// When it was applied, in seconds since the Unix epoch.
+    pub applied_at:  i64,
+    /// The configuration, as TOML, or as it was sent if it was encrypted.
+    pub cfg:         String,
+    /// Whether `cfg` is encrypted for the service group's key.
+    pub encrypted:   bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConfigHistory {
+    path: PathBuf,
+}
+
+impl ConfigHistory {
+    pub fn new(data_path: &Path, service_group: &ServiceGroup) -> Self {
+        ConfigHistory { path: data_path.join(CONFIG_HISTORY_DIR)
+                                       .join(service_group.to_string()), }
+    }
+
+    /// Keeps a configuration that was just applied, as it was gossiped, and forgets the oldest
+    /// ones past the limit.
+    pub fn record(&self, incarnation: u64, encrypted: bool, payload: &[u8]) -> Result<()> {
+        fs::create_dir_all(&self.path).map_err(|err| {
+                                          sup_error!(Error::BadDataPath(self.path.clone(), err))
+                                      })?;
+        let file = self.file_for(incarnation, encrypted);
+        let tmp_file = file.with_extension("tmp");
+        fs::write(&tmp_file, payload).map_err(|err| {
+                                         sup_error!(Error::BadDataPath(tmp_file.clone(), err))
+                                     })?;
+        set_permissions(&tmp_file)?;
+        fs::rename(&tmp_file, &file).map_err(|err| sup_error!(Error::BadDataPath(file, err)))?;
+
+        let incarnations = self.incarnations()?;
+        if incarnations.len() > CONFIG_HISTORY_LIMIT {
+            let forgotten = incarnations.len() - CONFIG_HISTORY_LIMIT;
+            for &(old, old_encrypted) in &incarnations[..forgotten] {
+                let old_file = self.file_for(old, old_encrypted);
+                fs::remove_file(&old_file).map_err(|err| {
+                                              sup_error!(Error::BadDataPath(old_file, err))
+                                          })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The configurations that are kept, oldest first.
+    pub fn versions(&self) -> Result<Vec<ConfigVersion>> {
+        self.incarnations()?
+            .into_iter()
+            .map(|(incarnation, encrypted)| {
+                let file = self.file_for(incarnation, encrypted);
+                let cfg = fs::read_to_string(&file).map_err(|err| {
+                              sup_error!(Error::BadDataPath(file.clone(), err))
+                          })?;
+                let applied_at = fs::metadata(&file).and_then(|m| m.modified())
+                                                    .ok()
+                                                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                                                    .map_or(0, |d| d.as_secs() as i64);
+                Ok(ConfigVersion { incarnation,
+                                   applied_at,
+                                   cfg,
+                                   encrypted })
+            })
+            .collect()
+    }
+
+    fn file_for(&self, incarnation: u64, encrypted: bool) -> PathBuf {
+        let extension = if encrypted {
+            ENCRYPTED_EXTENSION
+        } else {
+            PLAIN_EXTENSION
+        };
+        self.path.join(format!("{}.{}", incarnation, extension))
+    }
+
+    /// The incarnations of the configurations that are kept, in ascending order, with whether
+    /// each is encrypted.
+    fn incarnations(&self) -> Result<Vec<(u64, bool)>> {
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(ref err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(sup_error!(Error::BadDataPath(self.path.clone(), err))),
+        };
+        let mut incarnations: Vec<(u64, bool)> =
+            entries.filter_map(|entry| entry.ok())
+                   .map(|entry| entry.path())
+                   .filter_map(|path| {
+                       let encrypted = match path.extension()?.to_str()? {
+                           PLAIN_EXTENSION => false,
+                           ENCRYPTED_EXTENSION => true,
+                           _ => return None,
+                       };
+                       Some((path.file_stem()?.to_str()?.parse().ok()?, encrypted))
+                   })
+                   .collect();
+        incarnations.sort();
+        Ok(incarnations)
+    }
+}
+
+#[cfg(not(windows))]
+fn set_permissions(path: &Path) -> Result<()> {
+    use habitat_core::util::posix_perm;
+
+    posix_perm::set_permissions(path, CONFIG_HISTORY_PERMISSIONS)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn set_permissions(path: &Path) -> Result<()> {
+    use habitat_core::util::win_perm;
+
+    win_perm::harden_path(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn cfg(port: i64) -> Vec<u8> { format!("port = {}\n", port).into_bytes() }
+
+    #[test]
+    fn keeps_only_the_latest_configurations() {
+        let tmpdir = TempDir::new().unwrap();
+        let history = ConfigHistory::new(tmpdir.path(), &"redis.default".parse().unwrap());
+        assert!(history.versions().unwrap().is_empty());
+
+        for incarnation in 1..=(CONFIG_HISTORY_LIMIT as u64 + 2) {
+            history.record(incarnation, false, &cfg(6000 + incarnation as i64))
+                   .unwrap();
+        }
+        let versions = history.versions().unwrap();
+        assert_eq!(versions.len(), CONFIG_HISTORY_LIMIT);
+        assert_eq!(versions[0].incarnation, 3);
+        assert_eq!(versions[0].cfg, "port = 6003\n");
+        assert_eq!(versions.last().unwrap().incarnation,
+                   CONFIG_HISTORY_LIMIT as u64 + 2);
+    }
+
+    #[test]
+    fn orders_incarnations_numerically() {
+        let tmpdir = TempDir::new().unwrap();
+        let history = ConfigHistory::new(tmpdir.path(), &"redis.default".parse().unwrap());
+        history.record(10, false, &cfg(1)).unwrap();
+        history.record(9, true, b"BOX-1\n...").unwrap();
+        let incarnations: Vec<u64> = history.versions()
+                                            .unwrap()
+                                            .iter()
+                                            .map(|v| v.incarnation)
+                                            .collect();
+        assert_eq!(incarnations, vec![9, 10]);
+    }
+
+    #[test]
+    fn keeps_encrypted_configurations_encrypted_and_private() {
+        let tmpdir = TempDir::new().unwrap();
+        let history = ConfigHistory::new(tmpdir.path(), &"redis.default".parse().unwrap());
+        history.record(1, true, b"BOX-1\nsealed").unwrap();
+        let versions = history.versions().unwrap();
+        assert_eq!(versions[0].cfg, "BOX-1\nsealed");
+        assert!(versions[0].encrypted);
+
+        #[cfg(not(windows))]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(history.file_for(1, true)).unwrap()
+                                                             .permissions()
+                                                             .mode();
+            assert_eq!(mode & 0o777, CONFIG_HISTORY_PERMISSIONS);
+        }
+    }
+}
@@ -313,9 +313,9 @@ impl<'a> Serialize for Svc<'a> {
         map.serialize_entry("service", &self.service_group.service())?;
         map.serialize_entry("group", &self.service_group.group())?;
         map.serialize_entry("org", &self.service_group.org())?;
+        map.serialize_entry("service_group", &self.service_group.to_string())?;
         // TODO (CM): need to add application, environment (to
-        // maintain parity with SvcMember; see below), as well as the
-        // complete service_group as a string.
+        // maintain parity with SvcMember; see below).
 
         map.serialize_entry("election_is_running",
                             &(self.election_status.as_ref()
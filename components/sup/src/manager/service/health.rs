@@ -10,17 +10,30 @@ use futures::{future::{self,
                        Future,
                        Loop},
               IntoFuture};
-use habitat_common::templating::package::Pkg;
+use habitat_common::templating::{hooks::HookOutput,
+                                 package::Pkg};
 use habitat_core::service::{HealthCheckInterval,
                             ServiceGroup};
-use std::{fmt,
+use serde::{Serialize,
+            Serializer};
+use std::{collections::VecDeque,
+          fmt,
+          io::Read,
           ops::Deref,
+          result,
           sync::{Arc,
                  Mutex,
                  RwLock},
           time::{Duration,
                  Instant}};
 
+/// How many health check results are kept for each service.
+pub const HEALTH_CHECK_HISTORY_SIZE: usize = 20;
+
+/// How much of each of a health check hook's stdout and stderr is
+/// kept with its result. Longer output loses its beginning.
+const HEALTH_CHECK_OUTPUT_LIMIT: usize = 4096;
+
 /// The possible results of running a health check hook.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
 pub enum HealthCheckResult {
@@ -47,6 +60,102 @@ impl From<i8> for HealthCheckResult {
     }
 }
 
+/// The health check hook exit code that produces each status.
+impl From<HealthCheckResult> for i8 {
+    fn from(value: HealthCheckResult) -> i8 {
+        match value {
+            HealthCheckResult::Ok => 0,
+            HealthCheckResult::Warning => 1,
+            HealthCheckResult::Critical => 2,
+            HealthCheckResult::Unknown => 3,
+        }
+    }
+}
+
+/// What one run of a health check hook produced.
+#[derive(Debug, Default)]
+pub struct HealthCheckHookExit {
+    pub result:    HealthCheckResult,
+    /// `None` if the hook couldn't be run, or was killed by a signal.
+    pub exit_code: Option<i32>,
+    pub stdout:    String,
+    pub stderr:    String,
+}
+
+impl HealthCheckHookExit {
+    pub fn new(result: HealthCheckResult, exit_code: Option<i32>, output: &HookOutput) -> Self {
+        HealthCheckHookExit { result,
+                              exit_code,
+                              stdout: output_tail(output.stdout()),
+                              stderr: output_tail(output.stderr()) }
+    }
+}
+
+/// One entry in a service's health check history.
+#[derive(Clone, Debug, Serialize)]
+pub struct HealthCheckRecord {
+    /// When the check finished, in seconds since the Unix epoch.
+    pub timestamp:   i64,
+    #[serde(serialize_with = "serialize_status")]
+    pub status:      HealthCheckResult,
+    pub exit_code:   Option<i32>,
+    /// `None` if the service has no health check hook.
+    pub duration_ms: Option<u64>,
+    pub stdout:      String,
+    pub stderr:      String,
+}
+
+impl HealthCheckRecord {
+    fn new(exit: HealthCheckHookExit, duration: Option<Duration>) -> Self {
+        HealthCheckRecord { timestamp:   time::get_time().sec,
+                            status:      exit.result,
+                            exit_code:   exit.exit_code,
+                            duration_ms: duration.map(|d| {
+                                                     d.as_secs() * 1000
+                                                     + u64::from(d.subsec_millis())
+                                                 }),
+                            stdout:      exit.stdout,
+                            stderr:      exit.stderr, }
+    }
+}
+
+/// The most recent health check results for a service, oldest first.
+#[derive(Debug, Default, Serialize)]
+pub struct HealthCheckHistory(VecDeque<HealthCheckRecord>);
+
+impl HealthCheckHistory {
+    pub fn push(&mut self, record: HealthCheckRecord) {
+        if self.0.len() == HEALTH_CHECK_HISTORY_SIZE {
+            self.0.pop_front();
+        }
+        self.0.push_back(record);
+    }
+
+    pub fn latest(&self) -> Option<&HealthCheckRecord> { self.0.back() }
+
+    pub fn records(&self) -> impl Iterator<Item = &HealthCheckRecord> { self.0.iter() }
+}
+
+/// Show the status as the `/health` endpoint does.
+fn serialize_status<S>(status: &HealthCheckResult, serializer: S) -> result::Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    serializer.collect_str(status)
+}
+
+/// The end of a hook's output, at most `HEALTH_CHECK_OUTPUT_LIMIT`
+/// bytes of it.
+fn output_tail<R: Read>(reader: Option<R>) -> String {
+    let mut output = Vec::new();
+    if let Some(mut reader) = reader {
+        if let Err(err) = reader.read_to_end(&mut output) {
+            debug!("Unable to read health check output, {}", err);
+        }
+    }
+    let start = output.len().saturating_sub(HEALTH_CHECK_OUTPUT_LIMIT);
+    String::from_utf8_lossy(&output[start..]).into_owned()
+}
+
 impl fmt::Display for HealthCheckResult {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let msg = match *self {
@@ -137,7 +246,7 @@ impl State {
                                                   package,
                                                   svc_encrypted_password);
             Either::A(hr.into_future()
-                        .map(|(result, duration)| (result, Some(duration))))
+                        .map(|(exit, duration)| (exit, Some(duration))))
         } else {
             let status = match supervisor.lock()
                                          .expect("couldn't unlock supervisor")
@@ -146,23 +255,32 @@ impl State {
                 (true, _) => HealthCheckResult::Ok,
                 (false, _) => HealthCheckResult::Critical,
             };
+            let exit = HealthCheckHookExit { result: status,
+                                             ..Default::default() };
             // no hook means no execution time!
-            Either::B(lazy(move || Ok((status, None::<Duration>))))
+            Either::B(lazy(move || Ok((exit, None::<Duration>))))
         }.map_err(move |e| {
              error!("Error running health check hook for {}: {:?}",
                     service_group_ref, e)
          })
-         .and_then(move |(check_result, duration)| {
+         .and_then(move |(exit, duration)| {
+             let check_result = exit.result;
              event::health_check(service_event_metadata, check_result, duration);
              debug!("Caching HealthCheckResult = '{}' for '{}'",
                     check_result, service_group);
 
              *service_health_result.lock()
                                    .expect("Could not unlock service_health_result") = check_result;
-             gateway_state.write()
-                          .expect("GatewayState lock is poisoned")
-                          .health_check_data
-                          .insert(service_group.deref().clone(), check_result);
+             {
+                 let mut gs = gateway_state.write()
+                                           .expect("GatewayState lock is poisoned");
+                 gs.health_check_data
+                   .insert(service_group.deref().clone(), check_result);
+                 gs.health_check_history
+                   .entry(service_group.deref().clone())
+                   .or_default()
+                   .push(HealthCheckRecord::new(exit, duration));
+             }
 
              let interval = if check_result == HealthCheckResult::Ok {
                  // routine health check
@@ -198,10 +316,6 @@ impl State {
     /// delay, forever.
     pub fn check_repeatedly(self) -> impl Future<Item = (), Error = ()> {
         future::loop_fn(self, move |state| {
-            // TODO (CM): If we wanted to keep track of how many times
-            // a health check has failed in the past X executions, or
-            // do similar historical tracking, here's where we'd do
-            // it.
             let service_group = state.service_group.clone();
             state.clone().single_iteration().then(move |res| {
                                                 if res.is_ok() {
@@ -219,3 +333,42 @@ impl State {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(status: HealthCheckResult) -> HealthCheckRecord {
+        HealthCheckRecord::new(HealthCheckHookExit { result: status,
+                                                     ..Default::default() },
+                               None)
+    }
+
+    #[test]
+    fn history_keeps_only_the_most_recent_records() {
+        let mut history = HealthCheckHistory::default();
+        history.push(record(HealthCheckResult::Critical));
+        for _ in 0..HEALTH_CHECK_HISTORY_SIZE {
+            history.push(record(HealthCheckResult::Ok));
+        }
+        history.push(record(HealthCheckResult::Warning));
+
+        assert_eq!(history.records().count(), HEALTH_CHECK_HISTORY_SIZE);
+        assert!(history.records()
+                       .all(|r| r.status != HealthCheckResult::Critical));
+        assert_eq!(history.latest().unwrap().status, HealthCheckResult::Warning);
+    }
+
+    #[test]
+    fn output_tail_keeps_the_end_of_long_output() {
+        let output = format!("{}end", "x".repeat(HEALTH_CHECK_OUTPUT_LIMIT));
+        let tail = output_tail(Some(output.as_bytes()));
+        assert_eq!(tail.len(), HEALTH_CHECK_OUTPUT_LIMIT);
+        assert!(tail.ends_with("xend"));
+    }
+
+    #[test]
+    fn output_tail_of_missing_output_is_empty() {
+        assert_eq!(output_tail::<&[u8]>(None), "");
+    }
+}
@@ -16,7 +16,8 @@ use futures::{sync::oneshot,
               IntoFuture};
 use habitat_common::templating::hooks::Hook;
 use habitat_core::service::ServiceGroup;
-use std::{sync::Arc,
+use std::{sync::{Arc,
+                 Mutex},
           thread,
           time::{Duration,
                  Instant}};
@@ -26,6 +27,8 @@ pub struct HookRunner<H: Hook + Sync> {
     service_group: ServiceGroup,
     pkg:           Pkg,
     passwd:        Option<String>,
+    /// The hook's process, while it's running.
+    pid:           Arc<Mutex<Option<u32>>>,
 }
 
 impl<H> HookRunner<H> where H: Hook + Sync
@@ -38,8 +41,13 @@ impl<H> HookRunner<H> where H: Hook + Sync
         HookRunner { hook,
                      service_group,
                      pkg,
-                     passwd }
+                     passwd,
+                     pid: Arc::new(Mutex::new(None)) }
     }
+
+    /// The ID of the hook's process while it's running, so that a caller which gives up
+    /// waiting on the hook can kill it.
+    pub fn pid(&self) -> Arc<Mutex<Option<u32>>> { Arc::clone(&self.pid) }
 }
 impl<H: Hook + Sync + 'static> IntoFuture for HookRunner<H> {
     type Error = SupError;
@@ -63,13 +71,23 @@ impl<H: Hook + Sync + 'static> IntoFuture for HookRunner<H> {
                                       // we're not able to use the same timer for both :(
                                       let _timer = hook_timer(H::file_name());
                                       let start = Instant::now();
-                                      let exit_value = self.hook.run(&self.service_group,
-                                                                     &self.pkg,
-                                                                     self.passwd.as_ref());
+                                      let exit_value =
+                                          self.hook.run_and_watch(&self.service_group,
+                                                                  &self.pkg,
+                                                                  self.passwd.as_ref(),
+                                                                  |pid| {
+                                                                      set_pid(&self.pid, Some(pid))
+                                                                  });
+                                      set_pid(&self.pid, None);
                                       let run_time = start.elapsed();
-                                      tx.send((exit_value, run_time))
-                                        .expect("Couldn't send oneshot signal from HookRunner: \
-                                                 receiver went away");
+                                      // The receiver may legitimately be gone
+                                      // if the caller stopped waiting on us
+                                      // (e.g., a timed-out pre-stop hook).
+                                      if tx.send((exit_value, run_time)).is_err() {
+                                          debug!("HookRunner receiver went away before {} \
+                                                  finished",
+                                                 H::file_name());
+                                      }
                                   });
 
         match handle_result {
@@ -78,3 +96,7 @@ impl<H: Hook + Sync + 'static> IntoFuture for HookRunner<H> {
         }
     }
 }
+
+fn set_pid(slot: &Mutex<Option<u32>>, pid: Option<u32>) {
+    *slot.lock().expect("Hook pid lock is poisoned") = pid;
+}
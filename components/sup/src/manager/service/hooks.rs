@@ -46,6 +46,8 @@ impl Hook for FileUpdatedHook {
 
     fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
 
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer { &mut self.render_pair.renderer }
+
     fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
 
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
@@ -59,7 +61,7 @@ pub struct HealthCheckHook {
 }
 
 impl Hook for HealthCheckHook {
-    type ExitValue = health::HealthCheckResult;
+    type ExitValue = health::HealthCheckHookExit;
 
     fn file_name() -> &'static str { "health-check" }
 
@@ -69,9 +71,13 @@ impl Hook for HealthCheckHook {
                           stderr_log_path: hooks::stderr_log_path::<Self>(package_name), }
     }
 
-    fn handle_exit<'a>(&self, pkg: &Pkg, _: &'a HookOutput, status: ExitStatus) -> Self::ExitValue {
+    fn handle_exit<'a>(&self,
+                       pkg: &Pkg,
+                       output: &'a HookOutput,
+                       status: ExitStatus)
+                       -> Self::ExitValue {
         let pkg_name = &pkg.name;
-        match status.code() {
+        let result = match status.code() {
             Some(0) => health::HealthCheckResult::Ok,
             Some(1) => health::HealthCheckResult::Warning,
             Some(2) => health::HealthCheckResult::Critical,
@@ -85,13 +91,16 @@ impl Hook for HealthCheckHook {
                 Self::output_termination_message(pkg_name, status);
                 health::HealthCheckResult::default()
             }
-        }
+        };
+        health::HealthCheckHookExit::new(result, status.code(), output)
     }
 
     fn path(&self) -> &Path { &self.render_pair.path }
 
     fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
 
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer { &mut self.render_pair.renderer }
+
     fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
 
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
@@ -136,6 +145,53 @@ impl Hook for InitHook {
 
     fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
 
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer { &mut self.render_pair.renderer }
+
+    fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
+
+    fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreStartHook {
+    render_pair:     RenderPair,
+    stdout_log_path: PathBuf,
+    stderr_log_path: PathBuf,
+}
+
+impl Hook for PreStartHook {
+    type ExitValue = bool;
+
+    fn file_name() -> &'static str { "pre-start" }
+
+    fn new(package_name: &str, pair: RenderPair) -> Self {
+        PreStartHook { render_pair:     pair,
+                       stdout_log_path: hooks::stdout_log_path::<Self>(package_name),
+                       stderr_log_path: hooks::stderr_log_path::<Self>(package_name), }
+    }
+
+    fn handle_exit<'a>(&self, pkg: &Pkg, _: &'a HookOutput, status: ExitStatus) -> Self::ExitValue {
+        let pkg_name = &pkg.name;
+        match status.code() {
+            Some(0) => true,
+            Some(code) => {
+                outputln!(preamble pkg_name, "Pre start failed! '{}' exited with \
+                    status code {}", Self::file_name(), code);
+                false
+            }
+            None => {
+                Self::output_termination_message(pkg_name, status);
+                false
+            }
+        }
+    }
+
+    fn path(&self) -> &Path { &self.render_pair.path }
+
+    fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
+
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer { &mut self.render_pair.renderer }
+
     fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
 
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
@@ -180,6 +236,8 @@ impl Hook for RunHook {
 
     fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
 
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer { &mut self.render_pair.renderer }
+
     fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
 
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
@@ -217,6 +275,8 @@ impl Hook for PostRunHook {
 
     fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
 
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer { &mut self.render_pair.renderer }
+
     fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
 
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
@@ -260,6 +320,8 @@ impl Hook for ReloadHook {
 
     fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
 
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer { &mut self.render_pair.renderer }
+
     fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
 
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
@@ -297,6 +359,8 @@ impl Hook for ReconfigureHook {
 
     fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
 
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer { &mut self.render_pair.renderer }
+
     fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
 
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
@@ -370,6 +434,53 @@ impl Hook for SuitabilityHook {
 
     fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
 
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer { &mut self.render_pair.renderer }
+
+    fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
+
+    fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreStopHook {
+    render_pair:     RenderPair,
+    stdout_log_path: PathBuf,
+    stderr_log_path: PathBuf,
+}
+
+impl Hook for PreStopHook {
+    type ExitValue = bool;
+
+    fn file_name() -> &'static str { "pre-stop" }
+
+    fn new(package_name: &str, pair: RenderPair) -> Self {
+        PreStopHook { render_pair:     pair,
+                      stdout_log_path: hooks::stdout_log_path::<Self>(package_name),
+                      stderr_log_path: hooks::stderr_log_path::<Self>(package_name), }
+    }
+
+    fn handle_exit<'a>(&self, pkg: &Pkg, _: &'a HookOutput, status: ExitStatus) -> Self::ExitValue {
+        let pkg_name = &pkg.name;
+        match status.code() {
+            Some(0) => true,
+            Some(code) => {
+                outputln!(preamble pkg_name, "Pre stop failed! '{}' exited with \
+                    status code {}", Self::file_name(), code);
+                false
+            }
+            None => {
+                Self::output_termination_message(pkg_name, status);
+                false
+            }
+        }
+    }
+
+    fn path(&self) -> &Path { &self.render_pair.path }
+
+    fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
+
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer { &mut self.render_pair.renderer }
+
     fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
 
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
@@ -413,6 +524,8 @@ impl Hook for PostStopHook {
 
     fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
 
+    fn renderer_mut(&mut self) -> &mut TemplateRenderer { &mut self.render_pair.renderer }
+
     fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
 
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
@@ -428,37 +541,53 @@ pub struct HookTable {
     pub reload:       Option<ReloadHook>,
     pub reconfigure:  Option<ReconfigureHook>,
     pub suitability:  Option<SuitabilityHook>,
+    pub pre_start:    Option<PreStartHook>,
     pub run:          Option<RunHook>,
     pub post_run:     Option<PostRunHook>,
+    pub pre_stop:     Option<Arc<PreStopHook>>,
     pub post_stop:    Option<Arc<PostStopHook>>,
 }
 
 impl HookTable {
-    /// Read all available hook templates from the table's package directory into the table.
-    pub fn load<P, T>(package_name: &str, templates: T, hooks_path: P) -> Self
+    /// Read all available hook templates from the table's package directory into the table, with
+    /// the partials in `partials` available to each. If `strict` is set, rendering a hook that
+    /// refers to undefined variables fails.
+    pub fn load<P, T, Q>(package_name: &str,
+                         templates: T,
+                         hooks_path: P,
+                         partials: Q,
+                         strict: bool)
+                         -> Self
         where P: AsRef<Path>,
-              T: AsRef<Path>
+              T: AsRef<Path>,
+              Q: AsRef<Path>
     {
         let mut table = HookTable::default();
-        if let Ok(meta) = std::fs::metadata(templates.as_ref()) {
+        let (hooks_path, templates) = (hooks_path.as_ref(), templates.as_ref());
+        let loader = HookLoader { package_name,
+                                  hooks_path,
+                                  templates,
+                                  partials: partials.as_ref(),
+                                  strict };
+        if let Ok(meta) = std::fs::metadata(templates) {
             if meta.is_dir() {
-                table.file_updated = FileUpdatedHook::load(package_name, &hooks_path, &templates);
-                table.health_check =
-                    HealthCheckHook::load(package_name, &hooks_path, &templates).map(Arc::new);
-                table.suitability = SuitabilityHook::load(package_name, &hooks_path, &templates);
-                table.init = InitHook::load(package_name, &hooks_path, &templates);
-                table.reload = ReloadHook::load(package_name, &hooks_path, &templates);
-                table.reconfigure = ReconfigureHook::load(package_name, &hooks_path, &templates);
-                table.run = RunHook::load(package_name, &hooks_path, &templates);
-                table.post_run = PostRunHook::load(package_name, &hooks_path, &templates);
-                table.post_stop =
-                    PostStopHook::load(package_name, &hooks_path, &templates).map(Arc::new);
+                table.file_updated = loader.load();
+                table.health_check = loader.load::<HealthCheckHook>().map(Arc::new);
+                table.suitability = loader.load();
+                table.init = loader.load();
+                table.reload = loader.load();
+                table.reconfigure = loader.load();
+                table.pre_start = loader.load();
+                table.run = loader.load();
+                table.post_run = loader.load();
+                table.pre_stop = loader.load::<PreStopHook>().map(Arc::new);
+                table.post_stop = loader.load::<PostStopHook>().map(Arc::new);
             }
         }
         debug!("{}, Hooks loaded, destination={}, templates={}",
                package_name,
-               hooks_path.as_ref().display(),
-               templates.as_ref().display());
+               hooks_path.display(),
+               templates.display());
         table
     }
 
@@ -489,18 +618,66 @@ impl HookTable {
         if let Some(ref hook) = self.suitability {
             changed |= self.compile_one(hook, service_group, ctx);
         }
+        if let Some(ref hook) = self.pre_start {
+            changed |= self.compile_one(hook, service_group, ctx);
+        }
         if let Some(ref hook) = self.run {
             changed |= self.compile_one(hook, service_group, ctx);
         }
         if let Some(ref hook) = self.post_run {
             changed |= self.compile_one(hook, service_group, ctx);
         }
+        if let Some(ref hook) = self.pre_stop {
+            changed |= self.compile_one(hook.as_ref(), service_group, ctx);
+        }
         if let Some(ref hook) = self.post_stop {
             changed |= self.compile_one(hook.as_ref(), service_group, ctx);
         }
         changed
     }
 
+    /// Render all loaded hooks from the table without writing them, returning the path in their
+    /// destination service directory that each one is compiled to along with its content.
+    pub fn render<T>(&self, ctx: &T) -> habitat_common::error::Result<Vec<(PathBuf, String)>>
+        where T: Serialize
+    {
+        let mut rendered = vec![];
+        if let Some(ref hook) = self.file_updated {
+            rendered.push(hook.render(ctx)?);
+        }
+        if let Some(ref hook) = self.health_check {
+            rendered.push(hook.render(ctx)?);
+        }
+        if let Some(ref hook) = self.init {
+            rendered.push(hook.render(ctx)?);
+        }
+        if let Some(ref hook) = self.reload {
+            rendered.push(hook.render(ctx)?);
+        }
+        if let Some(ref hook) = self.reconfigure {
+            rendered.push(hook.render(ctx)?);
+        }
+        if let Some(ref hook) = self.suitability {
+            rendered.push(hook.render(ctx)?);
+        }
+        if let Some(ref hook) = self.pre_start {
+            rendered.push(hook.render(ctx)?);
+        }
+        if let Some(ref hook) = self.run {
+            rendered.push(hook.render(ctx)?);
+        }
+        if let Some(ref hook) = self.post_run {
+            rendered.push(hook.render(ctx)?);
+        }
+        if let Some(ref hook) = self.pre_stop {
+            rendered.push(hook.render(ctx)?);
+        }
+        if let Some(ref hook) = self.post_stop {
+            rendered.push(hook.render(ctx)?);
+        }
+        Ok(rendered)
+    }
+
     fn compile_one<H, T>(&self, hook: &H, service_group: &str, ctx: &T) -> bool
         where H: Hook,
               T: Serialize
@@ -516,6 +693,34 @@ impl HookTable {
     }
 }
 
+/// What every hook of a `HookTable` is loaded with.
+struct HookLoader<'a> {
+    package_name: &'a str,
+    hooks_path:   &'a Path,
+    templates:    &'a Path,
+    partials:     &'a Path,
+    strict:       bool,
+}
+
+impl<'a> HookLoader<'a> {
+    fn load<H>(&self) -> Option<H>
+        where H: Hook
+    {
+        H::load(self.package_name, self.hooks_path, self.templates).map(|mut hook| {
+            let renderer = hook.renderer_mut();
+            renderer.set_strict(self.strict);
+            if let Err(err) = renderer.register_partials(self.partials) {
+                outputln!(preamble self.package_name,
+                          "Failed to load partials for the {} hook from {}: {}",
+                          H::file_name(),
+                          self.partials.display(),
+                          err);
+            }
+            hook
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{super::RenderContext,
@@ -536,7 +741,8 @@ mod tests {
     use habitat_common::{cli::FS_ROOT,
                          templating::{config::Cfg,
                                       package::Pkg,
-                                      test_helpers::*},
+                                      test_helpers::*,
+                                      PARTIALS_DIR},
                          types::ListenCtlAddr};
     use habitat_core::{fs::cache_key_path,
                        package::{PackageIdent,
@@ -565,6 +771,8 @@ mod tests {
                       HealthCheckHook
                       InitHook
                       PostRunHook
+                      PreStartHook
+                      PreStopHook
                       ReconfigureHook
                       ReloadHook
                       RunHook
@@ -672,7 +880,11 @@ mod tests {
         // END RENDER CONTEXT SETUP
         ////////////////////////////////////////////////////////////////////////
 
-        let hook_table = HookTable::load(&service_group, &template_path, &hooks_path);
+        let hook_table = HookTable::load(&service_group,
+                                         &template_path,
+                                         &hooks_path,
+                                         template_path.join(PARTIALS_DIR),
+                                         false);
         assert_eq!(hook_table.compile(&service_group, &ctx), true);
 
         // Verify init hook
@@ -685,6 +897,15 @@ mod tests {
         let run_hook_content = file_content(&hook_table.run.as_ref().expect("no run hook??"));
         assert_eq!(run_hook_content, expected_run_hook);
 
+        // Verify pre-start hook
+        let pre_start_hook_content =
+            file_content(&hook_table.pre_start.as_ref().expect("no pre-start hook??"));
+        let expected_pre_start_hook = "#!/bin/bash\n\necho \"Preparing to start with Hello\"\n";
+        assert_eq!(pre_start_hook_content, expected_pre_start_hook);
+
+        // No pre-stop template was provided
+        assert!(hook_table.pre_stop.is_none());
+
         // Recompiling again results in no changes
         assert_eq!(hook_table.compile(&service_group, &ctx), false);
 
@@ -8,23 +8,40 @@
 // here. Ideally, those would exist only at the periphery of the
 // system, and we'd use separate internal types for our core logic.
 
+mod config_history;
 mod context;
 mod health;
 mod hook_runner;
 mod hooks;
+mod process_stats;
+mod restart;
+mod rollout;
 mod spawned_future;
 pub mod spec;
 mod supervisor;
 mod terminator;
+mod update_window;
 
-use self::{context::RenderContext,
+use self::{config_history::ConfigHistory,
+           context::RenderContext,
            hooks::HookTable,
+           process_stats::process_tree_stats,
+           restart::{RestartOutcome,
+                     RestartTracker},
            supervisor::Supervisor};
-pub use self::{health::HealthCheckResult,
+pub use self::{config_history::ConfigVersion,
+               health::{HealthCheckHistory,
+                        HealthCheckRecord,
+                        HealthCheckResult},
                hooks::HealthCheckHook,
+               restart::RestartBackoff,
+               rollout::{BatchSize,
+                         UpdateRollback,
+                         UpdateRollout},
                spec::{DesiredState,
                       IntoServiceSpec,
-                      ServiceSpec}};
+                      ServiceSpec},
+               update_window::UpdateWindow};
 use crate::{census::{CensusGroup,
                      CensusRing,
                      ElectionStatus,
@@ -32,11 +49,14 @@ use crate::{census::{CensusGroup,
             error::{Error,
                     Result,
                     SupError},
+            event,
             manager::{action::ShutdownSpec,
                       FsCfg,
                       GatewayState,
                       Sys},
-            sup_futures};
+            sup_futures,
+            sys,
+            util};
 use futures::{future,
               Future,
               IntoFuture};
@@ -48,26 +68,37 @@ pub use habitat_common::templating::{config::{Cfg,
                                                PkgProxy}};
 use habitat_common::{outputln,
                      templating::{config::CfgRenderer,
-                                  hooks::Hook}};
+                                  hooks::Hook,
+                                  secrets,
+                                  PARTIALS_DIR}};
 use habitat_core::{crypto::hash,
                    fs::{atomic_write,
                         svc_hooks_path,
                         SvcDir,
                         FS_ROOT_PATH},
+                   os::process::Pid,
                    package::{metadata::Bind,
                              PackageIdent,
-                             PackageInstall},
+                             PackageInstall,
+                             VersionConstraint},
                    service::{HealthCheckInterval,
+                             LogFileConfig,
+                             LogFileOverrides,
+                             ResourceLimits,
                              ServiceBind,
                              ServiceGroup},
                    ChannelIdent};
 use habitat_launcher_client::LauncherCli;
 use habitat_sup_protocol::types::BindingMode;
 pub use habitat_sup_protocol::types::{ProcessState,
+                                      RestartPolicy,
                                       Topology,
                                       UpdateStrategy};
-use prometheus::{HistogramTimer,
-                 HistogramVec};
+use prometheus::{CounterVec,
+                 HistogramTimer,
+                 HistogramVec,
+                 IntCounterVec,
+                 IntGaugeVec};
 use serde::{ser::SerializeStruct,
             Serialize,
             Serializer};
@@ -81,9 +112,11 @@ use std::{self,
           result,
           sync::{Arc,
                  Mutex,
-                 RwLock}};
+                 RwLock},
+          time::Duration as StdDuration};
 use time::Timespec;
 use tokio::runtime::TaskExecutor;
+use tokio_timer::Timeout;
 
 static LOGKEY: &'static str = "SR";
 
@@ -95,8 +128,63 @@ lazy_static! {
         register_histogram_vec!("hab_sup_hook_duration_seconds",
                                 "The time it takes for a hook to run",
                                 &["hook"]).unwrap();
+    static ref CGROUP_CPU_SECONDS: CounterVec =
+        register_counter_vec!("hab_sup_service_cgroup_cpu_seconds_total",
+                              "CPU time used by a resource-limited service",
+                              &["service_group", "ident"]).unwrap();
+    static ref CGROUP_CPU_THROTTLED_SECONDS: CounterVec =
+        register_counter_vec!("hab_sup_service_cgroup_cpu_throttled_seconds_total",
+                              "Time a resource-limited service was held back by its CPU quota",
+                              &["service_group", "ident"]).unwrap();
+    static ref CGROUP_MEMORY_BYTES: IntGaugeVec =
+        register_int_gauge_vec!("hab_sup_service_cgroup_memory_bytes",
+                                "Memory used by a resource-limited service",
+                                &["service_group", "ident"]).unwrap();
+    static ref CGROUP_PIDS: IntGaugeVec =
+        register_int_gauge_vec!("hab_sup_service_cgroup_pids",
+                                "Processes and threads in a resource-limited service",
+                                &["service_group", "ident"]).unwrap();
+    static ref CGROUP_OOM_KILLS: IntCounterVec =
+        register_int_counter_vec!("hab_sup_service_cgroup_oom_kills_total",
+                                  "Processes of a resource-limited service killed for exceeding \
+                                   its memory limit",
+                                  &["service_group", "ident"]).unwrap();
+    static ref SERVICE_MEMORY_BYTES: IntGaugeVec =
+        register_int_gauge_vec!("hab_sup_service_resident_memory_bytes",
+                                "Resident memory of a service's process and its children",
+                                &["service_group", "ident"]).unwrap();
+    static ref SERVICE_CPU_SECONDS: CounterVec =
+        register_counter_vec!("hab_sup_service_cpu_seconds_total",
+                              "CPU time used by a service's process and its children",
+                              &["service_group", "ident"]).unwrap();
+    static ref SERVICE_OPEN_FDS: IntGaugeVec =
+        register_int_gauge_vec!("hab_sup_service_open_fds",
+                                "Open file descriptors of a service's process and its children",
+                                &["service_group", "ident"]).unwrap();
+    static ref SERVICE_THREADS: IntGaugeVec =
+        register_int_gauge_vec!("hab_sup_service_threads",
+                                "Threads of a service's process and its children",
+                                &["service_group", "ident"]).unwrap();
+    static ref SERVICE_RESTARTS: IntCounterVec =
+        register_int_counter_vec!("hab_sup_service_restarts_total",
+                                  "Times a service's process has been restarted since it was \
+                                   loaded",
+                                  &["service_group", "ident"]).unwrap();
+    static ref SERVICE_HEALTH_CHECK_STATUS: IntGaugeVec =
+        register_int_gauge_vec!("hab_sup_service_health_check_status",
+                                "Result of a service's last health check: 0 ok, 1 warning, \
+                                 2 critical, 3 unknown",
+                                &["service_group", "ident"]).unwrap();
+    static ref SERVICE_STATE_AGE_SECONDS: IntGaugeVec =
+        register_int_gauge_vec!("hab_sup_service_state_age_seconds",
+                                "Seconds since a service's process last went up or down",
+                                &["service_group", "ident"]).unwrap();
 }
 
+/// How often to record the resource usage and status of services in
+/// the Supervisor's metrics.
+const METRICS_INTERVAL_SECS: i64 = 10;
+
 /// When evaluating whether a particular service group can satisfy a
 /// bind of the Service, there are several states it can be
 /// in. Depending on which point in the lifecycle of the Service we
@@ -127,8 +215,12 @@ pub struct Service {
     pub desired_state:       DesiredState,
     pub spec_file:           PathBuf,
     pub spec_ident:          PackageIdent,
+    pub version_constraint:  Option<VersionConstraint>,
     pub topology:            Topology,
     pub update_strategy:     UpdateStrategy,
+    pub update_windows:      Vec<UpdateWindow>,
+    pub update_rollout:      UpdateRollout,
+    pub update_rollback:     Option<UpdateRollback>,
     pub cfg:                 Cfg,
     pub pkg:                 Pkg,
     pub sys:                 Arc<Sys>,
@@ -137,6 +229,16 @@ pub struct Service {
 
     #[serde(skip_serializing)]
     config_renderer: CfgRenderer,
+    /// Whether the service's spec asks for its templates to be rendered in strict mode. Its
+    /// package may also ask for that.
+    strict_templates: bool,
+    /// Why the latest render of the service's templates was refused, if it was.
+    render_error: Option<String>,
+    /// Whether the service's templates have ever rendered, so there's something to start it with.
+    rendered: bool,
+    /// The configurations that have been applied to the service group with `hab config apply`.
+    #[serde(skip_serializing)]
+    config_history: ConfigHistory,
     // Note: This field is really only needed for serializing a
     // Service in the gateway (see ServiceProxy's Serialize
     // implementation). Ideally, we could get rid of this, since we're
@@ -148,6 +250,9 @@ pub struct Service {
     // hook, we need to wrap some Arc<Mutex<_>> protection around it
     // :(
     health_check_result: Arc<Mutex<HealthCheckResult>>,
+    /// The health check result in our latest service rumor.
+    #[serde(skip_serializing)]
+    gossiped_health: HealthCheckResult,
     last_election_status: ElectionStatus,
     needs_reload: bool,
     needs_reconfiguration: bool,
@@ -183,6 +288,16 @@ pub struct Service {
     supervisor: Arc<Mutex<Supervisor>>,
     svc_encrypted_password: Option<String>,
     health_check_interval: HealthCheckInterval,
+    restart_policy: RestartPolicy,
+    restart_backoff: RestartBackoff,
+    resource_limits: ResourceLimits,
+    #[serde(skip_serializing)]
+    metrics_updated: Timespec,
+    /// Log file settings from the service's spec.
+    log_file: LogFileOverrides,
+    /// The Supervisor's log file settings, for those the spec leaves unset.
+    #[serde(skip_serializing)]
+    default_log_file: Option<LogFileConfig>,
 
     #[serde(skip_serializing)]
     /// Whether a service's default configuration changed on a package
@@ -204,7 +319,8 @@ impl Service {
            spec: ServiceSpec,
            manager_fs_cfg: Arc<FsCfg>,
            organization: Option<&str>,
-           gateway_state: Arc<RwLock<GatewayState>>)
+           gateway_state: Arc<RwLock<GatewayState>>,
+           default_log_file: Option<LogFileConfig>)
            -> Result<Service> {
         spec.validate(&package)?;
         let all_pkg_binds = package.all_binds()?;
@@ -216,23 +332,37 @@ impl Service {
                                               organization)?;
         let config_root = Self::config_root(&pkg, spec.config_from.as_ref());
         let hooks_root = Self::hooks_root(&pkg, spec.config_from.as_ref());
+        let strict = spec.strict_templates || package.strict_templates()?;
+        let mut config_renderer = CfgRenderer::new(&config_root)?;
+        config_renderer.set_strict(strict);
         Ok(Service { sys,
                      cfg: Cfg::new(&pkg, spec.config_from.as_ref())?,
-                     config_renderer: CfgRenderer::new(&config_root)?,
+                     config_renderer,
+                     strict_templates: spec.strict_templates,
+                     render_error: None,
+                     rendered: false,
+                     config_history: ConfigHistory::new(&manager_fs_cfg.data_path,
+                                                        &service_group),
                      bldr_url: spec.bldr_url,
                      channel: spec.channel,
                      desired_state: spec.desired_state,
                      health_check_result: Default::default(),
+                     gossiped_health: Default::default(),
                      hooks: HookTable::load(&pkg.name,
                                             &hooks_root,
-                                            svc_hooks_path(&service_group.service())),
+                                            svc_hooks_path(&service_group.service()),
+                                            config_root.join(PARTIALS_DIR),
+                                            strict),
                      initialized: false,
                      last_election_status: ElectionStatus::None,
                      needs_reload: false,
                      needs_reconfiguration: false,
                      user_config_updated: false,
                      manager_fs_cfg,
-                     supervisor: Arc::new(Mutex::new(Supervisor::new(&service_group))),
+                     supervisor:
+                         Arc::new(Mutex::new(Supervisor::new(&service_group,
+                                                             RestartTracker::new(spec.restart_policy,
+                                                                                 spec.restart_backoff)))),
                      pkg,
                      service_group,
                      binds: spec.binds,
@@ -240,17 +370,65 @@ impl Service {
                      unsatisfied_binds: HashSet::new(),
                      binding_mode: spec.binding_mode,
                      spec_ident: spec.ident,
+                     version_constraint: spec.version_constraint,
                      spec_file,
                      topology: spec.topology,
                      update_strategy: spec.update_strategy,
+                     update_windows: spec.update_windows,
+                     update_rollout: spec.update_rollout,
+                     update_rollback: spec.update_rollback,
                      config_from: spec.config_from,
                      svc_encrypted_password: spec.svc_encrypted_password,
                      health_check_interval: spec.health_check_interval,
+                     restart_policy: spec.restart_policy,
+                     restart_backoff: spec.restart_backoff,
+                     resource_limits: spec.resource_limits,
+                     metrics_updated: Timespec::new(0, 0),
+                     log_file: spec.log_file,
+                     default_log_file,
                      defaults_updated: false,
                      gateway_state,
                      health_check_handle: None })
     }
 
+    /// The configurations that have been applied to the service group, oldest first.
+    pub fn config_history(&self) -> Result<Vec<ConfigVersion>> { self.config_history.versions() }
+
+    /// Renders the service's configuration files and hooks as they would be if the given
+    /// configuration were applied to its service group, without writing any of them. Returns a
+    /// unified diff against what's currently rendered for each file that would change, with the
+    /// values of any secrets redacted.
+    pub fn cfg_changes(&self,
+                       census_ring: &CensusRing,
+                       gossip: toml::value::Table)
+                       -> Result<Vec<(PathBuf, String)>> {
+        let mut cfg = self.cfg.clone();
+        cfg.gossip = Some(gossip);
+        let ctx = self.render_context_with(&cfg, census_ring);
+        let mut rendered = self.config_renderer
+                               .render(&self.pkg.svc_config_path, &ctx)?;
+        rendered.extend(self.hooks.render(&ctx)?);
+        let service_group = self.service_group.to_string();
+        Ok(rendered.into_iter()
+                   .filter_map(|(path, content)| {
+                       // Both sides are redacted before they're compared, so that a secret
+                       // spanning several lines is still recognized as a whole.
+                       let name = path.display().to_string();
+                       let content = secrets::redact(&service_group, &content);
+                       let diff = match fs::read_to_string(&path) {
+                           Ok(current) => {
+                               util::diff::unified(&secrets::redact(&service_group, &current),
+                                                   &content,
+                                                   &name,
+                                                   &name)
+                           }
+                           Err(_) => util::diff::unified("", &content, "/dev/null", &name),
+                       };
+                       diff.map(|diff| (path, diff))
+                   })
+                   .collect())
+    }
+
     /// Returns the config root given the package and optional config-from path.
     fn config_root(package: &Pkg, config_from: Option<&PathBuf>) -> PathBuf {
         config_from.and_then(|p| Some(p.as_path()))
@@ -269,17 +447,24 @@ impl Service {
                 spec: ServiceSpec,
                 manager_fs_cfg: Arc<FsCfg>,
                 organization: Option<&str>,
-                gateway_state: Arc<RwLock<GatewayState>>)
+                gateway_state: Arc<RwLock<GatewayState>>,
+                default_log_file: Option<LogFileConfig>)
                 -> Result<Service> {
         // The package for a spec should already be installed.
         let fs_root_path = Path::new(&*FS_ROOT_PATH);
-        let package = PackageInstall::load(&spec.ident, Some(fs_root_path))?;
+        let package = match spec.version_constraint {
+            Some(ref constraint) => {
+                PackageInstall::load_matching(&spec.ident, constraint, Some(fs_root_path))?
+            }
+            None => PackageInstall::load(&spec.ident, Some(fs_root_path))?,
+        };
         Ok(Self::new(sys,
                      &package,
                      spec,
                      manager_fs_cfg,
                      organization,
-                     gateway_state)?)
+                     gateway_state,
+                     default_log_file)?)
     }
 
     /// Create the service path for this package.
@@ -290,13 +475,21 @@ impl Service {
     }
 
     fn start(&mut self, launcher: &LauncherCli, executor: &TaskExecutor) {
+        if !self.pre_start() {
+            outputln!(preamble self.service_group,
+                      "Service start aborted; pre-start hook failed");
+            return;
+        }
+
         if let Some(err) = self.supervisor
                                .lock()
                                .expect("Couldn't lock supervisor")
                                .start(&self.pkg,
                                       &self.service_group,
                                       launcher,
-                                      self.svc_encrypted_password.as_ref().map(String::as_str))
+                                      self.svc_encrypted_password.as_ref().map(String::as_str),
+                                      &self.resource_limits,
+                                      self.log_file_config().as_ref())
                                .err()
         {
             outputln!(preamble self.service_group, "Service start failed: {}", err);
@@ -368,19 +561,22 @@ impl Service {
                 shutdown_spec: ShutdownSpec)
                 -> impl Future<Item = (), Error = SupError> {
         self.stop_health_checks();
+        remove_metrics(&self.service_group, &self.pkg.ident);
 
         let service_group = self.service_group.clone();
         let gs = Arc::clone(&self.gateway_state);
+        let supervisor = Arc::clone(&self.supervisor);
 
-        let f = self.supervisor
-                    .lock()
-                    .expect("Couldn't lock supervisor")
-                    .stop(shutdown_spec)
+        let f = self.pre_stop()
+                    .and_then(move |_| {
+                        supervisor.lock()
+                                  .expect("Couldn't lock supervisor")
+                                  .stop(shutdown_spec)
+                    })
                     .and_then(move |_| {
-                        gs.write()
-                          .expect("GatewayState lock is poisoned")
-                          .health_check_data
-                          .remove(&service_group);
+                        let mut gs = gs.write().expect("GatewayState lock is poisoned");
+                        gs.health_check_data.remove(&service_group);
+                        gs.health_check_history.remove(&service_group);
                         Ok(())
                     });
 
@@ -402,6 +598,11 @@ impl Service {
         let _timer = hook_timer("reload");
         self.needs_reload = false;
         if self.process_down() || self.hooks.reload.is_none() {
+            if !self.pre_start() {
+                outputln!(preamble self.service_group,
+                          "Service restart aborted; pre-start hook failed");
+                return;
+            }
             if let Some(err) =
                 self.supervisor
                     .lock()
@@ -409,7 +610,9 @@ impl Service {
                     .restart(&self.pkg,
                              &self.service_group,
                              launcher,
-                             self.svc_encrypted_password.as_ref().map(String::as_ref))
+                             self.svc_encrypted_password.as_ref().map(String::as_ref),
+                             &self.resource_limits,
+                             self.log_file_config().as_ref())
                     .err()
             {
                 outputln!(preamble self.service_group, "Service restart failed: {}", err);
@@ -431,7 +634,7 @@ impl Service {
 
     /// Performs updates and executes hooks.
     ///
-    /// Returns `true` if the service was updated.
+    /// Returns `true` if the service was updated, or its health changed, since the last tick.
     pub fn tick(&mut self,
                 census_ring: &CensusRing,
                 launcher: &LauncherCli,
@@ -464,6 +667,12 @@ impl Service {
             self.file_updated();
         }
 
+        // A service whose templates have never rendered has no configuration or hooks to run
+        // with, so it waits for them to render before it's initialized and started.
+        if !self.rendered && self.render_error.is_some() {
+            return svc_updated;
+        }
+
         match self.topology {
             Topology::Standalone => {
                 self.execute_hooks(launcher, executor);
@@ -511,6 +720,7 @@ impl Service {
                 }
             }
         }
+        self.update_metrics(launcher);
         if svc_updated {
             // The intention here is to do a health check soon after a
             // service's configuration changes, as a way to (among
@@ -525,7 +735,15 @@ impl Service {
             self.restart_health_checks(executor);
         }
 
-        svc_updated
+        // Members of a rolling update wait on each other's health, so
+        // a change in it needs a new rumor too.
+        let health = *self.health_check_result
+                          .lock()
+                          .expect("Couldn't lock health check result");
+        let health_changed = health != self.gossiped_health;
+        self.gossiped_health = health;
+
+        svc_updated || health_changed
     }
 
     pub fn to_spec(&self) -> ServiceSpec {
@@ -536,15 +754,24 @@ impl Service {
         }
         spec.bldr_url = self.bldr_url.clone();
         spec.channel = self.channel.clone();
+        spec.version_constraint = self.version_constraint.clone();
         spec.topology = self.topology;
         spec.update_strategy = self.update_strategy;
+        spec.update_windows = self.update_windows.clone();
+        spec.update_rollout = self.update_rollout;
+        spec.update_rollback = self.update_rollback;
         spec.binds = self.binds.clone();
         spec.binding_mode = self.binding_mode;
         spec.config_from = self.config_from.clone();
+        spec.strict_templates = self.strict_templates;
         if let Some(ref password) = self.svc_encrypted_password {
             spec.svc_encrypted_password = Some(password.clone())
         }
         spec.health_check_interval = self.health_check_interval;
+        spec.restart_policy = self.restart_policy;
+        spec.restart_backoff = self.restart_backoff;
+        spec.resource_limits = self.resource_limits;
+        spec.log_file = self.log_file;
         spec
     }
 
@@ -711,8 +938,22 @@ impl Service {
                 if config.incarnation <= self.cfg.gossip_incarnation {
                     return false;
                 }
-                self.cfg
-                    .set_gossip(config.incarnation, config.value.clone());
+                if let Err(e) = self.cfg
+                                    .set_gossip(config.incarnation, config.value.clone())
+                {
+                    outputln!(preamble self.service_group,
+                              "Rejected configuration version {}: {}", config.incarnation, e);
+                    return false;
+                }
+                if let Err(e) =
+                    self.config_history
+                        .record(config.incarnation, config.encrypted, &config.payload)
+                {
+                    outputln!(preamble self.service_group,
+                              "Unable to keep configuration version {}: {}",
+                              config.incarnation,
+                              e);
+                }
                 true
             }
             None => false,
@@ -742,23 +983,50 @@ impl Service {
         self.defaults_updated = false;
 
         if cfg_changed || census_ring.changed() {
-            let (reload, reconfigure) = {
+            let compiled = {
                 let ctx = self.render_context(census_ring);
 
-                // If any hooks have changed, execute the `reload` hook (if present) or restart the
-                // service.
-                let reload = self.compile_hooks(&ctx);
+                self.check_templates(&ctx).map(|_| {
+                    // If any hooks have changed, execute the `reload` hook (if present) or
+                    // restart the service.
+                    let reload = self.compile_hooks(&ctx);
 
-                // If the configuration has changed, execute the `reload` and `reconfigure` hooks.
-                // Note that the configuration does not necessarily change every time the user
-                // config has (e.g. when only a comment has been added to the latter)
-                let reconfigure = self.compile_configuration(&ctx);
+                    // If the configuration has changed, execute the `reload` and `reconfigure`
+                    // hooks. Note that the configuration does not necessarily change every time
+                    // the user config has (e.g. when only a comment has been added to the latter)
+                    let reconfigure = self.compile_configuration(&ctx);
 
-                (reload, reconfigure)
+                    (reload, reconfigure)
+                })
             };
 
-            self.needs_reload = reload;
-            self.needs_reconfiguration = reconfigure;
+            match compiled {
+                Ok((reload, reconfigure)) => {
+                    secrets::rendered(&self.service_group.to_string());
+                    self.render_error = None;
+                    self.rendered = true;
+                    self.needs_reload = reload;
+                    self.needs_reconfiguration = reconfigure;
+                }
+                Err(err) => {
+                    let err = err.to_string();
+                    // The same templates are checked again on every census change, so only a
+                    // new error is worth reporting.
+                    if self.render_error.as_ref() != Some(&err) {
+                        if self.rendered {
+                            outputln!(preamble self.service_group,
+                                      "Keeping the previously rendered files: {}",
+                                      err);
+                        } else {
+                            outputln!(preamble self.service_group,
+                                      "Waiting for the templates to render before starting: {}",
+                                      err);
+                        }
+                        event::service_render_failed(self, &err);
+                    }
+                    self.render_error = Some(err);
+                }
+            }
         }
 
         cfg_changed
@@ -780,6 +1048,7 @@ impl Service {
                                           self.sys.as_sys_info().clone(),
                                           exported);
         rumor.incarnation = incarnation;
+        rumor.health = Some(self.gossiped_health.to_string());
         rumor
     }
 
@@ -813,6 +1082,24 @@ impl Service {
         }
     }
 
+    /// Run pre-start hook if present.
+    ///
+    /// Unlike `init`, this runs before every start of the service's
+    /// process. Returns `false` if the hook failed, in which case the
+    /// process should not be started.
+    fn pre_start(&self) -> bool {
+        let _timer = hook_timer("pre-start");
+
+        match self.hooks.pre_start {
+            Some(ref hook) => {
+                hook.run(&self.service_group,
+                         &self.pkg,
+                         self.svc_encrypted_password.as_ref())
+            }
+            None => true,
+        }
+    }
+
     fn post_run(&mut self) {
         let _timer = hook_timer("post-run");
 
@@ -823,8 +1110,68 @@ impl Service {
         }
     }
 
-    // This hook method looks different from all the others because
-    // it's the only one that runs async right now.
+    /// Returns a future that runs the pre-stop hook, if present,
+    /// before the service process is sent its shutdown signal.
+    ///
+    /// The hook is given at most `HAB_PRE_STOP_HOOK_TIMEOUT_SECS` to
+    /// finish, after which it's killed along with any processes it
+    /// started; neither a failure nor a timeout will prevent the
+    /// service from being stopped.
+    fn pre_stop(&self) -> impl Future<Item = (), Error = SupError> {
+        habitat_core::env_config_duration!(PreStopHookTimeout,
+                                           HAB_PRE_STOP_HOOK_TIMEOUT_SECS,
+                                           StdDuration::from_secs(30));
+
+        let hook = match self.hooks.pre_stop {
+            Some(ref hook) => Arc::clone(hook),
+            None => return future::Either::A(future::ok(())),
+        };
+        let service_group = self.service_group.clone();
+        let runner = hook_runner::HookRunner::new(hook,
+                                                  self.service_group.clone(),
+                                                  self.pkg.clone(),
+                                                  self.svc_encrypted_password.clone());
+        let hook_pid = runner.pid();
+        let timeout: StdDuration = PreStopHookTimeout::configured_value().into();
+
+        future::Either::B(Timeout::new(runner.into_future(), timeout).then(move |result| {
+            if let Err(err) = result {
+                if err.is_elapsed() {
+                    let pid = hook_pid.lock()
+                                      .expect("Hook pid lock is poisoned")
+                                      .take();
+                    match pid {
+                        Some(pid) => {
+                            sys::service::kill_group(pid as Pid);
+                            outputln!(preamble service_group,
+                                      "{} hook did not finish within {} seconds; killed it \
+                                       (PID: {}) and its child processes, stopping service \
+                                       anyway",
+                                      hooks::PreStopHook::file_name(),
+                                      timeout.as_secs(),
+                                      pid);
+                        }
+                        None => {
+                            outputln!(preamble service_group,
+                                      "{} hook did not finish within {} seconds; stopping \
+                                       service anyway",
+                                      hooks::PreStopHook::file_name(),
+                                      timeout.as_secs());
+                        }
+                    }
+                } else {
+                    outputln!(preamble service_group,
+                              "{} hook failed to run: {:?}",
+                              hooks::PreStopHook::file_name(),
+                              err);
+                }
+            }
+            Ok(())
+        }))
+    }
+
+    // This hook method looks different from most of the others
+    // because it runs async.
     fn post_stop(&self) -> Option<hook_runner::HookRunner<hooks::PostStopHook>> {
         self.hooks.post_stop.as_ref().map(|hook| {
                                          hook_runner::HookRunner::new(Arc::clone(&hook),
@@ -849,6 +1196,17 @@ impl Service {
                                        })
     }
 
+    /// In strict mode, renders every configuration file and hook without writing any of them,
+    /// so that a template referring to undefined variables leaves all of them as they were.
+    fn check_templates(&self, ctx: &RenderContext<'_>) -> Result<()> {
+        if self.config_renderer.is_strict() {
+            self.config_renderer
+                .render(&self.pkg.svc_config_path, ctx)?;
+            self.hooks.render(ctx)?;
+        }
+        Ok(())
+    }
+
     /// Helper for compiling configuration templates into configuration files.
     ///
     /// Returns `true` if the configuration has changed.
@@ -938,6 +1296,9 @@ impl Service {
             }
         } else {
             self.check_process();
+            if self.process_down() && !self.restart_due(launcher) {
+                return;
+            }
             // NOTE: if you need reconfiguration and you DON'T have a
             // reload script, you're going to restart anyway.
             if self.needs_reload || self.process_down() || self.needs_reconfiguration {
@@ -950,6 +1311,161 @@ impl Service {
         }
     }
 
+    /// Consults the restart policy of a service whose process is down,
+    /// returning `true` if it should be restarted now.
+    fn restart_due(&self, launcher: &LauncherCli) -> bool {
+        let exited_pid = self.supervisor
+                             .lock()
+                             .expect("Couldn't lock supervisor")
+                             .take_exited_pid();
+        // The Launcher is asked for the exit code without holding the
+        // lock, as it can take a while to answer.
+        let exit_code = exited_pid.map(|pid| {
+                                      match launcher.exit_code(pid) {
+                                          Ok(code) => code,
+                                          Err(err) => {
+                                              warn!("Unable to get exit code of {}: {}", pid, err);
+                                              None
+                                          }
+                                      }
+                                  });
+        let mut supervisor = self.supervisor.lock().expect("Couldn't lock supervisor");
+        if exited_pid.is_some() || supervisor.restarts.is_idle() {
+            let (outcome, down) = match exit_code {
+                Some(code) => {
+                    let desc = code.map_or_else(|| "an unknown code".to_string(),
+                                                |c| format!("code {}", c));
+                    (supervisor.restarts.process_exited(code), format!("exited with {}", desc))
+                }
+                None => (supervisor.restarts.process_not_started(), "failed to start".to_string()),
+            };
+            match outcome {
+                RestartOutcome::Scheduled(delay) => {
+                    if delay > time::Duration::zero() {
+                        outputln!(preamble self.service_group,
+                                  "Service {}; restarting in {}s",
+                                  down,
+                                  delay.num_seconds());
+                    }
+                }
+                RestartOutcome::Declined => {
+                    outputln!(preamble self.service_group,
+                              "Service {}; not restarting due to '{}' restart policy",
+                              down,
+                              self.restart_policy);
+                }
+                RestartOutcome::GaveUp => {
+                    outputln!(preamble self.service_group,
+                              "Service restarted {} times within {}s; giving up",
+                              supervisor.restarts.count(),
+                              self.restart_backoff.window);
+                }
+            }
+        }
+        supervisor.restarts.restart_due()
+    }
+
+    /// The log file settings in effect for this service, if its output
+    /// is to be written to a file.
+    fn log_file_config(&self) -> Option<LogFileConfig> {
+        self.log_file
+            .apply(self.default_log_file.filter(|config| config.enabled))
+    }
+
+    /// Periodically records the resource usage and status of the
+    /// service in the Supervisor's metrics.
+    fn update_metrics(&mut self, launcher: &LauncherCli) {
+        let now = time::get_time();
+        if now - self.metrics_updated < time::Duration::seconds(METRICS_INTERVAL_SECS) {
+            return;
+        }
+        self.metrics_updated = now;
+
+        let ident = self.pkg.ident.to_string();
+        let labels: &[&str] = &[&self.service_group, &ident];
+        let pid = {
+            let supervisor = self.supervisor.lock().expect("Couldn't lock supervisor");
+            // The total only grows while the service is loaded, and its
+            // series are removed when it's unloaded.
+            let restarts = SERVICE_RESTARTS.with_label_values(labels);
+            restarts.inc_by(supervisor.restarts.total() as i64 - restarts.get());
+            SERVICE_STATE_AGE_SECONDS.with_label_values(labels)
+                                     .set((now - supervisor.state_entered).num_seconds());
+            supervisor.pid()
+        };
+        let health = *self.health_check_result
+                          .lock()
+                          .expect("Couldn't lock health check result");
+        SERVICE_HEALTH_CHECK_STATUS.with_label_values(labels)
+                                   .set(i64::from(i8::from(health)));
+
+        match pid {
+            Some(pid) => {
+                self.update_process_usage(pid, labels);
+                self.update_resource_usage(launcher, pid, labels);
+            }
+            None => remove_process_usage(labels),
+        }
+    }
+
+    fn update_process_usage(&self, pid: Pid, labels: &[&str]) {
+        let stats = match process_tree_stats(pid) {
+            Ok(stats) => stats,
+            Err(err) => {
+                debug!("Unable to get process statistics of {}: {}",
+                       self.service_group, err);
+                remove_process_usage(labels);
+                return;
+            }
+        };
+        SERVICE_MEMORY_BYTES.with_label_values(labels)
+                            .set(stats.rss_bytes as i64);
+        set_counter(&SERVICE_CPU_SECONDS, labels, stats.cpu_seconds);
+        SERVICE_THREADS.with_label_values(labels)
+                       .set(stats.threads as i64);
+        match stats.open_fds {
+            Some(fds) => SERVICE_OPEN_FDS.with_label_values(labels).set(fds as i64),
+            None => {
+                SERVICE_OPEN_FDS.remove_label_values(labels).ok();
+            }
+        }
+    }
+
+    /// Records the usage reported by the cgroup of a service running
+    /// with resource limits.
+    fn update_resource_usage(&self, launcher: &LauncherCli, pid: Pid, labels: &[&str]) {
+        if self.resource_limits.is_empty() {
+            return;
+        }
+        let usage = match launcher.usage(pid) {
+            Ok(Some(usage)) => usage,
+            Ok(None) => return,
+            Err(err) => {
+                debug!("Unable to get resource usage of {}: {}",
+                       self.service_group, err);
+                return;
+            }
+        };
+        if let Some(usec) = usage.cpu_usage_usec {
+            set_counter(&CGROUP_CPU_SECONDS, labels, usec as f64 / 1_000_000.0);
+        }
+        if let Some(usec) = usage.cpu_throttled_usec {
+            set_counter(&CGROUP_CPU_THROTTLED_SECONDS,
+                        labels,
+                        usec as f64 / 1_000_000.0);
+        }
+        if let Some(bytes) = usage.memory_current {
+            CGROUP_MEMORY_BYTES.with_label_values(labels)
+                               .set(bytes as i64);
+        }
+        if let Some(pids) = usage.pids_current {
+            CGROUP_PIDS.with_label_values(labels).set(pids as i64);
+        }
+        if let Some(kills) = usage.oom_kills {
+            set_int_counter(&CGROUP_OOM_KILLS, labels, kills as i64);
+        }
+    }
+
     /// Run file-updated hook if present.
     fn file_updated(&self) -> bool {
         let _timer = hook_timer("file-updated");
@@ -988,13 +1504,20 @@ impl Service {
 
     /// Helper for constructing a new render context for the service.
     fn render_context<'a>(&'a self, census: &'a CensusRing) -> RenderContext<'a> {
+        self.render_context_with(&self.cfg, census)
+    }
+
+    fn render_context_with<'a>(&'a self,
+                               cfg: &'a Cfg,
+                               census: &'a CensusRing)
+                               -> RenderContext<'a> {
         // Unsatisfied binds are filtered out; you only get bind
         // information in the render context if they actually satisfy
         // the contract!
         RenderContext::new(&self.service_group,
                            &self.sys,
                            &self.pkg,
-                           &self.cfg,
+                           cfg,
                            census,
                            self.binds
                                .iter()
@@ -1079,6 +1602,55 @@ impl fmt::Display for Service {
     }
 }
 
+/// Stop reporting metrics for a service that is no longer running.
+fn remove_metrics(service_group: &ServiceGroup, ident: &PackageIdent) {
+    let ident = ident.to_string();
+    let labels: &[&str] = &[service_group, &ident];
+    // These fail if usage was never recorded for the service.
+    CGROUP_CPU_SECONDS.remove_label_values(labels).ok();
+    CGROUP_CPU_THROTTLED_SECONDS.remove_label_values(labels).ok();
+    CGROUP_MEMORY_BYTES.remove_label_values(labels).ok();
+    CGROUP_PIDS.remove_label_values(labels).ok();
+    CGROUP_OOM_KILLS.remove_label_values(labels).ok();
+    remove_process_usage(labels);
+    SERVICE_RESTARTS.remove_label_values(labels).ok();
+    SERVICE_HEALTH_CHECK_STATUS.remove_label_values(labels).ok();
+    SERVICE_STATE_AGE_SECONDS.remove_label_values(labels).ok();
+}
+
+fn remove_process_usage(labels: &[&str]) {
+    SERVICE_MEMORY_BYTES.remove_label_values(labels).ok();
+    SERVICE_CPU_SECONDS.remove_label_values(labels).ok();
+    SERVICE_OPEN_FDS.remove_label_values(labels).ok();
+    SERVICE_THREADS.remove_label_values(labels).ok();
+}
+
+/// Brings a counter up to a total read from elsewhere. Counters only go
+/// up, so one whose total fell, as the CPU time of a process tree does
+/// when one of its processes exits, is started again from zero, which
+/// Prometheus takes as a reset.
+fn set_counter(counters: &CounterVec, labels: &[&str], total: f64) {
+    let current = counters.with_label_values(labels).get();
+    if total < current {
+        counters.remove_label_values(labels).ok();
+        counters.with_label_values(labels).inc_by(total);
+    } else {
+        counters.with_label_values(labels).inc_by(total - current);
+    }
+}
+
+/// `set_counter` for integer counters, such as a cgroup's OOM kills, which start again from
+/// zero when the service's cgroup is made anew.
+fn set_int_counter(counters: &IntCounterVec, labels: &[&str], total: i64) {
+    let current = counters.with_label_values(labels).get();
+    if total < current {
+        counters.remove_label_values(labels).ok();
+        counters.with_label_values(labels).inc_by(total);
+    } else {
+        counters.with_label_values(labels).inc_by(total - current);
+    }
+}
+
 // This returns a HistogramTimer that we can use to track how long hooks take to execute. Note that
 // times will get tracked automatically when the HistogramTimer goes out of scope.
 fn hook_timer(name: &str) -> HistogramTimer {
@@ -1121,9 +1693,9 @@ impl<'a> Serialize for ServiceProxy<'a> {
         where S: Serializer
     {
         let num_fields: usize = if self.config_rendering == ConfigRendering::Full {
-            27
+            37
         } else {
-            26
+            36
         };
 
         let s = &self.service;
@@ -1144,12 +1716,14 @@ impl<'a> Serialize for ServiceProxy<'a> {
         strukt.serialize_field("hooks", &s.hooks)?;
         strukt.serialize_field("initialized", &s.initialized)?;
         strukt.serialize_field("last_election_status", &s.last_election_status)?;
+        strukt.serialize_field("log_file", &s.log_file_config())?;
         strukt.serialize_field("manager_fs_cfg", &s.manager_fs_cfg)?;
         strukt.serialize_field("needs_reconfiguration", &s.needs_reconfiguration)?;
         strukt.serialize_field("needs_reload", &s.needs_reload)?;
 
         let pkg_proxy = PkgProxy::new(&s.pkg);
         strukt.serialize_field("pkg", &pkg_proxy)?;
+        strukt.serialize_field("render_error", &s.render_error)?;
 
         strukt.serialize_field("process",
                                s.supervisor
@@ -1160,12 +1734,20 @@ impl<'a> Serialize for ServiceProxy<'a> {
         strukt.serialize_field("spec_file", &s.spec_file)?;
         strukt.serialize_field("spec_ident", &s.spec_ident)?;
         strukt.serialize_field("spec_identifier", &s.spec_ident.to_string())?;
+        strukt.serialize_field("strict_templates", &s.strict_templates)?;
         strukt.serialize_field("svc_encrypted_password", &s.svc_encrypted_password)?;
         strukt.serialize_field("health_check_interval", &s.health_check_interval)?;
+        strukt.serialize_field("restart_policy", &s.restart_policy)?;
+        strukt.serialize_field("restart_backoff", &s.restart_backoff)?;
+        strukt.serialize_field("resource_limits", &s.resource_limits)?;
         strukt.serialize_field("sys", &s.sys)?;
         strukt.serialize_field("topology", &s.topology)?;
         strukt.serialize_field("update_strategy", &s.update_strategy)?;
+        strukt.serialize_field("update_windows", &s.update_windows)?;
+        strukt.serialize_field("update_rollout", &s.update_rollout)?;
+        strukt.serialize_field("update_rollback", &s.update_rollback)?;
         strukt.serialize_field("user_config_updated", &s.user_config_updated)?;
+        strukt.serialize_field("version_constraint", &s.version_constraint)?;
         strukt.end()
     }
 }
@@ -1211,8 +1793,9 @@ mod tests {
         let afs = Arc::new(fscfg);
 
         let gs = Arc::new(RwLock::new(GatewayState::default()));
-        Service::new(asys, &install, spec, afs, Some("haha"), gs).expect("I wanted a service to \
-                                                                          load, but it didn't")
+        Service::new(asys, &install, spec, afs, Some("haha"), gs, None).expect("I wanted a service \
+                                                                                to load, but it \
+                                                                                didn't")
     }
 
     #[test]
@@ -0,0 +1,169 @@
+//! Resource usage of a service's process tree, read from `/proc`.
+//!
+//! The Supervisor only knows the pid of the process the Launcher
+//! started, so the rest of the tree is found through the `children`
+//! file of each of its threads, and so on down. Only Linux has a `/proc`
+//! we can read this way; elsewhere there are no statistics.
+
+use habitat_core::os::process::Pid;
+use std::io;
+
+/// Totals across a process and all of its descendants.
+#[derive(Debug, Default, PartialEq)]
+pub struct ProcessStats {
+    pub rss_bytes:   u64,
+    pub cpu_seconds: f64,
+    /// `None` if we aren't allowed to look at the file descriptors of
+    /// one of the processes.
+    pub open_fds:    Option<u64>,
+    pub threads:     u64,
+}
+
+#[cfg(target_os = "linux")]
+pub fn process_tree_stats(pid: Pid) -> io::Result<ProcessStats> {
+    let clock_ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as f64;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+
+    // Read the root first, so a process that has gone is an error
+    // rather than an empty tree.
+    let root = read_stat(pid)?;
+    let mut total = ProcessStats { open_fds: Some(0),
+                                   ..Default::default() };
+    // Descendants may exit while we look at them.
+    let descendants = process_tree(pid).into_iter()
+                                       .skip(1)
+                                       .filter_map(|p| read_stat(p).ok().map(|stat| (p, stat)));
+    for (p, stat) in std::iter::once((pid, root)).chain(descendants) {
+        total.rss_bytes += stat.rss_pages * page_size;
+        total.cpu_seconds += (stat.utime + stat.stime) as f64 / clock_ticks;
+        total.threads += stat.threads;
+        total.open_fds = match (total.open_fds, std::fs::read_dir(format!("/proc/{}/fd", p))) {
+            (Some(fds), Ok(dir)) => Some(fds + dir.count() as u64),
+            _ => None,
+        };
+    }
+    Ok(total)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn process_tree_stats(_pid: Pid) -> io::Result<ProcessStats> {
+    Err(io::Error::new(io::ErrorKind::Other,
+                       "process statistics are only available on Linux"))
+}
+
+/// The fields we use from `/proc/<pid>/stat`.
+#[cfg(target_os = "linux")]
+#[derive(Debug, PartialEq)]
+struct ProcStat {
+    /// User and system CPU time, in clock ticks.
+    utime:     u64,
+    stime:     u64,
+    threads:   u64,
+    rss_pages: u64,
+}
+
+#[cfg(target_os = "linux")]
+fn read_stat(pid: Pid) -> io::Result<ProcStat> {
+    let contents = std::fs::read_to_string(format!("/proc/{}/stat", pid))?;
+    parse_stat(&contents).ok_or_else(|| {
+                             io::Error::new(io::ErrorKind::InvalidData,
+                                            format!("unexpected contents in /proc/{}/stat", pid))
+                         })
+}
+
+#[cfg(target_os = "linux")]
+fn parse_stat(contents: &str) -> Option<ProcStat> {
+    // The command name is in parentheses and may itself contain spaces
+    // or parentheses, so start after the last one.
+    let rest = &contents[contents.rfind(')')? + 1..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    // Fields are numbered as in proc(5); the first one after the
+    // command name is field 3.
+    let field = |n: usize| fields.get(n - 3).and_then(|f| f.parse::<u64>().ok());
+    Some(ProcStat { utime:     field(14)?,
+                    stime:     field(15)?,
+                    threads:   field(20)?,
+                    rss_pages: field(24)?, })
+}
+
+/// The given process and all of its descendants. Each thread of a
+/// process lists the children it started in its own `children` file.
+#[cfg(target_os = "linux")]
+fn process_tree(root: Pid) -> Vec<Pid> {
+    let mut tree = vec![root];
+    let mut next = 0;
+    while next < tree.len() {
+        for child in children(tree[next]) {
+            if !tree.contains(&child) {
+                tree.push(child);
+            }
+        }
+        next += 1;
+    }
+    tree
+}
+
+#[cfg(target_os = "linux")]
+fn children(pid: Pid) -> Vec<Pid> {
+    let tasks = match std::fs::read_dir(format!("/proc/{}/task", pid)) {
+        Ok(tasks) => tasks,
+        Err(_) => return Vec::new(),
+    };
+    tasks.filter_map(|task| std::fs::read_to_string(task.ok()?.path().join("children")).ok())
+         .flat_map(|contents| parse_children(&contents))
+         .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn parse_children(contents: &str) -> Vec<Pid> {
+    contents.split_whitespace()
+            .filter_map(|pid| pid.parse().ok())
+            .collect()
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn parse_stat_handles_awkward_command_names() {
+        let contents = "4242 (my (odd) cmd) S 4200 4242 4242 0 -1 4194560 1234 0 0 0 150 25 0 0 \
+                        20 0 3 0 123456 10000000 2048 18446744073709551615 1 1 0 0 0 0 0 0 0 0 \
+                        0 0 17 2 0 0 0 0 0\n";
+        assert_eq!(parse_stat(contents),
+                   Some(ProcStat { utime:     150,
+                                   stime:     25,
+                                   threads:   3,
+                                   rss_pages: 2048, }));
+    }
+
+    #[test]
+    fn parse_stat_rejects_truncated_contents() {
+        assert_eq!(parse_stat("4242 (cmd) S 4200 4242"), None);
+    }
+
+    #[test]
+    fn parse_children_reads_every_pid() {
+        assert_eq!(parse_children("4243 4250 \n"), vec![4243, 4250]);
+        assert_eq!(parse_children(""), Vec::<Pid>::new());
+    }
+
+    #[test]
+    fn process_tree_finds_children() {
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+        let tree = process_tree(std::process::id() as Pid);
+        child.kill().ok();
+        child.wait().ok();
+        assert_eq!(tree[0], std::process::id() as Pid);
+        assert!(tree.contains(&(child.id() as Pid)));
+    }
+
+    #[test]
+    fn process_tree_stats_reads_this_process() {
+        let stats = process_tree_stats(std::process::id() as Pid).unwrap();
+        assert!(stats.rss_bytes > 0);
+        assert!(stats.threads >= 1);
+        assert!(stats.open_fds.unwrap() > 0);
+    }
+}
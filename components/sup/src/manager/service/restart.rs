@@ -0,0 +1,316 @@
+//! Decides whether, and when, a service whose process has gone down
+//! should be restarted.
+//!
+//! Every restart is counted within a sliding window. The first restart
+//! in a window happens immediately; each subsequent one waits twice as
+//! long as the last (starting at `initial_delay` and capped at
+//! `max_delay`). Once `limit` restarts have happened within the window
+//! the Supervisor gives up and leaves the service down.
+
+use habitat_sup_protocol::types::{self as proto,
+                                  RestartPolicy};
+use serde::{ser::SerializeStruct,
+            Serialize,
+            Serializer};
+use std::{collections::VecDeque,
+          result};
+use time::{Duration,
+           Timespec};
+
+/// Delays and limits applied when restarting a service.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(default)]
+pub struct RestartBackoff {
+    /// Seconds to wait before the second restart within `window`.
+    pub initial_delay: u64,
+    /// Upper bound, in seconds, on the delay between restarts.
+    pub max_delay: u64,
+    /// Give up after this many restarts within `window`; zero never
+    /// gives up.
+    pub limit: u32,
+    /// Length, in seconds, of the sliding window restarts are counted
+    /// in.
+    pub window: u64,
+}
+
+impl Default for RestartBackoff {
+    fn default() -> Self {
+        RestartBackoff { initial_delay: 2,
+                         max_delay:     120,
+                         limit:         0,
+                         window:        600, }
+    }
+}
+
+impl RestartBackoff {
+    /// The delay before a restart, given how many restarts have
+    /// already happened within the window.
+    fn delay(&self, previous_restarts: usize) -> Duration {
+        if previous_restarts == 0 {
+            return Duration::zero();
+        }
+        let exponent = (previous_restarts - 1).min(32) as u32;
+        let secs = self.initial_delay
+                       .saturating_mul(2u64.saturating_pow(exponent))
+                       .min(self.max_delay);
+        Duration::seconds(secs as i64)
+    }
+
+    /// Overlay any values set in a protocol message onto these.
+    pub fn merge(&mut self, other: &proto::RestartBackoff) {
+        if let Some(initial_delay) = other.initial_delay {
+            self.initial_delay = initial_delay;
+        }
+        if let Some(max_delay) = other.max_delay {
+            self.max_delay = max_delay;
+        }
+        if let Some(limit) = other.limit {
+            self.limit = limit;
+        }
+        if let Some(window) = other.window {
+            self.window = window;
+        }
+    }
+}
+
+/// What happened as a result of a service's process going down.
+#[derive(Debug, PartialEq)]
+pub enum RestartOutcome {
+    /// The service will be restarted once the delay has passed.
+    Scheduled(Duration),
+    /// The restart policy says the service stays down.
+    Declined,
+    /// Too many restarts have happened within the window.
+    GaveUp,
+}
+
+#[derive(Debug, PartialEq)]
+enum State {
+    /// No restart is pending.
+    Idle,
+    /// A restart will happen at the given time.
+    Waiting(Timespec),
+    Declined,
+    GaveUp,
+}
+
+#[derive(Debug)]
+pub struct RestartTracker {
+    policy:         RestartPolicy,
+    backoff:        RestartBackoff,
+    restarts:       VecDeque<Timespec>,
+    /// Restarts since the tracker was created, regardless of window.
+    total:          u64,
+    state:          State,
+    last_exit_code: Option<i32>,
+}
+
+impl RestartTracker {
+    pub fn new(policy: RestartPolicy, backoff: RestartBackoff) -> Self {
+        RestartTracker { policy,
+                         backoff,
+                         restarts: VecDeque::new(),
+                         total: 0,
+                         state: State::Idle,
+                         last_exit_code: None }
+    }
+
+    /// True if the service went down without a restart decision having
+    /// been made yet.
+    pub fn is_idle(&self) -> bool { self.state == State::Idle }
+
+    /// Record that the service's process exited, deciding whether it
+    /// should be restarted.
+    ///
+    /// `exit_code` is `None` if it couldn't be determined. As that
+    /// doesn't say the process failed, an `on-failure` policy doesn't
+    /// restart it.
+    pub fn process_exited(&mut self, exit_code: Option<i32>) -> RestartOutcome {
+        self.process_exited_at(exit_code, time::get_time())
+    }
+
+    /// Record that the service's process couldn't be started, which
+    /// counts as a failure.
+    pub fn process_not_started(&mut self) -> RestartOutcome {
+        self.process_not_started_at(time::get_time())
+    }
+
+    fn process_exited_at(&mut self, exit_code: Option<i32>, now: Timespec) -> RestartOutcome {
+        self.last_exit_code = exit_code;
+        let failed = exit_code.map_or(false, |code| code != 0);
+        self.process_down_at(failed, now)
+    }
+
+    fn process_not_started_at(&mut self, now: Timespec) -> RestartOutcome {
+        self.last_exit_code = None;
+        self.process_down_at(true, now)
+    }
+
+    fn process_down_at(&mut self, failed: bool, now: Timespec) -> RestartOutcome {
+        let wanted = match self.policy {
+            RestartPolicy::Always => true,
+            RestartPolicy::OnFailure => failed,
+            RestartPolicy::Never => false,
+        };
+        if !wanted {
+            self.state = State::Declined;
+            return RestartOutcome::Declined;
+        }
+
+        self.prune(now);
+        if self.backoff.limit > 0 && self.restarts.len() >= self.backoff.limit as usize {
+            self.state = State::GaveUp;
+            return RestartOutcome::GaveUp;
+        }
+        let delay = self.backoff.delay(self.restarts.len());
+        self.state = State::Waiting(now + delay);
+        RestartOutcome::Scheduled(delay)
+    }
+
+    /// Returns true if a scheduled restart is due, recording it as
+    /// having happened.
+    pub fn restart_due(&mut self) -> bool { self.restart_due_at(time::get_time()) }
+
+    fn restart_due_at(&mut self, now: Timespec) -> bool {
+        match self.state {
+            State::Waiting(at) if now >= at => {
+                self.restarts.push_back(now);
+                self.total += 1;
+                self.state = State::Idle;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Number of restarts within the current window.
+    pub fn count(&self) -> usize {
+        let cutoff = time::get_time() - Duration::seconds(self.backoff.window as i64);
+        self.restarts.iter().filter(|t| **t > cutoff).count()
+    }
+
+    /// Number of restarts since the service was loaded.
+    pub fn total(&self) -> u64 { self.total }
+
+    pub fn gave_up(&self) -> bool { self.state == State::GaveUp }
+
+    pub fn last_exit_code(&self) -> Option<i32> { self.last_exit_code }
+
+    pub fn next_restart(&self) -> Option<Timespec> {
+        match self.state {
+            State::Waiting(at) => Some(at),
+            _ => None,
+        }
+    }
+
+    fn prune(&mut self, now: Timespec) {
+        let cutoff = now - Duration::seconds(self.backoff.window as i64);
+        while self.restarts.front().map_or(false, |t| *t <= cutoff) {
+            self.restarts.pop_front();
+        }
+    }
+}
+
+impl Serialize for RestartTracker {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let mut strukt = serializer.serialize_struct("restart", 5)?;
+        strukt.serialize_field("policy", &self.policy)?;
+        strukt.serialize_field("count", &self.count())?;
+        strukt.serialize_field("next_restart", &self.next_restart().map(|t| t.sec))?;
+        strukt.serialize_field("gave_up", &self.gave_up())?;
+        strukt.serialize_field("last_exit_code", &self.last_exit_code)?;
+        strukt.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(sec: i64) -> Timespec { Timespec::new(sec, 0) }
+
+    fn backoff(limit: u32) -> RestartBackoff {
+        RestartBackoff { initial_delay: 2,
+                         max_delay: 10,
+                         limit,
+                         window: 60 }
+    }
+
+    #[test]
+    fn delay_doubles_up_to_the_maximum() {
+        let b = backoff(0);
+        let delays: Vec<i64> = (0..6).map(|n| b.delay(n).num_seconds()).collect();
+        assert_eq!(delays, vec![0, 2, 4, 8, 10, 10]);
+    }
+
+    #[test]
+    fn first_restart_is_immediate_then_backs_off() {
+        let mut tracker = RestartTracker::new(RestartPolicy::Always, backoff(0));
+
+        assert_eq!(tracker.process_exited_at(Some(1), at(100)),
+                   RestartOutcome::Scheduled(Duration::zero()));
+        assert!(tracker.restart_due_at(at(100)));
+
+        assert_eq!(tracker.process_exited_at(Some(1), at(101)),
+                   RestartOutcome::Scheduled(Duration::seconds(2)));
+        assert!(!tracker.restart_due_at(at(102)));
+        assert!(tracker.restart_due_at(at(103)));
+    }
+
+    #[test]
+    fn restarts_outside_the_window_are_forgotten() {
+        let mut tracker = RestartTracker::new(RestartPolicy::Always, backoff(0));
+        tracker.process_exited_at(Some(1), at(100));
+        tracker.restart_due_at(at(100));
+
+        assert_eq!(tracker.process_exited_at(Some(1), at(200)),
+                   RestartOutcome::Scheduled(Duration::zero()));
+        tracker.restart_due_at(at(200));
+        assert_eq!(tracker.total(), 2);
+    }
+
+    #[test]
+    fn gives_up_after_the_limit() {
+        let mut tracker = RestartTracker::new(RestartPolicy::Always, backoff(2));
+        for now in &[100, 110] {
+            tracker.process_exited_at(Some(1), at(*now));
+            assert!(tracker.restart_due_at(at(*now + 5)));
+        }
+
+        assert_eq!(tracker.process_exited_at(Some(1), at(120)),
+                   RestartOutcome::GaveUp);
+        assert!(tracker.gave_up());
+        assert!(!tracker.restart_due_at(at(1000)));
+    }
+
+    #[test]
+    fn on_failure_ignores_clean_exits() {
+        let mut tracker = RestartTracker::new(RestartPolicy::OnFailure, backoff(0));
+        assert_eq!(tracker.process_exited_at(Some(0), at(100)),
+                   RestartOutcome::Declined);
+        assert!(!tracker.restart_due_at(at(100)));
+
+        // An unknown exit code isn't a failure either
+        let mut tracker = RestartTracker::new(RestartPolicy::OnFailure, backoff(0));
+        assert_eq!(tracker.process_exited_at(None, at(100)),
+                   RestartOutcome::Declined);
+
+        let mut tracker = RestartTracker::new(RestartPolicy::OnFailure, backoff(0));
+        assert_eq!(tracker.process_exited_at(Some(2), at(100)),
+                   RestartOutcome::Scheduled(Duration::zero()));
+
+        let mut tracker = RestartTracker::new(RestartPolicy::OnFailure, backoff(0));
+        assert_eq!(tracker.process_not_started_at(at(100)),
+                   RestartOutcome::Scheduled(Duration::zero()));
+    }
+
+    #[test]
+    fn never_leaves_the_service_down() {
+        let mut tracker = RestartTracker::new(RestartPolicy::Never, backoff(0));
+        assert_eq!(tracker.process_exited_at(Some(1), at(100)),
+                   RestartOutcome::Declined);
+        assert_eq!(tracker.last_exit_code(), Some(1));
+    }
+}
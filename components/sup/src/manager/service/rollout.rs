@@ -0,0 +1,184 @@
+//! How updates are rolled out across the members of a service group, and rolled back.
+//!
+//! Followers of a rolling update update in batches, in the order they come after the update leader
+//! in the census. A batch starts once the leader and every member of the earlier batches run the
+//! new package and pass their health checks. If one of them fails its health check for longer
+//! than `health_timeout`, the rollout is paused until that member recovers.
+//!
+//! A service with an `UpdateRollback` goes back to the release it ran before an update if its
+//! health check isn't passing once `grace_period` has passed since it restarted. It's then pinned
+//! to that release, so it isn't updated again, and a rolling update stops at it.
+
+use habitat_core::util::{deserialize_using_from_str,
+                         serialize_using_to_string};
+use habitat_sup_protocol::{net::NetErr,
+                           types as proto};
+use std::{fmt,
+          result,
+          str::FromStr};
+
+/// How many followers update at once.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BatchSize {
+    /// A fixed number of followers.
+    Count(u32),
+    /// A percentage of the followers, rounded up.
+    Percent(u32),
+}
+
+impl BatchSize {
+    /// The number of members in each batch, given how many followers are being updated. A batch
+    /// always has at least one member.
+    pub fn for_followers(self, followers: usize) -> usize {
+        let size = match self {
+            BatchSize::Count(count) => count as usize,
+            BatchSize::Percent(percent) => (followers * percent as usize + 99) / 100,
+        };
+        size.max(1)
+    }
+
+    fn from_proto(rollout: &proto::UpdateRollout) -> Option<Self> {
+        match (rollout.batch_size, rollout.batch_percent) {
+            (Some(count), _) => Some(BatchSize::Count(count)),
+            (None, Some(percent)) => Some(BatchSize::Percent(percent)),
+            (None, None) => None,
+        }
+    }
+}
+
+impl Default for BatchSize {
+    fn default() -> Self { BatchSize::Count(1) }
+}
+
+impl fmt::Display for BatchSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            BatchSize::Count(count) => write!(f, "{}", count),
+            BatchSize::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+impl FromStr for BatchSize {
+    type Err = NetErr;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let mut rollout = proto::UpdateRollout::default();
+        rollout.set_batch_size(value)?;
+        Ok(BatchSize::from_proto(&rollout).expect("A batch size was just set"))
+    }
+}
+
+/// How a service's rolling updates are rolled out.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(default)]
+pub struct UpdateRollout {
+    #[serde(deserialize_with = "deserialize_using_from_str",
+            serialize_with = "serialize_using_to_string")]
+    pub batch_size:     BatchSize,
+    /// Seconds an updated member may fail its health check before the rollout is paused.
+    pub health_timeout: u64,
+}
+
+impl Default for UpdateRollout {
+    fn default() -> Self {
+        UpdateRollout { batch_size:     BatchSize::default(),
+                        health_timeout: 600, }
+    }
+}
+
+impl UpdateRollout {
+    /// Overlay any values set in a protocol message onto these.
+    pub fn merge(&mut self, other: &proto::UpdateRollout) {
+        if let Some(batch_size) = BatchSize::from_proto(other) {
+            self.batch_size = batch_size;
+        }
+        if let Some(health_timeout) = other.health_timeout {
+            self.health_timeout = health_timeout;
+        }
+    }
+}
+
+/// How long an updated service has to pass its health check before it's rolled back.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(default)]
+pub struct UpdateRollback {
+    /// Seconds after the updated service starts.
+    pub grace_period: u64,
+}
+
+impl Default for UpdateRollback {
+    fn default() -> Self { UpdateRollback { grace_period: 300 } }
+}
+
+impl UpdateRollback {
+    /// Apply a protocol message to the given settings, returning `None` if it turns rolling back
+    /// off.
+    pub fn merge(current: Option<Self>, other: &proto::UpdateRollback) -> Option<Self> {
+        if other.enabled == Some(false) {
+            return None;
+        }
+        let mut rollback = current.unwrap_or_default();
+        if let Some(grace_period) = other.grace_period {
+            rollback.grace_period = grace_period;
+        }
+        Some(rollback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_size_for_followers() {
+        assert_eq!(BatchSize::Count(1).for_followers(10), 1);
+        assert_eq!(BatchSize::Count(3).for_followers(10), 3);
+        assert_eq!(BatchSize::Percent(25).for_followers(10), 3);
+        assert_eq!(BatchSize::Percent(100).for_followers(10), 10);
+        assert_eq!(BatchSize::Percent(10).for_followers(1), 1);
+        assert_eq!(BatchSize::Percent(50).for_followers(0), 1);
+    }
+
+    #[test]
+    fn batch_size_round_trips_through_a_string() {
+        for value in &["1", "4", "25%", "100%"] {
+            assert_eq!(BatchSize::from_str(value).unwrap().to_string(), *value);
+        }
+        assert!(BatchSize::from_str("0").is_err());
+        assert!(BatchSize::from_str("half").is_err());
+    }
+
+    #[test]
+    fn merge_overlays_set_values() {
+        let mut rollout = UpdateRollout::default();
+        rollout.merge(&proto::UpdateRollout { batch_size:     None,
+                                              batch_percent:  Some(20),
+                                              health_timeout: None, });
+        assert_eq!(rollout,
+                   UpdateRollout { batch_size:     BatchSize::Percent(20),
+                                   health_timeout: 600, });
+        rollout.merge(&proto::UpdateRollout { batch_size:     Some(2),
+                                              batch_percent:  None,
+                                              health_timeout: Some(60), });
+        assert_eq!(rollout,
+                   UpdateRollout { batch_size:     BatchSize::Count(2),
+                                   health_timeout: 60, });
+    }
+
+    #[test]
+    fn rollback_merge_turns_rolling_back_on_and_off() {
+        let on = UpdateRollback::merge(None,
+                                       &proto::UpdateRollback { enabled:      Some(true),
+                                                                grace_period: None, });
+        assert_eq!(on, Some(UpdateRollback::default()));
+        let longer = UpdateRollback::merge(on,
+                                           &proto::UpdateRollback { enabled:      None,
+                                                                    grace_period: Some(900), });
+        assert_eq!(longer, Some(UpdateRollback { grace_period: 900 }));
+        let off = UpdateRollback::merge(longer,
+                                        &proto::UpdateRollback { enabled:      Some(false),
+                                                                 grace_period: None, });
+        assert_eq!(off, None);
+    }
+}
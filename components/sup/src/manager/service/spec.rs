@@ -1,14 +1,22 @@
 use super::{BindingMode,
+            RestartBackoff,
+            RestartPolicy,
             Topology,
+            UpdateRollback,
+            UpdateWindow,
+            UpdateRollout,
             UpdateStrategy};
 use crate::error::{Error,
                    Result,
                    SupError};
 use habitat_core::{fs::atomic_write,
                    package::{PackageIdent,
-                             PackageInstall},
+                             PackageInstall,
+                             VersionConstraint},
                    service::{ApplicationEnvironment,
                              HealthCheckInterval,
+                             LogFileOverrides,
+                             ResourceLimits,
                              ServiceBind},
                    url::DEFAULT_BLDR_URL,
                    util::{deserialize_using_from_str,
@@ -89,12 +97,17 @@ pub fn deserialize_application_environment<'de, D>(
 }
 
 pub trait IntoServiceSpec {
-    fn into_spec(&self, spec: &mut ServiceSpec);
+    fn into_spec(&self, spec: &mut ServiceSpec) -> Result<()>;
 }
 
 impl IntoServiceSpec for habitat_sup_protocol::ctl::SvcLoad {
-    fn into_spec(&self, spec: &mut ServiceSpec) {
+    fn into_spec(&self, spec: &mut ServiceSpec) -> Result<()> {
         spec.ident = self.ident.clone().unwrap().into();
+        // The constraint qualifies the identifier, so it's replaced along with it.
+        spec.version_constraint = match self.version_constraint {
+            Some(ref constraint) => Some(constraint.parse()?),
+            None => None,
+        };
         spec.group = self.group
                          .clone()
                          .unwrap_or_else(|| DEFAULT_GROUP.to_string());
@@ -113,6 +126,13 @@ impl IntoServiceSpec for habitat_sup_protocol::ctl::SvcLoad {
         if let Some(update_strategy) = self.update_strategy {
             spec.update_strategy = UpdateStrategy::from_i32(update_strategy).unwrap_or_default();
         }
+        if let Some(ref update_windows) = self.update_windows {
+            spec.update_windows = update_windows.windows
+                                                .iter()
+                                                .cloned()
+                                                .map(UpdateWindow::from)
+                                                .collect();
+        }
         if let Some(ref list) = self.binds {
             spec.binds =
                 list.binds
@@ -135,6 +155,28 @@ impl IntoServiceSpec for habitat_sup_protocol::ctl::SvcLoad {
         if let Some(ref interval) = self.health_check_interval {
             spec.health_check_interval = interval.seconds.into()
         }
+        if let Some(restart_policy) = self.restart_policy {
+            spec.restart_policy = RestartPolicy::from_i32(restart_policy).unwrap_or_default();
+        }
+        if let Some(ref restart_backoff) = self.restart_backoff {
+            spec.restart_backoff.merge(restart_backoff);
+        }
+        if let Some(ref resource_limits) = self.resource_limits {
+            spec.resource_limits = resource_limits.clone().into();
+        }
+        if let Some(ref log_file) = self.log_file {
+            spec.log_file = log_file.clone().into();
+        }
+        if let Some(ref update_rollout) = self.update_rollout {
+            spec.update_rollout.merge(update_rollout);
+        }
+        if let Some(ref update_rollback) = self.update_rollback {
+            spec.update_rollback = UpdateRollback::merge(spec.update_rollback, update_rollback);
+        }
+        if let Some(strict_templates) = self.strict_templates {
+            spec.strict_templates = strict_templates;
+        }
+        Ok(())
     }
 }
 
@@ -150,16 +192,28 @@ pub struct ServiceSpec {
     pub application_environment: Option<ApplicationEnvironment>,
     pub bldr_url: String,
     pub channel: ChannelIdent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_constraint: Option<VersionConstraint>,
     pub topology: Topology,
     pub update_strategy: UpdateStrategy,
+    pub update_windows: Vec<UpdateWindow>,
     pub binds: Vec<ServiceBind>,
     pub binding_mode: BindingMode,
     pub config_from: Option<PathBuf>,
+    /// Whether rendering the service's templates fails when they refer to undefined variables,
+    /// even if its package doesn't ask for that.
+    pub strict_templates: bool,
     #[serde(deserialize_with = "deserialize_using_from_str",
             serialize_with = "serialize_using_to_string")]
     pub desired_state: DesiredState,
+    pub restart_policy: RestartPolicy,
     pub health_check_interval: HealthCheckInterval,
     pub svc_encrypted_password: Option<String>,
+    pub restart_backoff: RestartBackoff,
+    pub resource_limits: ResourceLimits,
+    pub log_file: LogFileOverrides,
+    pub update_rollout: UpdateRollout,
+    pub update_rollback: Option<UpdateRollback>,
 }
 
 impl ServiceSpec {
@@ -265,14 +319,23 @@ impl Default for ServiceSpec {
                       application_environment: None,
                       bldr_url:                DEFAULT_BLDR_URL.to_string(),
                       channel:                 ChannelIdent::stable(),
+                      version_constraint:      None,
                       topology:                Topology::default(),
                       update_strategy:         UpdateStrategy::default(),
+                      update_windows:          Vec::new(),
                       binds:                   Vec::default(),
                       binding_mode:            BindingMode::Strict,
                       config_from:             None,
+                      strict_templates:        false,
                       desired_state:           DesiredState::default(),
+                      restart_policy:          RestartPolicy::default(),
                       health_check_interval:   HealthCheckInterval::default(),
-                      svc_encrypted_password:  None, }
+                      svc_encrypted_password:  None,
+                      restart_backoff:         RestartBackoff::default(),
+                      resource_limits:         ResourceLimits::default(),
+                      log_file:                LogFileOverrides::default(),
+                      update_rollout:          UpdateRollout::default(),
+                      update_rollback:         None, }
     }
 }
 
@@ -306,7 +369,8 @@ mod test {
                                  HealthCheckInterval}};
 
     use super::*;
-    use crate::error::Error::*;
+    use crate::{error::Error::*,
+                manager::service::BatchSize};
 
     fn file_from_str<P: AsRef<Path>>(path: P, content: &str) {
         fs::create_dir_all(
@@ -336,14 +400,35 @@ mod test {
             group = "jobs"
             application_environment = "theinternet.preprod"
             bldr_url = "http://example.com/depot"
+            version_constraint = ">=1.2,<2"
             topology = "leader"
             update_strategy = "rolling"
+            update_windows = ["sat-sun 01:00-05:00 +01:00"]
             binds = ["cache:redis.cache@acmecorp", "db:postgres.app@acmecorp"]
             config_from = "/only/for/development"
+            strict_templates = true
+
+            restart_policy = "never"
 
             [health_check_interval]
             secs = 5
             nanos = 0
+
+            [restart_backoff]
+            max_delay = 30
+
+            [resource_limits]
+            cpu_quota = 1500
+            memory_max = 536870912
+
+            [log_file]
+            max_size = 1048576
+            retain = 2
+
+            [update_rollout]
+            batch_size = "2"
+
+            [update_rollback]
             "#;
         let spec = ServiceSpec::from_str(toml).unwrap();
 
@@ -353,6 +438,8 @@ mod test {
         assert_eq!(spec.application_environment,
                    Some(ApplicationEnvironment::from_str("theinternet.preprod").unwrap(),));
         assert_eq!(spec.bldr_url, String::from("http://example.com/depot"));
+        assert_eq!(spec.version_constraint,
+                   Some(VersionConstraint::from_str(">=1.2,<2").unwrap()));
         assert_eq!(spec.topology, Topology::Leader);
         assert_eq!(spec.update_strategy, UpdateStrategy::Rolling);
         assert_eq!(spec.binds,
@@ -360,8 +447,27 @@ mod test {
                         ServiceBind::from_str("db:postgres.app@acmecorp").unwrap(),]);
         assert_eq!(spec.config_from,
                    Some(PathBuf::from("/only/for/development")));
+        assert!(spec.strict_templates);
         assert_eq!(spec.health_check_interval,
                    HealthCheckInterval::from_str("5").unwrap());
+        assert_eq!(spec.restart_policy, RestartPolicy::Never);
+        assert_eq!(spec.restart_backoff,
+                   RestartBackoff { max_delay: 30,
+                                    ..Default::default() });
+        assert_eq!(spec.resource_limits,
+                   ResourceLimits { cpu_quota: Some(1500),
+                                    memory_max: Some(536_870_912),
+                                    ..Default::default() });
+        assert_eq!(spec.log_file,
+                   LogFileOverrides { max_size: Some(1_048_576),
+                                      retain: Some(2),
+                                      ..Default::default() });
+        assert_eq!(spec.update_rollout,
+                   UpdateRollout { batch_size: BatchSize::Count(2),
+                                   ..Default::default() });
+        assert_eq!(spec.update_rollback, Some(UpdateRollback::default()));
+        assert_eq!(spec.update_windows,
+                   vec![UpdateWindow::from_str("sat,sun 01:00-05:00 +01:00").unwrap()]);
     }
 
     #[test]
@@ -397,6 +503,29 @@ mod test {
         }
     }
 
+    #[test]
+    fn service_spec_from_svc_load_invalid_version_constraint() {
+        let mut load = habitat_sup_protocol::ctl::SvcLoad::default();
+        load.ident = Some(PackageIdent::from_str("core/redis").unwrap().into());
+        load.version_constraint = Some(">=banana".to_string());
+        let mut spec = ServiceSpec::default();
+
+        match load.into_spec(&mut spec) {
+            Err(e) => {
+                match e.err {
+                    HabitatCore(_) => (), // expected outcome
+                    e => panic!("Unexpected error returned: {:?}", e),
+                }
+            }
+            Ok(_) => panic!("An invalid version constraint should be refused"),
+        }
+
+        load.version_constraint = Some("~1.2".to_string());
+        load.into_spec(&mut spec).unwrap();
+        assert_eq!(spec.version_constraint,
+                   Some(VersionConstraint::from_str("~1.2").unwrap()));
+    }
+
     #[test]
     fn service_spec_from_str_invalid_binds() {
         let toml = r#"
@@ -426,8 +555,13 @@ mod test {
                               Some(ApplicationEnvironment::from_str("theinternet.preprod").unwrap()),
                           bldr_url:                String::from("http://example.com/depot"),
                           channel:                 ChannelIdent::unstable(),
+                          version_constraint:      Some(VersionConstraint::from_str("~1.2")
+                                                                          .unwrap()),
                           topology:                Topology::Leader,
                           update_strategy:         UpdateStrategy::AtOnce,
+                          update_windows:          vec![
+                UpdateWindow::from_str("mon-fri 02:00-04:00").unwrap(),
+            ],
                           binds:                   vec![
                 ServiceBind::from_str("cache:redis.cache@acmecorp").unwrap(),
                 ServiceBind::from_str("db:postgres.app@acmecorp").unwrap(),
@@ -435,8 +569,20 @@ mod test {
                           binding_mode:            BindingMode::Relaxed,
                           health_check_interval:   HealthCheckInterval::from_str("123").unwrap(),
                           config_from:             Some(PathBuf::from("/only/for/development")),
+                          strict_templates:        true,
                           desired_state:           DesiredState::Down,
-                          svc_encrypted_password:  None, };
+                          restart_policy:          RestartPolicy::OnFailure,
+                          svc_encrypted_password:  None,
+                          restart_backoff:         RestartBackoff { limit: 5,
+                                                                    ..Default::default() },
+                          resource_limits:         ResourceLimits { pids_max: Some(64),
+                                                                    ..Default::default() },
+                          log_file:                LogFileOverrides { max_age: Some(3600),
+                                                                      ..Default::default() },
+                          update_rollout:          UpdateRollout { batch_size:
+                                                                       BatchSize::Percent(25),
+                                                                   health_timeout: 300, },
+                          update_rollback:         Some(UpdateRollback { grace_period: 120 }), };
         let toml = spec.to_toml_string().unwrap();
 
         assert!(toml.contains(r#"ident = "origin/name/1.2.3/20170223130020""#,));
@@ -444,16 +590,30 @@ mod test {
         assert!(toml.contains(r#"application_environment = "theinternet.preprod""#,));
         assert!(toml.contains(r#"bldr_url = "http://example.com/depot""#));
         assert!(toml.contains(r#"channel = "unstable""#));
+        assert!(toml.contains(r#"version_constraint = "~1.2""#));
         assert!(toml.contains(r#"topology = "leader""#));
         assert!(toml.contains(r#"update_strategy = "at-once""#));
+        assert!(toml.contains(r#"update_windows = ["mon-fri 02:00-04:00 UTC"]"#));
         assert!(toml.contains(r#""cache:redis.cache@acmecorp""#));
         assert!(toml.contains(r#""db:postgres.app@acmecorp""#));
         assert!(toml.contains(r#"desired_state = "down""#));
         assert!(toml.contains(r#"config_from = "/only/for/development""#));
+        assert!(toml.contains(r#"strict_templates = true"#));
         assert!(toml.contains(r#"binding_mode = "relaxed""#));
         assert!(toml.contains(r#"[health_check_interval]"#));
         assert!(toml.contains(r#"secs = 123"#));
         assert!(toml.contains(r#"nanos = 0"#));
+        assert!(toml.contains(r#"restart_policy = "on-failure""#));
+        assert!(toml.contains(r#"[restart_backoff]"#));
+        assert!(toml.contains(r#"limit = 5"#));
+        assert!(toml.contains(r#"[resource_limits]"#));
+        assert!(toml.contains(r#"pids_max = 64"#));
+        assert!(toml.contains(r#"[log_file]"#));
+        assert!(toml.contains(r#"max_age = 3600"#));
+        assert!(toml.contains(r#"[update_rollout]"#));
+        assert!(toml.contains(r#"batch_size = "25%""#));
+        assert!(toml.contains(r#"[update_rollback]"#));
+        assert!(toml.contains(r#"grace_period = 120"#));
     }
 
     #[test]
@@ -535,6 +695,10 @@ mod test {
         let spec = ServiceSpec::from_file(path).unwrap();
 
         assert_eq!(spec.health_check_interval, HealthCheckInterval::default());
+        assert_eq!(spec.restart_policy, RestartPolicy::Always);
+        assert_eq!(spec.restart_backoff, RestartBackoff::default());
+        assert!(spec.resource_limits.is_empty());
+        assert!(spec.log_file.is_empty());
     }
 
     #[test]
@@ -599,8 +763,13 @@ mod test {
                               Some(ApplicationEnvironment::from_str("theinternet.preprod").unwrap()),
                           bldr_url:                String::from("http://example.com/depot"),
                           channel:                 ChannelIdent::unstable(),
+                          version_constraint:      Some(VersionConstraint::from_str("~1.2")
+                                                                          .unwrap()),
                           topology:                Topology::Leader,
                           update_strategy:         UpdateStrategy::AtOnce,
+                          update_windows:          vec![
+                UpdateWindow::from_str("mon-fri 02:00-04:00").unwrap(),
+            ],
                           binds:                   vec![
                 ServiceBind::from_str("cache:redis.cache@acmecorp").unwrap(),
                 ServiceBind::from_str("db:postgres.app@acmecorp").unwrap(),
@@ -608,8 +777,20 @@ mod test {
                           binding_mode:            BindingMode::Relaxed,
                           health_check_interval:   HealthCheckInterval::from_str("23").unwrap(),
                           config_from:             Some(PathBuf::from("/only/for/development")),
+                          strict_templates:        true,
                           desired_state:           DesiredState::Down,
-                          svc_encrypted_password:  None, };
+                          restart_policy:          RestartPolicy::OnFailure,
+                          svc_encrypted_password:  None,
+                          restart_backoff:         RestartBackoff { limit: 5,
+                                                                    ..Default::default() },
+                          resource_limits:         ResourceLimits { pids_max: Some(64),
+                                                                    ..Default::default() },
+                          log_file:                LogFileOverrides { max_age: Some(3600),
+                                                                      ..Default::default() },
+                          update_rollout:          UpdateRollout { batch_size:
+                                                                       BatchSize::Percent(25),
+                                                                   health_timeout: 300, },
+                          update_rollback:         Some(UpdateRollback { grace_period: 120 }), };
         spec.to_file(&path).unwrap();
         let toml = string_from_file(path);
 
@@ -618,16 +799,30 @@ mod test {
         assert!(toml.contains(r#"application_environment = "theinternet.preprod""#,));
         assert!(toml.contains(r#"bldr_url = "http://example.com/depot""#));
         assert!(toml.contains(r#"channel = "unstable""#));
+        assert!(toml.contains(r#"version_constraint = "~1.2""#));
         assert!(toml.contains(r#"topology = "leader""#));
         assert!(toml.contains(r#"update_strategy = "at-once""#));
+        assert!(toml.contains(r#"update_windows = ["mon-fri 02:00-04:00 UTC"]"#));
         assert!(toml.contains(r#""cache:redis.cache@acmecorp""#));
         assert!(toml.contains(r#""db:postgres.app@acmecorp""#));
         assert!(toml.contains(r#"desired_state = "down""#));
         assert!(toml.contains(r#"config_from = "/only/for/development""#));
+        assert!(toml.contains(r#"strict_templates = true"#));
         assert!(toml.contains(r#"binding_mode = "relaxed""#));
         assert!(toml.contains(r#"[health_check_interval]"#));
         assert!(toml.contains(r#"secs = 23"#));
         assert!(toml.contains(r#"nanos = 0"#));
+        assert!(toml.contains(r#"restart_policy = "on-failure""#));
+        assert!(toml.contains(r#"[restart_backoff]"#));
+        assert!(toml.contains(r#"limit = 5"#));
+        assert!(toml.contains(r#"[resource_limits]"#));
+        assert!(toml.contains(r#"pids_max = 64"#));
+        assert!(toml.contains(r#"[log_file]"#));
+        assert!(toml.contains(r#"max_age = 3600"#));
+        assert!(toml.contains(r#"[update_rollout]"#));
+        assert!(toml.contains(r#"batch_size = "25%""#));
+        assert!(toml.contains(r#"[update_rollback]"#));
+        assert!(toml.contains(r#"grace_period = 120"#));
     }
 
     #[test]
@@ -3,7 +3,8 @@
 /// The Supervisor is responsible for running any services we are asked to start. It handles
 /// spawning the new process, watching for failure, and ensuring the service is either up or
 /// down. If the process dies, the Supervisor will restart it.
-use super::{terminator,
+use super::{restart::RestartTracker,
+            terminator,
             ProcessState};
 use crate::{error::{Error,
                     Result,
@@ -19,7 +20,9 @@ use habitat_core::os::users;
 use habitat_core::{fs,
                    os::process::{self,
                                  Pid},
-                   service::ServiceGroup};
+                   service::{LogFileConfig,
+                             ResourceLimits,
+                             ServiceGroup}};
 use habitat_launcher_client::LauncherCli;
 use serde::{ser::SerializeStruct,
             Serialize,
@@ -40,17 +43,23 @@ pub struct Supervisor {
     pub preamble:      String,
     pub state:         ProcessState,
     pub state_entered: Timespec,
+    pub restarts:      RestartTracker,
     pid:               Option<Pid>,
     pid_file:          PathBuf,
+    /// The PID of a process that has gone down since we last looked,
+    /// and whose exit hasn't yet been acted upon.
+    exited_pid:        Option<Pid>,
 }
 
 impl Supervisor {
-    pub fn new(service_group: &ServiceGroup) -> Supervisor {
-        Supervisor { preamble:      service_group.to_string(),
-                     state:         ProcessState::Down,
+    pub fn new(service_group: &ServiceGroup, restarts: RestartTracker) -> Supervisor {
+        Supervisor { preamble: service_group.to_string(),
+                     state: ProcessState::Down,
                      state_entered: time::get_time(),
-                     pid:           None,
-                     pid_file:      fs::svc_pid_file(service_group.service()), }
+                     restarts,
+                     pid: None,
+                     pid_file: fs::svc_pid_file(service_group.service()),
+                     exited_pid: None }
     }
 
     /// Check if the child process is running
@@ -73,6 +82,9 @@ impl Supervisor {
             }
         }
         debug!("Could not find a live process with pid {:?}", self.pid);
+        if pid.is_some() {
+            self.exited_pid = pid;
+        }
         self.change_state(ProcessState::Down);
         self.cleanup_pidfile();
         self.pid = None;
@@ -132,7 +144,9 @@ impl Supervisor {
                  pkg: &Pkg,
                  group: &ServiceGroup,
                  launcher: &LauncherCli,
-                 svc_password: Option<&str>)
+                 svc_password: Option<&str>,
+                 limits: &ResourceLimits,
+                 log_file: Option<&LogFileConfig>)
                  -> Result<()> {
         let user_info = self.user_info(&pkg)?;
         outputln!(preamble self.preamble,
@@ -140,6 +154,8 @@ impl Supervisor {
                   user_info.username.as_ref().map_or("<anonymous>", String::as_str),
                   user_info.groupname.as_ref().map_or("<anonymous>", String::as_str)
         );
+        let log_path = fs::svc_logs_path(group.service()).join(format!("{}.log",
+                                                                       group.service()));
 
         // In the interests of having as little logic in the Launcher
         // as possible, and to support cloud-native uses of the
@@ -163,7 +179,9 @@ impl Supervisor {
                                  &pkg.svc_run,
                                  user_info,
                                  svc_password, // Windows optional
-                                 (*pkg.env).clone())?;
+                                 (*pkg.env).clone(),
+                                 limits,
+                                 log_file.map(|config| (log_path.as_path(), config)))?;
         self.pid = Some(pid);
         self.create_pidfile()?;
         self.change_state(ProcessState::Up);
@@ -182,6 +200,12 @@ impl Supervisor {
         (healthy, status)
     }
 
+    pub fn pid(&self) -> Option<Pid> { self.pid }
+
+    /// Returns the PID of a process found to have gone down since the
+    /// last call, if any.
+    pub fn take_exited_pid(&mut self) -> Option<Pid> { self.exited_pid.take() }
+
     /// Returns a future that stops a service asynchronously.
     pub fn stop(&self, shutdown_spec: ShutdownSpec) -> impl Future<Item = (), Error = SupError> {
         // TODO (CM): we should really just keep the service
@@ -208,8 +232,12 @@ impl Supervisor {
                    pkg: &Pkg,
                    group: &ServiceGroup,
                    launcher: &LauncherCli,
-                   svc_password: Option<&str>)
+                   svc_password: Option<&str>,
+                   limits: &ResourceLimits,
+                   log_file: Option<&LogFileConfig>)
                    -> Result<()> {
+        // The Launcher restarts a process with the arguments (including
+        // resource limits and log file) it was originally spawned with.
         match self.pid {
             Some(pid) => {
                 match launcher.restart(pid) {
@@ -226,7 +254,7 @@ impl Supervisor {
                     }
                 }
             }
-            None => self.start(pkg, group, launcher, svc_password),
+            None => self.start(pkg, group, launcher, svc_password, limits, log_file),
         }
     }
 
@@ -273,10 +301,11 @@ impl Serialize for Supervisor {
     fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
         where S: Serializer
     {
-        let mut strukt = serializer.serialize_struct("supervisor", 5)?;
+        let mut strukt = serializer.serialize_struct("supervisor", 4)?;
         strukt.serialize_field("pid", &self.pid)?;
         strukt.serialize_field("state", &self.state)?;
         strukt.serialize_field("state_entered", &self.state_entered.sec)?;
+        strukt.serialize_field("restart", &self.restarts)?;
         strukt.end()
     }
 }
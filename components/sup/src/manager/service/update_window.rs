@@ -0,0 +1,246 @@
+//! Maintenance windows that hold a service's updates until they open.
+//!
+//! An update that's found while none of a service's windows are open is held until the next one
+//! opens, and the service restarts into it then. A service without any windows restarts into an
+//! update as soon as it's found.
+
+use chrono::{DateTime,
+             Datelike,
+             Duration,
+             FixedOffset,
+             NaiveDateTime,
+             TimeZone,
+             Timelike,
+             Utc,
+             Weekday};
+use chrono_tz::Tz;
+use habitat_core::util::deserialize_using_from_str;
+use habitat_sup_protocol::{net::NetErr,
+                           types as proto};
+use serde::{Deserialize,
+            Deserializer,
+            Serialize,
+            Serializer};
+use std::{fmt,
+          result,
+          str::FromStr};
+
+/// A recurring period in which a service may restart into an update, e.g.
+/// "mon-fri 02:00-04:00 +01:00" or "sat 01:00-05:00 Europe/Paris".
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct UpdateWindow {
+    /// The days the window opens on, with Monday as the lowest bit.
+    days:  u32,
+    /// Minutes past midnight that the window opens and closes. A window that closes at or before
+    /// the time it opens runs past midnight.
+    start: u32,
+    end:   u32,
+    zone:  Zone,
+}
+
+/// The time zone a window's days and times are in.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Zone {
+    /// Seconds east of UTC.
+    Offset(i32),
+    /// A named zone, whose offset depends on the date, e.g. when daylight saving time is in
+    /// effect.
+    Named(Tz),
+}
+
+impl UpdateWindow {
+    fn opens_on(self, day: Weekday) -> bool { self.days & (1 << day.num_days_from_monday()) != 0 }
+
+    /// The given time on the window's clock.
+    fn local(self, now: DateTime<Utc>) -> NaiveDateTime {
+        match self.zone {
+            Zone::Offset(offset) => now.with_timezone(&FixedOffset::east(offset)).naive_local(),
+            Zone::Named(tz) => now.with_timezone(&tz).naive_local(),
+        }
+    }
+
+    /// The time at which the window's clock shows the given one. A time skipped when the clocks
+    /// go forward is taken to be an hour later, and one repeated when they go back the first
+    /// time round.
+    fn utc(self, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self.zone {
+            Zone::Offset(offset) => {
+                FixedOffset::east(offset).from_local_datetime(&local)
+                                         .single()
+                                         .map(|time| time.with_timezone(&Utc))
+            }
+            Zone::Named(tz) => {
+                tz.from_local_datetime(&local)
+                  .earliest()
+                  .or_else(|| tz.from_local_datetime(&(local + Duration::hours(1))).earliest())
+                  .map(|time| time.with_timezone(&Utc))
+            }
+        }
+    }
+
+    /// Returns true if the window is open at the given time.
+    pub fn is_open(self, now: DateTime<Utc>) -> bool {
+        let local = self.local(now);
+        let minute = local.hour() * 60 + local.minute();
+        if self.start < self.end {
+            self.opens_on(local.weekday()) && self.start <= minute && minute < self.end
+        } else {
+            (self.opens_on(local.weekday()) && minute >= self.start)
+            || (self.opens_on(local.weekday().pred()) && minute < self.end)
+        }
+    }
+
+    /// The first time after the given one that the window opens.
+    pub fn next_opening(self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let today = self.local(now).date();
+        (0..8).map(|days| today + Duration::days(days))
+              .filter(|date| self.opens_on(date.weekday()))
+              .filter_map(|date| self.utc(date.and_hms(self.start / 60, self.start % 60, 0)))
+              .find(|opening| *opening > now)
+    }
+
+    /// When an update found at the given time may be applied, given a service's windows: `None`
+    /// if it may be applied right away, because one of them is open or there aren't any.
+    pub fn held_until(windows: &[UpdateWindow], now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if windows.iter().any(|window| window.is_open(now)) {
+            return None;
+        }
+        windows.iter()
+               .filter_map(|window| window.next_opening(now))
+               .min()
+    }
+}
+
+impl From<proto::UpdateWindow> for UpdateWindow {
+    fn from(window: proto::UpdateWindow) -> Self {
+        // Names are checked when the window is parsed, so one that isn't known here comes from
+        // a newer Supervisor's zone database; its offset, if given, is the best we can do.
+        let zone = match window.time_zone.and_then(|name| name.parse().ok()) {
+            Some(tz) => Zone::Named(tz),
+            None => Zone::Offset(window.utc_offset.unwrap_or(0)),
+        };
+        UpdateWindow { days:  window.days,
+                       start: window.start,
+                       end:   window.end,
+                       zone }
+    }
+}
+
+impl From<UpdateWindow> for proto::UpdateWindow {
+    fn from(window: UpdateWindow) -> Self {
+        let (utc_offset, time_zone) = match window.zone {
+            Zone::Offset(offset) => (Some(offset), None),
+            Zone::Named(tz) => (None, Some(tz.name().to_string())),
+        };
+        proto::UpdateWindow { days:  window.days,
+                              start: window.start,
+                              end:   window.end,
+                              utc_offset,
+                              time_zone }
+    }
+}
+
+impl FromStr for UpdateWindow {
+    type Err = NetErr;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        Ok(proto::UpdateWindow::from_str(value)?.into())
+    }
+}
+
+impl fmt::Display for UpdateWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", proto::UpdateWindow::from(*self))
+    }
+}
+
+impl Serialize for UpdateWindow {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for UpdateWindow {
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        deserialize_using_from_str(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> { value.parse().unwrap() }
+
+    fn window(value: &str) -> UpdateWindow { value.parse().unwrap() }
+
+    #[test]
+    fn is_open_in_the_windows_time_zone() {
+        // 2019-06-03 is a Monday
+        let window = window("mon-fri 02:00-04:00 +02:00");
+        assert!(!window.is_open(at("2019-06-02T23:59:00Z")));
+        assert!(window.is_open(at("2019-06-03T00:00:00Z")));
+        assert!(window.is_open(at("2019-06-03T01:59:00Z")));
+        assert!(!window.is_open(at("2019-06-03T02:00:00Z")));
+        assert!(!window.is_open(at("2019-06-08T00:30:00Z")));
+    }
+
+    #[test]
+    fn is_open_in_a_named_zone_across_daylight_saving_time() {
+        // Paris is an hour ahead of UTC in winter and two in summer
+        let window = window("mon 02:00-04:00 Europe/Paris");
+        assert!(window.is_open(at("2019-01-07T01:00:00Z")));
+        assert!(!window.is_open(at("2019-01-07T03:00:00Z")));
+        assert!(window.is_open(at("2019-06-03T00:00:00Z")));
+        assert!(!window.is_open(at("2019-06-03T02:00:00Z")));
+        assert_eq!(window.next_opening(at("2019-01-01T00:00:00Z")),
+                   Some(at("2019-01-07T01:00:00Z")));
+        assert_eq!(window.next_opening(at("2019-06-01T00:00:00Z")),
+                   Some(at("2019-06-03T00:00:00Z")));
+    }
+
+    #[test]
+    fn opens_after_the_clocks_go_forward_over_its_start() {
+        // Clocks in Paris went from 02:00 to 03:00 on Sunday 2019-03-31
+        let window = window("sun 02:30-04:00 Europe/Paris");
+        assert_eq!(window.next_opening(at("2019-03-30T00:00:00Z")),
+                   Some(at("2019-03-31T01:30:00Z")));
+    }
+
+    #[test]
+    fn is_open_past_midnight() {
+        let window = window("fri 22:00-02:00");
+        assert!(window.is_open(at("2019-06-07T23:00:00Z")));
+        assert!(window.is_open(at("2019-06-08T01:00:00Z")));
+        assert!(!window.is_open(at("2019-06-08T23:00:00Z")));
+        assert!(!window.is_open(at("2019-06-07T01:00:00Z")));
+    }
+
+    #[test]
+    fn updates_are_held_until_the_next_window_opens() {
+        let windows = vec![window("sat 03:00-05:00"), window("wed 01:00-02:00 -01:00")];
+        assert_eq!(UpdateWindow::held_until(&windows, at("2019-06-03T12:00:00Z")),
+                   Some(at("2019-06-05T02:00:00Z")));
+        assert_eq!(UpdateWindow::held_until(&windows, at("2019-06-06T12:00:00Z")),
+                   Some(at("2019-06-08T03:00:00Z")));
+        assert_eq!(UpdateWindow::held_until(&windows, at("2019-06-08T04:00:00Z")), None);
+        assert_eq!(UpdateWindow::held_until(&[], at("2019-06-08T04:00:00Z")), None);
+    }
+
+    #[test]
+    fn serializes_as_a_string() {
+        let window = window("sat,sun 22:00-06:00 -05:00");
+        let json = r#""sat-sun 22:00-06:00 -05:00""#;
+        assert_eq!(serde_json::to_string(&window).unwrap(), json);
+        assert_eq!(serde_json::from_str::<UpdateWindow>(json).unwrap(), window);
+
+        let window = window("sat 01:00-05:00 America/New_York");
+        let json = r#""sat 01:00-05:00 America/New_York""#;
+        assert_eq!(serde_json::to_string(&window).unwrap(), json);
+        assert_eq!(serde_json::from_str::<UpdateWindow>(json).unwrap(), window);
+    }
+}
@@ -1,9 +1,20 @@
-use crate::{census::CensusRing,
+use crate::{census::{CensusGroup,
+                     CensusMember,
+                     CensusRing,
+                     MemberId,
+                     RolloutStatus},
             manager::{periodic::Periodic,
-                      service::{Service,
+                      service::{BatchSize,
+                                HealthCheckResult,
+                                Service,
                                 Topology,
-                                UpdateStrategy}},
+                                UpdateRollout,
+                                UpdateStrategy,
+                                UpdateWindow}},
+            error::Result,
             util};
+use chrono::{DateTime,
+             Utc};
 use habitat_butterfly;
 use habitat_common::{outputln,
                      ui::UI};
@@ -11,7 +22,8 @@ use habitat_core::{env as henv,
                    env::Config as EnvConfig,
                    package::{PackageIdent,
                              PackageInstall,
-                             PackageTarget},
+                             PackageTarget,
+                             VersionConstraint},
                    service::ServiceGroup,
                    ChannelIdent};
 use std::{cmp::{Ordering,
@@ -65,11 +77,141 @@ enum FollowerState {
     Updating(Receiver<PackageInstall>, Sender<()>),
 }
 
+/// What the local Supervisor has seen of a rolling update's progress
+/// through its service group.
+#[derive(Debug, Default)]
+struct RolloutTracker {
+    /// When each updated member was first seen failing its health
+    /// check.
+    critical_since: HashMap<MemberId, SteadyTime>,
+    /// The updated member whose failing health check has paused the
+    /// rollout.
+    paused_by:      Option<MemberId>,
+    status:         Option<RolloutStatus>,
+}
+
+impl RolloutTracker {
+    /// Catch up with the rollout's progress through the census group.
+    fn observe(&mut self,
+               census_group: &CensusGroup,
+               rollout: UpdateRollout,
+               now: SteadyTime) {
+        let package = match census_group.update_leader().and_then(|l| l.pkg.as_ref()) {
+            Some(package) => package,
+            None => {
+                self.status = None;
+                return;
+            }
+        };
+        let members: Vec<&CensusMember> = census_group.members().filter(|m| m.alive()).collect();
+        let updated: Vec<&CensusMember> = members.iter()
+                                                 .filter(|m| m.pkg.as_ref() == Some(package))
+                                                 .cloned()
+                                                 .collect();
+        if updated.len() == members.len() {
+            self.critical_since.clear();
+            self.paused_by = None;
+            self.status = None;
+            return;
+        }
+
+        self.critical_since
+            .retain(|id, _| updated.iter().any(|m| m.member_id == *id && is_critical(m)));
+        for member in updated.iter().filter(|m| is_critical(m)) {
+            self.critical_since
+                .entry(member.member_id.clone())
+                .or_insert(now);
+        }
+        let timeout = Duration::seconds(rollout.health_timeout as i64);
+        let paused_by = self.critical_since
+                            .iter()
+                            .filter(|(_, since)| now - **since >= timeout)
+                            .map(|(id, _)| id.clone())
+                            .min();
+        if paused_by != self.paused_by {
+            match paused_by {
+                Some(ref id) => {
+                    outputln!(preamble census_group.service_group,
+                              "Pausing the rolling update to {}; {} has failed its health check \
+                               for over {} seconds",
+                              package,
+                              id,
+                              rollout.health_timeout)
+                }
+                None => {
+                    outputln!(preamble census_group.service_group,
+                              "Resuming the rolling update to {}",
+                              package)
+                }
+            }
+            self.paused_by = paused_by;
+        }
+
+        let followers = members.len().saturating_sub(1);
+        self.status = Some(RolloutStatus { package:    package.to_string(),
+                                           batch_size: rollout.batch_size
+                                                              .for_followers(followers),
+                                           updated:    updated.len(),
+                                           healthy:    updated.iter()
+                                                              .filter(|m| is_healthy(m))
+                                                              .count(),
+                                           total:      members.len(),
+                                           paused_by:  self.paused_by.clone(), });
+    }
+}
+
+/// An update that was found outside of the service's maintenance
+/// windows, and is held until the next one opens.
+#[derive(Debug)]
+struct HeldUpdate {
+    ident: PackageIdent,
+    until: DateTime<Utc>,
+}
+
+/// An updated service that will be rolled back if it doesn't pass its
+/// health check by the end of its grace period.
+#[derive(Debug)]
+struct Probation {
+    from:  PackageIdent,
+    to:    PackageIdent,
+    /// The end of the grace period, which starts once the updated
+    /// service is first seen running.
+    until: Option<SteadyTime>,
+}
+
+impl Probation {
+    /// Returns the outcome of the probation once it's over: `Some(true)`
+    /// if the update is kept, `Some(false)` if it's to be rolled back.
+    fn observe(&mut self,
+               health: Option<HealthCheckResult>,
+               grace_period: Duration,
+               now: SteadyTime)
+               -> Option<bool> {
+        let until = *self.until.get_or_insert(now + grace_period);
+        if now < until {
+            None
+        } else {
+            Some(health == Some(HealthCheckResult::Ok))
+        }
+    }
+}
+
+/// An update that failed its health check, and the release to go back
+/// to.
+#[derive(Debug)]
+pub struct Rollback {
+    pub from: PackageIdent,
+    pub to:   PackageIdent,
+}
+
 /// The ServiceUpdater is in charge of updating a Service when a more recent version of a package
 /// has been published to a depot or installed to the local package cache.
 /// To use an update strategy, the supervisor must be configured to watch a depot for new versions.
 pub struct ServiceUpdater {
-    states: UpdaterStateList,
+    states:     UpdaterStateList,
+    rollouts:   HashMap<ServiceGroup, RolloutTracker>,
+    probations: HashMap<ServiceGroup, Probation>,
+    held:       HashMap<ServiceGroup, HeldUpdate>,
 
     butterfly: habitat_butterfly::Server,
 }
@@ -77,9 +219,71 @@ pub struct ServiceUpdater {
 impl ServiceUpdater {
     pub fn new(butterfly: habitat_butterfly::Server) -> Self {
         ServiceUpdater { states: UpdaterStateList::default(),
+                         rollouts: HashMap::new(),
+                         probations: HashMap::new(),
+                         held: HashMap::new(),
                          butterfly }
     }
 
+    /// The progress of the rolling update the given service group is
+    /// in, as of the last check for an updated package.
+    pub fn rollout_status(&self, service_group: &ServiceGroup) -> Option<RolloutStatus> {
+        self.rollouts
+            .get(service_group)
+            .and_then(|tracker| tracker.status.clone())
+    }
+
+    /// When the update held for the given service group will be
+    /// applied, if one is being held for a maintenance window.
+    pub fn held_until(&self, service_group: &ServiceGroup) -> Option<DateTime<Utc>> {
+        self.held.get(service_group).map(|held| held.until)
+    }
+
+    /// Put a service that is about to restart into its updated package
+    /// on probation, if it rolls back failed updates.
+    pub fn start_probation(&mut self, service: &Service, updated: &PackageIdent) {
+        if service.update_rollback.is_some() {
+            self.probations.insert(service.service_group.clone(),
+                                   Probation { from:  service.pkg.ident.clone(),
+                                               to:    updated.clone(),
+                                               until: None, });
+        }
+    }
+
+    /// Check on a service that's on probation after an update, given
+    /// its latest health check result. Returns the `Rollback` to make
+    /// if its grace period is over and it isn't healthy.
+    pub fn check_probation(&mut self,
+                           service: &Service,
+                           health: Option<HealthCheckResult>)
+                           -> Option<Rollback> {
+        let grace_period = match service.update_rollback {
+            Some(rollback) => Duration::seconds(rollback.grace_period as i64),
+            None => {
+                self.probations.remove(&service.service_group);
+                return None;
+            }
+        };
+        let outcome = match self.probations.get_mut(&service.service_group) {
+            // Until the service restarts, it still runs the old package.
+            Some(ref probation) if service.pkg.ident != probation.to => return None,
+            Some(probation) => probation.observe(health, grace_period, SteadyTime::now())?,
+            None => return None,
+        };
+        let probation = self.probations
+                            .remove(&service.service_group)
+                            .expect("Probation was just observed");
+        if outcome {
+            outputln!(preamble service.service_group,
+                      "Keeping the update to {}; it passed its health check",
+                      probation.to);
+            None
+        } else {
+            Some(Rollback { from: probation.to,
+                            to:   probation.from, })
+        }
+    }
+
     /// Register a new `Service` for updates. Returns `true` if the
     /// `ServiceUpdater` was modified (i.e., the given service has an
     /// `UpdateStrategy` that is not `None`).
@@ -106,7 +310,12 @@ impl ServiceUpdater {
     }
 
     /// Remove a `Service` from updates, e.g. if the service was unloaded.
+    // Probations are left alone: a service is also removed when it
+    // stops to restart into an update, and its probation has to
+    // outlive that.
     pub fn remove(&mut self, service: &Service) {
+        self.rollouts.remove(&service.service_group);
+        self.held.remove(&service.service_group);
         match self.states.remove(&service.service_group) {
             Some(UpdaterState::AtOnce(_rx, kill_tx)) => {
                 if kill_tx.send(()).is_err() {
@@ -148,24 +357,63 @@ impl ServiceUpdater {
     }
 
     /// See if the given service has an update. Returns the identifier
-    /// of the newly-updated service if a new version was installed,
-    /// thus signalling that the service should be restarted.
-    // If we ever need to modify this function, it would be an excellent opportunity to
-    // simplify the redundant aspects and remove this allow(clippy::cyclomatic_complexity),
-    // but changing it in the absence of other necessity seems like too much risk for the
-    // expected reward.
+    /// of the newly-updated service if a new version was installed and
+    /// the service may restart into it now, thus signalling that the
+    /// service should be restarted. Updates found outside of the
+    /// service's maintenance windows are held until the next one opens.
     pub fn check_for_updated_package(&mut self,
                                      service: &Service,
-                                     // TODO (CM): Strictly speaking, we don't need to pass
-                                     // CensusRing down into here, just the census group for our
-                                     // service.
                                      census_ring: &CensusRing)
                                      -> Option<PackageIdent> {
+        let held = self.held.remove(&service.service_group);
+        let ident = match self.poll_for_updated_package(service, census_ring) {
+            Some(ident) => ident,
+            None => held.as_ref()?.ident.clone(),
+        };
+        match UpdateWindow::held_until(&service.update_windows, Utc::now()) {
+            Some(until) => {
+                if held.map_or(true, |held| held.ident != ident) {
+                    outputln!(preamble service.service_group,
+                              "Holding the update to {} until the next maintenance window opens \
+                               at {}",
+                              ident,
+                              until.to_rfc3339());
+                }
+                self.held
+                    .insert(service.service_group.clone(), HeldUpdate { ident, until });
+                None
+            }
+            None => Some(ident),
+        }
+    }
+
+    /// Check whether the updater has found and installed a newer
+    /// package for the given service.
+    // If we ever need to modify this function, it would be an excellent opportunity to
+    // simplify the redundant aspects and remove this allow(clippy::cyclomatic_complexity),
+    // but changing it in the absence of other necessity seems like too much risk for the
+    // expected reward.
+    fn poll_for_updated_package(&mut self,
+                                service: &Service,
+                                // TODO (CM): Strictly speaking, we don't need to pass
+                                // CensusRing down into here, just the census group for our
+                                // service.
+                                census_ring: &CensusRing)
+                                -> Option<PackageIdent> {
         debug!("Checking for updated package!");
 
         // TODO (CM): can we do without this?
         let mut ident = None;
 
+        if service.update_strategy == UpdateStrategy::Rolling {
+            if let Some(census_group) = census_ring.census_group_for(&service.service_group) {
+                self.rollouts
+                    .entry(service.service_group.clone())
+                    .or_insert_with(RolloutTracker::default)
+                    .observe(census_group, service.update_rollout, SteadyTime::now());
+            }
+        }
+
         match self.states.get_mut(&service.service_group) {
             Some(&mut UpdaterState::AtOnce(ref mut rx, ref mut kill_tx)) => {
                 match rx.try_recv() {
@@ -275,16 +523,32 @@ impl ServiceUpdater {
                     FollowerState::Waiting => {
                         match census_ring.census_group_for(&service.service_group) {
                             Some(census_group) => {
-                                match (census_group.update_leader(),
-                                       census_group.previous_peer(),
-                                       census_group.me())
-                                {
-                                    (Some(leader), Some(peer), Some(me)) => {
+                                match (census_group.update_leader(), census_group.me()) {
+                                    (Some(leader), Some(me)) => {
                                         if leader.pkg == me.pkg {
                                             debug!("We're not in an update");
                                             return None;
                                         }
-                                        if leader.pkg != peer.pkg {
+                                        if service.spec_ident.fully_qualified() {
+                                            debug!("We're pinned to a release, so we won't \
+                                                    follow the update");
+                                            return None;
+                                        }
+                                        if self.rollouts
+                                               .get(&service.service_group)
+                                               .map_or(false, |r| r.paused_by.is_some())
+                                        {
+                                            debug!("We're in an update but it's paused");
+                                            return None;
+                                        }
+                                        let members: Vec<&CensusMember> =
+                                            census_group.members().filter(|m| m.alive()).collect();
+                                        let followers = followers_in_order(&members, leader);
+                                        if !is_turn_to_update(&followers,
+                                                              leader,
+                                                              &me.member_id,
+                                                              service.update_rollout.batch_size)
+                                        {
                                             debug!("We're in an update but it's not our turn");
                                             return None;
                                         }
@@ -341,6 +605,51 @@ impl ServiceUpdater {
     }
 }
 
+/// The followers of a rolling update, in the order they update: each
+/// follows the member before it in the census, starting with the one
+/// after the update leader.
+fn followers_in_order<'a>(members: &[&'a CensusMember],
+                          leader: &CensusMember)
+                          -> Vec<&'a CensusMember> {
+    let start = members.iter()
+                       .position(|m| m.member_id == leader.member_id)
+                       .map_or(0, |p| p + 1);
+    members[start..].iter()
+                    .chain(members[..start].iter())
+                    .filter(|m| m.member_id != leader.member_id)
+                    .cloned()
+                    .collect()
+}
+
+/// Returns true if the given follower may update to the leader's
+/// package: the leader, and every follower in the batches before its
+/// own, run the package and pass their health checks.
+fn is_turn_to_update(followers: &[&CensusMember],
+                     leader: &CensusMember,
+                     member_id: &str,
+                     batch_size: BatchSize)
+                     -> bool {
+    let position = match followers.iter().position(|m| m.member_id == member_id) {
+        Some(position) => position,
+        None => return false,
+    };
+    let batch = batch_size.for_followers(followers.len());
+    let first_of_batch = position / batch * batch;
+    is_healthy(leader)
+    && followers[..first_of_batch].iter()
+                                  .all(|m| m.pkg == leader.pkg && is_healthy(m))
+}
+
+/// Members running a Supervisor too old to report their health are
+/// taken to be healthy.
+fn is_healthy(member: &CensusMember) -> bool {
+    member.health.as_ref().map_or(true, |h| h == "OK")
+}
+
+fn is_critical(member: &CensusMember) -> bool {
+    member.health.as_ref().map_or(false, |h| h == "CRITICAL")
+}
+
 /// Represents how far apart checks for updates to individual services
 /// are, in milliseconds.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -385,6 +694,7 @@ impl EnvConfig for ServiceUpdatePeriod {
 struct Worker {
     current:     PackageIdent,
     spec_ident:  PackageIdent,
+    constraint:  Option<VersionConstraint>,
     builder_url: String,
     channel:     ChannelIdent,
 }
@@ -406,6 +716,7 @@ impl Worker {
     fn new(service: &Service) -> Self {
         Worker { current:     service.pkg.ident.clone(),
                  spec_ident:  service.spec_ident.clone(),
+                 constraint:  service.version_constraint.clone(),
                  builder_url: service.bldr_url.clone(),
                  channel:     service.channel.clone(), }
     }
@@ -501,9 +812,9 @@ impl Worker {
     }
 
     /// Continually poll for a new version of a package, installing it
-    /// when found.
+    /// when found. A service with a version constraint is only updated
+    /// to the latest release that matches it.
     fn run_poll(&mut self, sender: &Sender<PackageInstall>, kill_rx: &Receiver<()>) {
-        let install_source = (self.spec_ident.clone(), PackageTarget::active_target()).into();
         let mut next_time = SteadyTime::now();
 
         loop {
@@ -522,12 +833,7 @@ impl Worker {
             }
 
             if SteadyTime::now() >= next_time {
-                match util::pkg::install(// We don't want anything in here to print
-                                         &mut UI::with_sinks(),
-                                         &self.builder_url,
-                                         &install_source,
-                                         &self.channel)
-                {
+                match self.install_latest() {
                     Ok(maybe_newer_package) => {
                         if self.current < *maybe_newer_package.ident() {
                             outputln!("Updating from {} to {}",
@@ -551,6 +857,25 @@ impl Worker {
             thread::sleep(time::Duration::from_secs(1));
         }
     }
+
+    /// Install the latest release of the service's package, or the latest one matching its
+    /// version constraint.
+    fn install_latest(&self) -> Result<PackageInstall> {
+        // We don't want anything in here to print
+        let mut ui = UI::with_sinks();
+        let ident = match self.constraint {
+            Some(ref constraint) => {
+                util::pkg::latest_matching(&mut ui,
+                                           &self.builder_url,
+                                           &self.spec_ident,
+                                           constraint,
+                                           &self.channel)?
+            }
+            None => self.spec_ident.clone(),
+        };
+        let install_source = (ident, PackageTarget::active_target()).into();
+        util::pkg::install(&mut ui, &self.builder_url, &install_source, &self.channel)
+    }
 }
 
 #[cfg(test)]
@@ -570,6 +895,7 @@ mod tests {
         Worker { current:     "core/testing/1.0.0/20181109125930".parse()
                                                                  .expect("Can't parse ident!"),
                  spec_ident:  "core/testing".parse().expect("Can't parse ident!"),
+                 constraint:  None,
                  builder_url: String::from("https://bldr.habitat.sh"),
                  channel:     ChannelIdent::stable(), }
     }
@@ -654,4 +980,77 @@ mod tests {
         assert!(expected_period < *MIN_ALLOWED_PERIOD);
         assert_eq!(expected_period, worker.update_period());
     }
+
+    fn member(id: &str, pkg: &str, health: Option<&str>) -> CensusMember {
+        let mut member = CensusMember::default();
+        member.member_id = id.to_string();
+        member.pkg = Some(pkg.parse().expect("Can't parse ident!"));
+        member.health = health.map(str::to_string);
+        member
+    }
+
+    #[test]
+    fn followers_update_in_order_after_the_leader() {
+        let (a, b, c, d) = (member("a", "core/testing/1.0.0/1", Some("OK")),
+                            member("b", "core/testing/1.0.0/1", Some("OK")),
+                            member("c", "core/testing/1.0.0/1", Some("OK")),
+                            member("d", "core/testing/1.0.0/1", Some("OK")));
+        let members = vec![&a, &b, &c, &d];
+        let ids = |followers: Vec<&CensusMember>| -> Vec<String> {
+            followers.iter().map(|m| m.member_id.clone()).collect()
+        };
+        assert_eq!(ids(followers_in_order(&members, &c)), vec!["d", "a", "b"]);
+        assert_eq!(ids(followers_in_order(&members, &a)), vec!["b", "c", "d"]);
+    }
+
+    #[test]
+    fn batches_wait_for_earlier_batches_to_update_and_pass_health_checks() {
+        let leader = member("a", "core/testing/2.0.0/2", Some("OK"));
+        let b = member("b", "core/testing/2.0.0/2", Some("OK"));
+        let c = member("c", "core/testing/2.0.0/2", Some("UNKNOWN"));
+        let d = member("d", "core/testing/1.0.0/1", Some("OK"));
+        let e = member("e", "core/testing/1.0.0/1", Some("OK"));
+        let followers = vec![&b, &c, &d, &e];
+
+        // One at a time: d waits for c to pass its health check
+        assert!(is_turn_to_update(&followers, &leader, "c", BatchSize::Count(1)));
+        assert!(!is_turn_to_update(&followers, &leader, "d", BatchSize::Count(1)));
+
+        // In batches of two, d waits on both b and c; in batches of
+        // three, it's in the same batch as them
+        assert!(!is_turn_to_update(&followers, &leader, "d", BatchSize::Count(2)));
+        assert!(is_turn_to_update(&followers, &leader, "d", BatchSize::Count(3)));
+        assert!(!is_turn_to_update(&followers, &leader, "e", BatchSize::Percent(25)));
+        assert!(is_turn_to_update(&followers, &leader, "e", BatchSize::Percent(100)));
+
+        let unhealthy_leader = member("a", "core/testing/2.0.0/2", Some("CRITICAL"));
+        assert!(!is_turn_to_update(&followers, &unhealthy_leader, "b", BatchSize::Count(1)));
+        assert!(!is_turn_to_update(&followers, &leader, "z", BatchSize::Count(1)));
+    }
+
+    #[test]
+    fn probation_ends_after_the_grace_period() {
+        let grace_period = Duration::seconds(300);
+        let start = SteadyTime::now();
+        let mut probation = Probation { from:  "core/testing/1.0.0/1".parse().unwrap(),
+                                        to:    "core/testing/2.0.0/2".parse().unwrap(),
+                                        until: None, };
+
+        // The clock starts the first time the updated service is seen
+        assert_eq!(probation.observe(None, grace_period, start), None);
+        assert_eq!(probation.observe(Some(HealthCheckResult::Critical),
+                                     grace_period,
+                                     start + Duration::seconds(299)),
+                   None);
+        assert_eq!(probation.observe(Some(HealthCheckResult::Ok),
+                                     grace_period,
+                                     start + Duration::seconds(300)),
+                   Some(true));
+        assert_eq!(probation.observe(Some(HealthCheckResult::Warning),
+                                     grace_period,
+                                     start + Duration::seconds(300)),
+                   Some(false));
+        assert_eq!(probation.observe(None, grace_period, start + Duration::seconds(301)),
+                   Some(false));
+    }
 }
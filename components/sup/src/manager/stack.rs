@@ -0,0 +1,277 @@
+//! A stack file describes every service a Supervisor should run, each as a `[[service]]` table in
+//! the same format as a spec file:
+//!
+//! ```toml
+//! [[service]]
+//! ident = "core/postgresql"
+//! topology = "leader"
+//!
+//! [[service]]
+//! ident = "core/redis"
+//! group = "cache"
+//! update_strategy = "rolling"
+//! binds = ["db:postgresql.default"]
+//!
+//! [service.health_check_interval]
+//! secs = 10
+//! nanos = 0
+//! ```
+//!
+//! `hab sup apply` reconciles the Supervisor's spec files with a stack. A setting that a service's
+//! table leaves out has its default value, just as it would if the service were loaded without
+//! it.
+
+use super::service::spec::ServiceSpec;
+use crate::error::{Error,
+                   Result,
+                   SupError};
+use habitat_core::package::PackageIdent;
+use habitat_sup_protocol::types::{service_spec_change::Action,
+                                  ServiceSpecChange};
+use std::{collections::{BTreeSet,
+                        HashMap,
+                        HashSet},
+          str::FromStr};
+use toml::{self,
+           value::Table,
+           Value};
+
+static LOGKEY: &str = "ST";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Stack {
+    #[serde(default, rename = "service")]
+    pub services: Vec<ServiceSpec>,
+}
+
+impl Stack {
+    /// The changes that make `current`, the specs a Supervisor has, match the stack, in the order
+    /// the stack lists its services. Services that the stack doesn't list are unloaded if `prune`
+    /// is set, and left alone otherwise.
+    pub fn plan(&self, current: Vec<ServiceSpec>, prune: bool) -> Vec<SpecChange> {
+        // Spec files are named after their service, so that's what a service is known by.
+        let mut current: HashMap<String, ServiceSpec> =
+            current.into_iter()
+                   .map(|spec| (spec.ident.name.clone(), spec))
+                   .collect();
+        let mut changes: Vec<SpecChange> =
+            self.services
+                .iter()
+                .map(|desired| {
+                    match current.remove(&desired.ident.name) {
+                        None => SpecChange::Load(desired.clone()),
+                        Some(ref spec) if spec == desired => SpecChange::Unchanged(spec.clone()),
+                        Some(spec) => {
+                            SpecChange::Update { current: spec,
+                                                 desired: desired.clone(), }
+                        }
+                    }
+                })
+                .collect();
+        if prune {
+            let mut unlisted: Vec<ServiceSpec> =
+                current.into_iter().map(|(_, spec)| spec).collect();
+            unlisted.sort_by(|a, b| a.ident.name.cmp(&b.ident.name));
+            changes.extend(unlisted.into_iter().map(SpecChange::Unload));
+        }
+        changes
+    }
+}
+
+impl FromStr for Stack {
+    type Err = SupError;
+
+    fn from_str(toml: &str) -> Result<Self> {
+        let stack: Stack =
+            toml::from_str(toml).map_err(|e| sup_error!(Error::ServiceSpecParse(e)))?;
+        let mut names = HashSet::new();
+        for spec in &stack.services {
+            if spec.ident == PackageIdent::default() {
+                return Err(sup_error!(Error::MissingRequiredIdent));
+            }
+            if !names.insert(&spec.ident.name) {
+                return Err(sup_error!(Error::DuplicateStackService(spec.ident.name.clone())));
+            }
+        }
+        Ok(stack)
+    }
+}
+
+/// What applying a stack does to one service.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpecChange {
+    Load(ServiceSpec),
+    Update {
+        current: ServiceSpec,
+        desired: ServiceSpec,
+    },
+    Unload(ServiceSpec),
+    Unchanged(ServiceSpec),
+}
+
+impl SpecChange {
+    /// For an update, each setting that changes, as `name: old -> new`. Settings inside a table
+    /// are named by their path, e.g. `health_check_interval.secs`.
+    pub fn differences(&self) -> Vec<String> {
+        let mut differences = Vec::new();
+        if let SpecChange::Update { ref current, ref desired } = *self {
+            if let (Ok(Value::Table(current)), Ok(Value::Table(desired))) =
+                (Value::try_from(current), Value::try_from(desired))
+            {
+                table_differences("", &current, &desired, &mut differences);
+            }
+        }
+        differences
+    }
+
+    fn spec(&self) -> &ServiceSpec {
+        match *self {
+            SpecChange::Load(ref spec)
+            | SpecChange::Unload(ref spec)
+            | SpecChange::Unchanged(ref spec)
+            | SpecChange::Update { desired: ref spec,
+                                   .. } => spec,
+        }
+    }
+}
+
+impl<'a> From<&'a SpecChange> for ServiceSpecChange {
+    fn from(change: &'a SpecChange) -> Self {
+        let action = match *change {
+            SpecChange::Load(_) => Action::Load,
+            SpecChange::Update { .. } => Action::Update,
+            SpecChange::Unload(_) => Action::Unload,
+            SpecChange::Unchanged(_) => Action::Unchanged,
+        };
+        ServiceSpecChange { ident:       change.spec().ident.clone().into(),
+                            action:      action as i32,
+                            differences: change.differences(), }
+    }
+}
+
+fn table_differences(prefix: &str, current: &Table, desired: &Table, out: &mut Vec<String>) {
+    let keys: BTreeSet<&String> = current.keys().chain(desired.keys()).collect();
+    for key in keys {
+        let name = if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match (current.get(key), desired.get(key)) {
+            (Some(Value::Table(current)), Some(Value::Table(desired))) => {
+                table_differences(&name, current, desired, out)
+            }
+            (current, desired) if current != desired => {
+                out.push(format!("{}: {} -> {}", name, show(current), show(desired)))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A setting's value on one line, the way it would be written in a stack file.
+fn show(value: Option<&Value>) -> String {
+    match value {
+        None => "(none)".to_string(),
+        Some(Value::Table(table)) => {
+            let entries: Vec<String> = table.iter()
+                                            .map(|(k, v)| format!("{} = {}", k, show(Some(v))))
+                                            .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+        Some(Value::Array(items)) => {
+            let items: Vec<String> = items.iter().map(|v| show(Some(v))).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Some(value) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn spec(toml: &str) -> ServiceSpec { ServiceSpec::from_str(toml).unwrap() }
+
+    #[test]
+    fn stack_from_str() {
+        let toml = r#"
+            [[service]]
+            ident = "core/postgresql"
+            topology = "leader"
+
+            [[service]]
+            ident = "core/redis"
+            binds = ["db:postgresql.default"]
+
+            [service.health_check_interval]
+            secs = 10
+            nanos = 0
+            "#;
+        let stack = Stack::from_str(toml).unwrap();
+
+        assert_eq!(stack.services.len(), 2);
+        assert_eq!(stack.services[0],
+                   spec("ident = \"core/postgresql\"\ntopology = \"leader\""));
+        assert_eq!(stack.services[1],
+                   spec("ident = \"core/redis\"\nbinds = [\"db:postgresql.default\"]\n\
+                         [health_check_interval]\nsecs = 10\nnanos = 0"));
+        assert!(Stack::from_str("").unwrap().services.is_empty());
+    }
+
+    #[test]
+    fn stack_from_str_requires_one_ident_per_service() {
+        match Stack::from_str("[[service]]\ngroup = \"cache\"") {
+            Err(SupError { err: Error::MissingRequiredIdent,
+                           .. }) => {}
+            other => panic!("Expected a missing ident, got {:?}", other),
+        }
+        match Stack::from_str("[[service]]\nident = \"core/redis\"\n\
+                               [[service]]\nident = \"other/redis\"")
+        {
+            Err(SupError { err: Error::DuplicateStackService(name),
+                           .. }) => assert_eq!(name, "redis"),
+            other => panic!("Expected a duplicate service, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn plan_loads_updates_and_prunes() {
+        let stack = Stack::from_str("[[service]]\nident = \"core/redis\"\ngroup = \"cache\"\n\
+                                     [[service]]\nident = \"core/nginx\"\n\
+                                     [[service]]\nident = \"core/postgresql\"")
+                    .unwrap();
+        let current = vec![spec("ident = \"core/redis\""),
+                           spec("ident = \"core/postgresql\""),
+                           spec("ident = \"core/zookeeper\""),
+                           spec("ident = \"core/consul\"")];
+
+        let changes = stack.plan(current.clone(), false);
+        assert_eq!(changes,
+                   vec![SpecChange::Update { current: current[0].clone(),
+                                             desired: stack.services[0].clone(), },
+                        SpecChange::Load(stack.services[1].clone()),
+                        SpecChange::Unchanged(current[1].clone()),]);
+
+        let pruned = stack.plan(current.clone(), true);
+        assert_eq!(pruned[3..],
+                   [SpecChange::Unload(current[3].clone()),
+                    SpecChange::Unload(current[2].clone())]);
+    }
+
+    #[test]
+    fn differences_name_each_changed_setting() {
+        let change =
+            SpecChange::Update { current: spec("ident = \"core/redis\"\n\
+                                                [health_check_interval]\nsecs = 30\nnanos = 0"),
+                                 desired: spec("ident = \"core/redis\"\ngroup = \"cache\"\n\
+                                                binds = [\"db:postgresql.default\"]\n\
+                                                [health_check_interval]\nsecs = 10\nnanos = 0"), };
+        assert_eq!(change.differences(),
+                   vec!["binds: [] -> [\"db:postgresql.default\"]",
+                        "group: \"default\" -> \"cache\"",
+                        "health_check_interval.secs: 30 -> 10"]);
+        assert!(SpecChange::Load(spec("ident = \"core/redis\"")).differences()
+                                                               .is_empty());
+    }
+}
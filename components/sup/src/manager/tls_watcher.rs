@@ -0,0 +1,114 @@
+use crate::{error::{Error,
+                    Result},
+            http_gateway::GatewayTls,
+            manager::file_watcher::{default_file_watcher_with_no_initial_event,
+                                    Callbacks}};
+use habitat_common::outputln;
+use std::{path::{Path,
+                 PathBuf},
+          sync::{atomic::{AtomicBool,
+                          Ordering},
+                 Arc},
+          thread::Builder as ThreadBuilder};
+
+static LOGKEY: &'static str = "TW";
+
+pub struct TlsCallbacks {
+    have_events: Arc<AtomicBool>,
+}
+
+impl Callbacks for TlsCallbacks {
+    fn file_appeared(&mut self, _: &Path) { self.have_events.store(true, Ordering::Relaxed); }
+
+    fn file_modified(&mut self, _: &Path) { self.have_events.store(true, Ordering::Relaxed) }
+
+    // A file being replaced shows up as disappearing and then
+    // appearing again; we reload when it's back.
+    fn file_disappeared(&mut self, _: &Path) {}
+}
+
+/// Watches a gateway's certificate, key and client CA files,
+/// reloading them into the running gateway when any of them change.
+pub struct TlsWatcher {
+    tls:         GatewayTls,
+    /// The gateway the files belong to, for messages.
+    gateway:     &'static str,
+    have_events: Arc<AtomicBool>,
+}
+
+impl TlsWatcher {
+    pub fn run(tls: GatewayTls, gateway: &'static str) -> Result<Self> {
+        let have_events = Arc::new(AtomicBool::new(false));
+        for path in tls.paths() {
+            Self::setup_watcher(path, Arc::clone(&have_events))?;
+        }
+        Ok(TlsWatcher { tls,
+                        gateway,
+                        have_events })
+    }
+
+    fn setup_watcher(path: PathBuf, have_events: Arc<AtomicBool>) -> Result<()> {
+        ThreadBuilder::new().name(format!("tls-watcher-[{}]", path.display()))
+                            .spawn(move || {
+                                habitat_common::sync::mark_thread_alive();
+
+                                loop {
+                                    let have_events_for_loop = Arc::clone(&have_events);
+                                    if Self::file_watcher_loop_body(&path, have_events_for_loop) {
+                                        break;
+                                    }
+                                }
+                            })?;
+        Ok(())
+    }
+
+    fn file_watcher_loop_body(path: &PathBuf, have_events: Arc<AtomicBool>) -> bool {
+        let callbacks = TlsCallbacks { have_events };
+        let mut file_watcher = match default_file_watcher_with_no_initial_event(&path, callbacks) {
+            Ok(w) => w,
+            Err(sup_err) => {
+                match sup_err.err {
+                    Error::NotifyError(err) => {
+                        outputln!("TlsWatcher({}) failed to start watching the directories \
+                                   ({}), {}",
+                                  path.display(),
+                                  err,
+                                  "will try again",);
+                        return false;
+                    }
+                    _ => {
+                        outputln!("TlsWatcher({}) could not create file watcher, ending thread \
+                                   ({})",
+                                  path.display(),
+                                  sup_err);
+                        return true;
+                    }
+                }
+            }
+        };
+        if let Err(err) = file_watcher.run() {
+            outputln!("TlsWatcher({}) error during watching ({}), restarting",
+                      path.display(),
+                      err);
+        }
+        false
+    }
+
+    /// Reload the gateway's TLS files if any of them have changed since
+    /// the last call. A failed reload leaves the gateway using the files
+    /// it had; since a certificate and its key are rarely replaced at the
+    /// same instant, the reload is tried again on the next change.
+    pub fn reload_if_changed(&self) {
+        if !self.have_events.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        match self.tls.reload() {
+            Ok(()) => outputln!("Reloaded TLS certificates for the {}", self.gateway),
+            Err(err) => {
+                outputln!("Unable to reload TLS certificates for the {}, {}",
+                          self.gateway,
+                          err)
+            }
+        }
+    }
+}
@@ -23,6 +23,14 @@ pub fn kill(pid: Pid, shutdown_spec: ShutdownSpec) -> ShutdownMethod {
     process.kill(shutdown_spec)
 }
 
+/// Kill a process and the rest of its process group straight away. Hooks run as the leaders
+/// of their own process groups, so this takes any processes a hook started with it.
+pub fn kill_group(pid: Pid) {
+    if let Err(err) = signal(pid.neg(), Signal::KILL) {
+        debug!("Unable to kill process group {}: {}", pid, err);
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////
 // Private Code
 
@@ -40,6 +40,9 @@ pub fn kill(pid: Pid, shutdown_spec: ShutdownSpec) -> ShutdownMethod {
     }
 }
 
+/// Kill a process and its descendants straight away.
+pub fn kill_group(pid: Pid) { terminate_process_descendants(&build_proc_table(), pid); }
+
 ///////////////////////////////////////////////////////////////////////
 // Private Code
 
@@ -0,0 +1,150 @@
+//! Unified diffs between two versions of a text file, as `diff -u` prints them.
+
+use std::cmp;
+
+/// How many unchanged lines are shown around each change.
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+impl<'a> Line<'a> {
+    fn is_change(self) -> bool {
+        match self {
+            Line::Same(_) => false,
+            Line::Removed(_) | Line::Added(_) => true,
+        }
+    }
+
+    fn in_old(self) -> bool {
+        match self {
+            Line::Same(_) | Line::Removed(_) => true,
+            Line::Added(_) => false,
+        }
+    }
+
+    fn in_new(self) -> bool {
+        match self {
+            Line::Same(_) | Line::Added(_) => true,
+            Line::Removed(_) => false,
+        }
+    }
+}
+
+/// A unified diff from `old` to `new`, with the given names in its header, or `None` if they're
+/// the same.
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> Option<String> {
+    let lines = diff_lines(&old.lines().collect::<Vec<_>>(),
+                           &new.lines().collect::<Vec<_>>());
+    let changes: Vec<usize> = lines.iter()
+                                   .enumerate()
+                                   .filter(|(_, line)| line.is_change())
+                                   .map(|(i, _)| i)
+                                   .collect();
+    if changes.is_empty() {
+        return None;
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", old_name, new_name);
+    let mut changes = changes.into_iter().peekable();
+    while let Some(first) = changes.next() {
+        // Changes close enough for their context to touch go in the same hunk.
+        let mut last = first;
+        while let Some(&next) = changes.peek() {
+            if next - last > 2 * CONTEXT_LINES + 1 {
+                break;
+            }
+            last = next;
+            changes.next();
+        }
+        let start = first.saturating_sub(CONTEXT_LINES);
+        let end = cmp::min(last + CONTEXT_LINES + 1, lines.len());
+        let hunk = &lines[start..end];
+        diff.push_str(&format!("@@ -{} +{} @@\n",
+                               range(&lines[..start], hunk, Line::in_old),
+                               range(&lines[..start], hunk, Line::in_new)));
+        for line in hunk {
+            match *line {
+                Line::Same(text) => diff.push_str(&format!(" {}\n", text)),
+                Line::Removed(text) => diff.push_str(&format!("-{}\n", text)),
+                Line::Added(text) => diff.push_str(&format!("+{}\n", text)),
+            }
+        }
+    }
+    Some(diff)
+}
+
+/// The `start,count` range of a hunk in one of the files, where `in_file` picks out the lines
+/// that are in it. An empty range starts at the line before it.
+fn range<'a>(before: &[Line<'a>], hunk: &[Line<'a>], in_file: fn(Line<'a>) -> bool) -> String {
+    let preceding = before.iter().filter(|l| in_file(**l)).count();
+    let count = hunk.iter().filter(|l| in_file(**l)).count();
+    if count == 0 {
+        format!("{},0", preceding)
+    } else {
+        format!("{},{}", preceding + 1, count)
+    }
+}
+
+/// The lines of both files in order, keeping the longest run of lines they have in common.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    // common[i][j] is the length of the longest common subsequence of old[i..] and new[j..].
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                cmp::max(common[i + 1][j], common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::with_capacity(old.len() + new.len());
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(Line::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            lines.push(Line::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|l| Line::Removed(*l)));
+    lines.extend(new[j..].iter().map(|l| Line::Added(*l)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_files_have_no_diff() {
+        assert_eq!(unified("a\nb\n", "a\nb\n", "old", "new"), None);
+    }
+
+    #[test]
+    fn changes_are_shown_with_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n15\n16\n";
+        assert_eq!(unified(old, new, "a/test.conf", "b/test.conf").unwrap(),
+                   "--- a/test.conf\n+++ b/test.conf\n@@ -1,6 +1,6 @@\n 1\n 2\n-3\n+three\n 4\n \
+                    5\n 6\n@@ -11,5 +11,5 @@\n 11\n 12\n 13\n-14\n 15\n+16\n");
+    }
+
+    #[test]
+    fn a_new_file_is_all_additions() {
+        assert_eq!(unified("", "port = 80\n", "/dev/null", "b/test.conf").unwrap(),
+                   "--- /dev/null\n+++ b/test.conf\n@@ -0,0 +1,1 @@\n+port = 80\n");
+    }
+}
@@ -14,7 +14,9 @@ use habitat_core::{env as henv,
                    fs::{self,
                         FS_ROOT_PATH},
                    package::{PackageIdent,
-                             PackageInstall},
+                             PackageInstall,
+                             PackageTarget,
+                             VersionConstraint},
                    ChannelIdent,
                    AUTH_TOKEN_ENVVAR};
 use std::path::Path;
@@ -72,16 +74,32 @@ pub fn install<T>(ui: &mut T,
 ///
 /// Return the PackageInstall corresponding to the package that was
 /// installed, or was pre-existing.
+///
+/// A version constraint limits both the packages that satisfy the identifier and the release
+/// that's installed to those whose version matches it.
 pub fn satisfy_or_install<T>(ui: &mut T,
                              install_source: &InstallSource,
+                             constraint: Option<&VersionConstraint>,
                              bldr_url: &str,
                              channel: &ChannelIdent)
                              -> Result<PackageInstall>
     where T: UIWriter
 {
-    match installed(install_source) {
-        Some(package) => Ok(package),
-        None => install(ui, bldr_url, install_source, channel),
+    let installed = match constraint {
+        Some(constraint) => installed_matching(install_source, constraint),
+        None => installed(install_source),
+    };
+    match (installed, constraint) {
+        (Some(package), _) => Ok(package),
+        (None, Some(constraint)) => {
+            let latest =
+                latest_matching(ui, bldr_url, install_source.as_ref(), constraint, channel)?;
+            install(ui,
+                    bldr_url,
+                    &(latest, PackageTarget::active_target()).into(),
+                    channel)
+        }
+        (None, None) => install(ui, bldr_url, install_source, channel),
     }.and_then(|installed| {
          if installed.is_runnable() {
              Ok(installed)
@@ -99,3 +117,38 @@ pub fn installed<T>(ident: T) -> Option<PackageInstall>
     let fs_root_path = Path::new(&*FS_ROOT_PATH);
     PackageInstall::load(ident.as_ref(), Some(fs_root_path)).ok()
 }
+
+/// Returns the latest installed package for the given ident whose version matches the
+/// constraint, if one is present.
+pub fn installed_matching<T>(ident: T, constraint: &VersionConstraint) -> Option<PackageInstall>
+    where T: AsRef<PackageIdent>
+{
+    let fs_root_path = Path::new(&*FS_ROOT_PATH);
+    PackageInstall::load_matching(ident.as_ref(), constraint, Some(fs_root_path)).ok()
+}
+
+/// Helper function for use in the Supervisor to find the latest release of a package in a
+/// channel whose version matches the constraint.
+pub fn latest_matching<T>(ui: &mut T,
+                          url: &str,
+                          ident: &PackageIdent,
+                          constraint: &VersionConstraint,
+                          channel: &ChannelIdent)
+                          -> Result<PackageIdent>
+    where T: UIWriter
+{
+    let fs_root_path = Path::new(&*FS_ROOT_PATH);
+    let auth_token = henv::var(AUTH_TOKEN_ENVVAR).ok();
+
+    habitat_common::command::package::install::latest_matching(
+        ui,
+        url,
+        channel,
+        (ident, PackageTarget::active_target()),
+        constraint,
+        PRODUCT,
+        VERSION,
+        fs_root_path,
+        auth_token.as_ref().map(String::as_str),
+    ).map_err(SupError::from)
+}